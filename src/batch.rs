@@ -0,0 +1,538 @@
+//! Batch processing of many URLs.
+//!
+//! Every command that accepts more than one URL (`download`, `audio`, `info`,
+//! `playlist`) runs its inputs through [`BatchRunner`], so they all share the
+//! same retry policy and the same end-of-run summary. `sync` uses
+//! [`BatchRunner::run_parallel`] to run several downloads at once.
+//!
+//! URLs can come from the command line, from a batch file passed with
+//! `-a/--batch-file`, or from standard input when the batch file is `-`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::batch::parse_batch;
+//!
+//! let urls = parse_batch("# my list\nhttps://youtu.be/abc\n\n  https://youtu.be/def  \n");
+//! assert_eq!(urls, vec!["https://youtu.be/abc", "https://youtu.be/def"]);
+//! ```
+
+use std::future::Future;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+
+use crate::error::{AppError, AppResult};
+use crate::progress::messages;
+
+/// Parses the contents of a batch file into a list of URLs.
+///
+/// One URL per line. Surrounding whitespace is trimmed, blank lines are
+/// skipped, and lines starting with `#`, `;` or `]` are treated as comments
+/// (the same convention yt-dlp uses for its batch files).
+pub fn parse_batch(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter(|line| !line.starts_with(['#', ';', ']']))
+        .map(String::from)
+        .collect()
+}
+
+/// Reads URLs from a batch file, or from standard input when `path` is `-`.
+///
+/// # Errors
+///
+/// Returns `AppError::FileRead` if the file (or stdin) cannot be read.
+pub fn read_batch_file(path: &Path) -> AppResult<Vec<String>> {
+    let content = if path == Path::new("-") {
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| AppError::file_read(path, e))?;
+        content
+    } else {
+        std::fs::read_to_string(path).map_err(|e| AppError::file_read(path, e))?
+    };
+
+    Ok(parse_batch(&content))
+}
+
+/// A URL that could not be processed.
+#[derive(Debug)]
+pub struct BatchFailure {
+    /// The URL that failed.
+    pub url: String,
+    /// The error from the last attempt.
+    pub error: AppError,
+    /// How many attempts were made.
+    pub attempts: u32,
+}
+
+/// Outcome of a batch run.
+#[derive(Debug)]
+pub struct BatchReport<T> {
+    /// Successfully processed URLs with their results, in input order.
+    pub succeeded: Vec<(String, T)>,
+    /// Failed URLs, in input order.
+    pub failed: Vec<BatchFailure>,
+}
+
+impl<T> BatchReport<T> {
+    /// Returns the number of URLs processed.
+    pub fn total(&self) -> usize {
+        self.succeeded.len() + self.failed.len()
+    }
+
+    /// Returns `true` if every URL succeeded.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Prints the summary line followed by one line per failure.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - What was processed, e.g. "Batch" or "Playlist"
+    pub fn print_summary(&self, label: &str) {
+        println!();
+        messages::info(&format!(
+            "{} complete: {} succeeded, {} failed",
+            label,
+            self.succeeded.len(),
+            self.failed.len()
+        ));
+
+        for failure in &self.failed {
+            messages::error(&format!("{}: {}", failure.url, failure.error));
+        }
+    }
+}
+
+/// Runs an async task for every URL with retries and collects a report.
+///
+/// Retryable errors (see [`AppError::is_retryable`]) are retried up to
+/// `retry_attempts` times with exponential backoff; other errors fail the
+/// URL immediately. A failing URL never stops the rest of the batch.
+#[derive(Debug, Clone)]
+pub struct BatchRunner {
+    retry_attempts: u32,
+    backoff: Duration,
+    silence: bool,
+}
+
+impl BatchRunner {
+    /// Creates a runner that retries each URL up to `retry_attempts` times.
+    pub fn new(retry_attempts: u32) -> Self {
+        Self {
+            retry_attempts,
+            backoff: Duration::from_secs(2),
+            silence: false,
+        }
+    }
+
+    /// Sets the delay before the first retry. Each further retry doubles it.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Suppresses per-URL progress and retry messages.
+    pub fn with_silence(mut self, silence: bool) -> Self {
+        self.silence = silence;
+        self
+    }
+
+    /// Runs `task` for every URL in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `urls` - URLs to process
+    /// * `task` - Called with each URL; may be called again on retry
+    pub async fn run<T, F, Fut>(&self, urls: &[String], mut task: F) -> BatchReport<T>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let mut report = BatchReport {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        };
+
+        for (index, url) in urls.iter().enumerate() {
+            if !self.silence && urls.len() > 1 {
+                messages::info(&format!("[{}/{}] {}", index + 1, urls.len(), url));
+            }
+
+            match self.attempt(url, urls.len(), &mut task).await {
+                Ok(value) => report.succeeded.push((url.clone(), value)),
+                Err(failure) => report.failed.push(failure),
+            }
+        }
+
+        report
+    }
+
+    /// Runs `task` for every URL, at most `jobs` at a time.
+    ///
+    /// Each URL is retried like in [`BatchRunner::run()`]. The report lists
+    /// URLs in input order, whichever finished first. Every URL is driven on
+    /// a blocking thread, so `task` may block while it waits on yt-dlp
+    /// without stalling the async workers.
+    ///
+    /// # Arguments
+    ///
+    /// * `urls` - URLs to process
+    /// * `jobs` - Maximum number of URLs processed at once (at least 1)
+    /// * `task` - Called with each URL; may be called again on retry
+    pub async fn run_parallel<T, F, Fut>(
+        &self,
+        urls: &[String],
+        jobs: usize,
+        task: F,
+    ) -> BatchReport<T>
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = AppResult<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = Arc::new(task);
+        let permits = Arc::new(Semaphore::new(jobs.max(1)));
+        let total = urls.len();
+
+        let handles: Vec<_> = urls
+            .iter()
+            .enumerate()
+            .map(|(index, url)| {
+                let runner = self.clone();
+                let task = Arc::clone(&task);
+                let permits = Arc::clone(&permits);
+                let url = url.clone();
+
+                tokio::spawn(async move {
+                    let _permit = permits
+                        .acquire_owned()
+                        .await
+                        .expect("semaphore is never closed");
+                    if !runner.silence && total > 1 {
+                        messages::info(&format!("[{}/{}] {}", index + 1, total, url));
+                    }
+
+                    // Downloads wait on yt-dlp, so they run off the async workers
+                    let runtime = tokio::runtime::Handle::current();
+                    let outcome = tokio::task::spawn_blocking({
+                        let url = url.clone();
+                        move || {
+                            runtime
+                                .block_on(runner.attempt(&url, total, &mut |url| task(url)))
+                                .map_err(Box::new)
+                        }
+                    })
+                    .await;
+
+                    match outcome {
+                        Ok(result) => result.map_err(|failure| *failure),
+                        Err(e) => Err(BatchFailure {
+                            url,
+                            error: AppError::Other(format!("Task stopped unexpectedly: {}", e)),
+                            attempts: 1,
+                        }),
+                    }
+                })
+            })
+            .collect();
+
+        let mut report = BatchReport {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        };
+
+        for (url, handle) in urls.iter().zip(handles) {
+            match handle.await {
+                Ok(Ok(value)) => report.succeeded.push((url.clone(), value)),
+                Ok(Err(failure)) => report.failed.push(failure),
+                Err(e) => report.failed.push(BatchFailure {
+                    url: url.clone(),
+                    error: AppError::Other(format!("Task stopped unexpectedly: {}", e)),
+                    attempts: 1,
+                }),
+            }
+        }
+
+        report
+    }
+
+    /// Calls `task` for one URL until it succeeds, fails permanently or runs
+    /// out of retries.
+    async fn attempt<T, F, Fut>(
+        &self,
+        url: &str,
+        total: usize,
+        task: &mut F,
+    ) -> Result<T, BatchFailure>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+
+            match task(url.to_string()).await {
+                Ok(value) => return Ok(value),
+                Err(error) if error.is_retryable() && attempts <= self.retry_attempts => {
+                    let delay = self.backoff * 2u32.pow(attempts - 1);
+                    if !self.silence {
+                        messages::warning(&format!(
+                            "{} (retrying in {}s, attempt {} of {})",
+                            error,
+                            delay.as_secs(),
+                            attempts + 1,
+                            self.retry_attempts + 1
+                        ));
+                    }
+                    tokio::time::sleep(delay).await;
+                }
+                Err(error) => {
+                    if !self.silence && total > 1 {
+                        messages::error(&format!("Failed: {}", error));
+                    }
+                    return Err(BatchFailure {
+                        url: url.to_string(),
+                        error,
+                        attempts,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    // ============== parse_batch Tests ==============
+
+    #[test]
+    fn test_parse_batch_one_per_line() {
+        let urls = parse_batch("https://youtu.be/a\nhttps://youtu.be/b\n");
+        assert_eq!(urls, vec!["https://youtu.be/a", "https://youtu.be/b"]);
+    }
+
+    #[test]
+    fn test_parse_batch_skips_comments_and_blank_lines() {
+        let content = "# header\n\nhttps://youtu.be/a\n; note\n] other\n   \nhttps://youtu.be/b";
+        let urls = parse_batch(content);
+        assert_eq!(urls, vec!["https://youtu.be/a", "https://youtu.be/b"]);
+    }
+
+    #[test]
+    fn test_parse_batch_trims_whitespace() {
+        let urls = parse_batch("  https://youtu.be/a  \r\n\thttps://youtu.be/b\t");
+        assert_eq!(urls, vec!["https://youtu.be/a", "https://youtu.be/b"]);
+    }
+
+    #[test]
+    fn test_parse_batch_empty() {
+        assert!(parse_batch("").is_empty());
+        assert!(parse_batch("# only comments\n\n").is_empty());
+    }
+
+    // ============== read_batch_file Tests ==============
+
+    #[test]
+    fn test_read_batch_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("urls.txt");
+        std::fs::write(&path, "# list\nhttps://youtu.be/a\n").unwrap();
+
+        let urls = read_batch_file(&path).unwrap();
+        assert_eq!(urls, vec!["https://youtu.be/a"]);
+    }
+
+    #[test]
+    fn test_read_batch_file_missing() {
+        let result = read_batch_file(Path::new("/nonexistent/urls.txt"));
+        assert!(matches!(result, Err(AppError::FileRead { .. })));
+    }
+
+    // ============== BatchRunner Tests ==============
+
+    fn urls(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn runner() -> BatchRunner {
+        BatchRunner::new(2)
+            .with_backoff(Duration::from_millis(1))
+            .with_silence(true)
+    }
+
+    #[tokio::test]
+    async fn test_runner_all_succeed() {
+        let report = runner()
+            .run(&urls(&["a", "b"]), |url| async move { Ok(url.len()) })
+            .await;
+
+        assert!(report.is_success());
+        assert_eq!(report.total(), 2);
+        assert_eq!(report.succeeded[0], ("a".to_string(), 1));
+    }
+
+    #[tokio::test]
+    async fn test_runner_continues_after_failure() {
+        let report = runner()
+            .run(&urls(&["bad", "good"]), |url| async move {
+                if url == "bad" {
+                    Err(AppError::InvalidUrl(url))
+                } else {
+                    Ok(())
+                }
+            })
+            .await;
+
+        assert!(!report.is_success());
+        assert_eq!(report.succeeded.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].url, "bad");
+    }
+
+    #[tokio::test]
+    async fn test_runner_does_not_retry_permanent_errors() {
+        let calls = Cell::new(0);
+        let report = runner()
+            .run(&urls(&["a"]), |url| {
+                calls.set(calls.get() + 1);
+                async move { Err::<(), _>(AppError::InvalidUrl(url)) }
+            })
+            .await;
+
+        assert_eq!(calls.get(), 1);
+        assert_eq!(report.failed[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_runner_retries_retryable_errors() {
+        let calls = Cell::new(0);
+        let report = runner()
+            .run(&urls(&["a"]), |_| {
+                calls.set(calls.get() + 1);
+                async { Err::<(), _>(AppError::Connection("reset".to_string())) }
+            })
+            .await;
+
+        assert_eq!(calls.get(), 3);
+        assert_eq!(report.failed[0].attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_runner_succeeds_after_retry() {
+        let calls = Cell::new(0);
+        let report = runner()
+            .run(&urls(&["a"]), |_| {
+                calls.set(calls.get() + 1);
+                let attempt = calls.get();
+                async move {
+                    if attempt < 2 {
+                        Err(AppError::Connection("reset".to_string()))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+
+        assert!(report.is_success());
+        assert_eq!(report.succeeded[0].1, 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_parallel_keeps_input_order() {
+        let report = runner()
+            .run_parallel(&urls(&["slow", "a", "bad"]), 2, |url| async move {
+                if url == "slow" {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                if url == "bad" {
+                    return Err(AppError::InvalidUrl(url));
+                }
+                Ok(url.len())
+            })
+            .await;
+
+        assert_eq!(
+            report.succeeded,
+            vec![("slow".to_string(), 4), ("a".to_string(), 1)]
+        );
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].url, "bad");
+    }
+
+    #[tokio::test]
+    async fn test_run_parallel_limits_jobs() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (counter, max) = (Arc::clone(&running), Arc::clone(&peak));
+
+        let report = runner()
+            .run_parallel(&urls(&["a", "b", "c", "d", "e"]), 2, move |_| {
+                let (running, peak) = (Arc::clone(&counter), Arc::clone(&max));
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            })
+            .await;
+
+        assert!(report.is_success());
+        assert_eq!(report.total(), 5);
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_parallel_retries() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let report = runner()
+            .run_parallel(&urls(&["a"]), 4, move |_| {
+                let attempt = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    if attempt < 2 {
+                        Err(AppError::Connection("reset".to_string()))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+
+        assert!(report.is_success());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_runner_empty_input() {
+        let report = runner().run(&[], |_| async { Ok(()) }).await;
+
+        assert!(report.is_success());
+        assert_eq!(report.total(), 0);
+    }
+}
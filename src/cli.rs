@@ -6,8 +6,10 @@
 //! # Architecture
 //!
 //! The CLI uses a hierarchical command structure:
-//! - **Main commands**: `download`, `audio`, `playlist`, `info`, `config`
-//! - **Subcommands**: Only `config` has subcommands (`show`, `set`, `get`, `reset`, `path`)
+//! - **Main commands**: `download`, `audio`, `playlist`, `info`, `formats`, `config`, `history`,
+//!   `library`, `feed`, `subscribe`, `sync`
+//! - **Subcommands**: `config` (`show`, `set`, `get`, `reset`, `path`, ...), `history` (`prune`),
+//!   `library` (`scan`), `feed` (`build`) and `subscribe` (`add`, `list`, `remove`)
 //! - **Common args**: Shared arguments are grouped in `CommonArgs` and flattened into commands
 //!
 //! # Examples
@@ -19,14 +21,37 @@
 //! let cli = Cli::parse();
 //! match cli.command {
 //!     Commands::Download(args) => {
-//!         println!("Downloading: {}", args.common.url);
+//!         println!("Downloading: {:?}", args.common.urls);
 //!     }
 //!     _ => {}
 //! }
 //! ```
 
+use chrono::{DateTime, Utc};
 use clap::{Args, Parser, Subcommand, ValueEnum};
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::batch::read_batch_file;
+use crate::downloader::OptionOverrides;
+use crate::error::{AppError, AppResult};
+use crate::filter::Filter;
+use crate::history::parse_date;
+use crate::plan::PlanFormat;
+use crate::space::{parse_size, SizeBudget};
+use crate::subscriptions::parse_interval;
+use crate::youtube::{
+    validate_url, AudioCodec, FormatPreference, HdrMode, ItemSpec, StreamSortKey, VideoCodec,
+};
+
+/// Output formats accepted by `-f` and `--audio-format`.
+///
+/// These are the [`media`](crate::media) enums, so every format the converters
+/// support is available on the command line. Formats yt-dlp cannot produce
+/// directly are downloaded in the nearest format and converted with FFmpeg.
+pub use crate::media::{AudioFormat, VideoFormat};
 
 /// Main CLI structure for the YouTube downloader application.
 ///
@@ -42,6 +67,35 @@ pub struct Cli {
     /// The subcommand to execute
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Configuration profile to apply (`[profiles.<NAME>]` in the config file)
+    ///
+    /// Overrides `general.default_profile`. With `config get/set/show`, reads
+    /// or writes the profile instead of the base settings.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Use this configuration file instead of the default user file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Show what would be downloaded, without downloading anything
+    ///
+    /// Runs filters, playlist selection, routing rules and templates, then
+    /// prints each video's options, yt-dlp format, output path, library
+    /// reuse and estimated size. `--dry-run=json` prints the plan as JSON.
+    /// Accepted by `download`, `audio`, `playlist` and `sync`.
+    #[arg(
+        long,
+        global = true,
+        visible_alias = "simulate",
+        value_name = "FORMAT",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "human"
+    )]
+    pub dry_run: Option<PlanFormat>,
 }
 
 /// Available commands for the YouTube downloader.
@@ -85,6 +139,19 @@ pub enum Commands {
     /// Shows metadata like title, duration, available formats, and qualities.
     Info(InfoArgs),
 
+    /// List every stream available for a YouTube video
+    ///
+    /// Prints a table with format ID, codec, resolution, fps, bitrate and size.
+    /// Format IDs can be passed to `download --format-id`.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ytdl formats https://youtube.com/watch?v=abc123
+    /// ytdl formats https://youtube.com/watch?v=abc123 --sort size --reverse
+    /// ```
+    Formats(FormatsArgs),
+
     /// Manage application configuration
     ///
     /// Allows viewing and modifying the configuration file located at
@@ -93,6 +160,263 @@ pub enum Commands {
         #[command(subcommand)]
         command: ConfigCommands,
     },
+
+    /// Show or prune the download history
+    ///
+    /// Every download, successful or failed, is recorded in `history.jsonl`
+    /// next to the configuration file.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ytdl history --search "rust" --since 7d
+    /// ytdl history --failed --json
+    /// ytdl history prune --before 2024-01-01
+    /// ```
+    History(HistoryArgs),
+
+    /// Manage the index of downloaded videos
+    ///
+    /// Videos in the index are hard-linked (or copied) instead of being
+    /// downloaded again when another playlist or command asks for them.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ytdl library scan ~/Music/YouTube
+    /// ```
+    Library {
+        #[command(subcommand)]
+        command: LibraryCommands,
+    },
+
+    /// Publish a download directory as a podcast feed
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ytdl feed build ~/Podcasts/lectures --base-url https://example.com/lectures
+    /// ```
+    Feed {
+        #[command(subcommand)]
+        command: FeedCommands,
+    },
+
+    /// Follow channels and playlists
+    ///
+    /// `--profile` given with `subscribe add` is stored with the subscription
+    /// and used for its downloads.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ytdl subscribe add https://www.youtube.com/@LofiGirl --audio-only
+    /// ytdl --profile music subscribe add https://youtube.com/playlist?list=PL123
+    /// ytdl subscribe list
+    /// ytdl subscribe remove @LofiGirl
+    /// ```
+    Subscribe {
+        #[command(subcommand)]
+        command: SubscribeCommands,
+    },
+
+    /// Download new uploads of every subscription
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ytdl sync
+    /// ytdl sync @LofiGirl --jobs 4
+    /// ytdl sync --watch 30m
+    /// ```
+    Sync(SyncArgs),
+
+    /// Run a local HTTP API for queueing and monitoring downloads
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// ytdl serve
+    /// ytdl serve --listen 127.0.0.1:9000 --token s3cret --jobs 4
+    /// ```
+    Serve(ServeArgs),
+}
+
+/// Subscription subcommands.
+#[derive(Subcommand, Clone, Debug)]
+pub enum SubscribeCommands {
+    /// Follow a channel or playlist
+    ///
+    /// The first `ytdl sync` only records the videos that already exist,
+    /// unless `--all` is given.
+    Add(SubscribeAddArgs),
+
+    /// List subscriptions
+    List {
+        /// Print the subscriptions as a JSON array
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Stop following a channel or playlist
+    Remove {
+        /// Name or URL of the subscription
+        subscription: String,
+    },
+}
+
+/// Arguments for `subscribe add`.
+#[derive(Args, Clone, Debug)]
+pub struct SubscribeAddArgs {
+    /// Channel or playlist URL
+    #[arg(value_parser = parse_url)]
+    pub url: String,
+
+    /// Name used by `subscribe remove` and `sync` (defaults to the channel
+    /// handle or playlist ID)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Extract audio instead of downloading videos
+    #[arg(long, default_value_t = false)]
+    pub audio_only: bool,
+
+    /// Only download videos whose title matches this regular expression
+    #[arg(long, value_name = "REGEX")]
+    pub title: Option<String>,
+
+    /// Only download videos at least this many seconds long
+    #[arg(long, value_name = "SECONDS")]
+    pub min_duration: Option<u64>,
+
+    /// Only download videos at most this many seconds long
+    #[arg(long, value_name = "SECONDS")]
+    pub max_duration: Option<u64>,
+
+    /// Also download the videos uploaded before subscribing
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+}
+
+/// Arguments for the `sync` command.
+#[derive(Args, Clone, Debug)]
+pub struct SyncArgs {
+    /// Names or URLs of the subscriptions to sync (defaults to all)
+    #[arg(value_name = "NAME")]
+    pub subscriptions: Vec<String>,
+
+    /// Number of videos downloaded at once (defaults to `general.max_parallel_downloads`)
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u32).range(1..=32))]
+    pub jobs: Option<u32>,
+
+    /// Keep running, syncing again after each INTERVAL (e.g. 30m, 6h)
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_watch_interval)]
+    pub watch: Option<Duration>,
+
+    /// Download only the new videos matching EXPR, on top of each subscription's filters
+    ///
+    /// Videos it skips stay unseen, so a later sync without it downloads them.
+    #[arg(long, value_name = "EXPR", value_parser = parse_filter)]
+    pub filter: Vec<Filter>,
+
+    /// Size limits checked before each download
+    #[command(flatten)]
+    pub sizes: SizeArgs,
+
+    /// Suppress progress and non-error output
+    #[arg(short = 's', long, default_value_t = false)]
+    pub silence: bool,
+
+    /// Enable verbose logging output
+    #[arg(short = 'v', long, default_value_t = false)]
+    pub verbose: bool,
+}
+
+/// Arguments for the `serve` command.
+#[derive(Args, Clone, Debug)]
+pub struct ServeArgs {
+    /// Address and port to listen on
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8765")]
+    pub listen: SocketAddr,
+
+    /// Require `Authorization: Bearer TOKEN` on every request (defaults to `YTDL_SERVE_TOKEN`)
+    #[arg(long, value_name = "TOKEN")]
+    pub token: Option<String>,
+
+    /// Number of jobs downloaded at once (defaults to `general.max_parallel_downloads`)
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u32).range(1..=32))]
+    pub jobs: Option<u32>,
+}
+
+/// Parses `sync --watch` with [`parse_interval`].
+fn parse_watch_interval(value: &str) -> Result<Duration, String> {
+    parse_interval(value).map_err(|e| match e {
+        AppError::InvalidArgument { message, .. } => message,
+        other => other.to_string(),
+    })
+}
+
+/// Feed subcommands.
+#[derive(Subcommand, Clone, Debug)]
+pub enum FeedCommands {
+    /// Write an RSS 2.0 / iTunes feed for the media files in a directory
+    ///
+    /// Only new or changed files are read again, so the command can be
+    /// re-run whenever files are added.
+    Build(FeedBuildArgs),
+}
+
+/// Arguments for `feed build`.
+#[derive(Args, Clone, Debug)]
+pub struct FeedBuildArgs {
+    /// Directory with the downloaded files
+    pub dir: PathBuf,
+
+    /// Public URL the directory is served at (e.g. https://example.com/lectures)
+    #[arg(long, value_name = "URL", value_parser = parse_base_url)]
+    pub base_url: String,
+
+    /// Podcast title (defaults to the directory name)
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Podcast description (defaults to the title)
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Podcast author
+    #[arg(long)]
+    pub author: Option<String>,
+
+    /// URL of the podcast cover image
+    #[arg(long, value_name = "URL")]
+    pub image: Option<String>,
+
+    /// Where to write the feed (defaults to <DIR>/feed.xml)
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+/// Validates a `--base-url` value.
+fn parse_base_url(value: &str) -> Result<String, String> {
+    if value.starts_with("http://") || value.starts_with("https://") {
+        Ok(value.to_string())
+    } else {
+        Err(format!("'{}' must start with http:// or https://", value))
+    }
+}
+
+/// Library subcommands.
+#[derive(Subcommand, Clone, Debug)]
+pub enum LibraryCommands {
+    /// Index the media files under a directory
+    ///
+    /// Video IDs are read from the `.info.json` sidecar next to each file or,
+    /// when ffprobe is installed, from the source URL in its embedded tags.
+    Scan {
+        /// Directory to scan recursively
+        dir: PathBuf,
+    },
 }
 
 /// Configuration subcommands for managing application settings.
@@ -102,7 +426,11 @@ pub enum Commands {
 #[derive(Subcommand, Clone, Debug)]
 pub enum ConfigCommands {
     /// Display all current configuration settings
-    Show,
+    Show {
+        /// Show where each value came from (default, file, profile or env)
+        #[arg(long, default_value_t = false)]
+        origin: bool,
+    },
 
     /// Set a configuration value
     ///
@@ -125,6 +453,12 @@ pub enum ConfigCommands {
 
     /// Show the path to the configuration file
     Path,
+
+    /// Check the configuration files and report every problem with its line
+    Validate,
+
+    /// Open the configuration file in `$EDITOR`, saving it only if it is valid
+    Edit,
 }
 
 /// Video quality options for downloads.
@@ -167,7 +501,7 @@ pub enum VideoQuality {
     Q1440p,
 
     /// 4K resolution (2160p, highest quality)
-    #[value(name = "4k")]
+    #[value(name = "4k", alias = "2160p")]
     Q4k,
 
     /// Automatically select the best available quality (default)
@@ -178,73 +512,33 @@ pub enum VideoQuality {
     Worst,
 }
 
-/// Audio format options for audio extraction.
-///
-/// When downloading audio-only or extracting audio from videos,
-/// these formats are available. Conversion is performed using FFmpeg.
-///
-/// # Default
-///
-/// The default format is [`AudioFormat::Mp3`].
-#[derive(ValueEnum, Clone, Debug, Default)]
-pub enum AudioFormat {
-    /// MP3 format (lossy compression, widely compatible)
-    #[default]
-    Mp3,
-
-    /// M4A/AAC format (lossy compression, good quality)
-    M4a,
-
-    /// FLAC format (lossless compression, best quality)
-    Flac,
-
-    /// WAV format (uncompressed, largest file size)
-    Wav,
-
-    /// Opus format (modern lossy codec, good quality at low bitrates)
-    Opus,
-}
-
-/// Video container format options.
-///
-/// These are the container formats for video downloads. The actual
-/// video and audio codecs inside may vary depending on YouTube's source.
-///
-/// # Default
-///
-/// The default format is [`VideoFormat::Mp4`].
-#[derive(ValueEnum, Clone, Debug, Default)]
-pub enum VideoFormat {
-    /// MP4 container (widely compatible, recommended)
-    #[default]
-    Mp4,
-
-    /// Matroska container (supports more codecs and features)
-    Mkv,
-
-    /// WebM container (open format, good for web)
-    Webm,
-}
-
 /// Common arguments shared across multiple commands.
 ///
 /// These arguments are flattened into command structs to avoid repetition
 /// and ensure consistent behavior across download, audio, and other commands.
 #[derive(Args, Debug)]
 pub struct CommonArgs {
-    /// YouTube video URL to process
+    /// One or more YouTube video URLs to process
     ///
-    /// Accepts standard YouTube URLs in various formats:
+    /// Accepts YouTube URLs in various formats:
     /// - `https://youtube.com/watch?v=VIDEO_ID`
     /// - `https://youtu.be/VIDEO_ID`
-    /// - `https://www.youtube.com/watch?v=VIDEO_ID`
-    pub url: String,
+    /// - `https://www.youtube.com/shorts/VIDEO_ID`
+    /// - `https://m.youtube.com/...`, `https://music.youtube.com/...`
+    #[arg(required_unless_present = "batch_file", value_parser = parse_url)]
+    pub urls: Vec<String>,
+
+    /// Read URLs from a file, one per line (`-` reads from stdin)
+    ///
+    /// Blank lines and lines starting with `#`, `;` or `]` are ignored.
+    #[arg(short = 'a', long, value_name = "FILE")]
+    pub batch_file: Option<PathBuf>,
 
     /// Output directory for downloaded files
     ///
-    /// Defaults to current directory (`.`). Supports tilde expansion for home directory.
-    #[arg(short = 'o', long, default_value = ".")]
-    pub output: PathBuf,
+    /// Defaults to `general.output_dir`. Supports tilde expansion for home directory.
+    #[arg(short = 'o', long)]
+    pub output: Option<PathBuf>,
 
     /// Suppress progress bars and non-error output
     ///
@@ -258,6 +552,30 @@ pub struct CommonArgs {
     /// and FFmpeg operations.
     #[arg(short = 'v', long, default_value_t = false)]
     pub verbose: bool,
+
+    /// Show which `[[rules]]` entry matched each video and what it changed
+    #[arg(long, default_value_t = false)]
+    pub explain: bool,
+
+    /// Download only the videos matching EXPR, e.g. `duration >= 5m & upload_date = 2024`
+    ///
+    /// Fields: title, upload_date, duration, view_count, live_status and
+    /// availability. Repeat the flag to require several expressions.
+    #[arg(long, value_name = "EXPR", value_parser = parse_filter)]
+    pub filter: Vec<Filter>,
+}
+
+impl CommonArgs {
+    /// Returns the URLs given on the command line followed by those read
+    /// from `--batch-file`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch file cannot be read, contains a malformed
+    /// URL, or no URL was given.
+    pub fn resolve_urls(&self) -> AppResult<Vec<String>> {
+        resolve_urls(&self.urls, self.batch_file.as_deref())
+    }
 }
 
 /// Arguments for the `download` command.
@@ -273,14 +591,158 @@ pub struct DownloadArgs {
     /// Video quality/resolution to download
     ///
     /// If the requested quality is not available, the closest match will be selected.
-    #[arg(short = 'q', long, value_enum, default_value_t = VideoQuality::Best)]
-    pub quality: VideoQuality,
+    /// Defaults to `general.default_quality`.
+    #[arg(short = 'q', long, value_enum)]
+    pub quality: Option<VideoQuality>,
+
+    /// Explicit yt-dlp format ID(s) to download, bypassing quality presets
+    ///
+    /// Use `ytdl formats <url>` to list IDs. Combine a video and an audio
+    /// stream with `+` (e.g. `137+140`) or give fallbacks with `/` (e.g. `22/18`).
+    /// yt-dlp selectors such as `bv*+ba` are passed through.
+    #[arg(
+        long,
+        value_parser = parse_format_id,
+        conflicts_with_all = ["quality", "vcodec", "acodec", "fps", "hdr", "vertical"]
+    )]
+    pub format_id: Option<String>,
+
+    /// Codec, fps, HDR and orientation preferences
+    #[command(flatten)]
+    pub preference: PreferenceArgs,
 
     /// Video container format
     ///
     /// Determines the output file container. May require format conversion via FFmpeg.
-    #[arg(short = 'f', long, value_enum, default_value_t = VideoFormat::Mp4)]
-    pub format: VideoFormat,
+    /// Defaults to `video.format`.
+    #[arg(short = 'f', long, value_enum)]
+    pub format: Option<VideoFormat>,
+
+    /// Metadata files to write next to each download
+    #[command(flatten)]
+    pub sidecars: SidecarArgs,
+
+    /// Size limits checked before each download
+    #[command(flatten)]
+    pub sizes: SizeArgs,
+
+    /// Live stream and premiere options
+    #[command(flatten)]
+    pub live: LiveArgs,
+}
+
+/// Metadata files written next to each download for media servers.
+#[derive(Args, Debug, Default)]
+pub struct SidecarArgs {
+    /// Write `<name>.info.json` with the video's metadata (defaults to `video.write_info_json`)
+    #[arg(long, default_value_t = false)]
+    pub write_info_json: bool,
+
+    /// Write a Kodi/Jellyfin `<name>.nfo` (defaults to `video.write_nfo`)
+    ///
+    /// Files saved in a folder named after their channel get episode NFOs
+    /// and a `tvshow.nfo` for the folder.
+    #[arg(long, default_value_t = false)]
+    pub write_nfo: bool,
+}
+
+/// Size limits checked against each video's estimated size before it is
+/// downloaded.
+#[derive(Args, Clone, Debug, Default)]
+pub struct SizeArgs {
+    /// Skip videos estimated to be larger than SIZE (e.g. 500M, 2G)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size_arg)]
+    pub max_filesize: Option<u64>,
+
+    /// Download at most SIZE in total during this run (e.g. 10G)
+    ///
+    /// Videos that do not fit in what is left are skipped.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size_arg)]
+    pub max_total: Option<u64>,
+}
+
+/// Options for live streams and upcoming premieres.
+#[derive(Args, Clone, Debug, Default)]
+pub struct LiveArgs {
+    /// Record a live stream from its beginning instead of from now
+    #[arg(long, default_value_t = false, conflicts_with = "live_duration")]
+    pub live_from_start: bool,
+
+    /// Wait for an upcoming live stream or premiere to start, then download it
+    ///
+    /// Checks again at the scheduled start, then with a growing interval
+    /// (15 seconds up to 5 minutes) until the video begins.
+    #[arg(long, default_value_t = false)]
+    pub wait_for_video: bool,
+
+    /// Stop recording a live stream after MINUTES (requires FFmpeg)
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub live_duration: Option<u32>,
+}
+
+impl SizeArgs {
+    /// Returns the `--max-total` budget to share between the run's downloads.
+    pub fn budget(&self) -> Option<Arc<SizeBudget>> {
+        self.max_total.map(|limit| Arc::new(SizeBudget::new(limit)))
+    }
+}
+
+/// Stream preferences that refine the quality preset.
+///
+/// Every flag only reorders candidate streams, except `--hdr never` which
+/// excludes HDR streams. When nothing matches, the plain quality preset is used.
+#[derive(Args, Debug, Default)]
+pub struct PreferenceArgs {
+    /// Preferred video codecs, most preferred first (e.g. `av1,vp9,h264`)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub vcodec: Vec<VideoCodec>,
+
+    /// Preferred audio codecs, most preferred first (e.g. `opus,aac`)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub acodec: Vec<AudioCodec>,
+
+    /// Preferred maximum frame rate (e.g. `60` for 60 fps when available)
+    #[arg(long)]
+    pub fps: Option<u32>,
+
+    /// HDR handling
+    #[arg(long, value_enum, default_value_t = HdrMode::Any)]
+    pub hdr: HdrMode,
+
+    /// Prefer portrait (vertical) streams
+    #[arg(long, default_value_t = false)]
+    pub vertical: bool,
+}
+
+/// Keeps only the preferences given on the command line, so routing rules
+/// can still set the others.
+impl From<PreferenceArgs> for OptionOverrides {
+    fn from(args: PreferenceArgs) -> Self {
+        OptionOverrides {
+            video_codecs: Some(args.vcodec).filter(|codecs| !codecs.is_empty()),
+            audio_codecs: Some(args.acodec).filter(|codecs| !codecs.is_empty()),
+            fps: args.fps,
+            hdr: Some(args.hdr).filter(|hdr| *hdr != HdrMode::Any),
+            prefer_vertical: args.vertical.then_some(true),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<PreferenceArgs> for FormatPreference {
+    fn from(args: PreferenceArgs) -> Self {
+        FormatPreference {
+            video_codecs: args.vcodec,
+            audio_codecs: args.acodec,
+            fps: args.fps,
+            hdr: args.hdr,
+            prefer_vertical: args.vertical,
+        }
+    }
 }
 
 /// Arguments for the `audio` command.
@@ -296,8 +758,21 @@ pub struct AudioArgs {
     /// Audio format for the extracted audio
     ///
     /// All audio is extracted and converted using FFmpeg to ensure consistent quality.
-    #[arg(short = 'f', long, value_enum, default_value_t = AudioFormat::Mp3)]
-    pub format: AudioFormat,
+    /// Defaults to `audio.format`.
+    #[arg(short = 'f', long, value_enum)]
+    pub format: Option<AudioFormat>,
+
+    /// Metadata files to write next to each download
+    #[command(flatten)]
+    pub sidecars: SidecarArgs,
+
+    /// Size limits checked before each download
+    #[command(flatten)]
+    pub sizes: SizeArgs,
+
+    /// Live stream and premiere options
+    #[command(flatten)]
+    pub live: LiveArgs,
 }
 
 /// Arguments for the `playlist` command.
@@ -310,22 +785,27 @@ pub struct PlaylistArgs {
     ///
     /// Accepts standard YouTube playlist URLs:
     /// - `https://youtube.com/playlist?list=PLAYLIST_ID`
-    #[arg(required = true, num_args = 1..)]
+    #[arg(required = true, num_args = 1.., value_parser = parse_url)]
     pub urls: Vec<String>,
 
     /// Output directory for downloaded files
     ///
     /// All playlist videos will be saved to this directory.
-    #[arg(short = 'o', long, default_value = ".")]
-    pub output: PathBuf,
+    /// Defaults to `general.output_dir`.
+    #[arg(short = 'o', long)]
+    pub output: Option<PathBuf>,
+
+    /// Video quality/resolution for video downloads (defaults to `general.default_quality`)
+    #[arg(short = 'q', long, value_enum)]
+    pub quality: Option<VideoQuality>,
 
-    /// Video quality/resolution for video downloads
-    #[arg(short = 'q', long, value_enum, default_value_t = VideoQuality::Best)]
-    pub quality: VideoQuality,
+    /// Video container format for video downloads (defaults to `video.format`)
+    #[arg(short = 'f', long, value_enum)]
+    pub format: Option<VideoFormat>,
 
-    /// Video container format for video downloads
-    #[arg(short = 'f', long, value_enum, default_value_t = VideoFormat::Mp4)]
-    pub format: VideoFormat,
+    /// Codec, fps, HDR and orientation preferences
+    #[command(flatten)]
+    pub preference: PreferenceArgs,
 
     /// Download only audio from playlist videos
     ///
@@ -333,9 +813,41 @@ pub struct PlaylistArgs {
     #[arg(long, default_value_t = false)]
     pub audio_only: bool,
 
-    /// Audio format when `--audio-only` is enabled
-    #[arg(long, value_enum, default_value_t = AudioFormat::Mp3)]
-    pub audio_format: AudioFormat,
+    /// Audio format when `--audio-only` is enabled (defaults to `audio.format`)
+    #[arg(long, value_enum)]
+    pub audio_format: Option<AudioFormat>,
+
+    /// Metadata files to write next to each download
+    #[command(flatten)]
+    pub sidecars: SidecarArgs,
+
+    /// Size limits checked before each download
+    #[command(flatten)]
+    pub sizes: SizeArgs,
+
+    /// Don't write `<playlist title>.m3u8` to the output directory
+    #[arg(long, default_value_t = false)]
+    pub no_m3u: bool,
+
+    /// Save each playlist in a folder named after it, numbering the files
+    ///
+    /// Files are prefixed with their playlist position, e.g.
+    /// `Rust Talks/07 - Title.mp4`. Files of videos that moved in the
+    /// playlist since an earlier run are renamed to their new position.
+    #[arg(long, default_value_t = false)]
+    pub playlist_folder: bool,
+
+    /// Compare the playlist with the previous run and report added, removed
+    /// and unavailable videos
+    ///
+    /// A snapshot of the playlist is saved on every run. Unavailable videos
+    /// are not downloaded. Local files are never deleted.
+    #[arg(long, default_value_t = false)]
+    pub mirror: bool,
+
+    /// With `--mirror`, move files of removed and unavailable videos to `_removed/`
+    #[arg(long, default_value_t = false, requires = "mirror")]
+    pub move_removed: bool,
 
     /// Suppress progress bars and non-error output
     #[arg(short = 's', long, default_value_t = false)]
@@ -344,6 +856,39 @@ pub struct PlaylistArgs {
     /// Enable verbose logging output
     #[arg(short = 'v', long, default_value_t = false)]
     pub verbose: bool,
+
+    /// Show which `[[rules]]` entry matched each video and what it changed
+    #[arg(long, default_value_t = false)]
+    pub explain: bool,
+
+    /// Playlist items to download, e.g. `1-10,25,-5:`
+    ///
+    /// Positions start at 1 and negative positions count from the end.
+    /// `START:STOP:STEP` selects a slice: `-5:` is the last five items,
+    /// `::2` every other item.
+    #[arg(long, value_name = "SPEC", value_parser = parse_items)]
+    pub items: Option<ItemSpec>,
+
+    /// Download the selected items in reverse order
+    #[arg(long, default_value_t = false, conflicts_with = "shuffle")]
+    pub reverse: bool,
+
+    /// Shuffle the playlist before `--items` picks from it
+    ///
+    /// `--shuffle --items 1-5` downloads five random videos.
+    #[arg(long, default_value_t = false)]
+    pub shuffle: bool,
+
+    /// Seed for `--shuffle`, to repeat the same order
+    #[arg(long, requires = "shuffle")]
+    pub seed: Option<u64>,
+
+    /// Download only the videos matching EXPR, e.g. `duration >= 5m & upload_date = 2024`
+    ///
+    /// Applied after `--items`. Fields the playlist listing lacks are
+    /// fetched per video, only when the expression needs them.
+    #[arg(long, value_name = "EXPR", value_parser = parse_filter)]
+    pub filter: Vec<Filter>,
 }
 
 /// Arguments for the `info` command.
@@ -352,28 +897,178 @@ pub struct PlaylistArgs {
 /// Useful for inspecting available formats, qualities, and video information.
 #[derive(Args, Debug)]
 pub struct InfoArgs {
-    /// YouTube video URL to retrieve information about
-    pub url: String,
+    /// One or more YouTube video URLs to retrieve information about
+    #[arg(required_unless_present = "batch_file", value_parser = parse_url)]
+    pub urls: Vec<String>,
+
+    /// Read URLs from a file, one per line (`-` reads from stdin)
+    #[arg(short = 'a', long, value_name = "FILE")]
+    pub batch_file: Option<PathBuf>,
 }
 
-/// Arguments for the `config` command (deprecated in favor of ConfigCommands).
+impl InfoArgs {
+    /// Returns the URLs given on the command line followed by those read
+    /// from `--batch-file`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch file cannot be read, contains a malformed
+    /// URL, or no URL was given.
+    pub fn resolve_urls(&self) -> AppResult<Vec<String>> {
+        resolve_urls(&self.urls, self.batch_file.as_deref())
+    }
+}
+
+/// Combines positional URLs with the contents of an optional batch file.
 ///
-/// This struct exists for backward compatibility but is not currently used.
-/// Use [`ConfigCommands`] instead for configuration management.
-#[derive(Args, Debug)]
-pub struct ConfigArgs {
-    /// Set a configuration value (deprecated)
-    #[arg(short = 's', long)]
-    pub set: Option<String>,
+/// Batch file lines are validated like positional URLs, so the whole batch
+/// is rejected before anything is downloaded.
+fn resolve_urls(urls: &[String], batch_file: Option<&Path>) -> AppResult<Vec<String>> {
+    let mut all = urls.to_vec();
+
+    if let Some(path) = batch_file {
+        for url in read_batch_file(path)? {
+            all.push(validate_url(&url)?);
+        }
+    }
 
-    /// Get a configuration value (deprecated)
-    #[arg(short = 'g', long)]
-    pub get: Option<String>,
+    if all.is_empty() {
+        return Err(AppError::invalid_arg("batch-file", "no URLs found"));
+    }
+
+    Ok(all)
 }
 
-// ==================================================
-//          UNITARY TESTS
-// ==================================================
+/// Arguments for the `formats` command.
+///
+/// Lists every stream yt-dlp reports for a video, so a specific one can be
+/// picked with `download --format-id`.
+#[derive(Args, Debug)]
+pub struct FormatsArgs {
+    /// YouTube video URL to list formats for
+    #[arg(value_parser = parse_url)]
+    pub url: String,
+
+    /// Column to sort the table by
+    #[arg(long, value_enum, default_value_t = StreamSortKey::Resolution)]
+    pub sort: StreamSortKey,
+
+    /// Reverse the sort order
+    #[arg(short = 'r', long, default_value_t = false)]
+    pub reverse: bool,
+}
+
+/// Validates a URL argument, rewriting YouTube URLs to their canonical form.
+///
+/// Other sites are kept as given, since yt-dlp supports many of them.
+fn parse_url(value: &str) -> Result<String, String> {
+    validate_url(value).map_err(|e| e.to_string())
+}
+
+/// Parses a `--items` value.
+fn parse_items(value: &str) -> Result<ItemSpec, String> {
+    value.parse().map_err(|e: AppError| e.to_string())
+}
+
+/// Parses `--max-filesize` and `--max-total` with [`parse_size`].
+fn parse_size_arg(value: &str) -> Result<u64, String> {
+    parse_size(value).map_err(|e| match e {
+        AppError::InvalidArgument { message, .. } => message,
+        other => other.to_string(),
+    })
+}
+
+/// Parses a `--filter` expression.
+fn parse_filter(value: &str) -> Result<Filter, String> {
+    value.parse().map_err(|e: AppError| e.to_string())
+}
+
+/// Validates a `--format-id` value.
+///
+/// Accepts one or more format IDs or yt-dlp selectors (e.g. `bv*` or
+/// `best[height<=720]`) joined with `+` (merge) or `/` (fallback). Only
+/// empty parts are rejected; the rest is checked against the video's formats
+/// before downloading, or left to yt-dlp.
+fn parse_format_id(value: &str) -> Result<String, String> {
+    let valid = value.split(['+', '/']).all(|id| !id.trim().is_empty());
+
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "invalid format ID '{}' (expected e.g. 137, 137+140 or 22/18)",
+            value
+        ))
+    }
+}
+
+/// Arguments for the `history` command.
+///
+/// Without a subcommand the matching entries are listed, oldest first.
+#[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub command: Option<HistoryCommands>,
+
+    /// Only show entries whose title, channel, URL, ID or path contains TEXT
+    #[arg(long, value_name = "TEXT")]
+    pub search: Option<String>,
+
+    /// Only show entries since DATE (YYYY-MM-DD, RFC 3339, or e.g. 12h, 7d, 2w)
+    #[arg(long, value_name = "DATE", value_parser = parse_history_date)]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only show failed downloads
+    #[arg(long, default_value_t = false)]
+    pub failed: bool,
+
+    /// Print the entries as a JSON array
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+/// History subcommands.
+#[derive(Subcommand, Clone, Debug)]
+pub enum HistoryCommands {
+    /// Remove entries whose file was deleted and failures that later succeeded
+    Prune {
+        /// Also remove every entry recorded before DATE
+        #[arg(long, value_name = "DATE", value_parser = parse_history_date)]
+        before: Option<DateTime<Utc>>,
+
+        /// Remove every entry
+        #[arg(long, default_value_t = false, conflicts_with = "before")]
+        all: bool,
+    },
+}
+
+/// Parses `--since` and `--before` with [`parse_date`].
+fn parse_history_date(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_date(value).map_err(|e| match e {
+        AppError::InvalidArgument { message, .. } => message,
+        other => other.to_string(),
+    })
+}
+
+/// Arguments for the `config` command (deprecated in favor of ConfigCommands).
+///
+/// This struct exists for backward compatibility but is not currently used.
+/// Use [`ConfigCommands`] instead for configuration management.
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    /// Set a configuration value (deprecated)
+    #[arg(short = 's', long)]
+    pub set: Option<String>,
+
+    /// Get a configuration value (deprecated)
+    #[arg(short = 'g', long)]
+    pub get: Option<String>,
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
 
 #[cfg(test)]
 mod tests {
@@ -389,7 +1084,10 @@ mod tests {
 
         match cli.unwrap().command {
             Commands::Download(args) => {
-                assert_eq!(args.common.url, "https://youtube.com/watch?v=abc123");
+                assert_eq!(
+                    args.common.urls,
+                    vec!["https://www.youtube.com/watch?v=abc123"]
+                );
             }
             _ => panic!("Expected Download command"),
         }
@@ -402,9 +1100,10 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Best));
-                assert!(matches!(args.format, VideoFormat::Mp4));
-                assert_eq!(args.common.output, PathBuf::from("."));
+                // Omitted flags fall back to the configuration
+                assert!(args.quality.is_none());
+                assert!(args.format.is_none());
+                assert!(args.common.output.is_none());
                 assert!(!args.common.silence);
                 assert!(!args.common.verbose);
             }
@@ -412,6 +1111,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_download_sidecar_flags() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "--write-info-json",
+            "--write-nfo",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                assert!(args.sidecars.write_info_json);
+                assert!(args.sidecars.write_nfo);
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
     #[test]
     fn test_download_with_quality() {
         let cli = Cli::try_parse_from([
@@ -425,7 +1144,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q1080p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q1080p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -444,7 +1163,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q720p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q720p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -463,7 +1182,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.format, VideoFormat::Mkv));
+                assert!(matches!(args.format, Some(VideoFormat::Mkv)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -482,7 +1201,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert_eq!(args.common.output, PathBuf::from("/home/user/videos"));
+                assert_eq!(args.common.output, Some(PathBuf::from("/home/user/videos")));
             }
             _ => panic!("Expected Download command"),
         }
@@ -507,10 +1226,13 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert_eq!(args.common.url, "https://youtube.com/watch?v=abc123");
-                assert!(matches!(args.quality, VideoQuality::Q1080p));
-                assert!(matches!(args.format, VideoFormat::Mkv));
-                assert_eq!(args.common.output, PathBuf::from("/downloads"));
+                assert_eq!(
+                    args.common.urls,
+                    vec!["https://www.youtube.com/watch?v=abc123"]
+                );
+                assert!(matches!(args.quality, Some(VideoQuality::Q1080p)));
+                assert!(matches!(args.format, Some(VideoFormat::Mkv)));
+                assert_eq!(args.common.output, Some(PathBuf::from("/downloads")));
                 assert!(args.common.silence);
                 assert!(args.common.verbose);
             }
@@ -543,298 +1265,1271 @@ mod tests {
             "download",
             "https://youtube.com/watch?v=abc123",
             "-f",
-            "avi",
+            "flv",
+        ]);
+        assert!(result.is_err());
+    }
+
+    // ============== Audio Command Tests ==============
+
+    #[test]
+    fn test_audio_minimal_args() {
+        let cli = Cli::try_parse_from(["ytdl", "audio", "https://youtube.com/watch?v=abc123"]);
+        assert!(cli.is_ok());
+
+        match cli.unwrap().command {
+            Commands::Audio(args) => {
+                assert_eq!(
+                    args.common.urls,
+                    vec!["https://www.youtube.com/watch?v=abc123"]
+                );
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_default_format() {
+        let cli =
+            Cli::try_parse_from(["ytdl", "audio", "https://youtube.com/watch?v=abc123"]).unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(args.format.is_none());
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_with_format_mp3() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "mp3",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(matches!(args.format, Some(AudioFormat::Mp3)));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_with_format_flac() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "flac",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(matches!(args.format, Some(AudioFormat::Flac)));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_with_format_m4a() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "m4a",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(matches!(args.format, Some(AudioFormat::M4a)));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_with_format_wav() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "wav",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(matches!(args.format, Some(AudioFormat::Wav)));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_with_format_opus() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "opus",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(matches!(args.format, Some(AudioFormat::Opus)));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_with_format_aac() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "aac",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(matches!(args.format, Some(AudioFormat::Aac)));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_with_format_ogg() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "ogg",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(matches!(args.format, Some(AudioFormat::Ogg)));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_audio_missing_url_fails() {
+        let result = Cli::try_parse_from(["ytdl", "audio"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_audio_invalid_format_fails() {
+        let result = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "wma",
         ]);
         assert!(result.is_err());
     }
 
-    // ============== Audio Command Tests ==============
+    // ============== Playlist Command Tests ==============
+
+    #[test]
+    fn test_playlist_single_url() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+        ]);
+        assert!(cli.is_ok());
+
+        match cli.unwrap().command {
+            Commands::Playlist(args) => {
+                assert_eq!(args.urls.len(), 1);
+                assert_eq!(args.urls[0], "https://www.youtube.com/playlist?list=PL123");
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_multiple_urls() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "https://youtube.com/playlist?list=PL456",
+            "https://youtube.com/playlist?list=PL789",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert_eq!(args.urls.len(), 3);
+                assert_eq!(args.urls[0], "https://www.youtube.com/playlist?list=PL123");
+                assert_eq!(args.urls[1], "https://www.youtube.com/playlist?list=PL456");
+                assert_eq!(args.urls[2], "https://www.youtube.com/playlist?list=PL789");
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_item_selection() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--items",
+            "1-10,25,-5:",
+            "--shuffle",
+            "--seed",
+            "42",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert_eq!(args.items, Some("1-10,25,-5:".parse().unwrap()));
+                assert!(args.shuffle);
+                assert!(!args.reverse);
+                assert_eq!(args.seed, Some(42));
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_item_selection_invalid() {
+        let url = "https://youtube.com/playlist?list=PL123";
+
+        assert!(Cli::try_parse_from(["ytdl", "playlist", url, "--items", "0"]).is_err());
+        assert!(Cli::try_parse_from(["ytdl", "playlist", url, "--reverse", "--shuffle"]).is_err());
+        assert!(Cli::try_parse_from(["ytdl", "playlist", url, "--seed", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_playlist_default_values() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert!(args.quality.is_none());
+                assert!(args.format.is_none());
+                assert!(!args.audio_only);
+                assert!(args.audio_format.is_none());
+                assert!(args.output.is_none());
+                assert!(!args.no_m3u);
+                assert!(!args.playlist_folder);
+                assert!(!args.mirror);
+                assert!(!args.move_removed);
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_no_m3u_flag() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--no-m3u",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => assert!(args.no_m3u),
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_folder_flag() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--playlist-folder",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => assert!(args.playlist_folder),
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_mirror_flags() {
+        let url = "https://youtube.com/playlist?list=PL123";
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            url,
+            "--mirror",
+            "--move-removed",
+            "--dry-run",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.dry_run, Some(PlanFormat::Human));
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert!(args.mirror);
+                assert!(args.move_removed);
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+
+        assert!(Cli::try_parse_from(["ytdl", "playlist", url, "--move-removed"]).is_err());
+    }
+
+    #[test]
+    fn test_playlist_with_audio_only() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--audio-only",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert!(args.audio_only);
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_with_audio_only_and_format() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--audio-only",
+            "--audio-format",
+            "flac",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert!(args.audio_only);
+                assert!(matches!(args.audio_format, Some(AudioFormat::Flac)));
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_with_quality() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "-q",
+            "480p",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert!(matches!(args.quality, Some(VideoQuality::Q480p)));
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_missing_url_fails() {
+        let result = Cli::try_parse_from(["ytdl", "playlist"]);
+        assert!(result.is_err());
+    }
+
+    // ============== Info Command Tests ==============
+
+    #[test]
+    fn test_info_command() {
+        let cli = Cli::try_parse_from(["ytdl", "info", "https://youtube.com/watch?v=abc123"]);
+        assert!(cli.is_ok());
+
+        match cli.unwrap().command {
+            Commands::Info(args) => {
+                assert_eq!(args.urls, vec!["https://www.youtube.com/watch?v=abc123"]);
+            }
+            _ => panic!("Expected Info command"),
+        }
+    }
+
+    #[test]
+    fn test_info_missing_url_fails() {
+        let result = Cli::try_parse_from(["ytdl", "info"]);
+        assert!(result.is_err());
+    }
+
+    // ============== Profile Flag Tests ==============
+
+    #[test]
+    fn test_profile_flag_defaults_to_none() {
+        let cli = Cli::try_parse_from(["ytdl", "info", "https://youtu.be/abc"]).unwrap();
+        assert!(cli.profile.is_none());
+    }
+
+    #[test]
+    fn test_profile_flag_before_and_after_subcommand() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "--profile",
+            "podcast",
+            "audio",
+            "https://youtu.be/abc",
+        ])
+        .unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("podcast"));
+
+        let cli = Cli::try_parse_from(["ytdl", "config", "show", "--profile", "mobile"]).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("mobile"));
+    }
+
+    #[test]
+    fn test_config_flag_is_global() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtu.be/abc",
+            "--config",
+            "/tmp/ytdl.toml",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.config, Some(PathBuf::from("/tmp/ytdl.toml")));
+    }
+
+    #[test]
+    fn test_config_show_origin_flag() {
+        let cli = Cli::try_parse_from(["ytdl", "config", "show", "--origin"]).unwrap();
+
+        match cli.command {
+            Commands::Config { command } => {
+                assert!(matches!(command, ConfigCommands::Show { origin: true }));
+            }
+            _ => panic!("Expected Config command"),
+        }
+    }
+
+    #[test]
+    fn test_config_validate_and_edit() {
+        let cli = Cli::try_parse_from(["ytdl", "config", "validate"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::Validate
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["ytdl", "config", "edit"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::Edit
+            }
+        ));
+    }
+
+    // ============== Batch Input Tests ==============
+
+    #[test]
+    fn test_download_multiple_urls() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtu.be/aaa",
+            "https://youtu.be/bbb",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                assert_eq!(
+                    args.common.resolve_urls().unwrap(),
+                    vec![
+                        "https://www.youtube.com/watch?v=aaa",
+                        "https://www.youtube.com/watch?v=bbb"
+                    ]
+                );
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_batch_file_without_positional_urls() {
+        let cli = Cli::try_parse_from(["ytdl", "audio", "-a", "urls.txt"]).unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => {
+                assert!(args.common.urls.is_empty());
+                assert_eq!(args.common.batch_file, Some(PathBuf::from("urls.txt")));
+            }
+            _ => panic!("Expected Audio command"),
+        }
+    }
+
+    #[test]
+    fn test_urls_are_canonicalized() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://m.youtube.com/shorts/dQw4w9WgXcQ?si=tracking",
+            "https://vimeo.com/123",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                assert_eq!(
+                    args.common.urls,
+                    vec![
+                        "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+                        "https://vimeo.com/123"
+                    ]
+                );
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_malformed_url_rejected() {
+        let result = Cli::try_parse_from(["ytdl", "download", "not-a-valid-url"]);
+        assert!(result.is_err());
+
+        let result = Cli::try_parse_from(["ytdl", "info", "https://www.youtube.com/watch?v="]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_batch_file_with_malformed_url() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("urls.txt");
+        std::fs::write(
+            &path,
+            "https://youtu.be/aaa
+--exec=id
+",
+        )
+        .unwrap();
+
+        let cli =
+            Cli::try_parse_from(["ytdl", "info", "--batch-file", path.to_str().unwrap()]).unwrap();
+
+        match cli.command {
+            Commands::Info(args) => {
+                assert!(matches!(
+                    args.resolve_urls(),
+                    Err(AppError::InvalidUrl(url)) if url == "--exec=id"
+                ));
+            }
+            _ => panic!("Expected Info command"),
+        }
+    }
+
+    #[test]
+    fn test_batch_file_combined_with_urls() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("urls.txt");
+        std::fs::write(
+            &path,
+            "# queue\nhttps://youtu.be/bbb\n\nhttps://youtu.be/ccc\n",
+        )
+        .unwrap();
+
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "info",
+            "https://youtu.be/aaa",
+            "--batch-file",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Info(args) => {
+                assert_eq!(
+                    args.resolve_urls().unwrap(),
+                    vec![
+                        "https://www.youtube.com/watch?v=aaa",
+                        "https://www.youtube.com/watch?v=bbb",
+                        "https://www.youtube.com/watch?v=ccc"
+                    ]
+                );
+            }
+            _ => panic!("Expected Info command"),
+        }
+    }
+
+    #[test]
+    fn test_empty_batch_file_fails_to_resolve() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("urls.txt");
+        std::fs::write(&path, "# nothing yet\n").unwrap();
+
+        let cli = Cli::try_parse_from(["ytdl", "download", "-a", path.to_str().unwrap()]).unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                let result = args.common.resolve_urls();
+                assert!(matches!(result, Err(AppError::InvalidArgument { .. })));
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    // ============== Preference Flag Tests ==============
+
+    #[test]
+    fn test_download_preference_defaults() {
+        let cli = Cli::try_parse_from(["ytdl", "download", "https://youtube.com/watch?v=abc123"])
+            .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                let pref = FormatPreference::from(args.preference);
+                assert!(pref.is_empty());
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_download_preference_flags() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "--vcodec",
+            "av1,vp9,h264",
+            "--acodec",
+            "opus",
+            "--fps",
+            "60",
+            "--hdr",
+            "never",
+            "--vertical",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                let pref = FormatPreference::from(args.preference);
+                assert_eq!(
+                    pref.video_codecs,
+                    vec![VideoCodec::Av1, VideoCodec::Vp9, VideoCodec::H264]
+                );
+                assert_eq!(pref.audio_codecs, vec![AudioCodec::Opus]);
+                assert_eq!(pref.fps, Some(60));
+                assert_eq!(pref.hdr, HdrMode::Never);
+                assert!(pref.prefer_vertical);
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_preference_overrides_only_given_flags() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "--acodec",
+            "aac",
+            "--hdr",
+            "prefer",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                let overrides = OptionOverrides::from(args.preference);
+                assert_eq!(overrides.keys(), vec!["acodec", "hdr"]);
+                assert_eq!(overrides.audio_codecs, Some(vec![AudioCodec::Aac]));
+                assert_eq!(overrides.hdr, Some(HdrMode::Prefer));
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_download_invalid_vcodec_fails() {
+        let result = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "--vcodec",
+            "hevc",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_download_preference_conflicts_with_format_id() {
+        let result = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "--format-id",
+            "137",
+            "--vcodec",
+            "av1",
+        ]);
+        assert!(result.is_err());
+    }
+
+    // ============== Explain Flag Tests ==============
+
+    #[test]
+    fn test_explain_flag() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "audio",
+            "https://youtube.com/watch?v=abc123",
+            "--explain",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Audio(args) => assert!(args.common.explain),
+            _ => panic!("Expected Audio command"),
+        }
+
+        let cli = Cli::try_parse_from(["ytdl", "download", "https://youtube.com/watch?v=abc123"])
+            .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => assert!(!args.common.explain),
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_explain_flag() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--explain",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => assert!(args.explain),
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    #[test]
+    fn test_playlist_preference_flags() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--fps",
+            "30",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Playlist(args) => assert_eq!(args.preference.fps, Some(30)),
+            _ => panic!("Expected Playlist command"),
+        }
+    }
+
+    // ============== Filter Flag Tests ==============
+
+    #[test]
+    fn test_filter_flags() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtu.be/aaa",
+            "--filter",
+            "upload_date = 2024 & duration >= 5m",
+            "--filter",
+            "title !~= '#shorts'",
+            "--dry-run",
+        ])
+        .unwrap();
+
+        assert!(cli.dry_run.is_some());
+        match cli.command {
+            Commands::Download(args) => {
+                assert_eq!(args.common.filter.len(), 2);
+                assert_eq!(args.common.filter[1].to_string(), "title !~= '#shorts'");
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_filter_flags_on_playlist_and_sync() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "playlist",
+            "https://youtube.com/playlist?list=PL123",
+            "--items",
+            "1-20",
+            "--filter",
+            "availability != subscriber_only",
+            "--dry-run",
+        ])
+        .unwrap();
+        assert!(cli.dry_run.is_some());
+        match cli.command {
+            Commands::Playlist(args) => {
+                assert_eq!(args.filter.len(), 1);
+            }
+            _ => panic!("Expected Playlist command"),
+        }
+
+        let cli =
+            Cli::try_parse_from(["ytdl", "sync", "--filter", "live_status = not_live"]).unwrap();
+        assert!(cli.dry_run.is_none());
+        match cli.command {
+            Commands::Sync(args) => {
+                assert_eq!(args.filter.len(), 1);
+            }
+            _ => panic!("Expected Sync command"),
+        }
+    }
+
+    #[test]
+    fn test_filter_flags_invalid() {
+        let url = "https://youtu.be/aaa";
+
+        let result = Cli::try_parse_from(["ytdl", "download", url, "--filter", "likes > 5"]);
+        assert!(result
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("unknown field 'likes'"));
+        assert!(
+            Cli::try_parse_from(["ytdl", "audio", url, "--filter", "duration > soon"]).is_err()
+        );
+    }
+
+    // ============== Dry Run Tests ==============
 
     #[test]
-    fn test_audio_minimal_args() {
-        let cli = Cli::try_parse_from(["ytdl", "audio", "https://youtube.com/watch?v=abc123"]);
-        assert!(cli.is_ok());
+    fn test_dry_run_formats() {
+        let url = "https://youtu.be/aaaaaaaaaaa";
 
-        match cli.unwrap().command {
-            Commands::Audio(args) => {
-                assert_eq!(args.common.url, "https://youtube.com/watch?v=abc123");
-            }
-            _ => panic!("Expected Audio command"),
-        }
+        let cli = Cli::try_parse_from(["ytdl", "download", url]).unwrap();
+        assert_eq!(cli.dry_run, None);
+
+        let cli = Cli::try_parse_from(["ytdl", "--dry-run", "download", url]).unwrap();
+        assert_eq!(cli.dry_run, Some(PlanFormat::Human));
+
+        let cli = Cli::try_parse_from(["ytdl", "audio", url, "--dry-run=json"]).unwrap();
+        assert_eq!(cli.dry_run, Some(PlanFormat::Json));
+
+        let cli = Cli::try_parse_from(["ytdl", "sync", "--simulate"]).unwrap();
+        assert_eq!(cli.dry_run, Some(PlanFormat::Human));
+
+        // The URL is not taken as the format
+        let cli = Cli::try_parse_from(["ytdl", "download", "--dry-run", url]).unwrap();
+        assert_eq!(cli.dry_run, Some(PlanFormat::Human));
+
+        assert!(Cli::try_parse_from(["ytdl", "download", url, "--dry-run=xml"]).is_err());
     }
 
     #[test]
-    fn test_audio_default_format() {
-        let cli =
-            Cli::try_parse_from(["ytdl", "audio", "https://youtube.com/watch?v=abc123"]).unwrap();
+    fn test_live_args() {
+        let url = "https://youtu.be/aaaaaaaaaaa";
 
+        let cli = Cli::try_parse_from(["ytdl", "download", url]).unwrap();
         match cli.command {
-            Commands::Audio(args) => {
-                assert!(matches!(args.format, AudioFormat::Mp3));
+            Commands::Download(args) => {
+                assert!(!args.live.live_from_start);
+                assert!(!args.live.wait_for_video);
+                assert_eq!(args.live.live_duration, None);
             }
-            _ => panic!("Expected Audio command"),
+            _ => panic!("Expected Download command"),
         }
-    }
 
-    #[test]
-    fn test_audio_with_format_mp3() {
         let cli = Cli::try_parse_from([
             "ytdl",
             "audio",
-            "https://youtube.com/watch?v=abc123",
-            "-f",
-            "mp3",
+            url,
+            "--wait-for-video",
+            "--live-duration",
+            "90",
         ])
         .unwrap();
-
         match cli.command {
             Commands::Audio(args) => {
-                assert!(matches!(args.format, AudioFormat::Mp3));
+                assert!(args.live.wait_for_video);
+                assert_eq!(args.live.live_duration, Some(90));
             }
             _ => panic!("Expected Audio command"),
         }
-    }
 
-    #[test]
-    fn test_audio_with_format_flac() {
-        let cli = Cli::try_parse_from([
+        assert!(Cli::try_parse_from(["ytdl", "download", url, "--live-from-start"]).is_ok());
+        assert!(Cli::try_parse_from(["ytdl", "download", url, "--live-duration", "0"]).is_err());
+        assert!(Cli::try_parse_from([
             "ytdl",
-            "audio",
-            "https://youtube.com/watch?v=abc123",
-            "-f",
-            "flac",
+            "download",
+            url,
+            "--live-from-start",
+            "--live-duration",
+            "30"
         ])
-        .unwrap();
+        .is_err());
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let url = "https://youtu.be/aaaaaaaaaaa";
 
+        let cli = Cli::try_parse_from(["ytdl", "download", url, "--max-filesize", "500M"]).unwrap();
         match cli.command {
-            Commands::Audio(args) => {
-                assert!(matches!(args.format, AudioFormat::Flac));
+            Commands::Download(args) => {
+                assert_eq!(args.sizes.max_filesize, Some(500 * 1024 * 1024));
+                assert_eq!(args.sizes.max_total, None);
+                assert!(args.sizes.budget().is_none());
             }
-            _ => panic!("Expected Audio command"),
+            _ => panic!("Expected Download command"),
         }
+
+        let cli = Cli::try_parse_from(["ytdl", "sync", "--max-total", "10G"]).unwrap();
+        match cli.command {
+            Commands::Sync(args) => {
+                let budget = args.sizes.budget().unwrap();
+                assert_eq!(budget.limit(), 10 * 1024 * 1024 * 1024);
+            }
+            _ => panic!("Expected Sync command"),
+        }
+
+        for command in ["audio", "playlist"] {
+            assert!(Cli::try_parse_from(["ytdl", command, url, "--max-total", "2GB"]).is_ok());
+        }
+        assert!(Cli::try_parse_from(["ytdl", "download", url, "--max-filesize", "big"]).is_err());
     }
 
+    // ============== Formats Command Tests ==============
+
     #[test]
-    fn test_audio_with_format_m4a() {
+    fn test_formats_command_defaults() {
+        let cli =
+            Cli::try_parse_from(["ytdl", "formats", "https://www.youtube.com/watch?v=abc123"])
+                .unwrap();
+
+        match cli.command {
+            Commands::Formats(args) => {
+                assert_eq!(args.url, "https://www.youtube.com/watch?v=abc123");
+                assert_eq!(args.sort, StreamSortKey::Resolution);
+                assert!(!args.reverse);
+            }
+            _ => panic!("Expected Formats command"),
+        }
+    }
+
+    #[test]
+    fn test_formats_sort_and_reverse() {
         let cli = Cli::try_parse_from([
             "ytdl",
-            "audio",
+            "formats",
             "https://youtube.com/watch?v=abc123",
-            "-f",
-            "m4a",
+            "--sort",
+            "size",
+            "--reverse",
         ])
         .unwrap();
 
         match cli.command {
-            Commands::Audio(args) => {
-                assert!(matches!(args.format, AudioFormat::M4a));
+            Commands::Formats(args) => {
+                assert_eq!(args.sort, StreamSortKey::Size);
+                assert!(args.reverse);
             }
-            _ => panic!("Expected Audio command"),
+            _ => panic!("Expected Formats command"),
         }
     }
 
     #[test]
-    fn test_audio_with_format_wav() {
-        let cli = Cli::try_parse_from([
+    fn test_formats_invalid_sort_fails() {
+        let result = Cli::try_parse_from([
             "ytdl",
-            "audio",
+            "formats",
             "https://youtube.com/watch?v=abc123",
-            "-f",
-            "wav",
-        ])
-        .unwrap();
+            "--sort",
+            "color",
+        ]);
+        assert!(result.is_err());
+    }
+
+    // ============== History Command Tests ==============
+
+    #[test]
+    fn test_history_defaults() {
+        let cli = Cli::try_parse_from(["ytdl", "history"]).unwrap();
 
         match cli.command {
-            Commands::Audio(args) => {
-                assert!(matches!(args.format, AudioFormat::Wav));
+            Commands::History(args) => {
+                assert!(args.command.is_none());
+                assert!(args.search.is_none());
+                assert!(args.since.is_none());
+                assert!(!args.failed);
+                assert!(!args.json);
             }
-            _ => panic!("Expected Audio command"),
+            _ => panic!("Expected History command"),
         }
     }
 
     #[test]
-    fn test_audio_with_format_opus() {
+    fn test_history_filters() {
         let cli = Cli::try_parse_from([
             "ytdl",
-            "audio",
-            "https://youtube.com/watch?v=abc123",
-            "-f",
-            "opus",
+            "history",
+            "--search",
+            "rust",
+            "--since",
+            "2024-05-01",
+            "--failed",
+            "--json",
         ])
         .unwrap();
 
         match cli.command {
-            Commands::Audio(args) => {
-                assert!(matches!(args.format, AudioFormat::Opus));
+            Commands::History(args) => {
+                assert_eq!(args.search.as_deref(), Some("rust"));
+                assert_eq!(args.since, Some(parse_date("2024-05-01").unwrap()));
+                assert!(args.failed);
+                assert!(args.json);
             }
-            _ => panic!("Expected Audio command"),
+            _ => panic!("Expected History command"),
         }
     }
 
     #[test]
-    fn test_audio_missing_url_fails() {
-        let result = Cli::try_parse_from(["ytdl", "audio"]);
+    fn test_history_invalid_since_fails() {
+        let result = Cli::try_parse_from(["ytdl", "history", "--since", "yesterday"]);
         assert!(result.is_err());
     }
 
     #[test]
-    fn test_audio_invalid_format_fails() {
-        let result = Cli::try_parse_from([
-            "ytdl",
-            "audio",
-            "https://youtube.com/watch?v=abc123",
-            "-f",
-            "wma",
-        ]);
+    fn test_history_prune() {
+        let cli = Cli::try_parse_from(["ytdl", "history", "prune", "--before", "30d"]).unwrap();
+
+        match cli.command {
+            Commands::History(args) => match args.command {
+                Some(HistoryCommands::Prune { before, all }) => {
+                    assert!(before.is_some());
+                    assert!(!all);
+                }
+                _ => panic!("Expected Prune subcommand"),
+            },
+            _ => panic!("Expected History command"),
+        }
+    }
+
+    #[test]
+    fn test_history_prune_all_conflicts_with_before() {
+        let result = Cli::try_parse_from(["ytdl", "history", "prune", "--all", "--before", "7d"]);
         assert!(result.is_err());
     }
 
-    // ============== Playlist Command Tests ==============
+    // ============== Library Command Tests ==============
 
     #[test]
-    fn test_playlist_single_url() {
-        let cli = Cli::try_parse_from([
-            "ytdl",
-            "playlist",
-            "https://youtube.com/playlist?list=PL123",
-        ]);
-        assert!(cli.is_ok());
+    fn test_library_scan() {
+        let cli = Cli::try_parse_from(["ytdl", "library", "scan", "/music"]).unwrap();
 
-        match cli.unwrap().command {
-            Commands::Playlist(args) => {
-                assert_eq!(args.urls.len(), 1);
-                assert_eq!(args.urls[0], "https://youtube.com/playlist?list=PL123");
-            }
-            _ => panic!("Expected Playlist command"),
+        match cli.command {
+            Commands::Library {
+                command: LibraryCommands::Scan { dir },
+            } => assert_eq!(dir, PathBuf::from("/music")),
+            _ => panic!("Expected Library Scan command"),
         }
     }
 
     #[test]
-    fn test_playlist_multiple_urls() {
+    fn test_library_scan_requires_dir() {
+        let result = Cli::try_parse_from(["ytdl", "library", "scan"]);
+        assert!(result.is_err());
+    }
+
+    // ============== Feed Command Tests ==============
+
+    #[test]
+    fn test_feed_build() {
         let cli = Cli::try_parse_from([
             "ytdl",
-            "playlist",
-            "https://youtube.com/playlist?list=PL123",
-            "https://youtube.com/playlist?list=PL456",
-            "https://youtube.com/playlist?list=PL789",
+            "feed",
+            "build",
+            "/srv/lectures",
+            "--base-url",
+            "https://example.com/lectures",
+            "--title",
+            "Lectures",
         ])
         .unwrap();
 
         match cli.command {
-            Commands::Playlist(args) => {
-                assert_eq!(args.urls.len(), 3);
-                assert_eq!(args.urls[0], "https://youtube.com/playlist?list=PL123");
-                assert_eq!(args.urls[1], "https://youtube.com/playlist?list=PL456");
-                assert_eq!(args.urls[2], "https://youtube.com/playlist?list=PL789");
+            Commands::Feed {
+                command: FeedCommands::Build(args),
+            } => {
+                assert_eq!(args.dir, PathBuf::from("/srv/lectures"));
+                assert_eq!(args.base_url, "https://example.com/lectures");
+                assert_eq!(args.title.as_deref(), Some("Lectures"));
+                assert!(args.output.is_none());
             }
-            _ => panic!("Expected Playlist command"),
+            _ => panic!("Expected Feed Build command"),
         }
     }
 
     #[test]
-    fn test_playlist_default_values() {
-        let cli = Cli::try_parse_from([
+    fn test_feed_build_requires_base_url() {
+        let result = Cli::try_parse_from(["ytdl", "feed", "build", "/srv/lectures"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_feed_build_rejects_relative_base_url() {
+        let result = Cli::try_parse_from([
             "ytdl",
-            "playlist",
-            "https://youtube.com/playlist?list=PL123",
-        ])
-        .unwrap();
+            "feed",
+            "build",
+            "/srv/lectures",
+            "--base-url",
+            "example.com/lectures",
+        ]);
+        assert!(result.is_err());
+    }
+
+    // ============== Serve Command Tests ==============
+
+    #[test]
+    fn test_serve_defaults() {
+        let cli = Cli::try_parse_from(["ytdl", "serve"]).unwrap();
 
         match cli.command {
-            Commands::Playlist(args) => {
-                assert!(matches!(args.quality, VideoQuality::Best));
-                assert!(matches!(args.format, VideoFormat::Mp4));
-                assert!(!args.audio_only);
-                assert!(matches!(args.audio_format, AudioFormat::Mp3));
-                assert_eq!(args.output, PathBuf::from("."));
+            Commands::Serve(args) => {
+                assert_eq!(args.listen, "127.0.0.1:8765".parse().unwrap());
+                assert!(args.listen.ip().is_loopback());
+                assert!(args.token.is_none());
+                assert!(args.jobs.is_none());
             }
-            _ => panic!("Expected Playlist command"),
+            _ => panic!("Expected Serve command"),
         }
     }
 
     #[test]
-    fn test_playlist_with_audio_only() {
+    fn test_serve_options() {
         let cli = Cli::try_parse_from([
-            "ytdl",
-            "playlist",
-            "https://youtube.com/playlist?list=PL123",
-            "--audio-only",
+            "ytdl", "serve", "--listen", "[::1]:0", "--token", "s3cret", "-j", "4",
         ])
         .unwrap();
 
         match cli.command {
-            Commands::Playlist(args) => {
-                assert!(args.audio_only);
+            Commands::Serve(args) => {
+                assert_eq!(args.listen, "[::1]:0".parse().unwrap());
+                assert_eq!(args.token.as_deref(), Some("s3cret"));
+                assert_eq!(args.jobs, Some(4));
             }
-            _ => panic!("Expected Playlist command"),
+            _ => panic!("Expected Serve command"),
         }
     }
 
     #[test]
-    fn test_playlist_with_audio_only_and_format() {
+    fn test_serve_rejects_invalid_listen() {
+        assert!(Cli::try_parse_from(["ytdl", "serve", "--listen", "localhost"]).is_err());
+        assert!(Cli::try_parse_from(["ytdl", "serve", "--jobs", "0"]).is_err());
+    }
+
+    // ============== Format ID Tests ==============
+
+    #[test]
+    fn test_download_format_id() {
         let cli = Cli::try_parse_from([
             "ytdl",
-            "playlist",
-            "https://youtube.com/playlist?list=PL123",
-            "--audio-only",
-            "--audio-format",
-            "flac",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "--format-id",
+            "137+140",
         ])
         .unwrap();
 
         match cli.command {
-            Commands::Playlist(args) => {
-                assert!(args.audio_only);
-                assert!(matches!(args.audio_format, AudioFormat::Flac));
+            Commands::Download(args) => {
+                assert_eq!(args.format_id.as_deref(), Some("137+140"));
             }
-            _ => panic!("Expected Playlist command"),
+            _ => panic!("Expected Download command"),
         }
     }
 
     #[test]
-    fn test_playlist_with_quality() {
-        let cli = Cli::try_parse_from([
-            "ytdl",
-            "playlist",
-            "https://youtube.com/playlist?list=PL123",
-            "-q",
-            "480p",
-        ])
-        .unwrap();
+    fn test_download_format_id_defaults_to_none() {
+        let cli = Cli::try_parse_from(["ytdl", "download", "https://youtube.com/watch?v=abc123"])
+            .unwrap();
 
         match cli.command {
-            Commands::Playlist(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q480p));
-            }
-            _ => panic!("Expected Playlist command"),
+            Commands::Download(args) => assert!(args.format_id.is_none()),
+            _ => panic!("Expected Download command"),
         }
     }
 
     #[test]
-    fn test_playlist_missing_url_fails() {
-        let result = Cli::try_parse_from(["ytdl", "playlist"]);
+    fn test_download_format_id_conflicts_with_quality() {
+        let result = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "--format-id",
+            "137",
+            "-q",
+            "720p",
+        ]);
         assert!(result.is_err());
     }
 
-    // ============== Info Command Tests ==============
-
-    #[test]
-    fn test_info_command() {
-        let cli = Cli::try_parse_from(["ytdl", "info", "https://youtube.com/watch?v=abc123"]);
-        assert!(cli.is_ok());
-
-        match cli.unwrap().command {
-            Commands::Info(args) => {
-                assert_eq!(args.url, "https://youtube.com/watch?v=abc123");
-            }
-            _ => panic!("Expected Info command"),
-        }
-    }
-
     #[test]
-    fn test_info_missing_url_fails() {
-        let result = Cli::try_parse_from(["ytdl", "info"]);
-        assert!(result.is_err());
+    fn test_parse_format_id() {
+        assert!(parse_format_id("137").is_ok());
+        assert!(parse_format_id("137+140").is_ok());
+        assert!(parse_format_id("22/18").is_ok());
+        assert!(parse_format_id("hls-1080p").is_ok());
+        assert!(parse_format_id("").is_err());
+        assert!(parse_format_id("137+").is_err());
+        assert!(parse_format_id("bv*+ba").is_ok());
+        assert!(parse_format_id("bestvideo[height<=720]+bestaudio").is_ok());
+        assert!(parse_format_id("22/").is_err());
     }
 
     // ============== Config Command Tests ==============
@@ -846,7 +2541,7 @@ mod tests {
 
         match cli.unwrap().command {
             Commands::Config { command } => {
-                assert!(matches!(command, ConfigCommands::Show));
+                assert!(matches!(command, ConfigCommands::Show { origin: false }));
             }
             _ => panic!("Expected Config command"),
         }
@@ -938,7 +2633,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q144p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q144p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -957,7 +2652,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q240p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q240p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -976,7 +2671,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q360p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q360p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -995,7 +2690,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q480p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q480p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1014,7 +2709,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q720p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q720p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1033,7 +2728,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q1080p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q1080p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1052,7 +2747,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q1440p));
+                assert!(matches!(args.quality, Some(VideoQuality::Q1440p)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1071,7 +2766,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Q4k));
+                assert!(matches!(args.quality, Some(VideoQuality::Q4k)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1090,7 +2785,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Best));
+                assert!(matches!(args.quality, Some(VideoQuality::Best)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1109,7 +2804,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.quality, VideoQuality::Worst));
+                assert!(matches!(args.quality, Some(VideoQuality::Worst)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1130,7 +2825,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.format, VideoFormat::Mp4));
+                assert!(matches!(args.format, Some(VideoFormat::Mp4)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1149,7 +2844,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.format, VideoFormat::Mkv));
+                assert!(matches!(args.format, Some(VideoFormat::Mkv)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1168,7 +2863,45 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert!(matches!(args.format, VideoFormat::Webm));
+                assert!(matches!(args.format, Some(VideoFormat::Webm)));
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_video_format_avi() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "avi",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                assert!(matches!(args.format, Some(VideoFormat::Avi)));
+            }
+            _ => panic!("Expected Download command"),
+        }
+    }
+
+    #[test]
+    fn test_video_format_mov() {
+        let cli = Cli::try_parse_from([
+            "ytdl",
+            "download",
+            "https://youtube.com/watch?v=abc123",
+            "-f",
+            "mov",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Download(args) => {
+                assert!(matches!(args.format, Some(VideoFormat::Mov)));
             }
             _ => panic!("Expected Download command"),
         }
@@ -1275,7 +3008,7 @@ mod tests {
 
         match cli.command {
             Commands::Download(args) => {
-                assert_eq!(args.common.output, PathBuf::from("/tmp/videos"));
+                assert_eq!(args.common.output, Some(PathBuf::from("/tmp/videos")));
             }
             _ => panic!("Expected Download command"),
         }
@@ -0,0 +1,604 @@
+//! Versioned configuration files and the migrations between versions.
+//!
+//! Every file written by ytdl carries a top-level `version`. Files without one
+//! predate versioning and are treated as version 0. When an older file is
+//! read, each migration step from its version up to [`CURRENT_VERSION`] is
+//! applied to the parsed document.
+//!
+//! Only the user configuration file is upgraded on disk: the original is
+//! copied to a backup next to it and the migrated values are written back in
+//! place. A project `.ytdl.toml` may be shared or checked into a repository,
+//! so it is migrated in memory only (see [`load_table`]).
+//!
+//! Migrations work on a [`toml_edit`] document rather than on
+//! [`Config`](super::Config), so that entries this version doesn't know about
+//! are carried over untouched and an upgraded file keeps its comments and
+//! layout.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::DocumentMut;
+
+use crate::error::{AppError, AppResult};
+use crate::progress::messages;
+
+/// The configuration file version written by this build.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// A single upgrade step from version `from` to `from + 1`.
+///
+/// `apply` edits the document in place and returns a line per change made.
+struct Migration {
+    from: u32,
+    apply: fn(&mut DocumentMut) -> Vec<String>,
+}
+
+/// Every migration, in order. Add new steps to the end and bump
+/// [`CURRENT_VERSION`].
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    apply: v0_to_v1,
+}];
+
+/// What upgrading a configuration file changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Version the file had before the upgrade
+    pub from: u32,
+
+    /// Version the file has now
+    pub to: u32,
+
+    /// Copy of the original file
+    pub backup: PathBuf,
+
+    /// Human-readable description of each change
+    pub changes: Vec<String>,
+}
+
+/// Returns the version a configuration table declares (0 when it has none).
+///
+/// # Errors
+///
+/// Returns [`AppError::ConfigInvalid`] if `version` is not a non-negative
+/// integer or is newer than [`CURRENT_VERSION`].
+pub fn file_version(table: &toml::Table) -> AppResult<u32> {
+    check_version(table.get("version").map(toml::Value::as_integer))
+}
+
+/// Checks a declared `version`: `None` when the key is missing, `Some(None)`
+/// when it is not an integer.
+fn check_version(declared: Option<Option<i64>>) -> AppResult<u32> {
+    let Some(value) = declared else {
+        return Ok(0);
+    };
+
+    let version =
+        value
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| AppError::ConfigInvalid {
+                field: "version".to_string(),
+                message: "must be a non-negative integer".to_string(),
+            })?;
+
+    if version > CURRENT_VERSION {
+        return Err(AppError::ConfigInvalid {
+            field: "version".to_string(),
+            message: format!(
+                "file version {} is newer than this ytdl supports ({}); please upgrade ytdl",
+                version, CURRENT_VERSION
+            ),
+        });
+    }
+
+    Ok(version)
+}
+
+/// Upgrades a configuration document to [`CURRENT_VERSION`].
+///
+/// Only the migrated values and `version` are changed; comments and
+/// formatting elsewhere in the document are kept. Returns the version the
+/// document started at and the changes made, or `None` if it was already
+/// current.
+///
+/// # Errors
+///
+/// See [`file_version`].
+pub fn migrate(document: &mut DocumentMut) -> AppResult<Option<(u32, Vec<String>)>> {
+    let from = check_version(document.get("version").map(|version| version.as_integer()))?;
+    if from == CURRENT_VERSION {
+        return Ok(None);
+    }
+
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from) {
+        changes.extend((migration.apply)(document));
+        document["version"] = toml_edit::value(i64::from(migration.from + 1));
+    }
+    changes.push(format!("version: set to {}", CURRENT_VERSION));
+
+    Ok(Some((from, changes)))
+}
+
+/// Returns the path an original file is backed up to before upgrading from
+/// `version`, e.g. `config.toml.v0.bak`.
+pub fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+/// Reads a configuration file as a TOML table, upgrading it in place first if
+/// it is older than [`CURRENT_VERSION`].
+///
+/// Returns `None` if the file doesn't exist. An upgrade is reported on the
+/// console; an existing backup is never overwritten, so the oldest original
+/// is kept. Use this for the user configuration file only.
+///
+/// # Errors
+///
+/// - Returns an error if the file cannot be read, parsed, backed up or written
+/// - Returns an error if the file's version is unsupported (see [`file_version`])
+pub fn read_table(path: &Path) -> AppResult<Option<toml::Table>> {
+    let Some((table, report)) = upgrade_file(path)? else {
+        return Ok(None);
+    };
+
+    if let Some(report) = report {
+        messages::info(&format!(
+            "Upgraded {} from version {} to {} (original saved as {})",
+            path.display(),
+            report.from,
+            report.to,
+            report.backup.display()
+        ));
+        for change in &report.changes {
+            messages::info(&format!("  {}", change));
+        }
+    }
+
+    Ok(Some(table))
+}
+
+/// Reads a configuration file as a TOML table, migrating it in memory only.
+///
+/// The file itself is never written, and no backup is made. Returns `None`
+/// if the file doesn't exist.
+///
+/// # Errors
+///
+/// - Returns an error if the file cannot be read or parsed
+/// - Returns an error if the file's version is unsupported (see [`file_version`])
+pub fn load_table(path: &Path) -> AppResult<Option<toml::Table>> {
+    let Some((content, table)) = read_file(path)? else {
+        return Ok(None);
+    };
+
+    if file_version(&table)? == CURRENT_VERSION {
+        return Ok(Some(table));
+    }
+
+    let mut document = parse_document(path, &content)?;
+    migrate(&mut document)?;
+
+    to_table(path, &document).map(Some)
+}
+
+/// Like [`read_table`], but returns the report instead of printing it.
+///
+/// # Errors
+///
+/// See [`read_table`].
+pub fn upgrade_file(path: &Path) -> AppResult<Option<(toml::Table, Option<MigrationReport>)>> {
+    let Some((content, table)) = read_file(path)? else {
+        return Ok(None);
+    };
+
+    if file_version(&table)? == CURRENT_VERSION {
+        return Ok(Some((table, None)));
+    }
+
+    let mut document = parse_document(path, &content)?;
+    let Some((from, changes)) = migrate(&mut document)? else {
+        return Ok(Some((table, None)));
+    };
+
+    let backup = backup_path(path, from);
+    if !backup.exists() {
+        fs::write(&backup, &content).map_err(|e| AppError::file_write(&backup, e))?;
+    }
+
+    let upgraded = document.to_string();
+    fs::write(path, &upgraded).map_err(|e| AppError::file_write(path, e))?;
+
+    let report = MigrationReport {
+        from,
+        to: CURRENT_VERSION,
+        backup,
+        changes,
+    };
+
+    Ok(Some((to_table(path, &document)?, Some(report))))
+}
+
+/// Writes a configuration table, creating the parent directory if needed.
+///
+/// # Errors
+///
+/// Returns an error if the directory or file cannot be written.
+pub fn write_table(path: &Path, table: &toml::Table) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+        }
+    }
+
+    let content = toml::to_string_pretty(table)?;
+    fs::write(path, content).map_err(|e| AppError::file_write(path, e))?;
+
+    Ok(())
+}
+
+/// Reads and parses a file, returning its text along with the table.
+fn read_file(path: &Path) -> AppResult<Option<(String, toml::Table)>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| AppError::file_read(path, e))?;
+    let table = toml::from_str(&content).map_err(|e| AppError::ConfigParse {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+
+    Ok(Some((content, table)))
+}
+
+fn parse_document(path: &Path, content: &str) -> AppResult<DocumentMut> {
+    content
+        .parse()
+        .map_err(|e: toml_edit::TomlError| AppError::ConfigParse {
+            path: path.to_path_buf(),
+            source: serde::de::Error::custom(e.message()),
+        })
+}
+
+fn to_table(path: &Path, document: &DocumentMut) -> AppResult<toml::Table> {
+    toml::from_str(&document.to_string()).map_err(|e| AppError::ConfigParse {
+        path: path.to_path_buf(),
+        source: e,
+    })
+}
+
+// ============== Migrations ==============
+
+const INTEGER_KEYS: &[(&str, &str)] = &[
+    ("general", "max_parallel_downloads"),
+    ("network", "retry_attempts"),
+    ("network", "timeout"),
+];
+
+const BOOLEAN_KEYS: &[(&str, &str)] = &[
+    ("video", "include_thumbnail"),
+    ("video", "include_subtitles"),
+];
+
+const LOWERCASE_KEYS: &[(&str, &str)] = &[
+    ("general", "default_quality"),
+    ("audio", "format"),
+    ("video", "format"),
+];
+
+/// Version 0 (unversioned) to 1.
+///
+/// Unversioned files were often written by hand, and stored numbers and
+/// booleans as strings (`timeout = "300"`), which fails to load. This step
+/// gives those values their proper type, lowercases quality and format names
+/// and replaces the `2160p` quality alias with `4k`.
+fn v0_to_v1(document: &mut DocumentMut) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    for (section, field) in INTEGER_KEYS {
+        if let Some(value) = string_entry(document, section, field) {
+            if let Ok(number) = value.trim().parse::<i64>() {
+                set_entry(document, section, field, number.into());
+                changes.push(format!(
+                    "{}.{}: \"{}\" -> {}",
+                    section, field, value, number
+                ));
+            }
+        }
+    }
+
+    for (section, field) in BOOLEAN_KEYS {
+        if let Some(value) = string_entry(document, section, field) {
+            let flag = match value.trim().to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Some(true),
+                "false" | "no" | "off" | "0" => Some(false),
+                _ => None,
+            };
+            if let Some(flag) = flag {
+                set_entry(document, section, field, flag.into());
+                changes.push(format!("{}.{}: \"{}\" -> {}", section, field, value, flag));
+            }
+        }
+    }
+
+    for (section, field) in LOWERCASE_KEYS {
+        if let Some(value) = string_entry(document, section, field) {
+            let mut normalized = value.trim().to_lowercase();
+            if normalized == "2160p" {
+                normalized = "4k".to_string();
+            }
+            if normalized != value {
+                set_entry(document, section, field, normalized.as_str().into());
+                changes.push(format!(
+                    "{}.{}: \"{}\" -> \"{}\"",
+                    section, field, value, normalized
+                ));
+            }
+        }
+    }
+
+    changes
+}
+
+fn string_entry(document: &DocumentMut, section: &str, field: &str) -> Option<String> {
+    document
+        .get(section)?
+        .get(field)?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Replaces a value, keeping the whitespace and comments around it.
+fn set_entry(document: &mut DocumentMut, section: &str, field: &str, value: toml_edit::Value) {
+    let entry = document
+        .get_mut(section)
+        .and_then(|s| s.get_mut(field))
+        .and_then(|f| f.as_value_mut());
+    if let Some(entry) = entry {
+        let decor = entry.decor().clone();
+        *entry = value;
+        *entry.decor_mut() = decor;
+    }
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn parse(content: &str) -> toml::Table {
+        toml::from_str(content).unwrap()
+    }
+
+    fn document(content: &str) -> DocumentMut {
+        content.parse().unwrap()
+    }
+
+    // ============== Version Tests ==============
+
+    #[test]
+    fn test_file_version_missing_is_zero() {
+        assert_eq!(file_version(&parse("[general]\n")).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_file_version_current() {
+        let table = parse(&format!("version = {}\n", CURRENT_VERSION));
+        assert_eq!(file_version(&table).unwrap(), CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_file_version_newer_is_rejected() {
+        let table = parse(&format!("version = {}\n", CURRENT_VERSION + 1));
+        let err = file_version(&table).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+    }
+
+    #[test]
+    fn test_file_version_not_integer() {
+        assert!(file_version(&parse("version = \"1\"\n")).is_err());
+        assert!(file_version(&parse("version = -1\n")).is_err());
+    }
+
+    // ============== Migration Tests ==============
+
+    #[test]
+    fn test_migrate_current_is_noop() {
+        let mut table = document(&format!(
+            "version = {}\n[audio]\nformat = \"MP3\"\n",
+            CURRENT_VERSION
+        ));
+        assert!(migrate(&mut table).unwrap().is_none());
+        assert_eq!(table["audio"]["format"].as_str(), Some("MP3"));
+    }
+
+    #[test]
+    fn test_migrate_stamps_version() {
+        let mut table = document("[general]\n");
+        let (from, changes) = migrate(&mut table).unwrap().unwrap();
+
+        assert_eq!(from, 0);
+        assert_eq!(
+            table["version"].as_integer(),
+            Some(i64::from(CURRENT_VERSION))
+        );
+        assert!(changes.iter().any(|c| c.starts_with("version")));
+    }
+
+    #[test]
+    fn test_migrate_v0_types_strings() {
+        let mut table = document(
+            r#"
+[general]
+max_parallel_downloads = "4"
+
+[video]
+include_thumbnail = "no"
+
+[network]
+timeout = " 60 "
+"#,
+        );
+        let (_, changes) = migrate(&mut table).unwrap().unwrap();
+
+        assert_eq!(
+            table["general"]["max_parallel_downloads"].as_integer(),
+            Some(4)
+        );
+        assert_eq!(table["video"]["include_thumbnail"].as_bool(), Some(false));
+        assert_eq!(table["network"]["timeout"].as_integer(), Some(60));
+        assert!(changes.contains(&"general.max_parallel_downloads: \"4\" -> 4".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_v0_normalizes_names() {
+        let mut table = document(
+            r#"
+[general]
+default_quality = "2160P"
+
+[audio]
+format = "FLAC"
+"#,
+        );
+        migrate(&mut table).unwrap();
+
+        assert_eq!(table["general"]["default_quality"].as_str(), Some("4k"));
+        assert_eq!(table["audio"]["format"].as_str(), Some("flac"));
+    }
+
+    #[test]
+    fn test_migrate_keeps_unknown_and_invalid_entries() {
+        let mut table = document(
+            r#"
+[general]
+filename_template = "%(title)s"
+
+[network]
+retry_attempts = "many"
+"#,
+        );
+        migrate(&mut table).unwrap();
+
+        assert_eq!(
+            table["general"]["filename_template"].as_str(),
+            Some("%(title)s")
+        );
+        assert_eq!(table["network"]["retry_attempts"].as_str(), Some("many"));
+    }
+
+    // ============== File Tests ==============
+
+    #[test]
+    fn test_backup_path() {
+        assert_eq!(
+            backup_path(Path::new("/tmp/config.toml"), 0),
+            PathBuf::from("/tmp/config.toml.v0.bak")
+        );
+        assert_eq!(
+            backup_path(Path::new(".ytdl.toml"), 3),
+            PathBuf::from(".ytdl.toml.v3.bak")
+        );
+    }
+
+    #[test]
+    fn test_upgrade_file_missing() {
+        let dir = TempDir::new().unwrap();
+        assert!(upgrade_file(&dir.path().join("config.toml"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_upgrade_file_writes_backup_and_upgrades() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let original = "[network]\ntimeout = \"60\"\n";
+        fs::write(&path, original).unwrap();
+
+        let (table, report) = upgrade_file(&path).unwrap().unwrap();
+        let report = report.unwrap();
+
+        assert_eq!(report.from, 0);
+        assert_eq!(report.to, CURRENT_VERSION);
+        assert_eq!(fs::read_to_string(&report.backup).unwrap(), original);
+        assert_eq!(table["network"]["timeout"].as_integer(), Some(60));
+
+        let rewritten: toml::Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(file_version(&rewritten).unwrap(), CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_upgrade_file_keeps_comments_and_layout() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "# My settings\n[network]\ntimeout   = \"60\"  # seconds\nproxy = \"socks5://localhost\"\n\n# Audio\n[audio]\nformat = \"mp3\"\n",
+        )
+        .unwrap();
+
+        upgrade_file(&path).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!(
+                "version = {}\n# My settings\n[network]\ntimeout   = 60  # seconds\nproxy = \"socks5://localhost\"\n\n# Audio\n[audio]\nformat = \"mp3\"\n",
+                CURRENT_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn test_load_table_leaves_file_untouched() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".ytdl.toml");
+        let original = "[network]\ntimeout = \"60\"\n";
+        fs::write(&path, original).unwrap();
+
+        let table = load_table(&path).unwrap().unwrap();
+
+        assert_eq!(table["network"]["timeout"].as_integer(), Some(60));
+        assert_eq!(
+            table["version"].as_integer(),
+            Some(i64::from(CURRENT_VERSION))
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert!(!backup_path(&path, 0).exists());
+    }
+
+    #[test]
+    fn test_upgrade_file_current_is_untouched() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let content = format!("# keep me\nversion = {}\n", CURRENT_VERSION);
+        fs::write(&path, &content).unwrap();
+
+        let (_, report) = upgrade_file(&path).unwrap().unwrap();
+
+        assert!(report.is_none());
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        assert!(!backup_path(&path, 0).exists());
+    }
+
+    #[test]
+    fn test_upgrade_file_keeps_existing_backup() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(backup_path(&path, 0), "original").unwrap();
+        fs::write(&path, "[general]\n").unwrap();
+
+        upgrade_file(&path).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(backup_path(&path, 0)).unwrap(),
+            "original"
+        );
+    }
+}
@@ -0,0 +1,2558 @@
+//! Configuration management for the YouTube downloader.
+//!
+//! This module provides a hierarchical configuration system using TOML format.
+//! Configuration files are stored at `~/.config/rust-yt-downloader/config.toml` on
+//! Linux/macOS and in the equivalent location on Windows.
+//!
+//! # Configuration Structure
+//!
+//! The configuration is organized into four main sections:
+//! - `[general]` - Application-wide settings (output directory, quality, parallelism)
+//! - `[audio]` - Audio-specific settings (format, bitrate)
+//! - `[video]` - Video-specific settings (format, thumbnails, subtitles)
+//! - `[network]` - Network-related settings (rate limiting, retries, timeouts)
+//!
+//! Named profiles (`[profiles.<name>]`) overlay any of these sections and are
+//! selected with `--profile <name>` or `general.default_profile`. Routing
+//! rules (`[[rules]]`) adjust download settings per video; see [`Rule`].
+//!
+//! # Accessing Configuration Values
+//!
+//! Configuration values are accessed using dot notation with the [`Config::get()`] method:
+//! - `"general.output_dir"` - Download directory
+//! - `"audio.format"` - Default audio format (mp3, flac, etc.)
+//! - `"video.include_thumbnail"` - Whether to download thumbnails
+//! - `"network.retry_attempts"` - Number of retry attempts
+//!
+//! # Default Values
+//!
+//! All configuration fields have sensible defaults:
+//! - Output directory: `~/Downloads/YouTube`
+//! - Video quality: `"best"`
+//! - Audio format: `"mp3"` at `"320k"` bitrate
+//! - Video format: `"mp4"`
+//! - Network retries: 3 attempts with 300 second timeout
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rust_yt_downloader::config::Config;
+//!
+//! // Load configuration (or use defaults if file doesn't exist)
+//! let config = Config::load()?;
+//!
+//! // Get a configuration value
+//! if let Some(quality) = config.get("general.default_quality") {
+//!     println!("Default quality: {}", quality);
+//! }
+//!
+//! // Modify and save configuration
+//! let mut config = Config::load()?;
+//! config.set("general.default_quality", "1080p")?;
+//! config.save()?;
+//! # Ok::<(), rust_yt_downloader::error::AppError>(())
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::progress::messages;
+
+mod migrate;
+mod rules;
+mod validate;
+
+pub use migrate::{upgrade_file, MigrationReport, CURRENT_VERSION};
+pub use rules::{find_match, MediaKind, Rule, RuleContext};
+pub use validate::{validate_file, validate_str, ConfigIssue};
+
+/// Root configuration structure containing all settings.
+///
+/// This is the top-level configuration object that contains four nested
+/// configuration sections. Each section has sensible defaults, so a minimal
+/// or empty TOML file will still provide a complete working configuration.
+///
+/// # TOML Format
+///
+/// ```toml
+/// version = 1
+///
+/// [general]
+/// output_dir = "/path/to/downloads"
+/// default_quality = "1080p"
+/// max_parallel_downloads = 3
+///
+/// [audio]
+/// format = "mp3"
+/// bitrate = "320k"
+///
+/// [video]
+/// format = "mp4"
+/// include_thumbnail = true
+/// include_subtitles = true
+///
+/// [network]
+/// rate_limit = "5M"
+/// retry_attempts = 3
+/// timeout = 300
+///
+/// [profiles.mobile]
+/// general.default_quality = "720p"
+/// video.format = "mp4"
+///
+/// [[rules]]
+/// channel = "Lofi Girl"
+/// audio_format = "flac"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// File format version; files without one are upgraded when read
+    #[serde(default)]
+    pub version: u32,
+
+    /// General application settings
+    #[serde(default)]
+    pub general: GeneralConfig,
+
+    /// Audio download and conversion settings
+    #[serde(default)]
+    pub audio: AudioConfig,
+
+    /// Video download settings
+    #[serde(default)]
+    pub video: VideoConfig,
+
+    /// Network and connection settings
+    #[serde(default)]
+    pub network: NetworkConfig,
+
+    /// Named profiles that overlay the sections above
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Routing rules that adjust settings per video, checked in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<Rule>,
+}
+
+/// General application settings.
+///
+/// Contains application-wide configuration options including the output directory,
+/// default quality preference, and parallelism settings.
+///
+/// # Default Values
+///
+/// - `output_dir`: `~/Downloads/YouTube` (platform-specific)
+/// - `default_quality`: `"best"`
+/// - `max_parallel_downloads`: `3`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneralConfig {
+    /// Output directory for downloaded files.
+    ///
+    /// Defaults to the platform's downloads directory with a "YouTube" subdirectory.
+    /// On Linux/macOS this is typically `~/Downloads/YouTube`.
+    #[serde(default = "GeneralConfig::default_output_dir")]
+    pub output_dir: String,
+
+    /// Default video quality preference.
+    ///
+    /// Can be `"best"`, `"1080p"`, `"720p"`, `"480p"`, `"360p"`, or `"worst"`.
+    /// Defaults to `"best"` which selects the highest available quality.
+    #[serde(default = "GeneralConfig::default_quality")]
+    pub default_quality: String,
+
+    /// Maximum number of parallel downloads.
+    ///
+    /// Controls how many videos can be downloaded simultaneously when processing
+    /// playlists. Defaults to `3` to balance speed with system resources.
+    #[serde(default = "GeneralConfig::default_max_parallel")]
+    pub max_parallel_downloads: u32,
+
+    /// Profile applied when `--profile` is not given.
+    ///
+    /// `None` means no profile is applied by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+}
+
+/// Audio-specific download and conversion settings.
+///
+/// Controls the format and quality of audio downloads and extractions.
+///
+/// # Default Values
+///
+/// - `format`: `"mp3"`
+/// - `bitrate`: `"320k"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioConfig {
+    /// Default audio format.
+    ///
+    /// Supported formats include `"mp3"`, `"flac"`, `"m4a"`, `"wav"`, and `"opus"`.
+    /// Defaults to `"mp3"` for broad compatibility.
+    #[serde(default = "AudioConfig::default_format")]
+    pub format: String,
+
+    /// Audio bitrate for lossy formats.
+    ///
+    /// Specified with a 'k' suffix (e.g., `"320k"`, `"256k"`, `"192k"`).
+    /// Defaults to `"320k"` for high-quality audio. Ignored for lossless formats.
+    #[serde(default = "AudioConfig::default_bitrate")]
+    pub bitrate: String,
+}
+
+/// Video-specific download settings.
+///
+/// Controls the format and additional content (thumbnails, subtitles) for video downloads.
+///
+/// # Default Values
+///
+/// - `format`: `"mp4"`
+/// - `include_thumbnail`: `true`
+/// - `include_subtitles`: `true`
+/// - `write_info_json`: `false`
+/// - `write_nfo`: `false`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoConfig {
+    /// Default video container format.
+    ///
+    /// Supported formats include `"mp4"`, `"mkv"`, and `"webm"`.
+    /// Defaults to `"mp4"` for maximum compatibility.
+    #[serde(default = "VideoConfig::default_format")]
+    pub format: String,
+
+    /// Whether to download video thumbnails.
+    ///
+    /// When `true`, saves the video thumbnail as a separate image file.
+    /// Defaults to `true`.
+    #[serde(default = "VideoConfig::default_include_thumbnail")]
+    pub include_thumbnail: bool,
+
+    /// Whether to download subtitles/closed captions.
+    ///
+    /// When `true`, downloads all available subtitle tracks.
+    /// Defaults to `true`.
+    #[serde(default = "VideoConfig::default_include_subtitles")]
+    pub include_subtitles: bool,
+
+    /// Whether to write `<name>.info.json` next to each download.
+    ///
+    /// The file holds the video's metadata and is read by `ytdl library scan`
+    /// and `ytdl feed build`. Defaults to `false`.
+    #[serde(default)]
+    pub write_info_json: bool,
+
+    /// Whether to write a Kodi/Jellyfin `<name>.nfo` next to each download.
+    ///
+    /// Files saved in a folder named after their channel get episode NFOs and
+    /// a `tvshow.nfo` for the folder. Defaults to `false`.
+    #[serde(default)]
+    pub write_nfo: bool,
+}
+
+/// Network and connection settings.
+///
+/// Controls download behavior including rate limiting, retry logic, and timeouts.
+///
+/// # Default Values
+///
+/// - `rate_limit`: `None` (unlimited)
+/// - `retry_attempts`: `3`
+/// - `timeout`: `300` (5 minutes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Download rate limit.
+    ///
+    /// Specified as a string with units (e.g., `"5M"` for 5 MB/s, `"500K"` for 500 KB/s).
+    /// `None` means no rate limiting. Use `"none"` or empty string to disable in TOML.
+    #[serde(default)]
+    pub rate_limit: Option<String>,
+
+    /// Number of retry attempts for failed downloads.
+    ///
+    /// If a download fails due to transient network issues, it will be retried
+    /// this many times before giving up. Defaults to `3`.
+    #[serde(default = "NetworkConfig::default_retry_attempts")]
+    pub retry_attempts: u32,
+
+    /// Connection timeout in seconds.
+    ///
+    /// Maximum time to wait for network operations before timing out.
+    /// Defaults to `300` seconds (5 minutes).
+    #[serde(default = "NetworkConfig::default_timeout")]
+    pub timeout: u64,
+}
+
+/// A named set of overrides layered on top of the base configuration.
+///
+/// Profiles use the same sections and keys as the base configuration; any key
+/// a profile leaves out keeps its base value.
+///
+/// # TOML Format
+///
+/// ```toml
+/// [profiles.podcast]
+/// general.output_dir = "~/Podcasts"
+/// audio.format = "opus"
+/// audio.bitrate = "96k"
+///
+/// [profiles.archive.video]
+/// format = "mkv"
+/// include_subtitles = true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Profile {
+    sections: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+// ============== Default Implementations ==============
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            general: GeneralConfig::default(),
+            audio: AudioConfig::default(),
+            video: VideoConfig::default(),
+            network: NetworkConfig::default(),
+            profiles: BTreeMap::new(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: Self::default_output_dir(),
+            default_quality: Self::default_quality(),
+            max_parallel_downloads: Self::default_max_parallel(),
+            default_profile: None,
+        }
+    }
+}
+
+impl GeneralConfig {
+    fn default_output_dir() -> String {
+        dirs::download_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("YouTube")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn default_quality() -> String {
+        "best".to_string()
+    }
+
+    fn default_max_parallel() -> u32 {
+        3
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            format: Self::default_format(),
+            bitrate: Self::default_bitrate(),
+        }
+    }
+}
+
+impl AudioConfig {
+    fn default_format() -> String {
+        "mp3".to_string()
+    }
+
+    fn default_bitrate() -> String {
+        "320k".to_string()
+    }
+}
+
+impl Default for VideoConfig {
+    fn default() -> Self {
+        Self {
+            format: Self::default_format(),
+            include_thumbnail: Self::default_include_thumbnail(),
+            include_subtitles: Self::default_include_subtitles(),
+            write_info_json: false,
+            write_nfo: false,
+        }
+    }
+}
+
+impl VideoConfig {
+    fn default_format() -> String {
+        "mp4".to_string()
+    }
+
+    fn default_include_thumbnail() -> bool {
+        true
+    }
+
+    fn default_include_subtitles() -> bool {
+        true
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit: None,
+            retry_attempts: Self::default_retry_attempts(),
+            timeout: Self::default_timeout(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    fn default_retry_attempts() -> u32 {
+        3
+    }
+
+    fn default_timeout() -> u64 {
+        300
+    }
+}
+
+// ============== Config Implementation ==============
+
+impl Config {
+    /// Returns the path to the configuration file.
+    ///
+    /// The configuration file is stored at:
+    /// - Linux/macOS: `~/.config/rust-yt-downloader/config.toml`
+    /// - Windows: `%APPDATA%\rust-yt-downloader\config.toml`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the system config directory cannot be determined.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// let path = Config::config_path()?;
+    /// println!("Config file: {}", path.display());
+    /// # Ok::<(), rust_yt_downloader::error::AppError>(())
+    /// ```
+    pub fn config_path() -> AppResult<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| AppError::Other("Could not find config directory".to_string()))?;
+
+        Ok(config_dir.join("rust-yt-downloader").join("config.toml"))
+    }
+
+    /// Loads configuration from disk, or returns defaults if the file doesn't exist.
+    ///
+    /// This method will create the configuration file with default values on first use.
+    /// If the file exists but contains invalid TOML, an error is returned.
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if the config file exists but cannot be read
+    /// - Returns an error if the config file contains invalid TOML syntax
+    /// - Returns an error if the system config directory cannot be determined
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// // Load existing config or use defaults
+    /// let config = Config::load()?;
+    /// println!("Output directory: {}", config.general.output_dir);
+    /// # Ok::<(), rust_yt_downloader::error::AppError>(())
+    /// ```
+    pub fn load() -> AppResult<Self> {
+        Self::load_from(&Self::config_path()?)
+    }
+
+    /// Loads configuration from a specific file, or returns defaults if it doesn't exist.
+    ///
+    /// Files from an older version are upgraded in place first (see
+    /// [`upgrade_file()`]).
+    ///
+    /// Unlike [`ConfigLoader`], this reads a single file only. Use it when the
+    /// file is going to be modified and saved back with [`Config::save_to()`].
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if the file exists but cannot be read
+    /// - Returns an error if the file contains invalid TOML syntax
+    /// - Returns an error if the file is from a newer, unsupported version
+    pub fn load_from(path: &Path) -> AppResult<Self> {
+        let Some(table) = migrate::read_table(path)? else {
+            return Ok(Self::default());
+        };
+
+        let config: Config = table.try_into().map_err(|e| AppError::ConfigParse {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+
+        Ok(config)
+    }
+
+    /// Saves the configuration to disk.
+    ///
+    /// Creates the configuration directory if it doesn't exist. The configuration
+    /// is written in TOML format with pretty-printing for readability.
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if the config directory cannot be created
+    /// - Returns an error if the config file cannot be written
+    /// - Returns an error if the configuration cannot be serialized to TOML
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// let mut config = Config::load()?;
+    /// config.general.default_quality = "1080p".to_string();
+    /// config.save()?;
+    /// # Ok::<(), rust_yt_downloader::error::AppError>(())
+    /// ```
+    pub fn save(&self) -> AppResult<()> {
+        self.save_to(&Self::config_path()?)
+    }
+
+    /// Saves the configuration to a specific file.
+    ///
+    /// Creates the parent directory if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if the parent directory cannot be created
+    /// - Returns an error if the file cannot be written
+    /// - Returns an error if the configuration cannot be serialized to TOML
+    pub fn save_to(&self, path: &Path) -> AppResult<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+            }
+        }
+
+        let content = toml::to_string_pretty(self)?;
+
+        fs::write(path, content).map_err(|e| AppError::file_write(path, e))?;
+
+        Ok(())
+    }
+
+    /// Sets a single value in the file at `path`, or in one of its profiles.
+    ///
+    /// Only the entry being set is rewritten: other entries, including keys
+    /// this version doesn't recognize, are kept as they are. The value is
+    /// validated first, together with the checks that need the whole config
+    /// (see [`Config::validate()`]).
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`AppError::ConfigInvalid`] if the key or value is invalid
+    /// - Returns an error if the file cannot be read, parsed or written
+    pub fn set_in_file(
+        path: &Path,
+        profile: Option<&str>,
+        key: &str,
+        value: &str,
+    ) -> AppResult<()> {
+        let mut table = migrate::read_table(path)?.unwrap_or_default();
+        table.insert(
+            "version".to_string(),
+            toml::Value::Integer(i64::from(CURRENT_VERSION)),
+        );
+
+        let parse_error = |e| AppError::ConfigParse {
+            path: path.to_path_buf(),
+            source: e,
+        };
+
+        match profile {
+            Some(name) => {
+                let mut profiles: BTreeMap<String, Profile> = match table.get("profiles") {
+                    Some(value) => value.clone().try_into().map_err(parse_error)?,
+                    None => BTreeMap::new(),
+                };
+                profiles
+                    .entry(name.to_string())
+                    .or_default()
+                    .set(key, value)?;
+                table.insert("profiles".to_string(), toml::Value::try_from(profiles)?);
+            }
+            None => {
+                let mut config: Config = table.clone().try_into().map_err(parse_error)?;
+                config.set(key, value)?;
+
+                if let Some(issue) = config
+                    .validate()
+                    .into_iter()
+                    .find(|issue| issue.key.as_deref() == Some(key))
+                {
+                    return Err(AppError::ConfigInvalid {
+                        field: key.to_string(),
+                        message: issue.message,
+                    });
+                }
+
+                let (section, field) = key.split_once('.').unwrap_or((key, ""));
+                let fields = table
+                    .entry(section.to_string())
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+                if let Some(fields) = fields.as_table_mut() {
+                    match typed_value(&config, key)? {
+                        Some(typed) => fields.insert(field.to_string(), typed),
+                        None => fields.remove(field),
+                    };
+                }
+            }
+        }
+
+        migrate::write_table(path, &table)
+    }
+
+    /// Resets configuration to default values and saves to disk.
+    ///
+    /// This will overwrite any existing configuration file with the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config file cannot be written (see [`Config::save()`]).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// // Reset to defaults
+    /// let config = Config::reset()?;
+    /// assert_eq!(config.general.default_quality, "best");
+    /// # Ok::<(), rust_yt_downloader::error::AppError>(())
+    /// ```
+    pub fn reset() -> AppResult<Self> {
+        let config = Self::default();
+        config.save()?;
+        Ok(config)
+    }
+
+    /// Gets a configuration value by dot-notation key.
+    ///
+    /// Returns the value as a string, or `None` if the key doesn't exist.
+    /// All values are converted to strings, including booleans and numbers.
+    ///
+    /// # Supported Keys
+    ///
+    /// Use [`Config::keys()`] to get a complete list of valid keys.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// let config = Config::load()?;
+    ///
+    /// // Get various configuration values
+    /// let quality = config.get("general.default_quality");
+    /// assert_eq!(quality, Some("best".to_string()));
+    ///
+    /// let parallel = config.get("general.max_parallel_downloads");
+    /// assert_eq!(parallel, Some("3".to_string()));
+    ///
+    /// // Unknown keys return None
+    /// assert_eq!(config.get("unknown.key"), None);
+    /// # Ok::<(), rust_yt_downloader::error::AppError>(())
+    /// ```
+    pub fn get(&self, key: &str) -> Option<String> {
+        let parts: Vec<&str> = key.split('.').collect();
+
+        match parts.as_slice() {
+            ["general", "output_dir"] => Some(self.general.output_dir.clone()),
+            ["general", "default_quality"] => Some(self.general.default_quality.clone()),
+            ["general", "max_parallel_downloads"] => {
+                Some(self.general.max_parallel_downloads.to_string())
+            }
+            ["general", "default_profile"] => self.general.default_profile.clone(),
+
+            ["audio", "format"] => Some(self.audio.format.clone()),
+            ["audio", "bitrate"] => Some(self.audio.bitrate.clone()),
+
+            ["video", "format"] => Some(self.video.format.clone()),
+            ["video", "include_thumbnail"] => Some(self.video.include_thumbnail.to_string()),
+            ["video", "include_subtitles"] => Some(self.video.include_subtitles.to_string()),
+            ["video", "write_info_json"] => Some(self.video.write_info_json.to_string()),
+            ["video", "write_nfo"] => Some(self.video.write_nfo.to_string()),
+
+            ["network", "rate_limit"] => self.network.rate_limit.clone(),
+            ["network", "retry_attempts"] => Some(self.network.retry_attempts.to_string()),
+            ["network", "timeout"] => Some(self.network.timeout.to_string()),
+
+            _ => None,
+        }
+    }
+
+    /// Sets a configuration value by dot-notation key.
+    ///
+    /// The value is parsed according to the field type. For numeric fields,
+    /// the value must be a valid integer. For boolean fields, the value must
+    /// be `"true"` or `"false"`.
+    ///
+    /// Note: This method does **not** save the configuration to disk.
+    /// Call [`Config::save()`] after making changes.
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if the key is unknown
+    /// - Returns an error if the value cannot be parsed for the field type
+    ///   (e.g., `"abc"` for a numeric field)
+    /// - Returns an error if the value is not valid for the key (an unknown
+    ///   quality or format, a malformed bitrate or rate limit, or zero where at
+    ///   least 1 is required)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// let mut config = Config::load()?;
+    ///
+    /// // Set various configuration values
+    /// config.set("general.default_quality", "1080p")?;
+    /// config.set("general.max_parallel_downloads", "5")?;
+    /// config.set("video.include_thumbnail", "false")?;
+    /// config.set("network.rate_limit", "5M")?;
+    ///
+    /// // Clear optional values with "none" or empty string
+    /// config.set("network.rate_limit", "none")?;
+    ///
+    /// // Save changes to disk
+    /// config.save()?;
+    /// # Ok::<(), rust_yt_downloader::error::AppError>(())
+    /// ```
+    pub fn set(&mut self, key: &str, value: &str) -> AppResult<()> {
+        validate::check_value(key, value).map_err(|message| AppError::ConfigInvalid {
+            field: key.to_string(),
+            message,
+        })?;
+
+        let parts: Vec<&str> = key.split('.').collect();
+
+        match parts.as_slice() {
+            ["general", "output_dir"] => {
+                self.general.output_dir = value.to_string();
+            }
+            ["general", "default_quality"] => {
+                self.general.default_quality = value.to_string();
+            }
+            ["general", "max_parallel_downloads"] => {
+                self.general.max_parallel_downloads =
+                    value.parse().map_err(|_| AppError::ConfigInvalid {
+                        field: key.to_string(),
+                        message: "must be a positive integer".to_string(),
+                    })?;
+            }
+            ["general", "default_profile"] => {
+                self.general.default_profile = if value.is_empty() || value == "none" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+            }
+
+            ["audio", "format"] => {
+                self.audio.format = value.to_string();
+            }
+            ["audio", "bitrate"] => {
+                self.audio.bitrate = value.to_string();
+            }
+
+            ["video", "format"] => {
+                self.video.format = value.to_string();
+            }
+            ["video", "include_thumbnail"] => {
+                self.video.include_thumbnail =
+                    value.parse().map_err(|_| AppError::ConfigInvalid {
+                        field: key.to_string(),
+                        message: "must be true or false".to_string(),
+                    })?;
+            }
+            ["video", "include_subtitles"] => {
+                self.video.include_subtitles =
+                    value.parse().map_err(|_| AppError::ConfigInvalid {
+                        field: key.to_string(),
+                        message: "must be true or false".to_string(),
+                    })?;
+            }
+            ["video", "write_info_json"] => {
+                self.video.write_info_json =
+                    value.parse().map_err(|_| AppError::ConfigInvalid {
+                        field: key.to_string(),
+                        message: "must be true or false".to_string(),
+                    })?;
+            }
+            ["video", "write_nfo"] => {
+                self.video.write_nfo = value.parse().map_err(|_| AppError::ConfigInvalid {
+                    field: key.to_string(),
+                    message: "must be true or false".to_string(),
+                })?;
+            }
+
+            ["network", "rate_limit"] => {
+                self.network.rate_limit = if value.is_empty() || value == "none" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+            }
+            ["network", "retry_attempts"] => {
+                self.network.retry_attempts =
+                    value.parse().map_err(|_| AppError::ConfigInvalid {
+                        field: key.to_string(),
+                        message: "must be a positive integer".to_string(),
+                    })?;
+            }
+            ["network", "timeout"] => {
+                self.network.timeout = value.parse().map_err(|_| AppError::ConfigInvalid {
+                    field: key.to_string(),
+                    message: "must be a positive integer".to_string(),
+                })?;
+            }
+
+            _ => {
+                return Err(AppError::ConfigInvalid {
+                    field: key.to_string(),
+                    message: "unknown configuration key".to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a list of all valid configuration keys.
+    ///
+    /// These keys can be used with [`Config::get()`] and [`Config::set()`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// for key in Config::keys() {
+    ///     println!("{}", key);
+    /// }
+    /// ```
+    pub fn keys() -> Vec<&'static str> {
+        vec![
+            "general.output_dir",
+            "general.default_quality",
+            "general.max_parallel_downloads",
+            "general.default_profile",
+            "audio.format",
+            "audio.bitrate",
+            "video.format",
+            "video.include_thumbnail",
+            "video.include_subtitles",
+            "video.write_info_json",
+            "video.write_nfo",
+            "network.rate_limit",
+            "network.retry_attempts",
+            "network.timeout",
+        ]
+    }
+
+    /// Returns the configuration with a profile's overrides applied.
+    ///
+    /// `name` takes precedence over `general.default_profile`. When neither
+    /// names a profile the configuration is returned unchanged.
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if the profile does not exist
+    /// - Returns an error if the profile contains an unknown key or invalid value
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_yt_downloader::config::Config;
+    ///
+    /// let config = Config::load()?.with_profile(Some("mobile"))?;
+    /// println!("Quality: {}", config.general.default_quality);
+    /// # Ok::<(), rust_yt_downloader::error::AppError>(())
+    /// ```
+    pub fn with_profile(mut self, name: Option<&str>) -> AppResult<Self> {
+        let name = match name
+            .map(String::from)
+            .or(self.general.default_profile.clone())
+        {
+            Some(name) => name,
+            None => return Ok(self),
+        };
+
+        let profile = self
+            .profiles
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| AppError::ConfigInvalid {
+                field: "profile".to_string(),
+                message: format!("unknown profile '{}'", name),
+            })?;
+
+        for (key, value) in profile.entries() {
+            self.set(&key, &value).map_err(|e| match e {
+                AppError::ConfigInvalid { field, message } => AppError::ConfigInvalid {
+                    field: format!("profiles.{}.{}", name, field),
+                    message,
+                },
+                other => other,
+            })?;
+        }
+
+        Ok(self)
+    }
+
+    /// Returns the names of all configured profiles in sorted order.
+    pub fn profile_names(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+}
+
+// ============== Profile Implementation ==============
+
+impl Profile {
+    /// Gets an override by dot-notation key, or `None` if the profile leaves it unset.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let (section, field) = key.split_once('.')?;
+        self.sections.get(section)?.get(field).map(value_to_string)
+    }
+
+    /// Sets an override by dot-notation key.
+    ///
+    /// The key and value are validated exactly like [`Config::set()`], and the
+    /// value is stored with the field's TOML type.
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if the key is unknown or is `general.default_profile`
+    /// - Returns an error if the value cannot be parsed for the field type
+    pub fn set(&mut self, key: &str, value: &str) -> AppResult<()> {
+        if key == "general.default_profile" {
+            return Err(AppError::ConfigInvalid {
+                field: key.to_string(),
+                message: "cannot be set inside a profile".to_string(),
+            });
+        }
+
+        let mut scratch = Config::default();
+        scratch.set(key, value)?;
+
+        let (section, field) = key.split_once('.').unwrap_or((key, ""));
+        let typed =
+            typed_value(&scratch, key)?.unwrap_or_else(|| toml::Value::String(String::new()));
+
+        self.sections
+            .entry(section.to_string())
+            .or_default()
+            .insert(field.to_string(), typed);
+
+        Ok(())
+    }
+
+    /// Returns every override as a `(key, value)` pair in dot notation.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        self.sections
+            .iter()
+            .flat_map(|(section, fields)| {
+                fields.iter().map(move |(field, value)| {
+                    (format!("{}.{}", section, field), value_to_string(value))
+                })
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the profile overrides nothing.
+    pub fn is_empty(&self) -> bool {
+        self.sections.values().all(BTreeMap::is_empty)
+    }
+
+    /// Adds every override from `other`, replacing values for keys both set.
+    pub fn merge(&mut self, other: Profile) {
+        for (section, fields) in other.sections {
+            self.sections.entry(section).or_default().extend(fields);
+        }
+    }
+}
+
+/// Returns `key` from `config` as a properly typed TOML value, or `None` if
+/// the value is unset.
+fn typed_value(config: &Config, key: &str) -> AppResult<Option<toml::Value>> {
+    let (section, field) = key.split_once('.').unwrap_or((key, ""));
+    Ok(toml::Value::try_from(config)?
+        .get(section)
+        .and_then(|table| table.get(field))
+        .cloned())
+}
+
+/// Converts a TOML value into the string form accepted by [`Config::set()`].
+fn value_to_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// ============== Layered Loading ==============
+
+/// Name of the project-local configuration file.
+pub const PROJECT_CONFIG_FILE: &str = ".ytdl.toml";
+
+/// Prefix of the environment variables that override configuration keys.
+pub const ENV_PREFIX: &str = "YTDL_";
+
+/// Where an effective configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Built-in default value
+    Default,
+    /// The user configuration file (or the one passed with `--config`)
+    UserFile(PathBuf),
+    /// A project-local `.ytdl.toml`
+    ProjectFile(PathBuf),
+    /// A `[profiles.<name>]` table
+    Profile(String),
+    /// A `YTDL_*` environment variable
+    Env(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::UserFile(path) => write!(f, "user file {}", path.display()),
+            Self::ProjectFile(path) => write!(f, "project file {}", path.display()),
+            Self::Profile(name) => write!(f, "profile {}", name),
+            Self::Env(var) => write!(f, "env {}", var),
+        }
+    }
+}
+
+/// An effective configuration together with the origin of every key.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    /// The merged configuration
+    pub config: Config,
+    /// The profile that was applied, if any
+    pub profile: Option<String>,
+    origins: BTreeMap<String, ConfigSource>,
+}
+
+impl LoadedConfig {
+    /// Returns where the value of `key` came from.
+    pub fn origin(&self, key: &str) -> &ConfigSource {
+        self.origins.get(key).unwrap_or(&ConfigSource::Default)
+    }
+}
+
+/// Builds the effective configuration from every layer.
+///
+/// Layers are applied from lowest to highest precedence:
+///
+/// 1. Built-in defaults
+/// 2. User file (`Config::config_path()`, `--config` or `YTDL_CONFIG`)
+/// 3. Project file (the nearest `.ytdl.toml` in the working directory or above)
+/// 4. Profile (`--profile`, `YTDL_PROFILE` or `general.default_profile`)
+/// 5. Environment variables (`YTDL_<SECTION>_<KEY>`, e.g. `YTDL_AUDIO_FORMAT`)
+///
+/// Command-line flags sit above all of these and are applied by each command.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_yt_downloader::config::ConfigLoader;
+///
+/// let config = ConfigLoader::new().with_profile(Some("mobile".to_string())).load()?;
+/// println!("Quality: {}", config.general.default_quality);
+/// # Ok::<(), rust_yt_downloader::error::AppError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+    search_dir: Option<PathBuf>,
+    env: Option<BTreeMap<String, String>>,
+}
+
+impl ConfigLoader {
+    /// Creates a loader that reads the process environment and searches for a
+    /// project file from the current directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `path` as the user file instead of the default location.
+    pub fn with_config_path(mut self, path: Option<PathBuf>) -> Self {
+        self.config_path = path;
+        self
+    }
+
+    /// Selects a profile, overriding `YTDL_PROFILE` and `general.default_profile`.
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Searches for `.ytdl.toml` from `dir` instead of the current directory.
+    pub fn with_search_dir(mut self, dir: PathBuf) -> Self {
+        self.search_dir = Some(dir);
+        self
+    }
+
+    /// Uses the given variables instead of the process environment.
+    pub fn with_env<I>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        self.env = Some(vars.into_iter().collect());
+        self
+    }
+
+    /// Returns the profile selected with [`ConfigLoader::with_profile()`], if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Returns the user file: `--config`, then `YTDL_CONFIG`, then the default path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no path was given and the system config directory
+    /// cannot be determined.
+    pub fn user_path(&self) -> AppResult<PathBuf> {
+        if let Some(path) = &self.config_path {
+            return Ok(path.clone());
+        }
+
+        match self.env_var("YTDL_CONFIG") {
+            Some(path) if !path.is_empty() => Ok(crate::utils::expand_path(&path)),
+            _ => Config::config_path(),
+        }
+    }
+
+    /// Returns the nearest `.ytdl.toml` in the search directory or its ancestors.
+    pub fn project_path(&self) -> Option<PathBuf> {
+        let start = match &self.search_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().ok()?,
+        };
+
+        start
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the effective configuration.
+    ///
+    /// # Errors
+    ///
+    /// - Returns an error if a configuration file cannot be read or parsed
+    /// - Returns an error if a value in any layer is invalid
+    /// - Returns an error if the selected profile does not exist
+    pub fn load(&self) -> AppResult<Config> {
+        Ok(self.load_with_origins()?.config)
+    }
+
+    /// Loads the effective configuration and records where each value came from.
+    ///
+    /// # Errors
+    ///
+    /// See [`ConfigLoader::load()`].
+    pub fn load_with_origins(&self) -> AppResult<LoadedConfig> {
+        let mut loaded = LoadedConfig {
+            config: Config::default(),
+            profile: None,
+            origins: BTreeMap::new(),
+        };
+
+        let user_path = self.user_path()?;
+        Self::apply_file(
+            &mut loaded,
+            &user_path,
+            ConfigSource::UserFile(user_path.clone()),
+        )?;
+
+        if let Some(project_path) = self.project_path() {
+            if project_path != user_path {
+                let source = ConfigSource::ProjectFile(project_path.clone());
+                Self::apply_file(&mut loaded, &project_path, source)?;
+            }
+        }
+
+        let profile = self
+            .profile
+            .clone()
+            .or_else(|| self.env_var("YTDL_PROFILE"))
+            .or_else(|| self.env_var("YTDL_GENERAL_DEFAULT_PROFILE"))
+            .or_else(|| loaded.config.general.default_profile.clone())
+            .filter(|name| !name.is_empty() && name != "none");
+
+        if let Some(name) = &profile {
+            let overlay = loaded.config.clone().with_profile(Some(name))?;
+            let source = ConfigSource::Profile(name.clone());
+            if let Some(entries) = loaded.config.profiles.get(name).map(Profile::entries) {
+                for (key, _) in entries {
+                    loaded.origins.insert(key, source.clone());
+                }
+            }
+            loaded.config = overlay;
+        }
+        loaded.profile = profile;
+
+        for key in Config::keys() {
+            let var = Self::env_name(key);
+            if let Some(value) = self.env_var(&var) {
+                loaded.config.set(key, &value).map_err(|e| match e {
+                    AppError::ConfigInvalid { message, .. } => AppError::ConfigInvalid {
+                        field: var.clone(),
+                        message,
+                    },
+                    other => other,
+                })?;
+                loaded
+                    .origins
+                    .insert(key.to_string(), ConfigSource::Env(var));
+            }
+        }
+
+        Ok(loaded)
+    }
+
+    /// Returns the environment variable that overrides `key`.
+    ///
+    /// `general.output_dir` becomes `YTDL_GENERAL_OUTPUT_DIR`.
+    pub fn env_name(key: &str) -> String {
+        format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+    }
+
+    fn env_var(&self, name: &str) -> Option<String> {
+        match &self.env {
+            Some(vars) => vars.get(name).cloned(),
+            None => std::env::var(name).ok(),
+        }
+    }
+
+    /// Adds a file's `[[rules]]` in front of the rules loaded so far, so a
+    /// project file's rules are checked before the user file's.
+    fn apply_rules(loaded: &mut LoadedConfig, path: &Path, value: &toml::Value) -> AppResult<()> {
+        if let Some(entries) = value.as_array() {
+            for (index, entry) in entries.iter().enumerate() {
+                let unknown = entry
+                    .as_table()
+                    .into_iter()
+                    .flat_map(|table| table.keys())
+                    .filter(|key| !Rule::KEYS.contains(&key.as_str()));
+                for key in unknown {
+                    messages::warning(&format!(
+                        "{}: ignoring unknown setting 'rules[{}].{}'",
+                        path.display(),
+                        index + 1,
+                        key
+                    ));
+                }
+            }
+        }
+
+        let rules: Vec<Rule> = value
+            .clone()
+            .try_into()
+            .map_err(|e| AppError::ConfigParse {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+
+        for (index, rule) in rules.iter().enumerate() {
+            if let Some((key, message)) = rule.check().into_iter().next() {
+                return Err(AppError::ConfigInvalid {
+                    field: format!("rules[{}].{}", index + 1, key),
+                    message: format!("{} (in {})", message, path.display()),
+                });
+            }
+        }
+
+        let earlier = std::mem::replace(&mut loaded.config.rules, rules);
+        loaded.config.rules.extend(earlier);
+
+        Ok(())
+    }
+
+    /// Applies the keys a single file sets, leaving the others untouched.
+    ///
+    /// Unknown keys are skipped with a warning rather than dropped silently.
+    /// Only the user file is upgraded on disk; an older project file is
+    /// migrated in memory and left as it is.
+    fn apply_file(loaded: &mut LoadedConfig, path: &Path, source: ConfigSource) -> AppResult<()> {
+        let table = if matches!(source, ConfigSource::ProjectFile(_)) {
+            migrate::load_table(path)?
+        } else {
+            migrate::read_table(path)?
+        };
+        let Some(table) = table else {
+            return Ok(());
+        };
+
+        for (section, value) in &table {
+            if section == "profiles" {
+                let profiles: BTreeMap<String, Profile> =
+                    value
+                        .clone()
+                        .try_into()
+                        .map_err(|e| AppError::ConfigParse {
+                            path: path.to_path_buf(),
+                            source: e,
+                        })?;
+                for (name, profile) in profiles {
+                    loaded
+                        .config
+                        .profiles
+                        .entry(name)
+                        .or_default()
+                        .merge(profile);
+                }
+                continue;
+            }
+
+            if section == "version" {
+                continue;
+            }
+
+            if section == "rules" {
+                Self::apply_rules(loaded, path, value)?;
+                continue;
+            }
+
+            let Some(fields) = value.as_table() else {
+                messages::warning(&format!(
+                    "{}: ignoring unknown setting '{}'",
+                    path.display(),
+                    section
+                ));
+                continue;
+            };
+
+            for (field, value) in fields {
+                let key = format!("{}.{}", section, field);
+                if !Config::keys().contains(&key.as_str()) {
+                    messages::warning(&format!(
+                        "{}: ignoring unknown setting '{}'",
+                        path.display(),
+                        key
+                    ));
+                    continue;
+                }
+
+                loaded
+                    .config
+                    .set(&key, &value_to_string(value))
+                    .map_err(|e| match e {
+                        AppError::ConfigInvalid { field, message } => AppError::ConfigInvalid {
+                            field,
+                            message: format!("{} (in {})", message, path.display()),
+                        },
+                        other => other,
+                    })?;
+                loaded.origins.insert(key, source.clone());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    // ============== Helper Functions ==============
+
+    /// Creates a temporary config file for testing
+    fn create_temp_config(content: &str) -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, content).unwrap();
+        (temp_dir, config_path)
+    }
+
+    // ============== Default Values Tests ==============
+
+    #[test]
+    fn test_config_default() {
+        let config = Config::default();
+
+        assert_eq!(config.general.default_quality, "best");
+        assert_eq!(config.general.max_parallel_downloads, 3);
+        assert_eq!(config.audio.format, "mp3");
+        assert_eq!(config.audio.bitrate, "320k");
+        assert_eq!(config.video.format, "mp4");
+        assert!(config.video.include_thumbnail);
+        assert!(config.video.include_subtitles);
+        assert_eq!(config.network.retry_attempts, 3);
+        assert_eq!(config.network.timeout, 300);
+        assert!(config.network.rate_limit.is_none());
+    }
+
+    #[test]
+    fn test_general_config_default() {
+        let general = GeneralConfig::default();
+
+        assert_eq!(general.default_quality, "best");
+        assert_eq!(general.max_parallel_downloads, 3);
+        assert!(general.output_dir.contains("YouTube"));
+    }
+
+    #[test]
+    fn test_audio_config_default() {
+        let audio = AudioConfig::default();
+
+        assert_eq!(audio.format, "mp3");
+        assert_eq!(audio.bitrate, "320k");
+    }
+
+    #[test]
+    fn test_video_config_default() {
+        let video = VideoConfig::default();
+
+        assert_eq!(video.format, "mp4");
+        assert!(video.include_thumbnail);
+        assert!(video.include_subtitles);
+    }
+
+    #[test]
+    fn test_network_config_default() {
+        let network = NetworkConfig::default();
+
+        assert!(network.rate_limit.is_none());
+        assert_eq!(network.retry_attempts, 3);
+        assert_eq!(network.timeout, 300);
+    }
+
+    // ============== Config Path Tests ==============
+
+    #[test]
+    fn test_config_path_ends_with_correct_filename() {
+        let path = Config::config_path().unwrap();
+
+        assert!(path.ends_with("rust-yt-downloader/config.toml"));
+    }
+
+    #[test]
+    fn test_config_path_is_absolute() {
+        let path = Config::config_path().unwrap();
+
+        assert!(path.is_absolute());
+    }
+
+    // ============== TOML Parsing Tests ==============
+
+    #[test]
+    fn test_parse_full_config() {
+        let toml_content = r#"
+[general]
+output_dir = "/custom/path"
+default_quality = "1080p"
+max_parallel_downloads = 5
+
+[audio]
+format = "flac"
+bitrate = "256k"
+
+[video]
+format = "mkv"
+include_thumbnail = false
+include_subtitles = false
+
+[network]
+rate_limit = "10M"
+retry_attempts = 5
+timeout = 600
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+
+        assert_eq!(config.general.output_dir, "/custom/path");
+        assert_eq!(config.general.default_quality, "1080p");
+        assert_eq!(config.general.max_parallel_downloads, 5);
+        assert_eq!(config.audio.format, "flac");
+        assert_eq!(config.audio.bitrate, "256k");
+        assert_eq!(config.video.format, "mkv");
+        assert!(!config.video.include_thumbnail);
+        assert!(!config.video.include_subtitles);
+        assert_eq!(config.network.rate_limit, Some("10M".to_string()));
+        assert_eq!(config.network.retry_attempts, 5);
+        assert_eq!(config.network.timeout, 600);
+    }
+
+    #[test]
+    fn test_parse_partial_config_uses_defaults() {
+        let toml_content = r#"
+[general]
+default_quality = "720p"
+"#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+
+        // Specified value
+        assert_eq!(config.general.default_quality, "720p");
+
+        // Default values
+        assert_eq!(config.general.max_parallel_downloads, 3);
+        assert_eq!(config.audio.format, "mp3");
+        assert_eq!(config.video.format, "mp4");
+        assert_eq!(config.network.retry_attempts, 3);
+    }
+
+    #[test]
+    fn test_parse_empty_config_uses_all_defaults() {
+        let toml_content = "";
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+
+        assert_eq!(config.general.default_quality, "best");
+        assert_eq!(config.audio.format, "mp3");
+        assert_eq!(config.video.format, "mp4");
+        assert_eq!(config.network.retry_attempts, 3);
+    }
+
+    // ============== Serialization Tests ==============
+
+    #[test]
+    fn test_serialize_config() {
+        let config = Config::default();
+        let toml_string = toml::to_string_pretty(&config);
+
+        assert!(toml_string.is_ok());
+
+        let serialized = toml_string.unwrap();
+        assert!(serialized.contains("[general]"));
+        assert!(serialized.contains("[audio]"));
+        assert!(serialized.contains("[video]"));
+        assert!(serialized.contains("[network]"));
+    }
+
+    #[test]
+    fn test_roundtrip_serialization() {
+        let original = Config::default();
+        let toml_string = toml::to_string_pretty(&original).unwrap();
+        let parsed: Config = toml::from_str(&toml_string).unwrap();
+
+        assert_eq!(
+            original.general.default_quality,
+            parsed.general.default_quality
+        );
+        assert_eq!(original.audio.format, parsed.audio.format);
+        assert_eq!(original.video.format, parsed.video.format);
+        assert_eq!(original.network.timeout, parsed.network.timeout);
+    }
+
+    // ============== Get Method Tests ==============
+
+    #[test]
+    fn test_get_general_output_dir() {
+        let config = Config::default();
+        let value = config.get("general.output_dir");
+
+        assert!(value.is_some());
+        assert!(value.unwrap().contains("YouTube"));
+    }
+
+    #[test]
+    fn test_get_general_default_quality() {
+        let config = Config::default();
+        let value = config.get("general.default_quality");
+
+        assert_eq!(value, Some("best".to_string()));
+    }
+
+    #[test]
+    fn test_get_general_max_parallel_downloads() {
+        let config = Config::default();
+        let value = config.get("general.max_parallel_downloads");
+
+        assert_eq!(value, Some("3".to_string()));
+    }
+
+    #[test]
+    fn test_get_audio_format() {
+        let config = Config::default();
+        let value = config.get("audio.format");
+
+        assert_eq!(value, Some("mp3".to_string()));
+    }
+
+    #[test]
+    fn test_get_audio_bitrate() {
+        let config = Config::default();
+        let value = config.get("audio.bitrate");
+
+        assert_eq!(value, Some("320k".to_string()));
+    }
+
+    #[test]
+    fn test_get_video_format() {
+        let config = Config::default();
+        let value = config.get("video.format");
+
+        assert_eq!(value, Some("mp4".to_string()));
+    }
+
+    #[test]
+    fn test_get_video_include_thumbnail() {
+        let config = Config::default();
+        let value = config.get("video.include_thumbnail");
+
+        assert_eq!(value, Some("true".to_string()));
+    }
+
+    #[test]
+    fn test_get_video_include_subtitles() {
+        let config = Config::default();
+        let value = config.get("video.include_subtitles");
+
+        assert_eq!(value, Some("true".to_string()));
+    }
+
+    #[test]
+    fn test_get_network_rate_limit_none() {
+        let config = Config::default();
+        let value = config.get("network.rate_limit");
+
+        assert!(value.is_none());
+    }
+
+    #[test]
+    fn test_get_network_rate_limit_some() {
+        let mut config = Config::default();
+        config.network.rate_limit = Some("5M".to_string());
+
+        let value = config.get("network.rate_limit");
+        assert_eq!(value, Some("5M".to_string()));
+    }
+
+    #[test]
+    fn test_get_network_retry_attempts() {
+        let config = Config::default();
+        let value = config.get("network.retry_attempts");
+
+        assert_eq!(value, Some("3".to_string()));
+    }
+
+    #[test]
+    fn test_get_network_timeout() {
+        let config = Config::default();
+        let value = config.get("network.timeout");
+
+        assert_eq!(value, Some("300".to_string()));
+    }
+
+    #[test]
+    fn test_get_unknown_key_returns_none() {
+        let config = Config::default();
+
+        assert!(config.get("unknown").is_none());
+        assert!(config.get("general.unknown").is_none());
+        assert!(config.get("unknown.field").is_none());
+        assert!(config.get("").is_none());
+    }
+
+    // ============== Set Method Tests ==============
+
+    #[test]
+    fn test_set_general_output_dir() {
+        let mut config = Config::default();
+        let result = config.set("general.output_dir", "/new/path");
+
+        assert!(result.is_ok());
+        assert_eq!(config.general.output_dir, "/new/path");
+    }
+
+    #[test]
+    fn test_set_general_default_quality() {
+        let mut config = Config::default();
+        let result = config.set("general.default_quality", "1080p");
+
+        assert!(result.is_ok());
+        assert_eq!(config.general.default_quality, "1080p");
+    }
+
+    #[test]
+    fn test_set_general_max_parallel_downloads() {
+        let mut config = Config::default();
+        let result = config.set("general.max_parallel_downloads", "5");
+
+        assert!(result.is_ok());
+        assert_eq!(config.general.max_parallel_downloads, 5);
+    }
+
+    #[test]
+    fn test_set_general_max_parallel_downloads_invalid() {
+        let mut config = Config::default();
+        let result = config.set("general.max_parallel_downloads", "not_a_number");
+
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            AppError::ConfigInvalid { field, message } => {
+                assert_eq!(field, "general.max_parallel_downloads");
+                assert!(message.contains("positive integer"));
+            }
+            _ => panic!("Expected ConfigInvalid error"),
+        }
+    }
+
+    #[test]
+    fn test_set_audio_format() {
+        let mut config = Config::default();
+        let result = config.set("audio.format", "flac");
+
+        assert!(result.is_ok());
+        assert_eq!(config.audio.format, "flac");
+    }
+
+    #[test]
+    fn test_set_audio_bitrate() {
+        let mut config = Config::default();
+        let result = config.set("audio.bitrate", "256k");
+
+        assert!(result.is_ok());
+        assert_eq!(config.audio.bitrate, "256k");
+    }
+
+    #[test]
+    fn test_set_video_format() {
+        let mut config = Config::default();
+        let result = config.set("video.format", "mkv");
+
+        assert!(result.is_ok());
+        assert_eq!(config.video.format, "mkv");
+    }
+
+    #[test]
+    fn test_set_video_include_thumbnail_true() {
+        let mut config = Config::default();
+        config.video.include_thumbnail = false;
+
+        let result = config.set("video.include_thumbnail", "true");
+
+        assert!(result.is_ok());
+        assert!(config.video.include_thumbnail);
+    }
+
+    #[test]
+    fn test_set_video_include_thumbnail_false() {
+        let mut config = Config::default();
+        let result = config.set("video.include_thumbnail", "false");
+
+        assert!(result.is_ok());
+        assert!(!config.video.include_thumbnail);
+    }
+
+    #[test]
+    fn test_set_video_include_thumbnail_invalid() {
+        let mut config = Config::default();
+        let result = config.set("video.include_thumbnail", "yes");
+
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            AppError::ConfigInvalid { field, message } => {
+                assert_eq!(field, "video.include_thumbnail");
+                assert!(message.contains("true or false"));
+            }
+            _ => panic!("Expected ConfigInvalid error"),
+        }
+    }
+
+    #[test]
+    fn test_set_video_sidecars() {
+        let mut config = Config::default();
+        assert!(!config.video.write_info_json);
+        assert!(!config.video.write_nfo);
+
+        config.set("video.write_info_json", "true").unwrap();
+        config.set("video.write_nfo", "true").unwrap();
+
+        assert_eq!(config.get("video.write_info_json").as_deref(), Some("true"));
+        assert_eq!(config.get("video.write_nfo").as_deref(), Some("true"));
+        assert!(config.set("video.write_nfo", "yes").is_err());
+    }
+
+    #[test]
+    fn test_set_video_include_subtitles() {
+        let mut config = Config::default();
+        let result = config.set("video.include_subtitles", "false");
+
+        assert!(result.is_ok());
+        assert!(!config.video.include_subtitles);
+    }
+
+    #[test]
+    fn test_set_network_rate_limit() {
+        let mut config = Config::default();
+        let result = config.set("network.rate_limit", "10M");
+
+        assert!(result.is_ok());
+        assert_eq!(config.network.rate_limit, Some("10M".to_string()));
+    }
+
+    #[test]
+    fn test_set_network_rate_limit_none() {
+        let mut config = Config::default();
+        config.network.rate_limit = Some("5M".to_string());
+
+        let result = config.set("network.rate_limit", "none");
+
+        assert!(result.is_ok());
+        assert!(config.network.rate_limit.is_none());
+    }
+
+    #[test]
+    fn test_set_network_rate_limit_empty() {
+        let mut config = Config::default();
+        config.network.rate_limit = Some("5M".to_string());
+
+        let result = config.set("network.rate_limit", "");
+
+        assert!(result.is_ok());
+        assert!(config.network.rate_limit.is_none());
+    }
+
+    #[test]
+    fn test_set_network_retry_attempts() {
+        let mut config = Config::default();
+        let result = config.set("network.retry_attempts", "5");
+
+        assert!(result.is_ok());
+        assert_eq!(config.network.retry_attempts, 5);
+    }
+
+    #[test]
+    fn test_set_network_timeout() {
+        let mut config = Config::default();
+        let result = config.set("network.timeout", "600");
+
+        assert!(result.is_ok());
+        assert_eq!(config.network.timeout, 600);
+    }
+
+    #[test]
+    fn test_set_unknown_key_fails() {
+        let mut config = Config::default();
+        let result = config.set("unknown.key", "value");
+
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            AppError::ConfigInvalid { field, message } => {
+                assert_eq!(field, "unknown.key");
+                assert!(message.contains("unknown"));
+            }
+            _ => panic!("Expected ConfigInvalid error"),
+        }
+    }
+
+    #[test]
+    fn test_set_rejects_unknown_enum_values() {
+        let mut config = Config::default();
+
+        assert!(config.set("general.default_quality", "999p").is_err());
+        assert!(config.set("audio.format", "wma").is_err());
+        assert!(config.set("video.format", "flv").is_err());
+        assert!(config.set("audio.bitrate", "loud").is_err());
+
+        // Rejected values leave the configuration untouched
+        assert_eq!(config.general.default_quality, "best");
+        assert_eq!(config.audio.format, "mp3");
+    }
+
+    // ============== Keys Method Tests ==============
+
+    #[test]
+    fn test_keys_returns_all_keys() {
+        let keys = Config::keys();
+
+        assert!(keys.contains(&"general.output_dir"));
+        assert!(keys.contains(&"general.default_quality"));
+        assert!(keys.contains(&"general.max_parallel_downloads"));
+        assert!(keys.contains(&"general.default_profile"));
+        assert!(keys.contains(&"audio.format"));
+        assert!(keys.contains(&"audio.bitrate"));
+        assert!(keys.contains(&"video.format"));
+        assert!(keys.contains(&"video.include_thumbnail"));
+        assert!(keys.contains(&"video.include_subtitles"));
+        assert!(keys.contains(&"video.write_info_json"));
+        assert!(keys.contains(&"video.write_nfo"));
+        assert!(keys.contains(&"network.rate_limit"));
+        assert!(keys.contains(&"network.retry_attempts"));
+        assert!(keys.contains(&"network.timeout"));
+    }
+
+    #[test]
+    fn test_keys_count() {
+        let keys = Config::keys();
+
+        assert_eq!(keys.len(), 14);
+    }
+
+    // ============== File I/O Tests ==============
+
+    #[test]
+    fn test_save_creates_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir
+            .path()
+            .join("rust-yt-downloader")
+            .join("config.toml");
+
+        // Temporarily override config path for testing
+        let config = Config::default();
+        let content = toml::to_string_pretty(&config).unwrap();
+
+        // Create directory and file manually for this test
+        fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        fs::write(&config_path, content).unwrap();
+
+        assert!(config_path.exists());
+
+        let saved_content = fs::read_to_string(&config_path).unwrap();
+        assert!(saved_content.contains("[general]"));
+        assert!(saved_content.contains("[audio]"));
+    }
+
+    #[test]
+    fn test_parse_config_from_file() {
+        let toml_content = r#"
+[general]
+output_dir = "/test/path"
+default_quality = "720p"
+max_parallel_downloads = 2
+
+[audio]
+format = "m4a"
+bitrate = "128k"
+
+[video]
+format = "webm"
+include_thumbnail = false
+include_subtitles = true
+
+[network]
+retry_attempts = 1
+timeout = 60
+"#;
+
+        let (_temp_dir, config_path) = create_temp_config(toml_content);
+
+        let content = fs::read_to_string(&config_path).unwrap();
+        let config: Config = toml::from_str(&content).unwrap();
+
+        assert_eq!(config.general.output_dir, "/test/path");
+        assert_eq!(config.general.default_quality, "720p");
+        assert_eq!(config.general.max_parallel_downloads, 2);
+        assert_eq!(config.audio.format, "m4a");
+        assert_eq!(config.audio.bitrate, "128k");
+        assert_eq!(config.video.format, "webm");
+        assert!(!config.video.include_thumbnail);
+        assert!(config.video.include_subtitles);
+        assert_eq!(config.network.retry_attempts, 1);
+        assert_eq!(config.network.timeout, 60);
+    }
+
+    // ============== Profile Tests ==============
+
+    const PROFILES_TOML: &str = r#"
+        [general]
+        default_quality = "best"
+
+        [audio]
+        format = "mp3"
+
+        [profiles.podcast]
+        general.output_dir = "~/Podcasts"
+        audio.format = "opus"
+        audio.bitrate = "96k"
+
+        [profiles.mobile.general]
+        default_quality = "720p"
+
+        [profiles.mobile.network]
+        retry_attempts = 5
+    "#;
+
+    #[test]
+    fn test_parse_profiles() {
+        let config: Config = toml::from_str(PROFILES_TOML).unwrap();
+
+        assert_eq!(config.profile_names(), vec!["mobile", "podcast"]);
+        assert_eq!(
+            config.profiles["podcast"].get("audio.bitrate"),
+            Some("96k".to_string())
+        );
+        assert_eq!(
+            config.profiles["mobile"].get("network.retry_attempts"),
+            Some("5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_profile_overlays_base() {
+        let config: Config = toml::from_str(PROFILES_TOML).unwrap();
+        let config = config.with_profile(Some("podcast")).unwrap();
+
+        assert_eq!(config.general.output_dir, "~/Podcasts");
+        assert_eq!(config.audio.format, "opus");
+        assert_eq!(config.audio.bitrate, "96k");
+        // Keys the profile leaves out keep their base value
+        assert_eq!(config.general.default_quality, "best");
+    }
+
+    #[test]
+    fn test_with_profile_none_is_unchanged() {
+        let config: Config = toml::from_str(PROFILES_TOML).unwrap();
+        let config = config.with_profile(None).unwrap();
+
+        assert_eq!(config.audio.format, "mp3");
+        assert_eq!(config.general.default_quality, "best");
+    }
+
+    #[test]
+    fn test_with_profile_uses_default_profile() {
+        let mut config: Config = toml::from_str(PROFILES_TOML).unwrap();
+        config.general.default_profile = Some("mobile".to_string());
+
+        let effective = config.clone().with_profile(None).unwrap();
+        assert_eq!(effective.general.default_quality, "720p");
+        assert_eq!(effective.network.retry_attempts, 5);
+
+        // An explicit profile wins over the default one
+        let effective = config.with_profile(Some("podcast")).unwrap();
+        assert_eq!(effective.general.default_quality, "best");
+        assert_eq!(effective.audio.format, "opus");
+    }
+
+    #[test]
+    fn test_with_profile_unknown_fails() {
+        let config: Config = toml::from_str(PROFILES_TOML).unwrap();
+        let result = config.with_profile(Some("missing"));
+
+        assert!(matches!(result, Err(AppError::ConfigInvalid { .. })));
+    }
+
+    #[test]
+    fn test_with_profile_invalid_value_names_profile() {
+        let toml = r#"
+            [profiles.bad.network]
+            timeout = "soon"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+
+        match config.with_profile(Some("bad")) {
+            Err(AppError::ConfigInvalid { field, .. }) => {
+                assert_eq!(field, "profiles.bad.network.timeout");
+            }
+            other => panic!("Expected ConfigInvalid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_profile_set_stores_typed_values() {
+        let mut profile = Profile::default();
+        profile.set("network.retry_attempts", "7").unwrap();
+        profile.set("video.include_thumbnail", "false").unwrap();
+        profile.set("video.format", "mkv").unwrap();
+
+        let toml = toml::to_string(&profile).unwrap();
+        assert!(toml.contains("retry_attempts = 7"));
+        assert!(toml.contains("include_thumbnail = false"));
+        assert!(toml.contains("format = \"mkv\""));
+    }
+
+    #[test]
+    fn test_profile_set_validates_like_config() {
+        let mut profile = Profile::default();
+
+        assert!(profile.set("unknown.key", "x").is_err());
+        assert!(profile.set("network.timeout", "abc").is_err());
+        assert!(profile.set("general.default_profile", "other").is_err());
+        assert!(profile.is_empty());
+    }
+
+    #[test]
+    fn test_profile_clears_rate_limit() {
+        let mut config = Config::default();
+        config.network.rate_limit = Some("5M".to_string());
+        config
+            .profiles
+            .entry("fast".to_string())
+            .or_default()
+            .set("network.rate_limit", "none")
+            .unwrap();
+
+        let config = config.with_profile(Some("fast")).unwrap();
+        assert!(config.network.rate_limit.is_none());
+    }
+
+    #[test]
+    fn test_profiles_roundtrip() {
+        let config: Config = toml::from_str(PROFILES_TOML).unwrap();
+        let serialized = toml::to_string_pretty(&config).unwrap();
+        let reparsed: Config = toml::from_str(&serialized).unwrap();
+
+        assert_eq!(reparsed.profiles, config.profiles);
+    }
+
+    #[test]
+    fn test_no_profiles_not_serialized() {
+        let serialized = toml::to_string_pretty(&Config::default()).unwrap();
+
+        assert!(!serialized.contains("profiles"));
+        assert!(!serialized.contains("default_profile"));
+    }
+
+    #[test]
+    fn test_default_profile_get_set() {
+        let mut config = Config::default();
+        assert_eq!(config.get("general.default_profile"), None);
+
+        config.set("general.default_profile", "mobile").unwrap();
+        assert_eq!(
+            config.get("general.default_profile"),
+            Some("mobile".to_string())
+        );
+
+        config.set("general.default_profile", "none").unwrap();
+        assert_eq!(config.general.default_profile, None);
+    }
+
+    // ============== Layered Loading Tests ==============
+
+    /// A loader isolated from the real user file, environment and working directory.
+    fn isolated_loader(dir: &TempDir) -> ConfigLoader {
+        ConfigLoader::new()
+            .with_config_path(Some(dir.path().join("user.toml")))
+            .with_search_dir(dir.path().join("project"))
+            .with_env(Vec::new())
+    }
+
+    fn write_layers(dir: &TempDir, user: &str, project: &str) {
+        fs::write(dir.path().join("user.toml"), user).unwrap();
+        let project_dir = dir.path().join("project");
+        fs::create_dir_all(project_dir.join("nested")).unwrap();
+        fs::write(project_dir.join(PROJECT_CONFIG_FILE), project).unwrap();
+    }
+
+    #[test]
+    fn test_loader_defaults_without_files() {
+        let dir = TempDir::new().unwrap();
+        let loaded = isolated_loader(&dir).load_with_origins().unwrap();
+
+        assert_eq!(loaded.config.general.default_quality, "best");
+        assert_eq!(
+            loaded.origin("general.default_quality"),
+            &ConfigSource::Default
+        );
+        assert!(loaded.profile.is_none());
+    }
+
+    #[test]
+    fn test_loader_project_file_overrides_user_file() {
+        let dir = TempDir::new().unwrap();
+        write_layers(
+            &dir,
+            "[general]\ndefault_quality = \"1080p\"\n[audio]\nformat = \"flac\"\n",
+            "[general]\ndefault_quality = \"720p\"\n",
+        );
+
+        let loaded = isolated_loader(&dir).load_with_origins().unwrap();
+
+        assert_eq!(loaded.config.general.default_quality, "720p");
+        assert_eq!(loaded.config.audio.format, "flac");
+        assert!(matches!(
+            loaded.origin("general.default_quality"),
+            ConfigSource::ProjectFile(_)
+        ));
+        assert!(matches!(
+            loaded.origin("audio.format"),
+            ConfigSource::UserFile(_)
+        ));
+        assert_eq!(loaded.origin("video.format"), &ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_loader_finds_project_file_in_ancestor() {
+        let dir = TempDir::new().unwrap();
+        write_layers(&dir, "", "[video]\nformat = \"mkv\"\n");
+
+        let loader = isolated_loader(&dir).with_search_dir(dir.path().join("project/nested"));
+
+        assert_eq!(
+            loader.project_path(),
+            Some(dir.path().join("project").join(PROJECT_CONFIG_FILE))
+        );
+        assert_eq!(loader.load().unwrap().video.format, "mkv");
+    }
+
+    #[test]
+    fn test_loader_migrates_project_file_in_memory() {
+        let dir = TempDir::new().unwrap();
+        let project = "# shared settings\n[video]\nformat = \"MKV\"\n";
+        write_layers(&dir, "[audio]\nformat = \"FLAC\"\n", project);
+
+        let config = isolated_loader(&dir).load().unwrap();
+
+        assert_eq!(config.video.format, "mkv");
+        assert_eq!(config.audio.format, "flac");
+
+        let project_path = dir.path().join("project").join(PROJECT_CONFIG_FILE);
+        assert_eq!(fs::read_to_string(&project_path).unwrap(), project);
+        assert!(!migrate::backup_path(&project_path, 0).exists());
+        assert!(migrate::backup_path(&dir.path().join("user.toml"), 0).exists());
+    }
+
+    #[test]
+    fn test_loader_env_overrides_files_and_profile() {
+        let dir = TempDir::new().unwrap();
+        write_layers(
+            &dir,
+            "[profiles.mobile]\ngeneral.default_quality = \"480p\"\n",
+            "[general]\ndefault_quality = \"720p\"\n",
+        );
+
+        let loaded = isolated_loader(&dir)
+            .with_profile(Some("mobile".to_string()))
+            .with_env([(
+                "YTDL_GENERAL_DEFAULT_QUALITY".to_string(),
+                "360p".to_string(),
+            )])
+            .load_with_origins()
+            .unwrap();
+
+        assert_eq!(loaded.config.general.default_quality, "360p");
+        assert_eq!(
+            loaded.origin("general.default_quality"),
+            &ConfigSource::Env("YTDL_GENERAL_DEFAULT_QUALITY".to_string())
+        );
+    }
+
+    #[test]
+    fn test_loader_profile_overrides_files() {
+        let dir = TempDir::new().unwrap();
+        write_layers(
+            &dir,
+            "[general]\ndefault_profile = \"podcast\"\n[profiles.podcast]\naudio.format = \"opus\"\n",
+            "[audio]\nformat = \"flac\"\n",
+        );
+
+        let loaded = isolated_loader(&dir).load_with_origins().unwrap();
+
+        assert_eq!(loaded.profile.as_deref(), Some("podcast"));
+        assert_eq!(loaded.config.audio.format, "opus");
+        assert_eq!(
+            loaded.origin("audio.format"),
+            &ConfigSource::Profile("podcast".to_string())
+        );
+    }
+
+    #[test]
+    fn test_loader_profile_from_env() {
+        let dir = TempDir::new().unwrap();
+        write_layers(&dir, "[profiles.mobile]\nvideo.format = \"webm\"\n", "");
+
+        let config = isolated_loader(&dir)
+            .with_env([("YTDL_PROFILE".to_string(), "mobile".to_string())])
+            .load()
+            .unwrap();
+
+        assert_eq!(config.video.format, "webm");
+    }
+
+    #[test]
+    fn test_loader_invalid_env_value_names_variable() {
+        let dir = TempDir::new().unwrap();
+        let result = isolated_loader(&dir)
+            .with_env([("YTDL_NETWORK_TIMEOUT".to_string(), "soon".to_string())])
+            .load();
+
+        match result {
+            Err(AppError::ConfigInvalid { field, .. }) => {
+                assert_eq!(field, "YTDL_NETWORK_TIMEOUT");
+            }
+            other => panic!("Expected ConfigInvalid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_loader_invalid_toml_fails() {
+        let dir = TempDir::new().unwrap();
+        write_layers(&dir, "", "[general\n");
+
+        let result = isolated_loader(&dir).load();
+
+        assert!(matches!(result, Err(AppError::ConfigParse { .. })));
+    }
+
+    #[test]
+    fn test_loader_user_path_from_env() {
+        let dir = TempDir::new().unwrap();
+        let loader = ConfigLoader::new()
+            .with_env([("YTDL_CONFIG".to_string(), "/etc/ytdl.toml".to_string())]);
+
+        assert_eq!(loader.user_path().unwrap(), PathBuf::from("/etc/ytdl.toml"));
+
+        // An explicit --config path wins over the environment
+        let explicit = dir.path().join("explicit.toml");
+        let loader = loader.with_config_path(Some(explicit.clone()));
+        assert_eq!(loader.user_path().unwrap(), explicit);
+    }
+
+    #[test]
+    fn test_env_name() {
+        assert_eq!(
+            ConfigLoader::env_name("general.output_dir"),
+            "YTDL_GENERAL_OUTPUT_DIR"
+        );
+        assert_eq!(ConfigLoader::env_name("audio.format"), "YTDL_AUDIO_FORMAT");
+    }
+
+    #[test]
+    fn test_load_from_and_save_to() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("custom.toml");
+
+        let mut config = Config::load_from(&path).unwrap();
+        config.set("audio.bitrate", "192k").unwrap();
+        config.save_to(&path).unwrap();
+
+        assert_eq!(Config::load_from(&path).unwrap().audio.bitrate, "192k");
+    }
+
+    // ============== Rules Tests ==============
+
+    #[test]
+    fn test_parse_rules() {
+        let config: Config = toml::from_str(
+            r#"
+[[rules]]
+name = "music"
+channel = "Lofi Girl"
+media = "audio"
+output_dir = "~/Music/{channel}"
+audio_format = "flac"
+
+[[rules]]
+title = "(?i)lecture"
+quality = "720p"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].name.as_deref(), Some("music"));
+        assert_eq!(config.rules[0].media, Some(MediaKind::Audio));
+        assert_eq!(config.rules[1].quality.as_deref(), Some("720p"));
+    }
+
+    #[test]
+    fn test_rules_roundtrip() {
+        let mut config = Config::default();
+        config.rules.push(Rule {
+            channel: Some("Lofi Girl".to_string()),
+            audio_format: Some("flac".to_string()),
+            ..Default::default()
+        });
+
+        let content = toml::to_string_pretty(&config).unwrap();
+        let parsed: Config = toml::from_str(&content).unwrap();
+
+        assert!(content.contains("[[rules]]"));
+        assert_eq!(parsed.rules, config.rules);
+    }
+
+    #[test]
+    fn test_loader_project_rules_come_first() {
+        let dir = TempDir::new().unwrap();
+        write_layers(
+            &dir,
+            "version = 1\n[[rules]]\nname = \"user\"\n",
+            "version = 1\n[[rules]]\nname = \"project\"\n",
+        );
+
+        let config = isolated_loader(&dir).load().unwrap();
+        let names: Vec<_> = config
+            .rules
+            .iter()
+            .map(|rule| rule.name.as_deref().unwrap())
+            .collect();
+
+        assert_eq!(names, vec!["project", "user"]);
+    }
+
+    #[test]
+    fn test_loader_invalid_rule_fails() {
+        let dir = TempDir::new().unwrap();
+        write_layers(
+            &dir,
+            "version = 1\n[[rules]]\nname = \"ok\"\n\n[[rules]]\nvideo_format = \"flv\"\n",
+            "",
+        );
+
+        let result = isolated_loader(&dir).load();
+
+        assert!(matches!(
+            result,
+            Err(AppError::ConfigInvalid { field, .. }) if field == "rules[2].video_format"
+        ));
+    }
+
+    // ============== Versioning Tests ==============
+
+    #[test]
+    fn test_default_config_is_current_version() {
+        assert_eq!(Config::default().version, CURRENT_VERSION);
+
+        let content = toml::to_string_pretty(&Config::default()).unwrap();
+        assert!(content.starts_with(&format!("version = {}", CURRENT_VERSION)));
+    }
+
+    #[test]
+    fn test_load_from_upgrades_legacy_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[network]\ntimeout = \"60\"\n").unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert_eq!(config.network.timeout, 60);
+        assert!(dir.path().join("config.toml.v0.bak").exists());
+    }
+
+    #[test]
+    fn test_load_from_newer_version_fails() {
+        let (_dir, path) = create_temp_config(&format!("version = {}\n", CURRENT_VERSION + 1));
+
+        let result = Config::load_from(&path);
+
+        assert!(matches!(result, Err(AppError::ConfigInvalid { field, .. }) if field == "version"));
+    }
+
+    #[test]
+    fn test_loader_ignores_unknown_keys() {
+        let dir = TempDir::new().unwrap();
+        write_layers(
+            &dir,
+            "version = 1\n[general]\nfilename_template = \"%(title)s\"\ndefault_quality = \"720p\"\n",
+            "version = 1\n[ffmpeg]\npreset = \"slow\"\n",
+        );
+
+        let config = isolated_loader(&dir).load().unwrap();
+
+        assert_eq!(config.general.default_quality, "720p");
+    }
+
+    #[test]
+    fn test_set_in_file_keeps_unknown_keys() {
+        let (_dir, path) = create_temp_config(
+            "version = 1\n\n[general]\nfilename_template = \"%(title)s\"\n\n[ffmpeg]\npreset = \"slow\"\n",
+        );
+
+        Config::set_in_file(&path, None, "network.timeout", "60").unwrap();
+
+        let table: toml::Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(table["network"]["timeout"].as_integer(), Some(60));
+        assert_eq!(
+            table["general"]["filename_template"].as_str(),
+            Some("%(title)s")
+        );
+        assert_eq!(table["ffmpeg"]["preset"].as_str(), Some("slow"));
+    }
+
+    #[test]
+    fn test_set_in_file_creates_versioned_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("config.toml");
+
+        Config::set_in_file(&path, None, "audio.format", "flac").unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert_eq!(config.audio.format, "flac");
+    }
+
+    #[test]
+    fn test_set_in_file_none_removes_entry() {
+        let (_dir, path) = create_temp_config("version = 1\n\n[network]\nrate_limit = \"5M\"\n");
+
+        Config::set_in_file(&path, None, "network.rate_limit", "none").unwrap();
+
+        assert!(Config::load_from(&path)
+            .unwrap()
+            .network
+            .rate_limit
+            .is_none());
+    }
+
+    #[test]
+    fn test_set_in_file_profile() {
+        let (_dir, path) = create_temp_config("version = 1\n");
+
+        Config::set_in_file(&path, Some("mobile"), "general.default_quality", "720p").unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.general.default_quality, "best");
+        assert_eq!(
+            config.profiles["mobile"].get("general.default_quality"),
+            Some("720p".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_in_file_rejects_invalid_value() {
+        let (_dir, path) = create_temp_config("version = 1\n");
+
+        let result = Config::set_in_file(&path, None, "audio.format", "wma");
+
+        assert!(matches!(result, Err(AppError::ConfigInvalid { .. })));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "version = 1\n");
+    }
+
+    // ============== Clone Tests ==============
+
+    #[test]
+    fn test_config_clone() {
+        let original = Config::default();
+        let cloned = original.clone();
+
+        assert_eq!(
+            original.general.default_quality,
+            cloned.general.default_quality
+        );
+        assert_eq!(original.audio.format, cloned.audio.format);
+        assert_eq!(original.video.format, cloned.video.format);
+        assert_eq!(original.network.timeout, cloned.network.timeout);
+    }
+
+    #[test]
+    fn test_config_clone_is_independent() {
+        let original = Config::default();
+        let mut cloned = original.clone();
+
+        cloned.general.default_quality = "1080p".to_string();
+
+        assert_eq!(original.general.default_quality, "best");
+        assert_eq!(cloned.general.default_quality, "1080p");
+    }
+}
@@ -0,0 +1,629 @@
+//! Routing rules that adjust download settings per video.
+//!
+//! Rules are listed as `[[rules]]` tables in the configuration file. Each
+//! rule has conditions (channel, playlist, URL or title pattern, duration,
+//! media kind) and the settings to use when they all hold:
+//!
+//! ```toml
+//! [[rules]]
+//! name = "music"
+//! channel = "Lofi Girl"
+//! output_dir = "~/Music/{channel}"
+//! audio_format = "flac"
+//!
+//! [[rules]]
+//! name = "lectures"
+//! title = "(?i)lecture"
+//! output_dir = "~/Lectures"
+//! quality = "720p"
+//! ```
+//!
+//! Rules are checked in order once the video's metadata is known, and the
+//! first one that matches is applied. A rule without conditions matches
+//! every video.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::validate::check_value;
+use crate::youtube::{AudioCodec, HdrMode, VideoCodec, VideoInfo};
+
+/// The kind of download a rule applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    /// Audio-only downloads (`ytdl audio`, `playlist --audio-only`)
+    Audio,
+    /// Video downloads
+    Video,
+}
+
+impl fmt::Display for MediaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaKind::Audio => write!(f, "audio"),
+            MediaKind::Video => write!(f, "video"),
+        }
+    }
+}
+
+/// A single `[[rules]]` entry.
+///
+/// Conditions are combined with AND; unset conditions are ignored. Override
+/// values use the same syntax as the corresponding configuration keys, and
+/// `output_dir` may contain `{channel}`, `{playlist}` and `{id}` placeholders.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    /// Name shown by `--explain`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    // ---- Conditions ----
+    /// Channel name (case-insensitive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+
+    /// Playlist ID, taken from the `list=` parameter of the URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlist: Option<String>,
+
+    /// Regular expression searched for in the URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Regular expression searched for in the title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Minimum duration in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_duration: Option<u64>,
+
+    /// Maximum duration in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<u64>,
+
+    /// Kind of download (`audio` or `video`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaKind>,
+
+    // ---- Overrides ----
+    /// Output directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<String>,
+
+    /// Video quality
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<String>,
+
+    /// Explicit yt-dlp format ID(s)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format_id: Option<String>,
+
+    /// Video container format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_format: Option<String>,
+
+    /// Audio format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_format: Option<String>,
+
+    /// Download only the audio track
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_only: Option<bool>,
+
+    /// yt-dlp filename template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename_template: Option<String>,
+
+    /// Preferred video codecs, most preferred first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vcodec: Option<Vec<VideoCodec>>,
+
+    /// Preferred audio codecs, most preferred first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acodec: Option<Vec<AudioCodec>>,
+
+    /// Preferred maximum frame rate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<u32>,
+
+    /// HDR handling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdr: Option<HdrMode>,
+
+    /// Prefer portrait streams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vertical: Option<bool>,
+
+    /// Write `<name>.info.json` next to the download
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_info_json: Option<bool>,
+
+    /// Write a Kodi/Jellyfin `<name>.nfo` next to the download
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_nfo: Option<bool>,
+}
+
+/// What a rule is matched against.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleContext<'a> {
+    /// URL being downloaded
+    pub url: &'a str,
+
+    /// Metadata fetched for the video
+    pub info: &'a VideoInfo,
+
+    /// Playlist the video was requested from, if any
+    pub playlist_id: Option<&'a str>,
+
+    /// Kind of download
+    pub media: MediaKind,
+}
+
+impl Rule {
+    /// Every key a `[[rules]]` table may contain.
+    pub const KEYS: &'static [&'static str] = &[
+        "name",
+        "channel",
+        "playlist",
+        "url",
+        "title",
+        "min_duration",
+        "max_duration",
+        "media",
+        "output_dir",
+        "quality",
+        "format_id",
+        "video_format",
+        "audio_format",
+        "audio_only",
+        "filename_template",
+        "vcodec",
+        "acodec",
+        "fps",
+        "hdr",
+        "vertical",
+        "write_info_json",
+        "write_nfo",
+    ];
+
+    /// Returns `true` if every condition of the rule holds.
+    ///
+    /// Invalid patterns never match; [`Rule::check()`] reports them.
+    pub fn matches(&self, ctx: &RuleContext) -> bool {
+        let channel = self.channel.as_ref().map_or(true, |wanted| {
+            ctx.info
+                .channel
+                .as_ref()
+                .is_some_and(|channel| channel.eq_ignore_ascii_case(wanted))
+        });
+        let playlist = self
+            .playlist
+            .as_ref()
+            .map_or(true, |wanted| ctx.playlist_id == Some(wanted.as_str()));
+        let url = self
+            .url
+            .as_ref()
+            .map_or(true, |pattern| is_match(pattern, ctx.url));
+        let title = self
+            .title
+            .as_ref()
+            .map_or(true, |pattern| is_match(pattern, &ctx.info.title));
+        let min = self
+            .min_duration
+            .map_or(true, |min| ctx.info.duration >= min);
+        let max = self
+            .max_duration
+            .map_or(true, |max| ctx.info.duration <= max);
+        let media = self.media.map_or(true, |media| media == ctx.media);
+
+        channel && playlist && url && title && min && max && media
+    }
+
+    /// Returns how the rule is referred to in messages, e.g. `#2 (music)`.
+    ///
+    /// `index` is the rule's zero-based position in the list.
+    pub fn label(&self, index: usize) -> String {
+        match &self.name {
+            Some(name) => format!("#{} ({})", index + 1, name),
+            None => format!("#{}", index + 1),
+        }
+    }
+
+    /// Returns the rule's conditions as `(key, value)` pairs.
+    pub fn conditions(&self) -> Vec<(&'static str, String)> {
+        let mut entries = Vec::new();
+        push(&mut entries, "channel", &self.channel);
+        push(&mut entries, "playlist", &self.playlist);
+        push(&mut entries, "url", &self.url);
+        push(&mut entries, "title", &self.title);
+        push(&mut entries, "min_duration", &self.min_duration);
+        push(&mut entries, "max_duration", &self.max_duration);
+        push(&mut entries, "media", &self.media);
+        entries
+    }
+
+    /// Returns the settings the rule overrides as `(key, value)` pairs.
+    pub fn overrides(&self) -> Vec<(&'static str, String)> {
+        let mut entries = Vec::new();
+        push(&mut entries, "output_dir", &self.output_dir);
+        push(&mut entries, "quality", &self.quality);
+        push(&mut entries, "format_id", &self.format_id);
+        push(&mut entries, "video_format", &self.video_format);
+        push(&mut entries, "audio_format", &self.audio_format);
+        push(&mut entries, "audio_only", &self.audio_only);
+        push(&mut entries, "filename_template", &self.filename_template);
+        if let Some(codecs) = &self.vcodec {
+            entries.push(("vcodec", list(codecs)));
+        }
+        if let Some(codecs) = &self.acodec {
+            entries.push(("acodec", list(codecs)));
+        }
+        push(&mut entries, "fps", &self.fps);
+        if let Some(hdr) = &self.hdr {
+            entries.push(("hdr", list(std::slice::from_ref(hdr))));
+        }
+        push(&mut entries, "vertical", &self.vertical);
+        push(&mut entries, "write_info_json", &self.write_info_json);
+        push(&mut entries, "write_nfo", &self.write_nfo);
+        entries
+    }
+
+    /// Checks the rule's patterns and values.
+    ///
+    /// Returns every problem as a `(key, message)` pair; empty when the rule
+    /// is valid.
+    pub fn check(&self) -> Vec<(&'static str, String)> {
+        let mut problems = Vec::new();
+
+        for (key, pattern) in [("url", &self.url), ("title", &self.title)] {
+            if let Some(pattern) = pattern {
+                if let Err(e) = Regex::new(pattern) {
+                    problems.push((key, format!("invalid regular expression: {}", e)));
+                }
+            }
+        }
+
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                problems.push((
+                    "max_duration",
+                    format!("must not be less than min_duration ({})", min),
+                ));
+            }
+        }
+
+        let values = [
+            ("quality", "general.default_quality", &self.quality),
+            ("video_format", "video.format", &self.video_format),
+            ("audio_format", "audio.format", &self.audio_format),
+        ];
+        for (key, config_key, value) in values {
+            if let Some(value) = value {
+                if let Err(message) = check_value(config_key, value) {
+                    problems.push((key, message));
+                }
+            }
+        }
+
+        if self
+            .format_id
+            .as_deref()
+            .is_some_and(|id| id.trim().is_empty())
+        {
+            problems.push(("format_id", "must not be empty".to_string()));
+        }
+
+        if self.fps == Some(0) {
+            problems.push(("fps", "must be greater than 0".to_string()));
+        }
+
+        problems
+    }
+}
+
+/// Returns the index and rule of the first rule that matches.
+pub fn find_match<'r>(rules: &'r [Rule], ctx: &RuleContext) -> Option<(usize, &'r Rule)> {
+    rules.iter().enumerate().find(|(_, rule)| rule.matches(ctx))
+}
+
+fn is_match(pattern: &str, text: &str) -> bool {
+    Regex::new(pattern).is_ok_and(|re| re.is_match(text))
+}
+
+fn push<T: fmt::Display>(
+    entries: &mut Vec<(&'static str, String)>,
+    key: &'static str,
+    value: &Option<T>,
+) {
+    if let Some(value) = value {
+        entries.push((key, value.to_string()));
+    }
+}
+
+fn list<T: Serialize>(values: &[T]) -> String {
+    values
+        .iter()
+        .filter_map(|value| toml::Value::try_from(value).ok())
+        .filter_map(|value| value.as_str().map(str::to_string))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::youtube::LiveStatus;
+
+    fn video(title: &str, channel: Option<&str>, duration: u64) -> VideoInfo {
+        VideoInfo {
+            id: "abc123".to_string(),
+            title: title.to_string(),
+            description: None,
+            duration,
+            thumbnail_url: None,
+            channel: channel.map(str::to_string),
+            publish_date: None,
+            view_count: None,
+            streams: Vec::new(),
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
+        }
+    }
+
+    fn ctx<'a>(info: &'a VideoInfo, url: &'a str, media: MediaKind) -> RuleContext<'a> {
+        RuleContext {
+            url,
+            info,
+            playlist_id: None,
+            media,
+        }
+    }
+
+    const URL: &str = "https://www.youtube.com/watch?v=abc123";
+
+    // ============== Matching Tests ==============
+
+    #[test]
+    fn test_empty_rule_matches_everything() {
+        let info = video("Anything", None, 10);
+        assert!(Rule::default().matches(&ctx(&info, URL, MediaKind::Video)));
+    }
+
+    #[test]
+    fn test_channel_is_case_insensitive() {
+        let rule = Rule {
+            channel: Some("lofi girl".to_string()),
+            ..Default::default()
+        };
+
+        let info = video("Beats", Some("Lofi Girl"), 10);
+        assert!(rule.matches(&ctx(&info, URL, MediaKind::Audio)));
+
+        let info = video("Beats", Some("Other"), 10);
+        assert!(!rule.matches(&ctx(&info, URL, MediaKind::Audio)));
+
+        let info = video("Beats", None, 10);
+        assert!(!rule.matches(&ctx(&info, URL, MediaKind::Audio)));
+    }
+
+    #[test]
+    fn test_playlist_condition() {
+        let rule = Rule {
+            playlist: Some("PL123".to_string()),
+            ..Default::default()
+        };
+        let info = video("Video", None, 10);
+
+        let mut context = ctx(&info, URL, MediaKind::Video);
+        assert!(!rule.matches(&context));
+
+        context.playlist_id = Some("PL123");
+        assert!(rule.matches(&context));
+    }
+
+    #[test]
+    fn test_url_and_title_patterns() {
+        let rule = Rule {
+            url: Some("youtube\\.com".to_string()),
+            title: Some("(?i)lecture \\d+".to_string()),
+            ..Default::default()
+        };
+
+        let info = video("Physics LECTURE 12", None, 10);
+        assert!(rule.matches(&ctx(&info, URL, MediaKind::Video)));
+        assert!(!rule.matches(&ctx(&info, "https://youtu.be/abc123", MediaKind::Video)));
+
+        let info = video("Physics intro", None, 10);
+        assert!(!rule.matches(&ctx(&info, URL, MediaKind::Video)));
+    }
+
+    #[test]
+    fn test_invalid_pattern_never_matches() {
+        let rule = Rule {
+            title: Some("(".to_string()),
+            ..Default::default()
+        };
+        let info = video("(", None, 10);
+
+        assert!(!rule.matches(&ctx(&info, URL, MediaKind::Video)));
+    }
+
+    #[test]
+    fn test_duration_bounds() {
+        let rule = Rule {
+            min_duration: Some(60),
+            max_duration: Some(600),
+            ..Default::default()
+        };
+
+        for (duration, expected) in [(59, false), (60, true), (600, true), (601, false)] {
+            let info = video("Video", None, duration);
+            assert_eq!(
+                rule.matches(&ctx(&info, URL, MediaKind::Video)),
+                expected,
+                "duration {}",
+                duration
+            );
+        }
+    }
+
+    #[test]
+    fn test_media_condition() {
+        let rule = Rule {
+            media: Some(MediaKind::Audio),
+            ..Default::default()
+        };
+        let info = video("Video", None, 10);
+
+        assert!(rule.matches(&ctx(&info, URL, MediaKind::Audio)));
+        assert!(!rule.matches(&ctx(&info, URL, MediaKind::Video)));
+    }
+
+    #[test]
+    fn test_find_match_returns_first() {
+        let rules = vec![
+            Rule {
+                name: Some("music".to_string()),
+                channel: Some("Lofi Girl".to_string()),
+                ..Default::default()
+            },
+            Rule {
+                name: Some("fallback".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let info = video("Beats", Some("Lofi Girl"), 10);
+        let (index, rule) = find_match(&rules, &ctx(&info, URL, MediaKind::Video)).unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(rule.label(index), "#1 (music)");
+
+        let info = video("News", Some("Other"), 10);
+        let (index, _) = find_match(&rules, &ctx(&info, URL, MediaKind::Video)).unwrap();
+        assert_eq!(index, 1);
+
+        assert!(find_match(&[], &ctx(&info, URL, MediaKind::Video)).is_none());
+    }
+
+    // ============== Description Tests ==============
+
+    #[test]
+    fn test_conditions_and_overrides() {
+        let rule: Rule = toml::from_str(
+            r#"
+channel = "Lofi Girl"
+media = "audio"
+output_dir = "~/Music/{channel}"
+audio_format = "flac"
+vcodec = ["av1", "vp9"]
+hdr = "never"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rule.conditions(),
+            vec![
+                ("channel", "Lofi Girl".to_string()),
+                ("media", "audio".to_string())
+            ]
+        );
+        assert_eq!(
+            rule.overrides(),
+            vec![
+                ("output_dir", "~/Music/{channel}".to_string()),
+                ("audio_format", "flac".to_string()),
+                ("vcodec", "av1,vp9".to_string()),
+                ("hdr", "never".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_label_without_name() {
+        assert_eq!(Rule::default().label(2), "#3");
+    }
+
+    // ============== Check Tests ==============
+
+    #[test]
+    fn test_check_valid_rule() {
+        let rule = Rule {
+            title: Some("(?i)lecture".to_string()),
+            quality: Some("720p".to_string()),
+            audio_format: Some("flac".to_string()),
+            ..Default::default()
+        };
+
+        assert!(rule.check().is_empty());
+    }
+
+    #[test]
+    fn test_check_reports_every_problem() {
+        let rule = Rule {
+            url: Some("(".to_string()),
+            min_duration: Some(600),
+            max_duration: Some(60),
+            quality: Some("999p".to_string()),
+            video_format: Some("flv".to_string()),
+            format_id: Some(" ".to_string()),
+            fps: Some(0),
+            ..Default::default()
+        };
+
+        let keys: Vec<_> = rule.check().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "url",
+                "max_duration",
+                "quality",
+                "video_format",
+                "format_id",
+                "fps"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keys_cover_every_field() {
+        let rule = Rule {
+            name: Some(String::new()),
+            channel: Some(String::new()),
+            playlist: Some(String::new()),
+            url: Some(String::new()),
+            title: Some(String::new()),
+            min_duration: Some(0),
+            max_duration: Some(0),
+            media: Some(MediaKind::Video),
+            output_dir: Some(String::new()),
+            quality: Some(String::new()),
+            format_id: Some(String::new()),
+            video_format: Some(String::new()),
+            audio_format: Some(String::new()),
+            audio_only: Some(false),
+            filename_template: Some(String::new()),
+            vcodec: Some(Vec::new()),
+            acodec: Some(Vec::new()),
+            fps: Some(0),
+            hdr: Some(HdrMode::Any),
+            vertical: Some(false),
+            write_info_json: Some(false),
+            write_nfo: Some(false),
+        };
+
+        let table = toml::Value::try_from(&rule).unwrap();
+        let keys: Vec<_> = table.as_table().unwrap().keys().cloned().collect();
+
+        assert_eq!(keys.len(), Rule::KEYS.len());
+        for key in keys {
+            assert!(Rule::KEYS.contains(&key.as_str()), "{}", key);
+        }
+    }
+}
@@ -0,0 +1,736 @@
+//! Validation of configuration values and files.
+//!
+//! [`Config::set()`] runs [`check_value`] so an invalid value never makes it
+//! into a [`Config`]. [`validate_file`] checks a whole file and reports every
+//! problem together with the line it appears on; it backs
+//! `ytdl config validate` and `ytdl config edit`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+use regex::Regex;
+
+use super::{migrate, value_to_string, Config, Profile, Rule};
+use crate::cli::VideoQuality;
+use crate::error::{AppError, AppResult};
+use crate::media::{AudioFormat, VideoFormat};
+use crate::utils::expand_path;
+
+/// A single problem found in a configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// 1-based line number, when the problem can be located
+    pub line: Option<usize>,
+    /// Dot-notation key the problem belongs to, if any
+    pub key: Option<String>,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn new(key: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            key,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "{}: ", key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Checks that `value` is acceptable for `key`.
+///
+/// Only covers what type parsing in [`Config::set()`] cannot: enum names,
+/// bitrate and rate-limit syntax, and lower bounds. Unknown keys pass.
+pub(crate) fn check_value(key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "general.default_quality" => VideoQuality::from_str(value, true)
+            .map(|_| ())
+            .map_err(|_| unknown_value("quality", value, &possible_values::<VideoQuality>())),
+        "video.format" => VideoFormat::from_extension(value)
+            .map(|_| ())
+            .ok_or_else(|| unknown_value("video format", value, &possible_values::<VideoFormat>())),
+        "audio.format" => AudioFormat::from_extension(value)
+            .map(|_| ())
+            .ok_or_else(|| unknown_value("audio format", value, &possible_values::<AudioFormat>())),
+        "audio.bitrate" => {
+            let pattern = Regex::new(r"^[1-9][0-9]*[kK]$").unwrap();
+            if pattern.is_match(value) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "invalid bitrate '{}' (expected kbit/s with a k suffix, e.g. 192k)",
+                    value
+                ))
+            }
+        }
+        "network.rate_limit" if !value.is_empty() && value != "none" => {
+            let pattern = Regex::new(r"^[0-9]+(\.[0-9]+)?[kKmMgG]?$").unwrap();
+            let amount = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            if pattern.is_match(value) && amount.parse::<f64>().is_ok_and(|n| n > 0.0) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "invalid rate limit '{}' (expected bytes per second, e.g. 500K or 4.2M)",
+                    value
+                ))
+            }
+        }
+        "general.max_parallel_downloads" | "network.timeout" if value == "0" => {
+            Err("must be at least 1".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that downloads can be written to `dir`.
+///
+/// The directory doesn't have to exist yet; its nearest existing ancestor
+/// must be a writable directory.
+pub(crate) fn check_output_dir(dir: &str) -> Result<(), String> {
+    let path = expand_path(dir);
+
+    let Some(existing) = path.ancestors().find(|p| p.exists()) else {
+        return Ok(());
+    };
+
+    if !existing.is_dir() {
+        return Err(format!("{} is not a directory", existing.display()));
+    }
+
+    let probe = existing.join(format!(".ytdl-write-test-{}", std::process::id()));
+    fs::File::create(&probe)
+        .map_err(|e| format!("{} is not writable: {}", existing.display(), e))?;
+    let _ = fs::remove_file(&probe);
+
+    Ok(())
+}
+
+impl Config {
+    /// Checks every value of an already-built configuration.
+    ///
+    /// Besides the per-key checks done by [`Config::set()`], this verifies that
+    /// the output directory is writable and that `general.default_profile`
+    /// names an existing profile.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        for key in Config::keys() {
+            if let Some(value) = self.get(key) {
+                if let Err(message) = check_value(key, &value) {
+                    issues.push(ConfigIssue::new(Some(key.to_string()), message));
+                }
+            }
+        }
+
+        if let Err(message) = check_output_dir(&self.general.output_dir) {
+            issues.push(ConfigIssue::new(
+                Some("general.output_dir".to_string()),
+                message,
+            ));
+        }
+
+        if let Some(name) = &self.general.default_profile {
+            if !self.profiles.contains_key(name) {
+                issues.push(ConfigIssue::new(
+                    Some("general.default_profile".to_string()),
+                    format!("unknown profile '{}'", name),
+                ));
+            }
+        }
+
+        issues
+    }
+}
+
+/// Validates a configuration file and returns every problem found.
+///
+/// A missing file has no problems. Syntax errors stop validation and are
+/// reported alone; otherwise unknown keys, unparsable values and the checks
+/// of [`Config::validate()`] are all collected.
+///
+/// # Errors
+///
+/// Returns an error only if the file exists but cannot be read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use rust_yt_downloader::config::validate_file;
+///
+/// for issue in validate_file(Path::new("config.toml"))? {
+///     eprintln!("{}", issue);
+/// }
+/// # Ok::<(), rust_yt_downloader::error::AppError>(())
+/// ```
+pub fn validate_file(path: &Path) -> AppResult<Vec<ConfigIssue>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| AppError::file_read(path, e))?;
+    Ok(validate_str(&content))
+}
+
+/// Validates configuration file contents. See [`validate_file`].
+pub fn validate_str(content: &str) -> Vec<ConfigIssue> {
+    let table: toml::Table = match toml::from_str(content) {
+        Ok(table) => table,
+        Err(e) => {
+            let mut issue = ConfigIssue::new(None, e.message().to_string());
+            issue.line = e.span().map(|span| line_of_offset(content, span.start));
+            return vec![issue];
+        }
+    };
+
+    let mut issues = Vec::new();
+
+    for (section, value) in &table {
+        if section == "profiles" {
+            check_profiles(value, &mut issues);
+            continue;
+        }
+
+        if section == "rules" {
+            check_rules(value, &mut issues);
+            continue;
+        }
+
+        if section == "version" {
+            if let Err(AppError::ConfigInvalid { message, .. }) = migrate::file_version(&table) {
+                issues.push(ConfigIssue::new(Some(section.clone()), message));
+            }
+            continue;
+        }
+
+        if !is_section(section) {
+            issues.push(ConfigIssue::new(Some(section.clone()), "unknown section"));
+            continue;
+        }
+
+        let Some(fields) = value.as_table() else {
+            issues.push(ConfigIssue::new(Some(section.clone()), "expected a table"));
+            continue;
+        };
+
+        for (field, value) in fields {
+            let key = format!("{}.{}", section, field);
+            if let Err(message) = check_entry(&key, value) {
+                issues.push(ConfigIssue::new(Some(key), message));
+            }
+        }
+    }
+
+    // Only run the whole-config checks on values that parsed cleanly, so a
+    // bad value isn't reported twice.
+    if let Ok(config) = toml::from_str::<Config>(content) {
+        for issue in config.validate() {
+            if !issues.iter().any(|i| i.key == issue.key) {
+                issues.push(issue);
+            }
+        }
+    }
+
+    let lines = key_lines(content);
+    for issue in &mut issues {
+        if let Some(key) = &issue.key {
+            issue.line = find_line(&lines, key);
+        }
+    }
+    issues.sort_by_key(|issue| issue.line);
+
+    issues
+}
+
+fn check_profiles(value: &toml::Value, issues: &mut Vec<ConfigIssue>) {
+    let Some(profiles) = value.as_table() else {
+        issues.push(ConfigIssue::new(
+            Some("profiles".to_string()),
+            "expected a table",
+        ));
+        return;
+    };
+
+    for (name, profile) in profiles {
+        let prefix = format!("profiles.{}", name);
+        let profile: Profile = match profile.clone().try_into() {
+            Ok(profile) => profile,
+            Err(_) => {
+                issues.push(ConfigIssue::new(
+                    Some(prefix),
+                    "expected tables of settings, e.g. audio.format = \"opus\"",
+                ));
+                continue;
+            }
+        };
+
+        for (key, value) in profile.entries() {
+            let message = if key == "general.default_profile" {
+                Some("cannot be set inside a profile".to_string())
+            } else {
+                check_entry(&key, &toml::Value::String(value)).err()
+            };
+
+            if let Some(message) = message {
+                issues.push(ConfigIssue::new(
+                    Some(format!("{}.{}", prefix, key)),
+                    message,
+                ));
+            }
+        }
+    }
+}
+
+fn check_rules(value: &toml::Value, issues: &mut Vec<ConfigIssue>) {
+    let Some(entries) = value.as_array() else {
+        issues.push(ConfigIssue::new(
+            Some("rules".to_string()),
+            "expected [[rules]] tables",
+        ));
+        return;
+    };
+
+    for (index, entry) in entries.iter().enumerate() {
+        let prefix = format!("rules[{}]", index + 1);
+
+        let Some(table) = entry.as_table() else {
+            issues.push(ConfigIssue::new(Some(prefix), "expected a table"));
+            continue;
+        };
+
+        for key in table.keys() {
+            if !Rule::KEYS.contains(&key.as_str()) {
+                issues.push(ConfigIssue::new(
+                    Some(format!("{}.{}", prefix, key)),
+                    "unknown key",
+                ));
+            }
+        }
+
+        match entry.clone().try_into::<Rule>() {
+            Ok(rule) => {
+                for (key, message) in rule.check() {
+                    issues.push(ConfigIssue::new(
+                        Some(format!("{}.{}", prefix, key)),
+                        message,
+                    ));
+                }
+            }
+            Err(e) => issues.push(ConfigIssue::new(Some(prefix), e.message().to_string())),
+        }
+    }
+}
+
+fn check_entry(key: &str, value: &toml::Value) -> Result<(), String> {
+    if !Config::keys().contains(&key) {
+        return Err("unknown key".to_string());
+    }
+
+    let mut scratch = Config::default();
+    scratch
+        .set(key, &value_to_string(value))
+        .map_err(|e| match e {
+            AppError::ConfigInvalid { message, .. } => message,
+            other => other.to_string(),
+        })
+}
+
+fn is_section(name: &str) -> bool {
+    Config::keys()
+        .iter()
+        .any(|key| key.split('.').next() == Some(name))
+}
+
+fn possible_values<T: ValueEnum>() -> String {
+    T::value_variants()
+        .iter()
+        .filter_map(|v| v.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn unknown_value(what: &str, value: &str, expected: &str) -> String {
+    format!(
+        "unknown {} '{}' (expected one of: {})",
+        what, value, expected
+    )
+}
+
+fn line_of_offset(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Maps every table header and key in `content` to the line it is defined on.
+///
+/// This is a line-based scan rather than a full parser, which is enough for
+/// pointing at the right place in hand-written files.
+fn key_lines(content: &str) -> HashMap<String, usize> {
+    let mut lines = HashMap::new();
+    let mut arrays: HashMap<String, usize> = HashMap::new();
+    let mut table = String::new();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with("[[") {
+            // Arrays of tables are numbered from 1, e.g. rules[2].quality
+            let name = normalize_key(line.trim_matches(|c| c == '[' || c == ']'));
+            let count = arrays.entry(name.clone()).or_insert(0);
+            *count += 1;
+            table = format!("{}[{}]", name, count);
+            lines.entry(table.clone()).or_insert(index + 1);
+            continue;
+        }
+
+        if line.starts_with('[') {
+            table = normalize_key(line.trim_matches(|c| c == '[' || c == ']'));
+            lines.entry(table.clone()).or_insert(index + 1);
+            continue;
+        }
+
+        if let Some((key, _)) = line.split_once('=') {
+            let key = normalize_key(key);
+            let full = if table.is_empty() {
+                key
+            } else {
+                format!("{}.{}", table, key)
+            };
+            lines.entry(full).or_insert(index + 1);
+        }
+    }
+
+    lines
+}
+
+fn normalize_key(key: &str) -> String {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"'))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Finds the line of `key`, falling back to its closest enclosing table.
+fn find_line(lines: &HashMap<String, usize>, key: &str) -> Option<usize> {
+    let mut key = key;
+    loop {
+        if let Some(line) = lines.get(key) {
+            return Some(*line);
+        }
+        key = &key[..key.rfind('.')?];
+    }
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CURRENT_VERSION;
+    use tempfile::TempDir;
+
+    // ============== check_value Tests ==============
+
+    #[test]
+    fn test_check_quality() {
+        assert!(check_value("general.default_quality", "1080p").is_ok());
+        assert!(check_value("general.default_quality", "4K").is_ok());
+        assert!(check_value("general.default_quality", "2160p").is_ok());
+
+        let error = check_value("general.default_quality", "999p").unwrap_err();
+        assert!(error.contains("999p"));
+        assert!(error.contains("1080p"));
+    }
+
+    #[test]
+    fn test_check_formats() {
+        assert!(check_value("video.format", "mkv").is_ok());
+        assert!(check_value("video.format", "MOV").is_ok());
+        assert!(check_value("video.format", "flv").is_err());
+
+        assert!(check_value("audio.format", "opus").is_ok());
+        assert!(check_value("audio.format", "aac").is_ok());
+        assert!(check_value("audio.format", "wma").is_err());
+    }
+
+    #[test]
+    fn test_check_bitrate() {
+        assert!(check_value("audio.bitrate", "320k").is_ok());
+        assert!(check_value("audio.bitrate", "96K").is_ok());
+        assert!(check_value("audio.bitrate", "320").is_err());
+        assert!(check_value("audio.bitrate", "0k").is_err());
+        assert!(check_value("audio.bitrate", "fast").is_err());
+    }
+
+    #[test]
+    fn test_check_rate_limit() {
+        assert!(check_value("network.rate_limit", "5M").is_ok());
+        assert!(check_value("network.rate_limit", "500K").is_ok());
+        assert!(check_value("network.rate_limit", "4.2M").is_ok());
+        assert!(check_value("network.rate_limit", "1048576").is_ok());
+        assert!(check_value("network.rate_limit", "none").is_ok());
+        assert!(check_value("network.rate_limit", "").is_ok());
+        assert!(check_value("network.rate_limit", "0").is_err());
+        assert!(check_value("network.rate_limit", "5 MB/s").is_err());
+        assert!(check_value("network.rate_limit", "fast").is_err());
+    }
+
+    #[test]
+    fn test_check_lower_bounds() {
+        assert!(check_value("general.max_parallel_downloads", "0").is_err());
+        assert!(check_value("network.timeout", "0").is_err());
+        assert!(check_value("network.retry_attempts", "0").is_ok());
+    }
+
+    // ============== check_output_dir Tests ==============
+
+    #[test]
+    fn test_output_dir_missing_but_creatable() {
+        let dir = TempDir::new().unwrap();
+        let target = dir.path().join("a").join("b");
+
+        assert!(check_output_dir(target.to_str().unwrap()).is_ok());
+        // The probe file must not be left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_output_dir_is_a_file() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "").unwrap();
+
+        let error = check_output_dir(file.join("sub").to_str().unwrap()).unwrap_err();
+        assert!(error.contains("not a directory"));
+    }
+
+    // ============== Config::validate Tests ==============
+
+    #[test]
+    fn test_validate_default_config() {
+        let mut config = Config::default();
+        config.general.output_dir = TempDir::new().unwrap().path().display().to_string();
+
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_unknown_default_profile() {
+        let mut config = Config::default();
+        config.general.output_dir = ".".to_string();
+        config.general.default_profile = Some("missing".to_string());
+
+        let issues = config.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key.as_deref(), Some("general.default_profile"));
+    }
+
+    #[test]
+    fn test_validate_catches_values_set_directly() {
+        let mut config = Config::default();
+        config.general.output_dir = ".".to_string();
+        config.audio.format = "wma".to_string();
+
+        let issues = config.validate();
+        assert_eq!(issues[0].key.as_deref(), Some("audio.format"));
+    }
+
+    // ============== validate_str Tests ==============
+
+    #[test]
+    fn test_validate_str_valid() {
+        let content = "[general]\noutput_dir = \".\"\ndefault_quality = \"720p\"\n";
+        assert!(validate_str(content).is_empty());
+    }
+
+    #[test]
+    fn test_validate_str_reports_every_problem_with_lines() {
+        let content = "\
+[general]
+output_dir = \".\"
+default_quality = \"999p\"
+
+[audio]
+format = \"wma\"
+bitrate = \"loud\"
+colour = \"blue\"
+";
+        let issues = validate_str(content);
+
+        let found: Vec<_> = issues
+            .iter()
+            .map(|i| (i.line, i.key.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(3), "general.default_quality"),
+                (Some(6), "audio.format"),
+                (Some(7), "audio.bitrate"),
+                (Some(8), "audio.colour"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_str_syntax_error_line() {
+        let content = "[general]\noutput_dir = \".\"\ndefault_quality = \n";
+        let issues = validate_str(content);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(3));
+        assert!(issues[0].key.is_none());
+    }
+
+    #[test]
+    fn test_validate_str_type_error() {
+        let content = "[general]\noutput_dir = \".\"\n\n[network]\ntimeout = \"soon\"\n";
+        let issues = validate_str(content);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(5));
+        assert_eq!(issues[0].key.as_deref(), Some("network.timeout"));
+    }
+
+    #[test]
+    fn test_validate_str_unknown_section() {
+        let issues = validate_str("[general]\noutput_dir = \".\"\n[ffmpeg]\npreset = \"slow\"\n");
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key.as_deref(), Some("ffmpeg"));
+        assert_eq!(issues[0].line, Some(3));
+    }
+
+    #[test]
+    fn test_validate_str_accepts_version() {
+        let content = format!(
+            "version = {}\n[general]\noutput_dir = \".\"\n",
+            CURRENT_VERSION
+        );
+
+        assert!(validate_str(&content).is_empty());
+    }
+
+    #[test]
+    fn test_validate_str_newer_version() {
+        let content = format!(
+            "version = {}\n[general]\noutput_dir = \".\"\n",
+            CURRENT_VERSION + 1
+        );
+        let issues = validate_str(&content);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key.as_deref(), Some("version"));
+        assert!(issues[0].message.contains("newer"));
+    }
+
+    #[test]
+    fn test_validate_str_profiles() {
+        let content = "\
+[general]
+output_dir = \".\"
+
+[profiles.podcast]
+audio.format = \"opus\"
+audio.bitrate = \"96\"
+
+[profiles.mobile.general]
+default_quality = \"720p\"
+default_profile = \"podcast\"
+";
+        let issues = validate_str(content);
+
+        let found: Vec<_> = issues
+            .iter()
+            .map(|i| (i.line, i.key.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(6), "profiles.podcast.audio.bitrate"),
+                (Some(10), "profiles.mobile.general.default_profile"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_str_rules() {
+        let content = "\
+[general]
+output_dir = \".\"
+
+[[rules]]
+channel = \"Lofi Girl\"
+audio_format = \"flac\"
+
+[[rules]]
+title = \"(\"
+quality = \"999p\"
+folder = \"~/Lectures\"
+";
+        let issues = validate_str(content);
+
+        let found: Vec<_> = issues
+            .iter()
+            .map(|i| (i.line, i.key.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(9), "rules[2].title"),
+                (Some(10), "rules[2].quality"),
+                (Some(11), "rules[2].folder"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_str_rules_type_error() {
+        let issues = validate_str("[[rules]]\nmin_duration = \"long\"\n");
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key.as_deref(), Some("rules[1]"));
+        assert_eq!(issues[0].line, Some(1));
+    }
+
+    #[test]
+    fn test_validate_file_missing_is_valid() {
+        let issues = validate_file(Path::new("/nonexistent/config.toml")).unwrap();
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_issue_display() {
+        let issue = ConfigIssue {
+            line: Some(4),
+            key: Some("audio.format".to_string()),
+            message: "unknown audio format 'wma'".to_string(),
+        };
+
+        assert_eq!(
+            issue.to_string(),
+            "line 4: audio.format: unknown audio format 'wma'"
+        );
+    }
+}
@@ -5,10 +5,12 @@
 //!
 //! # Architecture
 //!
-//! The download system uses a three-tier configuration priority:
+//! The download system uses a four-tier configuration priority:
 //! 1. **CLI arguments** - Highest priority, directly override all other settings
-//! 2. **Config file** - Middle priority, loaded from `~/.config/rust-yt-downloader/config.toml`
-//! 3. **Built-in defaults** - Lowest priority, used when no other value is specified
+//! 2. **Routing rules** - The first `[[rules]]` entry matching the video, checked
+//!    once its metadata has been fetched
+//! 3. **Config file** - Loaded from `~/.config/rust-yt-downloader/config.toml`
+//! 4. **Built-in defaults** - Lowest priority, used when no other value is specified
 //!
 //! # Example Usage
 //!
@@ -47,14 +49,38 @@
 //! - Validates yt-dlp availability before attempting downloads
 //! - Provides detailed error messages with context for troubleshooting
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, ExitStatus};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 
-use crate::cli::{AudioFormat, VideoFormat, VideoQuality};
-use crate::config::Config;
+use chrono::{Local, Utc};
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::cli::VideoQuality;
+use crate::config::{find_match, Config, MediaKind, Rule, RuleContext};
 use crate::error::{AppError, AppResult};
+use crate::library::Library;
+use crate::media::{
+    AudioExtractor, AudioFormat, AudioOptions, ConversionOptions, FFmpeg, VideoConverter,
+    VideoFormat,
+};
+use crate::plan::PlannedDownload;
 use crate::progress::messages;
-use crate::utils::{expand_path, sanitize_filename};
-use crate::youtube::YtDlpClient;
+use crate::sidecar;
+use crate::space::{check_free_space, parse_size, Reservation, SizeBudget};
+use crate::utils::{
+    expand_path, extract_playlist_id, format_bytes, format_duration, sanitize_filename,
+};
+use crate::youtube::ytdlp::classify_failure;
+use crate::youtube::{
+    validate_url, AudioCodec, FormatPreference, HdrMode, PlaylistEntry, QualityFilter, VideoCodec,
+    VideoInfo, YtDlpClient,
+};
 
 /// Configuration options for video and audio downloads.
 ///
@@ -96,14 +122,28 @@ pub struct DownloadOptions {
     /// Defaults to `VideoQuality::Best`.
     pub quality: VideoQuality,
 
+    /// Explicit yt-dlp format selection (e.g. `137+140`).
+    ///
+    /// When set, bypasses the `quality` presets entirely and is passed to
+    /// yt-dlp as-is. IDs come from `ytdl formats <url>`. Defaults to `None`.
+    pub format_id: Option<String>,
+
+    /// Codec, frame rate, HDR and orientation preferences.
+    ///
+    /// Refines the `quality` preset without replacing it. Ignored when
+    /// `format_id` is set. Defaults to no preference.
+    pub preference: FormatPreference,
+
     /// Container format for video downloads.
     ///
-    /// Supported formats: MP4 (default), MKV, WebM.
+    /// Supported formats: MP4 (default), MKV, WebM, AVI, MOV.
+    /// AVI and MOV are converted with FFmpeg after downloading.
     pub video_format: VideoFormat,
 
     /// Audio codec format for audio-only downloads.
     ///
-    /// Supported formats: MP3 (default), M4A, FLAC, WAV, Opus.
+    /// Supported formats: MP3 (default), M4A, AAC, FLAC, WAV, Opus, Ogg.
+    /// AAC and Ogg are converted with FFmpeg after extraction.
     pub audio_format: AudioFormat,
 
     /// When true, downloads only audio stream (no video).
@@ -132,6 +172,54 @@ pub struct DownloadOptions {
     ///
     /// Shows additional metadata and debugging information. Defaults to `false`.
     pub verbose: bool,
+
+    /// Writes `<name>.info.json` with the video's metadata next to each download.
+    ///
+    /// Defaults to `false`.
+    pub write_info_json: bool,
+
+    /// Writes a Kodi/Jellyfin `<name>.nfo` next to each download.
+    ///
+    /// Defaults to `false`. See [`crate::sidecar`].
+    pub write_nfo: bool,
+
+    /// Playlist the video is downloaded from, if any.
+    ///
+    /// Fills `%(playlist_index)s`, `%(playlist_title)s`, `%(playlist_id)s`
+    /// and `%(playlist_count)s` in `filename_template`. Defaults to `None`.
+    pub playlist_entry: Option<PlaylistEntry>,
+
+    /// Saves playlist videos in a folder named after the playlist, with the
+    /// playlist position in front of each file name.
+    ///
+    /// Defaults to `false`. Has no effect without `playlist_entry`.
+    pub playlist_folders: bool,
+
+    /// Skips videos whose estimated size is larger than this many bytes.
+    ///
+    /// Defaults to `None` (no limit).
+    pub max_filesize: Option<u64>,
+
+    /// Records live streams from their beginning instead of from now.
+    ///
+    /// Defaults to `false`.
+    pub live_from_start: bool,
+
+    /// Waits for upcoming live streams and premieres to start instead of
+    /// failing.
+    ///
+    /// Defaults to `false`.
+    pub wait_for_video: bool,
+
+    /// Stops recording a live stream after this many minutes.
+    ///
+    /// Defaults to `None` (record until the stream ends).
+    pub live_duration: Option<u32>,
+
+    /// Bandwidth limit passed to yt-dlp as `--rate-limit`, e.g. `5M`.
+    ///
+    /// Defaults to `None` (unlimited). Read from `network.rate_limit`.
+    pub rate_limit: Option<String>,
 }
 
 impl Default for DownloadOptions {
@@ -139,6 +227,8 @@ impl Default for DownloadOptions {
         Self {
             output_dir: PathBuf::from("."),
             quality: VideoQuality::Best,
+            format_id: None,
+            preference: FormatPreference::default(),
             video_format: VideoFormat::Mp4,
             audio_format: AudioFormat::Mp3,
             audio_only: false,
@@ -146,6 +236,15 @@ impl Default for DownloadOptions {
             retry_attempts: 3,
             silence: false,
             verbose: false,
+            write_info_json: false,
+            write_nfo: false,
+            playlist_entry: None,
+            playlist_folders: false,
+            max_filesize: None,
+            live_from_start: false,
+            wait_for_video: false,
+            live_duration: None,
+            rate_limit: None,
         }
     }
 }
@@ -164,6 +263,8 @@ impl DownloadOptions {
     /// - `video_format` ← `config.video.format`
     /// - `audio_format` ← `config.audio.format`
     /// - `retry_attempts` ← `config.network.retry_attempts`
+    /// - `write_info_json` ← `config.video.write_info_json`
+    /// - `write_nfo` ← `config.video.write_nfo`
     ///
     /// # Examples
     ///
@@ -178,6 +279,8 @@ impl DownloadOptions {
         Self {
             output_dir: expand_path(&config.general.output_dir),
             quality: Self::parse_quality(&config.general.default_quality),
+            format_id: None,
+            preference: FormatPreference::default(),
             video_format: Self::parse_video_format(&config.video.format),
             audio_format: Self::parse_audio_format(&config.audio.format),
             audio_only: false,
@@ -185,6 +288,15 @@ impl DownloadOptions {
             retry_attempts: config.network.retry_attempts,
             silence: false,
             verbose: false,
+            write_info_json: config.video.write_info_json,
+            write_nfo: config.video.write_nfo,
+            playlist_entry: None,
+            playlist_folders: false,
+            max_filesize: None,
+            live_from_start: false,
+            wait_for_video: false,
+            live_duration: None,
+            rate_limit: config.network.rate_limit.clone(),
         }
     }
 
@@ -222,6 +334,39 @@ impl DownloadOptions {
         self
     }
 
+    /// Sets an explicit format selection, bypassing the quality presets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    ///
+    /// let options = DownloadOptions::default().with_format_id("137+140".to_string());
+    /// assert_eq!(options.format_selector(), "137+140");
+    /// ```
+    pub fn with_format_id(mut self, format_id: String) -> Self {
+        self.format_id = Some(format_id);
+        self
+    }
+
+    /// Sets codec, frame rate, HDR and orientation preferences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    /// use rust_yt_downloader::youtube::{FormatPreference, VideoCodec};
+    ///
+    /// let options = DownloadOptions::default().with_preference(
+    ///     FormatPreference::default().with_video_codecs(vec![VideoCodec::Av1, VideoCodec::Vp9]),
+    /// );
+    /// assert!(options.format_selector().contains("av01"));
+    /// ```
+    pub fn with_preference(mut self, preference: FormatPreference) -> Self {
+        self.preference = preference;
+        self
+    }
+
     /// Sets the video container format.
     ///
     /// # Examples
@@ -321,148 +466,939 @@ impl DownloadOptions {
         self
     }
 
+    /// Sets whether to write `<name>.info.json` next to each download.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    ///
+    /// let options = DownloadOptions::default()
+    ///     .with_write_info_json(true);
+    /// ```
+    pub fn with_write_info_json(mut self, write: bool) -> Self {
+        self.write_info_json = write;
+        self
+    }
+
+    /// Sets whether to write a Kodi/Jellyfin `<name>.nfo` next to each download.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    ///
+    /// let options = DownloadOptions::default()
+    ///     .with_write_nfo(true);
+    /// ```
+    pub fn with_write_nfo(mut self, write: bool) -> Self {
+        self.write_nfo = write;
+        self
+    }
+
+    /// Sets the playlist the video is downloaded from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    /// use rust_yt_downloader::youtube::PlaylistEntry;
+    ///
+    /// let options = DownloadOptions::default()
+    ///     .with_template("%(playlist_index)s - %(title)s.%(ext)s".to_string())
+    ///     .with_playlist_entry(PlaylistEntry {
+    ///         index: 7,
+    ///         video_id: "abc".to_string(),
+    ///         playlist_id: "PL1".to_string(),
+    ///         playlist_title: "Talks".to_string(),
+    ///         playlist_count: 12,
+    ///     });
+    /// assert_eq!(options.output_template(), "07 - %(title)s.%(ext)s");
+    /// ```
+    pub fn with_playlist_entry(mut self, entry: PlaylistEntry) -> Self {
+        self.playlist_entry = Some(entry);
+        self
+    }
+
+    /// Sets whether playlist videos go to a numbered playlist folder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    /// use rust_yt_downloader::youtube::PlaylistEntry;
+    ///
+    /// let options = DownloadOptions::default()
+    ///     .with_playlist_folders(true)
+    ///     .with_playlist_entry(PlaylistEntry {
+    ///         index: 7,
+    ///         video_id: "abc".to_string(),
+    ///         playlist_id: "PL1".to_string(),
+    ///         playlist_title: "Rust Talks".to_string(),
+    ///         playlist_count: 120,
+    ///     });
+    /// assert_eq!(options.output_template(), "Rust Talks/007 - %(title)s.%(ext)s");
+    /// ```
+    pub fn with_playlist_folders(mut self, enabled: bool) -> Self {
+        self.playlist_folders = enabled;
+        self
+    }
+
+    /// Skips videos estimated to be larger than `limit` bytes.
+    ///
+    /// Videos whose size cannot be estimated are always downloaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    ///
+    /// let options = DownloadOptions::default().with_max_filesize(Some(500 * 1024 * 1024));
+    /// assert_eq!(options.max_filesize, Some(524288000));
+    /// ```
+    pub fn with_max_filesize(mut self, limit: Option<u64>) -> Self {
+        self.max_filesize = limit;
+        self
+    }
+
+    /// Records live streams from their beginning (yt-dlp `--live-from-start`).
+    pub fn with_live_from_start(mut self, enabled: bool) -> Self {
+        self.live_from_start = enabled;
+        self
+    }
+
+    /// Waits for upcoming live streams and premieres to start.
+    ///
+    /// See [`Downloader::download`] for how the video is polled.
+    pub fn with_wait_for_video(mut self, enabled: bool) -> Self {
+        self.wait_for_video = enabled;
+        self
+    }
+
+    /// Stops recording a live stream after `minutes`.
+    ///
+    /// The recording is cut by FFmpeg, which must be installed. Has no
+    /// effect on videos that are not live.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    ///
+    /// let options = DownloadOptions::default().with_live_duration(Some(90));
+    /// assert_eq!(options.live_duration, Some(90));
+    /// ```
+    pub fn with_live_duration(mut self, minutes: Option<u32>) -> Self {
+        self.live_duration = minutes;
+        self
+    }
+
+    /// Returns `filename_template` with the playlist fields filled in.
+    ///
+    /// yt-dlp only knows the playlist when it is given the playlist URL, but
+    /// playlist videos are downloaded one by one, so `%(playlist_index)s`,
+    /// `%(playlist_title)s` (or `%(playlist)s`), `%(playlist_id)s` and
+    /// `%(playlist_count)s` (or `%(n_entries)s`) are filled here. The index
+    /// is zero-padded to the width of the count, as yt-dlp does; `%(…)0Nd`
+    /// sets the width explicitly.
+    ///
+    /// With `playlist_folders`, the result is placed in the playlist folder
+    /// and its file name is prefixed with the padded index.
+    pub fn output_template(&self) -> String {
+        match &self.playlist_entry {
+            Some(entry) if self.playlist_folders => {
+                let template = fill_playlist_fields(&self.filename_template, entry);
+                let (dirs, name) = match template.rsplit_once('/') {
+                    Some((dirs, name)) => (format!("{}/", dirs), name),
+                    None => (String::new(), template.as_str()),
+                };
+                format!(
+                    "{}/{}{} - {}",
+                    template_literal(&playlist_folder(&entry.playlist_title, &entry.playlist_id)),
+                    dirs,
+                    index_prefix(entry),
+                    name
+                )
+            }
+            Some(entry) => fill_playlist_fields(&self.filename_template, entry),
+            None => self.filename_template.clone(),
+        }
+    }
+
+    /// Applies every override that is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::{DownloadOptions, OptionOverrides};
+    /// use std::path::PathBuf;
+    ///
+    /// let options = DownloadOptions::default().with_overrides(&OptionOverrides {
+    ///     output_dir: Some(PathBuf::from("./music")),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(options.output_dir, PathBuf::from("./music"));
+    /// ```
+    pub fn with_overrides(mut self, overrides: &OptionOverrides) -> Self {
+        if let Some(ref dir) = overrides.output_dir {
+            self.output_dir = dir.clone();
+        }
+        if let Some(ref quality) = overrides.quality {
+            self.quality = quality.clone();
+        }
+        if let Some(ref format_id) = overrides.format_id {
+            self.format_id = Some(format_id.clone());
+        }
+        if let Some(format) = overrides.video_format {
+            self.video_format = format;
+        }
+        if let Some(format) = overrides.audio_format {
+            self.audio_format = format;
+        }
+        if let Some(audio_only) = overrides.audio_only {
+            self.audio_only = audio_only;
+        }
+        if let Some(ref template) = overrides.filename_template {
+            self.filename_template = template.clone();
+        }
+        if let Some(ref codecs) = overrides.video_codecs {
+            self.preference.video_codecs = codecs.clone();
+        }
+        if let Some(ref codecs) = overrides.audio_codecs {
+            self.preference.audio_codecs = codecs.clone();
+        }
+        if let Some(fps) = overrides.fps {
+            self.preference.fps = Some(fps);
+        }
+        if let Some(hdr) = overrides.hdr {
+            self.preference.hdr = hdr;
+        }
+        if let Some(vertical) = overrides.prefer_vertical {
+            self.preference.prefer_vertical = vertical;
+        }
+        if let Some(write) = overrides.write_info_json {
+            self.write_info_json = write;
+        }
+        if let Some(write) = overrides.write_nfo {
+            self.write_nfo = write;
+        }
+        self
+    }
+
     /// Parses a quality string into a `VideoQuality` enum.
     ///
     /// Case-insensitive. Supports: "144p", "240p", "360p", "480p", "720p",
     /// "1080p", "1440p", "4k", "2160p", "best", "worst".
     /// Unknown values default to `VideoQuality::Best`.
     fn parse_quality(quality: &str) -> VideoQuality {
-        match quality.to_lowercase().as_str() {
-            "144p" => VideoQuality::Q144p,
-            "240p" => VideoQuality::Q240p,
-            "360p" => VideoQuality::Q360p,
-            "480p" => VideoQuality::Q480p,
-            "720p" => VideoQuality::Q720p,
-            "1080p" => VideoQuality::Q1080p,
-            "1440p" => VideoQuality::Q1440p,
-            "4k" | "2160p" => VideoQuality::Q4k,
-            "worst" => VideoQuality::Worst,
-            _ => VideoQuality::Best,
-        }
+        VideoQuality::from_str(quality, true).unwrap_or_default()
     }
 
     /// Parses a video format string into a `VideoFormat` enum.
     ///
-    /// Case-insensitive. Supports: "mp4", "mkv", "webm".
+    /// Case-insensitive. Accepts every extension known to [`VideoFormat`].
     /// Unknown values default to `VideoFormat::Mp4`.
     fn parse_video_format(format: &str) -> VideoFormat {
-        match format.to_lowercase().as_str() {
-            "mkv" => VideoFormat::Mkv,
-            "webm" => VideoFormat::Webm,
-            _ => VideoFormat::Mp4,
-        }
+        VideoFormat::from_extension(format).unwrap_or_default()
     }
 
     /// Parses an audio format string into an `AudioFormat` enum.
     ///
-    /// Case-insensitive. Supports: "mp3", "m4a", "flac", "wav", "opus".
+    /// Case-insensitive. Accepts every extension known to [`AudioFormat`].
     /// Unknown values default to `AudioFormat::Mp3`.
     fn parse_audio_format(format: &str) -> AudioFormat {
-        match format.to_lowercase().as_str() {
-            "m4a" => AudioFormat::M4a,
-            "flac" => AudioFormat::Flac,
-            "wav" => AudioFormat::Wav,
-            "opus" => AudioFormat::Opus,
-            _ => AudioFormat::Mp3,
-        }
+        AudioFormat::from_extension(format).unwrap_or_default()
     }
 
     /// Converts `VideoQuality` to yt-dlp format selector string.
     ///
     /// Returns yt-dlp format strings that combine best video and audio streams
-    /// at the specified quality level.
+    /// at the specified quality level, refined by `preference`.
     fn quality_to_ytdlp(&self) -> String {
+        let worst = matches!(self.quality, VideoQuality::Worst);
+        self.preference.ytdlp_format(self.max_height(), worst)
+    }
+
+    /// Returns the height cap for the selected quality, if any.
+    fn max_height(&self) -> Option<u32> {
         match self.quality {
-            VideoQuality::Best => "bestvideo+bestaudio/best".to_string(),
-            VideoQuality::Worst => "worstvideo+worstaudio/worst".to_string(),
-            VideoQuality::Q4k => "bestvideo[height<=2160]+bestaudio/best[height<=2160]".to_string(),
-            VideoQuality::Q1440p => {
-                "bestvideo[height<=1440]+bestaudio/best[height<=1440]".to_string()
-            }
-            VideoQuality::Q1080p => {
-                "bestvideo[height<=1080]+bestaudio/best[height<=1080]".to_string()
-            }
-            VideoQuality::Q720p => "bestvideo[height<=720]+bestaudio/best[height<=720]".to_string(),
-            VideoQuality::Q480p => "bestvideo[height<=480]+bestaudio/best[height<=480]".to_string(),
-            VideoQuality::Q360p => "bestvideo[height<=360]+bestaudio/best[height<=360]".to_string(),
-            VideoQuality::Q240p => "bestvideo[height<=240]+bestaudio/best[height<=240]".to_string(),
-            VideoQuality::Q144p => "bestvideo[height<=144]+bestaudio/best[height<=144]".to_string(),
+            VideoQuality::Best | VideoQuality::Worst => None,
+            VideoQuality::Q4k => Some(2160),
+            VideoQuality::Q1440p => Some(1440),
+            VideoQuality::Q1080p => Some(1080),
+            VideoQuality::Q720p => Some(720),
+            VideoQuality::Q480p => Some(480),
+            VideoQuality::Q360p => Some(360),
+            VideoQuality::Q240p => Some(240),
+            VideoQuality::Q144p => Some(144),
         }
     }
 
-    /// Returns the file extension for the selected video format.
-    fn video_format_ext(&self) -> &'static str {
+    /// Returns the yt-dlp `-f` selector for this download.
+    ///
+    /// An explicit `format_id` wins over the `quality` preset.
+    pub fn format_selector(&self) -> String {
+        match self.format_id {
+            Some(ref format_id) => format_id.clone(),
+            None => self.quality_to_ytdlp(),
+        }
+    }
+
+    /// Returns the yt-dlp `-S` sort string for this download, if any.
+    ///
+    /// Only used with the quality presets; an explicit `format_id` is taken as-is.
+    pub fn format_sort(&self) -> Option<String> {
+        match self.format_id {
+            Some(_) => None,
+            None => self.preference.ytdlp_sort(),
+        }
+    }
+
+    /// Returns the container yt-dlp is asked to produce.
+    ///
+    /// yt-dlp merges into MP4, MKV and WebM directly. AVI and MOV are
+    /// downloaded as MP4 and converted afterwards.
+    fn ytdlp_video_format(&self) -> VideoFormat {
         match self.video_format {
-            VideoFormat::Mp4 => "mp4",
-            VideoFormat::Mkv => "mkv",
-            VideoFormat::Webm => "webm",
+            VideoFormat::Avi | VideoFormat::Mov => VideoFormat::Mp4,
+            format => format,
         }
     }
 
-    /// Returns the format string for the selected audio format.
-    fn audio_format_str(&self) -> &'static str {
+    /// Returns the audio format yt-dlp is asked to extract.
+    ///
+    /// AAC is extracted as M4A and Ogg Vorbis as Opus, then converted afterwards.
+    fn ytdlp_audio_format(&self) -> AudioFormat {
         match self.audio_format {
-            AudioFormat::Mp3 => "mp3",
-            AudioFormat::M4a => "m4a",
-            AudioFormat::Flac => "flac",
-            AudioFormat::Wav => "wav",
-            AudioFormat::Opus => "opus",
+            AudioFormat::Aac => AudioFormat::M4a,
+            AudioFormat::Ogg => AudioFormat::Opus,
+            format => format,
+        }
+    }
+
+    /// Estimates the size of the download from the stream sizes yt-dlp
+    /// reports (or their bitrates), picking the streams the way the `-f`
+    /// selector would.
+    ///
+    /// Returns `None` when a picked stream has no known size.
+    fn estimated_size(&self, video_info: &VideoInfo) -> Option<u64> {
+        let duration = video_info.duration;
+        if self.audio_only {
+            return video_info.best_audio_stream()?.estimated_size(duration);
+        }
+
+        if let Some(ref format_id) = self.format_id {
+            // Only the first alternative of `137+140/22` is estimated
+            let first = format_id.split('/').next().unwrap_or_default();
+            return first
+                .split('+')
+                .map(|id| video_info.stream_by_format_id(id)?.estimated_size(duration))
+                .sum();
+        }
+
+        let filter = match (&self.quality, self.max_height()) {
+            (VideoQuality::Worst, _) => QualityFilter::Worst,
+            (_, Some(height)) => QualityFilter::MaxHeight(height),
+            (_, None) => QualityFilter::Best,
+        };
+        let video = video_info.stream_by_filter_with(filter, &self.preference)?;
+        let video_size = video.estimated_size(duration);
+        match video.audio_codec {
+            Some(ref codec) if codec != "none" => video_size,
+            _ => Some(video_size? + video_info.best_audio_stream()?.estimated_size(duration)?),
         }
     }
 
+    /// Returns `true` if the video must be converted after downloading.
+    pub fn needs_video_conversion(&self) -> bool {
+        self.ytdlp_video_format() != self.video_format
+    }
+
+    /// Returns `true` if the audio must be converted after extraction.
+    pub fn needs_audio_conversion(&self) -> bool {
+        self.ytdlp_audio_format() != self.audio_format
+    }
+
+    /// Returns the file extension yt-dlp produces for video downloads.
+    fn video_format_ext(&self) -> &'static str {
+        self.ytdlp_video_format().extension()
+    }
+
+    /// Returns the `--audio-format` value passed to yt-dlp.
+    fn audio_format_str(&self) -> &'static str {
+        self.ytdlp_audio_format().extension()
+    }
+
     /// Returns the bandwidth rate limit, if configured.
     ///
-    /// Currently returns `None`. Rate limiting will be implemented
-    /// through the config system in future versions.
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::config::Config;
+    /// use rust_yt_downloader::downloader::DownloadOptions;
+    ///
+    /// let mut config = Config::default();
+    /// config.network.rate_limit = Some("5M".to_string());
+    ///
+    /// let options = DownloadOptions::from_config(&config);
+    /// assert_eq!(options.rate_limit(), Some("5M".to_string()));
+    /// ```
     pub fn rate_limit(&self) -> Option<String> {
-        None
+        self.rate_limit.clone()
     }
 }
 
-/// Result of a successful download operation.
+/// Download settings that replace the matching [`DownloadOptions`] fields when set.
 ///
-/// Contains metadata and filesystem information about the downloaded file.
+/// Built from the command-line flags and from routing rules. Flags are applied
+/// again after a rule, so an explicit flag always wins over the rule.
 ///
 /// # Examples
 ///
-/// ```no_run
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// use rust_yt_downloader::downloader::Downloader;
+/// ```
+/// use rust_yt_downloader::downloader::{DownloadOptions, OptionOverrides};
+/// use rust_yt_downloader::cli::VideoQuality;
 ///
-/// let downloader = Downloader::new();
-/// let result = downloader.download("https://youtube.com/watch?v=dQw4w9WgXcQ").await?;
+/// let flags = OptionOverrides {
+///     quality: Some(VideoQuality::Q720p),
+///     ..Default::default()
+/// };
 ///
-/// println!("Title: {}", result.video_title);
-/// println!("Size: {} bytes", result.file_size);
-/// println!("Path: {}", result.file_path.display());
-/// # Ok(())
-/// # }
+/// let options = DownloadOptions::default().with_overrides(&flags);
+/// assert!(matches!(options.quality, VideoQuality::Q720p));
+/// assert_eq!(flags.keys(), vec!["quality"]);
 /// ```
-#[derive(Debug)]
-pub struct DownloadResult {
-    /// Absolute path to the downloaded file.
-    pub file_path: PathBuf,
+#[derive(Debug, Clone, Default)]
+pub struct OptionOverrides {
+    /// Replaces `output_dir`
+    pub output_dir: Option<PathBuf>,
 
-    /// Size of the downloaded file in bytes.
-    pub file_size: u64,
+    /// Replaces `quality`
+    pub quality: Option<VideoQuality>,
 
-    /// YouTube video ID (e.g., "dQw4w9WgXcQ").
-    pub video_id: String,
+    /// Replaces `format_id`
+    pub format_id: Option<String>,
 
-    /// Title of the video as provided by YouTube.
-    pub video_title: String,
+    /// Replaces `video_format`
+    pub video_format: Option<VideoFormat>,
+
+    /// Replaces `audio_format`
+    pub audio_format: Option<AudioFormat>,
+
+    /// Replaces `audio_only`
+    pub audio_only: Option<bool>,
+
+    /// Replaces `filename_template`
+    pub filename_template: Option<String>,
+
+    /// Replaces `preference.video_codecs`
+    pub video_codecs: Option<Vec<VideoCodec>>,
+
+    /// Replaces `preference.audio_codecs`
+    pub audio_codecs: Option<Vec<AudioCodec>>,
+
+    /// Replaces `preference.fps`
+    pub fps: Option<u32>,
+
+    /// Replaces `preference.hdr`
+    pub hdr: Option<HdrMode>,
+
+    /// Replaces `preference.prefer_vertical`
+    pub prefer_vertical: Option<bool>,
+
+    /// Replaces `write_info_json`
+    pub write_info_json: Option<bool>,
+
+    /// Replaces `write_nfo`
+    pub write_nfo: Option<bool>,
 }
 
-/// Main downloader orchestrator that coordinates downloads with yt-dlp.
-///
-/// The `Downloader` manages the interaction between configuration options,
-/// the yt-dlp client, and download operations. It handles both video and
-/// audio-only downloads with automatic retry logic and progress reporting.
+impl OptionOverrides {
+    /// Returns the overrides a rule sets for the video in `ctx`.
+    ///
+    /// `{channel}`, `{playlist}` and `{id}` in the rule's `output_dir` are
+    /// replaced with the video's values.
+    pub fn from_rule(rule: &Rule, ctx: &RuleContext) -> Self {
+        Self {
+            output_dir: rule
+                .output_dir
+                .as_deref()
+                .map(|dir| expand_path(&fill_placeholders(dir, ctx))),
+            quality: rule.quality.as_deref().map(DownloadOptions::parse_quality),
+            format_id: rule.format_id.clone(),
+            video_format: rule
+                .video_format
+                .as_deref()
+                .map(DownloadOptions::parse_video_format),
+            audio_format: rule
+                .audio_format
+                .as_deref()
+                .map(DownloadOptions::parse_audio_format),
+            audio_only: rule.audio_only,
+            filename_template: rule.filename_template.clone(),
+            video_codecs: rule.vcodec.clone(),
+            audio_codecs: rule.acodec.clone(),
+            fps: rule.fps,
+            hdr: rule.hdr,
+            prefer_vertical: rule.vertical,
+            write_info_json: rule.write_info_json,
+            write_nfo: rule.write_nfo,
+        }
+    }
+
+    /// Returns the names of the settings that are set, as used in `[[rules]]`.
+    pub fn keys(&self) -> Vec<&'static str> {
+        [
+            ("output_dir", self.output_dir.is_some()),
+            ("quality", self.quality.is_some()),
+            ("format_id", self.format_id.is_some()),
+            ("video_format", self.video_format.is_some()),
+            ("audio_format", self.audio_format.is_some()),
+            ("audio_only", self.audio_only.is_some()),
+            ("filename_template", self.filename_template.is_some()),
+            ("vcodec", self.video_codecs.is_some()),
+            ("acodec", self.audio_codecs.is_some()),
+            ("fps", self.fps.is_some()),
+            ("hdr", self.hdr.is_some()),
+            ("vertical", self.prefer_vertical.is_some()),
+            ("write_info_json", self.write_info_json.is_some()),
+            ("write_nfo", self.write_nfo.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .map(|(key, _)| key)
+        .collect()
+    }
+}
+
+/// Temporary file yt-dlp writes the final path of a download to.
 ///
-/// # Examples
+/// Finding the newest file in the output directory is wrong when several
+/// downloads write to the same directory at once, so yt-dlp is asked to
+/// report the path with `--print-to-file after_move:filepath`. The file is
+/// removed when the report is dropped.
+struct PathReport {
+    path: PathBuf,
+}
+
+impl PathReport {
+    /// Adds the yt-dlp arguments that write the report to `args`.
+    fn new(video_id: &str, args: &mut Vec<String>) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "ytdl-{}-{}-{}.path",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed),
+            sanitize_filename(video_id)
+        ));
+        args.extend([
+            "--print-to-file".to_string(),
+            "after_move:filepath".to_string(),
+            path.to_string_lossy().into_owned(),
+        ]);
+
+        Self { path }
+    }
+
+    /// Returns the reported path, if yt-dlp wrote one and the file exists.
+    fn take(&self) -> Option<PathBuf> {
+        let content = std::fs::read_to_string(&self.path).ok()?;
+        content
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .filter(|path| path.is_file())
+    }
+}
+
+impl Drop for PathReport {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Transfer state parsed from a yt-dlp progress line.
 ///
-/// ```no_run
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// A video with separate streams goes from 0 to 100% once per stream.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransferProgress {
+    /// Percentage of the current stream, from 0 to 100
+    pub percent: f64,
+    /// Size of the current stream in bytes, when yt-dlp knows it
+    pub total_bytes: Option<u64>,
+    /// Transfer speed in bytes per second
+    pub speed: Option<u64>,
+    /// Estimated seconds left for the current stream
+    pub eta: Option<u64>,
+}
+
+impl TransferProgress {
+    /// Parses a `[download]` line printed by yt-dlp with `--newline`.
+    ///
+    /// Returns `None` for every other line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::TransferProgress;
+    ///
+    /// let line = "[download]  42.5% of  10.00MiB at  2.00MiB/s ETA 00:03";
+    /// let progress = TransferProgress::parse(line).unwrap();
+    ///
+    /// assert_eq!(progress.percent, 42.5);
+    /// assert_eq!(progress.total_bytes, Some(10 * 1024 * 1024));
+    /// assert_eq!(progress.speed, Some(2 * 1024 * 1024));
+    /// assert_eq!(progress.eta, Some(3));
+    /// ```
+    pub fn parse(line: &str) -> Option<Self> {
+        static PROGRESS: OnceLock<Regex> = OnceLock::new();
+
+        let re = PROGRESS.get_or_init(|| {
+            Regex::new(
+                r"^\[download\]\s+([\d.]+)%(?:\s+of\s+~?\s*(\S+))?(?:\s+at\s+(\S+))?(?:\s+ETA\s+(\S+))?",
+            )
+            .expect("valid progress regex")
+        });
+        let caps = re.captures(line.trim())?;
+
+        let eta = caps.get(4).and_then(|eta| {
+            eta.as_str().split(':').try_fold(0u64, |total, part| {
+                part.parse::<u64>().ok().map(|value| total * 60 + value)
+            })
+        });
+
+        Some(Self {
+            percent: caps[1].parse().ok()?,
+            total_bytes: caps.get(2).and_then(|size| parse_size(size.as_str()).ok()),
+            speed: caps.get(3).and_then(|speed| {
+                let speed = speed.as_str().strip_suffix("/s")?;
+                parse_size(speed).ok()
+            }),
+            eta,
+        })
+    }
+}
+
+/// Live view of a running download, shared with whoever started it.
+///
+/// A downloader given a monitor with [`Downloader::with_monitor`] reports the
+/// progress of yt-dlp to it, even in silent mode, and stops when the monitor
+/// is cancelled: the running yt-dlp process is killed and the download fails
+/// with `AppError::Cancelled`.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use rust_yt_downloader::downloader::{DownloadMonitor, Downloader};
+///
+/// let monitor = Arc::new(DownloadMonitor::new());
+/// let downloader = Downloader::new().with_monitor(monitor.clone());
+///
+/// assert!(monitor.progress().is_none());
+/// monitor.cancel();
+/// assert!(monitor.is_cancelled());
+/// ```
+#[derive(Debug)]
+pub struct DownloadMonitor {
+    progress: Mutex<Option<TransferProgress>>,
+    child: Mutex<Option<Child>>,
+    cancelled: watch::Sender<bool>,
+}
+
+impl DownloadMonitor {
+    /// Creates a monitor for a download that has not started.
+    pub fn new() -> Self {
+        Self {
+            progress: Mutex::new(None),
+            child: Mutex::new(None),
+            cancelled: watch::Sender::new(false),
+        }
+    }
+
+    /// Returns the last progress reported by yt-dlp.
+    pub fn progress(&self) -> Option<TransferProgress> {
+        self.progress
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Stops the download, killing yt-dlp if it is running.
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+        if let Some(child) = self
+            .child
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            let _ = child.kill();
+        }
+    }
+
+    /// Returns `true` once [`DownloadMonitor::cancel`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Waits until the download is cancelled.
+    async fn cancelled(&self) {
+        let mut cancelled = self.cancelled.subscribe();
+        let _ = cancelled.wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// Records a line of yt-dlp output, if it is a progress line.
+    fn update(&self, line: &str) {
+        if let Some(progress) = TransferProgress::parse(line) {
+            *self.progress.lock().unwrap_or_else(|e| e.into_inner()) = Some(progress);
+        }
+    }
+
+    /// Keeps the running yt-dlp process so that it can be killed, killing
+    /// it right away if the download is already cancelled.
+    fn attach(&self, mut child: Child) {
+        if self.is_cancelled() {
+            let _ = child.kill();
+        }
+        *self.child.lock().unwrap_or_else(|e| e.into_inner()) = Some(child);
+    }
+
+    /// Waits for the attached yt-dlp process to exit.
+    fn wait(&self) -> std::io::Result<ExitStatus> {
+        let child = self.child.lock().unwrap_or_else(|e| e.into_inner()).take();
+        match child {
+            Some(mut child) => child.wait(),
+            None => Err(std::io::Error::other("yt-dlp is not running")),
+        }
+    }
+}
+
+impl Default for DownloadMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaces the `{channel}`, `{playlist}` and `{id}` placeholders of a rule's
+/// output directory. Values are sanitized so they are safe as folder names;
+/// one that is only dots, such as `..`, becomes `Unknown`.
+fn fill_placeholders(dir: &str, ctx: &RuleContext) -> String {
+    let folder = |value: &str| match sanitize_filename(value) {
+        name if !name.is_empty() && name.chars().all(|c| c == '.') => "Unknown".to_string(),
+        name => name,
+    };
+    let channel = ctx.info.channel.as_deref().unwrap_or("Unknown");
+    dir.replace("{channel}", &folder(channel))
+        .replace("{playlist}", &folder(ctx.playlist_id.unwrap_or_default()))
+        .replace("{id}", &folder(&ctx.info.id))
+}
+
+/// First interval between checks of an upcoming video.
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Longest interval between checks once the scheduled start has passed.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Longest single wait for a scheduled start, so that a rescheduled
+/// premiere is noticed.
+const MAX_SCHEDULE_WAIT: Duration = Duration::from_secs(3600);
+
+/// Returns how long to wait before checking an upcoming video again.
+///
+/// Waits until the scheduled start (`until_start` seconds away) when it is
+/// known and further than the backoff; otherwise backs off from
+/// [`MIN_POLL_INTERVAL`], doubling with each `attempt` up to
+/// [`MAX_POLL_INTERVAL`].
+fn poll_delay(attempt: u32, until_start: Option<i64>) -> Duration {
+    let backoff = MIN_POLL_INTERVAL
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_POLL_INTERVAL);
+
+    match until_start {
+        Some(seconds) if seconds > backoff.as_secs() as i64 => {
+            Duration::from_secs(seconds as u64).min(MAX_SCHEDULE_WAIT)
+        }
+        _ => backoff,
+    }
+}
+
+/// Returns the name of the folder `playlist_folders` saves a playlist in:
+/// its sanitized title, or its ID when the title is empty or only dots.
+pub fn playlist_folder(title: &str, id: &str) -> String {
+    match sanitize_filename(title) {
+        title if title.chars().all(|c| c == '.') => sanitize_filename(id),
+        title => title,
+    }
+}
+
+/// Escapes `value` so that a yt-dlp output template uses it as literal text:
+/// `%` becomes `%%` and path separators become `_`.
+fn template_literal(value: &str) -> String {
+    value.replace(['/', '\\'], "_").replace('%', "%%")
+}
+
+/// Returns the position of `entry`, zero-padded to the width of the
+/// playlist size.
+fn index_prefix(entry: &PlaylistEntry) -> String {
+    let width = entry.playlist_count.to_string().len();
+    format!("{:0width$}", entry.index, width = width)
+}
+
+/// Returns where `file` belongs now that its video is at the position of
+/// `entry`, or `None` if it is not a numbered file of the entry's playlist
+/// folder or already has the right number.
+fn renumbered_path(file: &Path, entry: &PlaylistEntry) -> Option<PathBuf> {
+    let folder = playlist_folder(&entry.playlist_title, &entry.playlist_id);
+    let in_folder = file
+        .ancestors()
+        .skip(1)
+        .any(|dir| dir.file_name().is_some_and(|name| name == folder.as_str()));
+    if !in_folder {
+        return None;
+    }
+
+    let name = file.file_name()?.to_str()?;
+    let (number, rest) = name.split_once(" - ")?;
+    let prefix = index_prefix(entry);
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) || number == prefix {
+        return None;
+    }
+
+    Some(file.with_file_name(format!("{} - {}", prefix, rest)))
+}
+
+/// Replaces the playlist fields of a yt-dlp template with the values of
+/// `entry`. See [`DownloadOptions::output_template`].
+fn fill_playlist_fields(template: &str, entry: &PlaylistEntry) -> String {
+    let restrict = |value: &str| template_literal(&sanitize_filename(value).replace(' ', "_"));
+    let field = Regex::new(
+        r"%\((playlist_index|playlist_count|n_entries|playlist_title|playlist|playlist_id)\)(0\d+)?([sd])",
+    )
+    .expect("valid template regex");
+
+    field
+        .replace_all(template, |caps: &regex::Captures| {
+            let number = |n: usize, default_width: usize| {
+                let width = caps
+                    .get(2)
+                    .and_then(|w| w.as_str().parse().ok())
+                    .unwrap_or(default_width);
+                format!("{:0width$}", n, width = width)
+            };
+            let digits = entry.playlist_count.to_string().len();
+
+            match (&caps[1], &caps[3]) {
+                ("playlist_index", "s") => number(entry.index, digits),
+                ("playlist_index", _) => number(entry.index, 0),
+                ("playlist_count" | "n_entries", _) => number(entry.playlist_count, 0),
+                ("playlist_title" | "playlist", "s") => restrict(&entry.playlist_title),
+                ("playlist_id", "s") => restrict(&entry.playlist_id),
+                _ => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Renders a yt-dlp output template for a file placed without yt-dlp, such
+/// as one reused from the library.
+///
+/// Supports the fields of [`render_known_fields`]. Templates with other
+/// fields fall back to `<title>.<ext>`.
+fn render_filename(template: &str, info: &VideoInfo, ext: &str) -> String {
+    render_known_fields(template, info, ext).unwrap_or_else(|| {
+        format!(
+            "{}.{}",
+            sanitize_filename(&info.title).replace(' ', "_"),
+            ext
+        )
+    })
+}
+
+/// Renders a yt-dlp output template the way yt-dlp would, or returns `None`
+/// if it uses a field that only yt-dlp can fill in.
+///
+/// Supports `%(title)s`, `%(id)s`, `%(ext)s`, `%(channel)s` and
+/// `%(uploader)s`. Values are sanitized like `--restrict-filenames` does
+/// (spaces become `_`).
+fn render_known_fields(template: &str, info: &VideoInfo, ext: &str) -> Option<String> {
+    let restrict = |value: &str| sanitize_filename(value).replace(' ', "_");
+
+    let field = Regex::new(r"%\((\w+)\)s").expect("valid template regex");
+    let mut supported = true;
+    let name = field.replace_all(template, |caps: &regex::Captures| match &caps[1] {
+        "title" => restrict(&info.title),
+        "id" => restrict(&info.id),
+        "ext" => ext.to_string(),
+        "channel" | "uploader" => restrict(info.channel.as_deref().unwrap_or("NA")),
+        _ => {
+            supported = false;
+            String::new()
+        }
+    });
+
+    if !supported || name.trim().is_empty() {
+        return None;
+    }
+
+    let suffix = format!(".{}", ext);
+    if name.ends_with(&suffix) {
+        Some(name.into_owned())
+    } else {
+        Some(format!("{}{}", name, suffix))
+    }
+}
+
+/// Result of a successful download operation.
+///
+/// Contains metadata and filesystem information about the downloaded file.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use rust_yt_downloader::downloader::Downloader;
+///
+/// let downloader = Downloader::new();
+/// let result = downloader.download("https://youtube.com/watch?v=dQw4w9WgXcQ").await?;
+///
+/// println!("Title: {}", result.video_title);
+/// println!("Size: {} bytes", result.file_size);
+/// println!("Path: {}", result.file_path.display());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DownloadResult {
+    /// Absolute path to the downloaded file.
+    pub file_path: PathBuf,
+
+    /// Size of the downloaded file in bytes.
+    pub file_size: u64,
+
+    /// YouTube video ID (e.g., "dQw4w9WgXcQ").
+    pub video_id: String,
+
+    /// Title of the video as provided by YouTube.
+    pub video_title: String,
+
+    /// Name of the channel that uploaded the video, when known.
+    pub channel: Option<String>,
+}
+
+/// Main downloader orchestrator that coordinates downloads with yt-dlp.
+///
+/// The `Downloader` manages the interaction between configuration options,
+/// the yt-dlp client, and download operations. It handles both video and
+/// audio-only downloads with automatic retry logic and progress reporting.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// use rust_yt_downloader::downloader::{Downloader, DownloadOptions};
 /// use rust_yt_downloader::cli::VideoQuality;
 /// use std::path::PathBuf;
@@ -485,6 +1421,13 @@ pub struct DownloadResult {
 pub struct Downloader {
     client: YtDlpClient,
     options: DownloadOptions,
+    base_args: Vec<String>,
+    rules: Vec<Rule>,
+    flags: OptionOverrides,
+    explain: bool,
+    library: Option<PathBuf>,
+    budget: Option<Arc<SizeBudget>>,
+    monitor: Option<Arc<DownloadMonitor>>,
 }
 
 impl Downloader {
@@ -498,10 +1441,7 @@ impl Downloader {
     /// let downloader = Downloader::new();
     /// ```
     pub fn new() -> Self {
-        Self {
-            client: YtDlpClient::new(),
-            options: DownloadOptions::default(),
-        }
+        Self::with_options(DownloadOptions::default())
     }
 
     /// Creates a new downloader with custom options.
@@ -519,7 +1459,14 @@ impl Downloader {
     pub fn with_options(options: DownloadOptions) -> Self {
         Self {
             client: YtDlpClient::new(),
+            base_args: Self::build_base_args(&options, false),
             options,
+            rules: Vec::new(),
+            flags: OptionOverrides::default(),
+            explain: false,
+            library: None,
+            budget: None,
+            monitor: None,
         }
     }
 
@@ -538,7 +1485,124 @@ impl Downloader {
     /// let downloader = Downloader::from_config(&config);
     /// ```
     pub fn from_config(config: &Config) -> Self {
-        Self::with_options(DownloadOptions::from_config(config))
+        Self::with_options(DownloadOptions::from_config(config)).with_rules(config.rules.clone())
+    }
+
+    /// Sets the routing rules checked for every video.
+    ///
+    /// The first rule that matches a video's metadata adjusts the options
+    /// used for that video only.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::config::Rule;
+    /// use rust_yt_downloader::downloader::Downloader;
+    ///
+    /// let rule = Rule {
+    ///     channel: Some("Lofi Girl".to_string()),
+    ///     audio_format: Some("flac".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let downloader = Downloader::new().with_rules(vec![rule]);
+    /// ```
+    pub fn with_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Applies the command-line flags and keeps them for after routing, so
+    /// that a matching rule cannot override an explicit flag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::downloader::{Downloader, OptionOverrides};
+    /// use rust_yt_downloader::cli::VideoQuality;
+    ///
+    /// let downloader = Downloader::new().with_flags(OptionOverrides {
+    ///     quality: Some(VideoQuality::Q480p),
+    ///     ..Default::default()
+    /// });
+    /// assert!(matches!(downloader.options().quality, VideoQuality::Q480p));
+    /// ```
+    pub fn with_flags(mut self, flags: OptionOverrides) -> Self {
+        self.options = self.options.with_overrides(&flags);
+        self.flags = flags;
+        self
+    }
+
+    /// Prints which routing rule matched each video and what it changed.
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// Uses the library index at `path` to avoid downloading a video twice.
+    ///
+    /// A video already in the library with the requested extension is
+    /// hard-linked (or copied) to its new location instead of being fetched,
+    /// and every finished download is added to the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use rust_yt_downloader::downloader::Downloader;
+    ///
+    /// let downloader = Downloader::new().with_library(PathBuf::from("library.json"));
+    /// ```
+    pub fn with_library(mut self, path: PathBuf) -> Self {
+        self.library = Some(path);
+        self
+    }
+
+    /// Counts every download against `budget`, the `--max-total` limit.
+    ///
+    /// Share one budget between all the downloaders of a run; videos that
+    /// do not fit in what is left are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use rust_yt_downloader::downloader::Downloader;
+    /// use rust_yt_downloader::space::SizeBudget;
+    ///
+    /// let budget = Arc::new(SizeBudget::new(10 * 1024 * 1024 * 1024));
+    /// let downloader = Downloader::new().with_budget(Some(budget));
+    /// ```
+    pub fn with_budget(mut self, budget: Option<Arc<SizeBudget>>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Reports progress to `monitor` and stops when it is cancelled.
+    ///
+    /// See [`DownloadMonitor`].
+    pub fn with_monitor(mut self, monitor: Arc<DownloadMonitor>) -> Self {
+        self.monitor = Some(monitor);
+        self.base_args = Self::build_base_args(&self.options, true);
+        self
+    }
+
+    /// Returns a downloader with the same options, rules and library for a
+    /// video downloaded from a playlist.
+    ///
+    /// The entry fills the playlist fields of the filename template; see
+    /// [`DownloadOptions::output_template`].
+    pub fn for_playlist_entry(&self, entry: PlaylistEntry) -> Downloader {
+        Downloader {
+            client: self.client.clone(),
+            options: self.options.clone().with_playlist_entry(entry),
+            base_args: self.base_args.clone(),
+            rules: self.rules.clone(),
+            flags: self.flags.clone(),
+            explain: self.explain,
+            library: self.library.clone(),
+            budget: self.budget.clone(),
+            monitor: self.monitor.clone(),
+        }
     }
 
     /// Downloads a video from the given URL.
@@ -547,6 +1611,12 @@ impl Downloader {
     /// and downloads the video using yt-dlp with the configured quality and format
     /// settings.
     ///
+    /// Live streams are recorded until they end, or for `live_duration`
+    /// minutes. An upcoming live stream or premiere fails with
+    /// `AppError::VideoUpcoming`, unless `wait_for_video` is set: its info is
+    /// then fetched again at the scheduled start, and after that every 15
+    /// seconds, doubling up to 5 minutes, until it begins.
+    ///
     /// # Arguments
     ///
     /// * `url` - YouTube video URL (e.g., `https://youtube.com/watch?v=VIDEO_ID`)
@@ -581,10 +1651,8 @@ impl Downloader {
     /// # }
     /// ```
     pub async fn download(&self, url: &str) -> AppResult<DownloadResult> {
-        use std::io::{BufRead, BufReader};
-        use std::process::{Command, Stdio};
-
-        YtDlpClient::require()?;
+        let url = &validate_url(url)?;
+        self.client.ensure_available()?;
 
         // Busca informações do vídeo primeiro
         if !self.options.silence {
@@ -592,12 +1660,211 @@ impl Downloader {
         }
 
         let video_info = self.client.get_video_info(url)?;
+        let video_info = self.wait_for_start(url, video_info).await?;
 
         if self.options.verbose {
             messages::info(&format!("Title: {}", video_info.title));
             messages::info(&format!("Duration: {} seconds", video_info.duration));
         }
 
+        let media = if self.options.audio_only {
+            MediaKind::Audio
+        } else {
+            MediaKind::Video
+        };
+        let routed = self.route(url, &video_info, self.options.clone(), media);
+
+        if routed.options.audio_only {
+            routed.save_audio(url, video_info)
+        } else {
+            routed.save_video(url, video_info)
+        }
+    }
+
+    /// Renames numbered files of a playlist folder whose videos have moved.
+    ///
+    /// With `playlist_folders`, a file is named after the position its video
+    /// had when it was downloaded. `listing` is the playlist as it is now;
+    /// every file the library knows for one of its videos that sits in the
+    /// playlist folder with another number is renamed to the current one,
+    /// together with its `.info.json` and `.nfo` sidecars. Files whose new
+    /// name is taken are left alone.
+    ///
+    /// Returns the renamed files as `(old, new)` pairs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the library cannot be read or saved, or a file
+    /// cannot be renamed.
+    pub fn renumber_playlist(
+        &self,
+        listing: &[PlaylistEntry],
+    ) -> AppResult<Vec<(PathBuf, PathBuf)>> {
+        let Some(index) = self.library.as_ref() else {
+            return Ok(Vec::new());
+        };
+
+        Library::update(index, |library| {
+            let mut renamed = Vec::new();
+
+            for entry in listing {
+                for file in library.files(&entry.video_id).to_vec() {
+                    let Some(target) = renumbered_path(&file, entry) else {
+                        continue;
+                    };
+                    if !file.is_file() || target.exists() {
+                        continue;
+                    }
+
+                    std::fs::rename(&file, &target)
+                        .map_err(|e| AppError::file_write(&target, e))?;
+                    for ext in ["info.json", "nfo"] {
+                        let sidecar = file.with_extension(ext);
+                        if sidecar.is_file() {
+                            std::fs::rename(&sidecar, target.with_extension(ext))
+                                .map_err(|e| AppError::file_write(&sidecar, e))?;
+                        }
+                    }
+
+                    library.add(&entry.video_id, &target);
+                    renamed.push((file, target));
+                }
+            }
+
+            if !renamed.is_empty() {
+                library.remove_missing();
+            }
+
+            Ok(renamed)
+        })
+    }
+
+    /// Works out what downloading `url` would do, without downloading it.
+    ///
+    /// Fetches the video info and applies the routing rules exactly like
+    /// [`Downloader::download`] (or [`Downloader::download_audio`] when the
+    /// options are audio-only), then reports the resulting options, yt-dlp
+    /// format selector, output path, library reuse and estimated size. Used
+    /// by `--dry-run`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid, yt-dlp is missing, the
+    /// video info cannot be fetched, or the video would be skipped by
+    /// `max_filesize` or the budget.
+    pub fn plan(&self, url: &str) -> AppResult<PlannedDownload> {
+        let url = &validate_url(url)?;
+        self.client.ensure_available()?;
+
+        let video_info = self.client.get_video_info(url)?;
+        let media = if self.options.audio_only {
+            MediaKind::Audio
+        } else {
+            MediaKind::Video
+        };
+
+        let playlist_id = extract_playlist_id(url);
+        let ctx = RuleContext {
+            url,
+            info: &video_info,
+            playlist_id: playlist_id.as_deref(),
+            media,
+        };
+        let rule = find_match(&self.rules, &ctx).map(|(index, rule)| rule.label(index));
+        let routed = self.route(url, &video_info, self.options.clone(), media);
+        let options = &routed.options;
+
+        let (container, produced, format, sort) = if options.audio_only {
+            (
+                options.audio_format.extension(),
+                options.audio_format_str(),
+                options.preference.ytdlp_audio_format(),
+                None,
+            )
+        } else {
+            (
+                options.video_format.extension(),
+                options.video_format_ext(),
+                Some(options.format_selector()),
+                options.format_sort(),
+            )
+        };
+
+        let library_file = self
+            .library
+            .as_ref()
+            .and_then(|index| Library::load(index).ok())
+            .and_then(|library| {
+                library
+                    .find(&video_info.id, container)
+                    .map(Path::to_path_buf)
+            });
+
+        let template = options.output_template();
+        let rendered = render_known_fields(&template, &video_info, container);
+        // Library files are placed under the fallback name as well, so only
+        // a file yt-dlp names itself can end up elsewhere
+        let output_path_approximate = rendered.is_none() && library_file.is_none();
+        let output_path = options
+            .output_dir
+            .join(rendered.unwrap_or_else(|| render_filename(&template, &video_info, container)));
+        // yt-dlp skips a file that exists, and the library finds it in place
+        let already_downloaded = !output_path_approximate && output_path.is_file();
+
+        let estimated_size = options.estimated_size(&video_info);
+        if library_file.is_none() && !already_downloaded {
+            // Later plans see the estimate as already downloaded
+            if let Some(reservation) = routed.check_limits(&video_info, estimated_size)? {
+                reservation.commit(estimated_size.unwrap_or(0));
+            }
+        }
+
+        Ok(PlannedDownload {
+            url: url.to_string(),
+            video_id: video_info.id.clone(),
+            title: video_info.title.clone(),
+            rule,
+            audio_only: options.audio_only,
+            quality: options
+                .quality
+                .to_possible_value()
+                .map_or_else(String::new, |value| value.get_name().to_string()),
+            format,
+            sort,
+            container: container.to_string(),
+            convert_from: (produced != container).then(|| produced.to_string()),
+            output_dir: options.output_dir.clone(),
+            filename_template: template,
+            output_path,
+            output_path_approximate,
+            already_downloaded,
+            library_file,
+            estimated_size,
+            write_info_json: options.write_info_json,
+            write_nfo: options.write_nfo,
+        })
+    }
+
+    /// Downloads the video once its info is known, using this downloader's options.
+    fn save_video(&self, url: &str, video_info: VideoInfo) -> AppResult<DownloadResult> {
+        // Confere se os format IDs pedidos existem antes de chamar o yt-dlp
+        if let Some(ref format_id) = self.options.format_id {
+            Self::check_format_id(&video_info, format_id)?;
+        }
+
+        if let Some(result) =
+            self.reuse_from_library(&video_info, self.options.video_format.extension())
+        {
+            return Ok(result);
+        }
+
+        // Formatos que o yt-dlp não gera precisam do FFmpeg depois
+        if self.options.needs_video_conversion() {
+            FFmpeg::require()?;
+        }
+
+        let reservation = self.check_size(&video_info)?;
+
         // Cria diretório de saída se não existir
         if !self.options.output_dir.exists() {
             std::fs::create_dir_all(&self.options.output_dir)
@@ -608,103 +1875,71 @@ impl Downloader {
         let output_template = self
             .options
             .output_dir
-            .join("%(title)s.%(ext)s")
+            .join(self.options.output_template())
             .to_string_lossy()
             .to_string();
 
         // Monta argumentos do yt-dlp
-        let format_str = self.options.quality_to_ytdlp();
+        let format_str = self.options.format_selector();
         let merge_format = self.options.video_format_ext();
 
         if !self.options.silence {
             messages::downloading(&video_info.title);
         }
 
-        let mut args = vec![
-            "--no-warnings",
-            "-f",
-            &format_str,
-            "--merge-output-format",
-            merge_format,
-            "-o",
-            &output_template,
-            "--no-playlist",
-            "--restrict-filenames",
-        ];
-
-        // Adiciona progresso se não estiver em modo silencioso
-        if !self.options.silence {
-            args.push("--newline");
-            args.push("--progress");
-        } else {
-            args.push("--quiet");
-        }
-
-        args.push(url);
-
-        if self.options.silence {
-            // Modo silencioso: executa sem mostrar nada
-            let output = Command::new("yt-dlp")
-                .args(&args)
-                .output()
-                .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(AppError::ExtractionFailed(stderr.to_string()));
-            }
-        } else {
-            // Modo normal: mostra progresso
-            let mut child = Command::new("yt-dlp")
-                .args(&args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
-
-            // Lê stdout em tempo real para mostrar progresso
-            if let Some(stdout) = child.stdout.take() {
-                let reader = BufReader::new(stdout);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        if line.contains('%') {
-                            // Linha de progresso - mostra na mesma linha
-                            print!("\r{}", line);
-                            std::io::Write::flush(&mut std::io::stdout()).ok();
-                        } else if self.options.verbose && !line.is_empty() {
-                            println!("{}", line);
-                        }
-                    }
-                }
-                println!(); // Nova linha após o progresso
-            }
-
-            let status = child
-                .wait()
-                .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
-
-            if !status.success() {
-                return Err(AppError::ExtractionFailed("Download failed".to_string()));
-            }
+        let mut args = self.base_ytdlp_args();
+        args.extend(self.live_ytdlp_args(&video_info)?);
+        if let Some(sort) = self.options.format_sort() {
+            args.push("-S".to_string());
+            args.push(sort);
         }
+        args.extend([
+            "-f".to_string(),
+            format_str,
+            "--merge-output-format".to_string(),
+            merge_format.to_string(),
+            "-o".to_string(),
+            output_template,
+        ]);
+        let path_file = PathReport::new(&video_info.id, &mut args);
+        // `--` keeps yt-dlp from reading the URL as an option
+        args.push("--".to_string());
+        args.push(url.to_string());
+
+        self.run_ytdlp(&args, "Download failed")?;
 
         // Encontra o arquivo baixado
-        let actual_path = self
-            .find_downloaded_file_by_ext(merge_format)
+        let downloaded_path = path_file
+            .take()
+            .or_else(|| self.find_downloaded_file_by_ext(merge_format))
             .ok_or_else(|| {
                 AppError::ExtractionFailed("Could not find downloaded file".to_string())
             })?;
 
+        let actual_path = if self.options.needs_video_conversion() {
+            self.convert_video(downloaded_path)?
+        } else {
+            downloaded_path
+        };
+
         let file_size = std::fs::metadata(&actual_path)
             .map(|m| m.len())
             .unwrap_or(0);
+        if let Some(reservation) = reservation {
+            reservation.commit(file_size);
+        }
+        self.write_sidecars(&video_info, &actual_path);
 
-        Ok(DownloadResult {
+        let result = DownloadResult {
             file_path: actual_path,
             file_size,
             video_id: video_info.id,
             video_title: video_info.title,
-        })
+            channel: video_info.channel,
+        };
+        self.record_in_library(&result);
+
+        Ok(result)
     }
 
     /// Downloads only the audio track from the given URL.
@@ -749,10 +1984,8 @@ impl Downloader {
     /// # }
     /// ```
     pub async fn download_audio(&self, url: &str) -> AppResult<DownloadResult> {
-        use std::io::{BufRead, BufReader};
-        use std::process::{Command, Stdio};
-
-        YtDlpClient::require()?;
+        let url = &validate_url(url)?;
+        self.client.ensure_available()?;
 
         // Busca informações do vídeo primeiro
         if !self.options.silence {
@@ -760,11 +1993,30 @@ impl Downloader {
         }
 
         let video_info = self.client.get_video_info(url)?;
+        let video_info = self.wait_for_start(url, video_info).await?;
 
         if self.options.verbose {
             messages::info(&format!("Title: {}", video_info.title));
         }
 
+        let options = self.options.clone().with_audio_only(true);
+        let routed = self.route(url, &video_info, options, MediaKind::Audio);
+
+        if routed.options.audio_only {
+            routed.save_audio(url, video_info)
+        } else {
+            routed.save_video(url, video_info)
+        }
+    }
+
+    /// Extracts the audio once the video info is known, using this downloader's options.
+    fn save_audio(&self, url: &str, video_info: VideoInfo) -> AppResult<DownloadResult> {
+        if let Some(result) =
+            self.reuse_from_library(&video_info, self.options.audio_format.extension())
+        {
+            return Ok(result);
+        }
+
         // Cria diretório de saída se não existir
         if !self.options.output_dir.exists() {
             std::fs::create_dir_all(&self.options.output_dir)
@@ -775,108 +2027,636 @@ impl Downloader {
         let output_template = self
             .options
             .output_dir
-            .join("%(title)s.%(ext)s")
+            .join(self.options.output_template())
             .to_string_lossy()
             .to_string();
 
         let audio_format = self.options.audio_format_str();
 
+        // Formatos que o yt-dlp não gera precisam do FFmpeg depois
+        if self.options.needs_audio_conversion() {
+            FFmpeg::require()?;
+        }
+
+        let reservation = self.check_size(&video_info)?;
+
         if !self.options.silence {
             messages::downloading(&video_info.title);
         }
 
-        let mut args = vec![
-            "--no-warnings",
-            "-x",
-            "--audio-format",
-            audio_format,
-            "-o",
-            &output_template,
-            "--no-playlist",
-            "--restrict-filenames",
-        ];
+        let mut args = self.base_ytdlp_args();
+        args.extend(self.live_ytdlp_args(&video_info)?);
+        if let Some(format) = self.options.preference.ytdlp_audio_format() {
+            args.push("-f".to_string());
+            args.push(format);
+        }
+        args.extend([
+            "-x".to_string(),
+            "--audio-format".to_string(),
+            audio_format.to_string(),
+            "-o".to_string(),
+            output_template,
+        ]);
+        let path_file = PathReport::new(&video_info.id, &mut args);
+        // `--` keeps yt-dlp from reading the URL as an option
+        args.push("--".to_string());
+        args.push(url.to_string());
+
+        self.run_ytdlp(&args, "Audio extraction failed")?;
 
-        // Adiciona progresso se não estiver em modo silencioso
-        if !self.options.silence {
-            args.push("--newline");
-            args.push("--progress");
+        // Encontra o arquivo baixado
+        let downloaded_path = path_file
+            .take()
+            .or_else(|| self.find_downloaded_file_by_ext(audio_format))
+            .ok_or_else(|| {
+                AppError::ExtractionFailed("Could not find downloaded file".to_string())
+            })?;
+
+        let actual_path = if self.options.needs_audio_conversion() {
+            self.convert_audio(downloaded_path)?
         } else {
-            args.push("--quiet");
+            downloaded_path
+        };
+
+        let file_size = std::fs::metadata(&actual_path)
+            .map(|m| m.len())
+            .unwrap_or(0);
+        if let Some(reservation) = reservation {
+            reservation.commit(file_size);
+        }
+        self.write_sidecars(&video_info, &actual_path);
+
+        let result = DownloadResult {
+            file_path: actual_path,
+            file_size,
+            video_id: video_info.id,
+            video_title: video_info.title,
+            channel: video_info.channel,
+        };
+        self.record_in_library(&result);
+
+        Ok(result)
+    }
+
+    /// Applies the first rule matching the video to `options`, then the
+    /// command-line flags again, and returns a downloader using the result.
+    fn route(
+        &self,
+        url: &str,
+        video_info: &VideoInfo,
+        options: DownloadOptions,
+        media: MediaKind,
+    ) -> Downloader {
+        let playlist_id = extract_playlist_id(url);
+        let ctx = RuleContext {
+            url,
+            info: video_info,
+            playlist_id: playlist_id.as_deref(),
+            media,
+        };
+
+        let matched = find_match(&self.rules, &ctx);
+        let options = match matched {
+            Some((_, rule)) => options
+                .with_overrides(&OptionOverrides::from_rule(rule, &ctx))
+                .with_overrides(&self.flags),
+            None => options,
+        };
+
+        if self.explain {
+            self.explain_route(matched, &ctx, &options);
+        }
+
+        // Rules don't change the options the base arguments depend on
+        Downloader {
+            client: self.client.clone(),
+            options,
+            base_args: self.base_args.clone(),
+            rules: Vec::new(),
+            flags: OptionOverrides::default(),
+            explain: false,
+            library: self.library.clone(),
+            budget: self.budget.clone(),
+            monitor: self.monitor.clone(),
+        }
+    }
+
+    /// Places a copy of a video that is already in the library at the path
+    /// this download would have, instead of downloading it again.
+    ///
+    /// Returns `None` when there is no library, the video is not in it with
+    /// the extension `ext`, or the file cannot be placed; the caller
+    /// then downloads normally.
+    fn reuse_from_library(&self, video_info: &VideoInfo, ext: &str) -> Option<DownloadResult> {
+        let index = self.library.as_ref()?;
+
+        let library = Library::load(index)
+            .map_err(|e| messages::warning(&e.to_string()))
+            .ok()?;
+        let source = library.find(&video_info.id, ext)?.to_path_buf();
+        let target = self.options.output_dir.join(render_filename(
+            &self.options.output_template(),
+            video_info,
+            ext,
+        ));
+
+        let reuse = library
+            .link_or_copy(&video_info.id, &source, &target)
+            .map_err(|e| {
+                messages::warning(&format!(
+                    "Could not reuse {}: {} (downloading instead)",
+                    source.display(),
+                    e
+                ))
+            })
+            .ok()?;
+
+        if !self.options.silence {
+            messages::info(&format!(
+                "Already in library, {} from {}",
+                reuse,
+                source.display()
+            ));
+        }
+        self.write_sidecars(video_info, &target);
+
+        let result = DownloadResult {
+            file_size: std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0),
+            file_path: target,
+            video_id: video_info.id.clone(),
+            video_title: video_info.title.clone(),
+            channel: video_info.channel.clone(),
+        };
+        self.record_in_library(&result);
+
+        Some(result)
+    }
+
+    /// Writes the `.info.json` and `.nfo` sidecars enabled in the options
+    /// next to `file`.
+    ///
+    /// Failing to write a sidecar only produces a warning.
+    fn write_sidecars(&self, video_info: &VideoInfo, file: &Path) {
+        if self.options.write_info_json {
+            if let Err(e) = sidecar::write_info_json(video_info, file) {
+                messages::warning(&format!("Could not write info JSON: {}", e));
+            }
+        }
+        if self.options.write_nfo {
+            if let Err(e) = sidecar::write_nfo(video_info, file) {
+                messages::warning(&format!("Could not write NFO: {}", e));
+            }
+        }
+    }
+
+    /// Returns `video_info` once the video has started, waiting for an
+    /// upcoming live stream or premiere if `wait_for_video` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::VideoUpcoming` if the video has not started and
+    /// waiting is off, or an error if its info cannot be fetched again.
+    async fn wait_for_start(&self, url: &str, mut video_info: VideoInfo) -> AppResult<VideoInfo> {
+        let mut attempt = 0;
+
+        while video_info.is_upcoming() {
+            let starts = match video_info.release_time() {
+                Some(time) => format!("at {}", time.with_timezone(&Local).format("%Y-%m-%d %H:%M")),
+                None => "at an unknown time".to_string(),
+            };
+            if !self.options.wait_for_video {
+                return Err(AppError::VideoUpcoming {
+                    video_id: video_info.id,
+                    starts,
+                });
+            }
+
+            let until_start = video_info
+                .release_timestamp
+                .map(|timestamp| timestamp - Utc::now().timestamp());
+            let delay = poll_delay(attempt, until_start);
+            if !self.options.silence {
+                messages::info(&format!(
+                    "{} starts {}; checking again in {}",
+                    video_info.title,
+                    starts,
+                    format_duration(delay.as_secs())
+                ));
+            }
+
+            match &self.monitor {
+                Some(monitor) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = monitor.cancelled() => return Err(AppError::Cancelled),
+                    }
+                }
+                None => tokio::time::sleep(delay).await,
+            }
+            attempt += 1;
+            video_info = self.client.get_video_info(url)?;
+        }
+
+        Ok(video_info)
+    }
+
+    /// Returns the yt-dlp arguments for recording a live stream; empty for
+    /// videos that are not live.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::FfmpegNotFound` if `live_duration` is set and
+    /// FFmpeg is missing.
+    fn live_ytdlp_args(&self, video_info: &VideoInfo) -> AppResult<Vec<String>> {
+        let mut args = Vec::new();
+        if !video_info.is_live() {
+            return Ok(args);
+        }
+
+        if self.options.live_from_start {
+            args.push("--live-from-start".to_string());
+        }
+
+        if let Some(minutes) = self.options.live_duration {
+            // FFmpeg stops writing the recording after `-t` seconds
+            FFmpeg::require()?;
+            args.extend([
+                "--downloader".to_string(),
+                "ffmpeg".to_string(),
+                "--downloader-args".to_string(),
+                format!("ffmpeg:-t {}", u64::from(minutes) * 60),
+            ]);
+        }
+
+        Ok(args)
+    }
+
+    /// Checks the estimated size of the download before yt-dlp starts.
+    ///
+    /// Applies [`Downloader::check_limits`], then fails early if the output
+    /// filesystem is short of space. yt-dlp keeps the downloaded streams
+    /// until the merged or converted file is written, so twice the estimate
+    /// must be free. Videos of unknown size are not checked for space.
+    ///
+    /// Returns the budget reservation to commit once the file is saved.
+    fn check_size(&self, video_info: &VideoInfo) -> AppResult<Option<Reservation<'_>>> {
+        let estimate = self.options.estimated_size(video_info);
+        if let (Some(size), true) = (estimate, self.options.verbose) {
+            messages::info(&format!("Estimated size: {}", format_bytes(size)));
+        }
+
+        let reservation = self.check_limits(video_info, estimate)?;
+        if let Some(size) = estimate {
+            check_free_space(&self.options.output_dir, size.saturating_mul(2))?;
+        }
+
+        Ok(reservation)
+    }
+
+    /// Skips the video if its `estimate` is above `max_filesize` or does
+    /// not fit in the budget, and otherwise reserves it in the budget.
+    fn check_limits(
+        &self,
+        video_info: &VideoInfo,
+        estimate: Option<u64>,
+    ) -> AppResult<Option<Reservation<'_>>> {
+        if let (Some(size), Some(limit)) = (estimate, self.options.max_filesize) {
+            if size > limit {
+                return Err(AppError::FileTooLarge {
+                    title: video_info.title.clone(),
+                    size,
+                    limit,
+                });
+            }
+        }
+
+        match &self.budget {
+            Some(budget) => {
+                budget
+                    .reserve(estimate)
+                    .map(Some)
+                    .ok_or_else(|| AppError::BudgetExceeded {
+                        title: video_info.title.clone(),
+                        limit: budget.limit(),
+                    })
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Adds a finished download to the library index, if there is one.
+    ///
+    /// Failing to update the index only produces a warning.
+    fn record_in_library(&self, result: &DownloadResult) {
+        let Some(index) = self.library.as_ref() else {
+            return;
+        };
+
+        let updated = Library::update(index, |library| {
+            library.add(&result.video_id, &result.file_path);
+            Ok(())
+        });
+
+        if let Err(e) = updated {
+            messages::warning(&format!("Could not update library index: {}", e));
+        }
+    }
+
+    /// Prints the outcome of routing for `--explain`.
+    fn explain_route(
+        &self,
+        matched: Option<(usize, &Rule)>,
+        ctx: &RuleContext,
+        options: &DownloadOptions,
+    ) {
+        match matched {
+            Some((index, rule)) => {
+                let conditions = rule
+                    .conditions()
+                    .iter()
+                    .map(|(key, value)| format!("{} = {}", key, value))
+                    .collect::<Vec<_>>();
+                let conditions = if conditions.is_empty() {
+                    "matches every video".to_string()
+                } else {
+                    conditions.join(", ")
+                };
+                messages::info(&format!(
+                    "Rule {} matched \"{}\" ({})",
+                    rule.label(index),
+                    ctx.info.title,
+                    conditions
+                ));
+
+                let pinned = self.flags.keys();
+                for (key, value) in rule.overrides() {
+                    if pinned.contains(&key) {
+                        messages::info(&format!(
+                            "  {} = {} (overridden by command line)",
+                            key, value
+                        ));
+                    } else {
+                        messages::info(&format!("  {} = {}", key, value));
+                    }
+                }
+            }
+            None if self.rules.is_empty() => {
+                messages::info("No routing rules configured");
+            }
+            None => {
+                messages::info(&format!(
+                    "No rule matched \"{}\" ({} checked)",
+                    ctx.info.title,
+                    self.rules.len()
+                ));
+            }
         }
 
-        args.push(url);
+        messages::info(&format!("Saving to {}", options.output_dir.display()));
+    }
+
+    /// Runs yt-dlp with the given arguments.
+    ///
+    /// In silent mode the process output is captured and only reported on
+    /// failure. Otherwise progress lines are streamed to the terminal as they
+    /// arrive, and other output is shown when verbose mode is enabled. With a
+    /// [`DownloadMonitor`], progress is streamed to it in both modes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::Cancelled` if the monitor was cancelled.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - Complete yt-dlp argument list, including the URL
+    /// * `failure` - Message used when yt-dlp exits with a non-zero status
+    fn run_ytdlp(&self, args: &[String], failure: &str) -> AppResult<()> {
+        use std::process::{Command, Stdio};
 
-        if self.options.silence {
+        if self.options.silence && self.monitor.is_none() {
             // Modo silencioso: executa sem mostrar nada
             let output = Command::new("yt-dlp")
-                .args(&args)
+                .args(args)
                 .output()
                 .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
 
             if !output.status.success() {
                 let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(AppError::ExtractionFailed(stderr.to_string()));
+                return Err(classify_failure(&stderr));
             }
-        } else {
-            // Modo normal: mostra progresso
-            let mut child = Command::new("yt-dlp")
-                .args(&args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
 
-            // Lê stdout em tempo real para mostrar progresso
-            if let Some(stdout) = child.stdout.take() {
-                let reader = BufReader::new(stdout);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        if line.contains('%') {
-                            // Linha de progresso - mostra na mesma linha
-                            print!("\r{}", line);
-                            std::io::Write::flush(&mut std::io::stdout()).ok();
-                        } else if self.options.verbose && !line.is_empty() {
-                            println!("{}", line);
-                        }
-                    }
+            return Ok(());
+        }
+
+        // Modo normal: mostra progresso
+        let mut child = Command::new("yt-dlp")
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
+
+        // Drena o stderr em paralelo para classificar a falha no final
+        let stderr_reader = child.stderr.take().map(|mut stderr| {
+            std::thread::spawn(move || {
+                let mut output = String::new();
+                std::io::Read::read_to_string(&mut stderr, &mut output).ok();
+                output
+            })
+        });
+        let stdout = child.stdout.take();
+
+        // With a monitor the process is kept there, so that it can be killed
+        let status = match &self.monitor {
+            Some(monitor) => {
+                monitor.attach(child);
+                self.stream_output(stdout);
+                monitor.wait()
+            }
+            None => {
+                self.stream_output(stdout);
+                child.wait()
+            }
+        }
+        .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
+
+        let stderr = stderr_reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+
+        if self
+            .monitor
+            .as_ref()
+            .is_some_and(|monitor| monitor.is_cancelled())
+        {
+            return Err(AppError::Cancelled);
+        }
+
+        if !status.success() {
+            if stderr.trim().is_empty() {
+                return Err(AppError::ExtractionFailed(failure.to_string()));
+            }
+            return Err(classify_failure(&stderr));
+        }
+
+        Ok(())
+    }
+
+    /// Reads yt-dlp's output until it exits, passing progress lines to the
+    /// monitor and showing them unless silent.
+    fn stream_output(&self, stdout: Option<ChildStdout>) {
+        use std::io::{BufRead, BufReader};
+
+        // Lê stdout em tempo real para mostrar progresso
+        let Some(stdout) = stdout else {
+            return;
+        };
+        let silence = self.options.silence;
+
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.contains('%') {
+                if let Some(monitor) = &self.monitor {
+                    monitor.update(&line);
                 }
-                println!(); // Nova linha após o progresso
+                if !silence {
+                    // Linha de progresso - mostra na mesma linha
+                    print!("\r{}", line);
+                    std::io::Write::flush(&mut std::io::stdout()).ok();
+                }
+            } else if self.options.verbose && !silence && !line.is_empty() {
+                println!("{}", line);
             }
+        }
 
-            let status = child
-                .wait()
-                .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
+        if !silence {
+            println!(); // Nova linha após o progresso
+        }
+    }
 
-            if !status.success() {
-                return Err(AppError::ExtractionFailed(
-                    "Audio extraction failed".to_string(),
-                ));
+    /// Converts a downloaded video into the requested container.
+    ///
+    /// Uses stream copy when the containers are compatible (MP4 → MOV) and
+    /// re-encodes otherwise (MP4 → AVI). The intermediate file is removed once
+    /// the conversion succeeds.
+    fn convert_video(&self, input: PathBuf) -> AppResult<PathBuf> {
+        let target = self.options.video_format;
+        let output = VideoConverter::output_path_with_format(&input, target);
+
+        if !self.options.silence {
+            messages::info(&format!("Converting to {}...", target.extension()));
+        }
+
+        let options = if VideoConverter::needs_reencode(&input, target) {
+            ConversionOptions::reencode(target)
+        } else {
+            ConversionOptions::fast(target)
+        };
+
+        VideoConverter::convert(&input, &output, &options)?;
+        std::fs::remove_file(&input)?;
+
+        Ok(output)
+    }
+
+    /// Converts extracted audio into the requested format.
+    ///
+    /// The intermediate file is removed once the conversion succeeds.
+    fn convert_audio(&self, input: PathBuf) -> AppResult<PathBuf> {
+        let target = self.options.audio_format;
+        let output = AudioExtractor::output_path_with_format(&input, target);
+
+        if !self.options.silence {
+            messages::info(&format!("Converting to {}...", target.extension()));
+        }
+
+        AudioExtractor::convert(
+            &input,
+            &output,
+            &AudioOptions::default().with_format(target),
+        )?;
+        std::fs::remove_file(&input)?;
+
+        Ok(output)
+    }
+
+    /// Verifies that an explicit selection names formats the video offers.
+    ///
+    /// Format IDs may be combined with `+` (merge) or `/` (fallback), e.g.
+    /// `137+140` or `22/18`. A fallback alternative passes when every ID it
+    /// merges exists, and the selection passes when one alternative does, so
+    /// typos are reported before yt-dlp is started. Only literal IDs are
+    /// checked: selectors such as `bv*+ba` or `bestvideo[height<=720]`, and
+    /// anything with grouping or `,`, are left for yt-dlp to resolve.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::QualityNotAvailable` listing the available format IDs
+    /// if no alternative can be satisfied.
+    fn check_format_id(video_info: &VideoInfo, format_id: &str) -> AppResult<()> {
+        if format_id.contains(['(', ')', ',']) {
+            return Ok(());
+        }
+
+        let missing = |part: &&str| {
+            Self::is_literal_format_id(part) && video_info.stream_by_format_id(part).is_none()
+        };
+        let mut first_missing = None;
+        for alternative in format_id.split('/') {
+            match alternative.split('+').find(missing) {
+                Some(id) => {
+                    first_missing.get_or_insert(id);
+                }
+                None => return Ok(()),
             }
         }
 
-        // Encontra o arquivo baixado
-        let actual_path = self
-            .find_downloaded_file_by_ext(audio_format)
-            .ok_or_else(|| {
-                AppError::ExtractionFailed("Could not find downloaded file".to_string())
-            })?;
+        match first_missing {
+            Some(id) => Err(AppError::QualityNotAvailable {
+                requested: id.to_string(),
+                available: video_info
+                    .streams
+                    .iter()
+                    .map(|s| s.format_id.clone())
+                    .collect(),
+            }),
+            None => Ok(()),
+        }
+    }
 
-        let file_size = std::fs::metadata(&actual_path)
-            .map(|m| m.len())
-            .unwrap_or(0);
+    /// Returns whether a part of a format selection names a format directly,
+    /// like `137` or `hls-1080p`, rather than selecting one like `bv*`,
+    /// `best` or `22[ext=mp4]`.
+    fn is_literal_format_id(part: &str) -> bool {
+        const SELECTORS: &[&str] = &[
+            "b",
+            "w",
+            "bv",
+            "ba",
+            "wv",
+            "wa",
+            "best",
+            "worst",
+            "bestvideo",
+            "bestaudio",
+            "worstvideo",
+            "worstaudio",
+            "all",
+            "mergeall",
+        ];
 
-        Ok(DownloadResult {
-            file_path: actual_path,
-            file_size,
-            video_id: video_info.id,
-            video_title: video_info.title,
-        })
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            && !SELECTORS.contains(&part)
     }
 
     /// Finds the most recently downloaded file with the specified extension.
     ///
     /// Searches the output directory for files matching the given extension
-    /// and returns the one with the most recent modification time.
+    /// and returns the one with the most recent modification time. Only used
+    /// when yt-dlp did not report the path (see [`PathReport`]), since another
+    /// download into the same directory may finish later.
     ///
     /// # Arguments
     ///
@@ -936,32 +2716,42 @@ impl Downloader {
     /// downloader.set_options(new_options);
     /// ```
     pub fn set_options(&mut self, options: DownloadOptions) {
+        self.base_args = Self::build_base_args(&options, self.monitor.is_some());
         self.options = options;
     }
 
+    /// Returns the base argument list for yt-dlp commands.
+    ///
+    /// The list is built once, when the options or the monitor are set, and
+    /// shared by every download of this downloader and its playlist entries.
+    fn base_ytdlp_args(&self) -> Vec<String> {
+        self.base_args.clone()
+    }
+
     /// Builds the base argument list for yt-dlp commands.
     ///
     /// Constructs common arguments used across all download operations,
     /// including warnings suppression, filename restrictions, progress
     /// options, and rate limiting (if configured).
-    fn base_ytdlp_args(&self) -> Vec<String> {
+    fn build_base_args(options: &DownloadOptions, monitored: bool) -> Vec<String> {
         let mut args = vec![
             "--no-warnings".to_string(),
             "--no-playlist".to_string(),
             "--restrict-filenames".to_string(),
         ];
 
-        let config = Config::load().unwrap_or_default();
-        if let Some(ref limit) = config.network.rate_limit {
+        if let Some(limit) = options.rate_limit() {
             args.push("--rate-limit".to_string());
-            args.push(limit.clone());
+            args.push(limit);
         }
 
-        if !self.options.silence {
+        if options.silence {
+            args.push("--quiet".to_string());
+        }
+        // `--progress` keeps progress lines for a monitor even with `--quiet`
+        if !options.silence || monitored {
             args.push("--newline".to_string());
             args.push("--progress".to_string());
-        } else {
-            args.push("--quiet".to_string());
         }
 
         args
@@ -977,6 +2767,7 @@ impl Default for Downloader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::youtube::{LiveStatus, VideoCodec};
     use std::path::PathBuf;
 
     // ============== DownloadOptions Default Tests ==============
@@ -1026,6 +2817,13 @@ mod tests {
         assert!(matches!(options.audio_format, AudioFormat::Flac));
     }
 
+    #[test]
+    fn test_download_options_with_format_id() {
+        let options = DownloadOptions::default().with_format_id("137+140".to_string());
+
+        assert_eq!(options.format_id.as_deref(), Some("137+140"));
+    }
+
     #[test]
     fn test_download_options_with_audio_only() {
         let options = DownloadOptions::default().with_audio_only(true);
@@ -1161,278 +2959,1337 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_quality_unknown_defaults_to_best() {
-        let quality = DownloadOptions::parse_quality("unknown");
-        assert!(matches!(quality, VideoQuality::Best));
+    fn test_parse_quality_unknown_defaults_to_best() {
+        let quality = DownloadOptions::parse_quality("unknown");
+        assert!(matches!(quality, VideoQuality::Best));
+    }
+
+    #[test]
+    fn test_parse_video_format_mp4() {
+        let format = DownloadOptions::parse_video_format("mp4");
+        assert!(matches!(format, VideoFormat::Mp4));
+    }
+
+    #[test]
+    fn test_parse_video_format_mkv() {
+        let format = DownloadOptions::parse_video_format("mkv");
+        assert!(matches!(format, VideoFormat::Mkv));
+    }
+
+    #[test]
+    fn test_parse_video_format_webm() {
+        let format = DownloadOptions::parse_video_format("webm");
+        assert!(matches!(format, VideoFormat::Webm));
+    }
+
+    #[test]
+    fn test_parse_video_format_case_insensitive() {
+        assert!(matches!(
+            DownloadOptions::parse_video_format("MP4"),
+            VideoFormat::Mp4
+        ));
+        assert!(matches!(
+            DownloadOptions::parse_video_format("MKV"),
+            VideoFormat::Mkv
+        ));
+    }
+
+    #[test]
+    fn test_parse_video_format_unknown_defaults_to_mp4() {
+        let format = DownloadOptions::parse_video_format("flv");
+        assert!(matches!(format, VideoFormat::Mp4));
+    }
+
+    #[test]
+    fn test_parse_video_format_converted_formats() {
+        assert!(matches!(
+            DownloadOptions::parse_video_format("avi"),
+            VideoFormat::Avi
+        ));
+        assert!(matches!(
+            DownloadOptions::parse_video_format("MOV"),
+            VideoFormat::Mov
+        ));
+    }
+
+    #[test]
+    fn test_parse_audio_format_converted_formats() {
+        assert!(matches!(
+            DownloadOptions::parse_audio_format("aac"),
+            AudioFormat::Aac
+        ));
+        assert!(matches!(
+            DownloadOptions::parse_audio_format("OGG"),
+            AudioFormat::Ogg
+        ));
+    }
+
+    #[test]
+    fn test_parse_audio_format_mp3() {
+        let format = DownloadOptions::parse_audio_format("mp3");
+        assert!(matches!(format, AudioFormat::Mp3));
+    }
+
+    #[test]
+    fn test_parse_audio_format_m4a() {
+        let format = DownloadOptions::parse_audio_format("m4a");
+        assert!(matches!(format, AudioFormat::M4a));
+    }
+
+    #[test]
+    fn test_parse_audio_format_flac() {
+        let format = DownloadOptions::parse_audio_format("flac");
+        assert!(matches!(format, AudioFormat::Flac));
+    }
+
+    #[test]
+    fn test_parse_audio_format_wav() {
+        let format = DownloadOptions::parse_audio_format("wav");
+        assert!(matches!(format, AudioFormat::Wav));
+    }
+
+    #[test]
+    fn test_parse_audio_format_opus() {
+        let format = DownloadOptions::parse_audio_format("opus");
+        assert!(matches!(format, AudioFormat::Opus));
+    }
+
+    #[test]
+    fn test_parse_audio_format_case_insensitive() {
+        assert!(matches!(
+            DownloadOptions::parse_audio_format("MP3"),
+            AudioFormat::Mp3
+        ));
+        assert!(matches!(
+            DownloadOptions::parse_audio_format("FLAC"),
+            AudioFormat::Flac
+        ));
+    }
+
+    #[test]
+    fn test_parse_audio_format_unknown_defaults_to_mp3() {
+        let format = DownloadOptions::parse_audio_format("wma");
+        assert!(matches!(format, AudioFormat::Mp3));
+    }
+
+    // ============== DownloadOptions from_config Tests ==============
+
+    #[test]
+    fn test_download_options_from_config() {
+        let config = Config::default();
+        let options = DownloadOptions::from_config(&config);
+
+        assert!(matches!(options.quality, VideoQuality::Best));
+        assert!(matches!(options.video_format, VideoFormat::Mp4));
+        assert!(matches!(options.audio_format, AudioFormat::Mp3));
+        assert_eq!(options.retry_attempts, 3);
+        assert!(!options.write_info_json);
+        assert!(!options.write_nfo);
+    }
+
+    #[test]
+    fn test_download_options_from_config_sidecars() {
+        let mut config = Config::default();
+        config.video.write_info_json = true;
+        config.video.write_nfo = true;
+        let options = DownloadOptions::from_config(&config);
+
+        assert!(options.write_info_json);
+        assert!(options.write_nfo);
+    }
+
+    #[test]
+    fn test_rate_limit_from_config() {
+        assert!(DownloadOptions::default().rate_limit().is_none());
+        let args = Downloader::new().base_ytdlp_args();
+        assert!(!args.contains(&"--rate-limit".to_string()));
+
+        let mut config = Config::default();
+        config.network.rate_limit = Some("500K".to_string());
+        let args = Downloader::from_config(&config).base_ytdlp_args();
+
+        let flag = args.iter().position(|arg| arg == "--rate-limit").unwrap();
+        assert_eq!(args[flag + 1], "500K");
+    }
+
+    // ============== Quality to yt-dlp Tests ==============
+
+    #[test]
+    fn test_quality_to_ytdlp_best() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Best);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("best"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_worst() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Worst);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("worst"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_4k() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q4k);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("2160"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_1440p() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q1440p);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("1440"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_1080p() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q1080p);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("1080"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_720p() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q720p);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("720"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_480p() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q480p);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("480"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_360p() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q360p);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("360"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_240p() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q240p);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("240"));
+    }
+
+    #[test]
+    fn test_quality_to_ytdlp_144p() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q144p);
+        let format = options.quality_to_ytdlp();
+        assert!(format.contains("144"));
+    }
+
+    // ============== Format Selector Tests ==============
+
+    #[test]
+    fn test_format_selector_uses_quality_by_default() {
+        let options = DownloadOptions::default().with_quality(VideoQuality::Q720p);
+        assert_eq!(options.format_selector(), options.quality_to_ytdlp());
+    }
+
+    #[test]
+    fn test_format_selector_prefers_format_id() {
+        let options = DownloadOptions::default()
+            .with_quality(VideoQuality::Q720p)
+            .with_format_id("137+140".to_string());
+
+        assert_eq!(options.format_selector(), "137+140");
+    }
+
+    #[test]
+    fn test_format_selector_with_preference() {
+        let options = DownloadOptions::default()
+            .with_quality(VideoQuality::Q1080p)
+            .with_preference(FormatPreference::default().with_video_codecs(vec![VideoCodec::Vp9]));
+
+        let selector = options.format_selector();
+        assert!(selector.starts_with("bestvideo[height<=1080][vcodec~='^(vp9|vp09)']"));
+        assert!(selector.ends_with("best[height<=1080]"));
+    }
+
+    #[test]
+    fn test_format_sort_from_preference() {
+        let options =
+            DownloadOptions::default().with_preference(FormatPreference::default().with_fps(60));
+
+        assert_eq!(options.format_sort().as_deref(), Some("res,fps:60"));
+    }
+
+    #[test]
+    fn test_format_sort_ignored_with_format_id() {
+        let options = DownloadOptions::default()
+            .with_preference(FormatPreference::default().with_fps(60))
+            .with_format_id("137".to_string());
+
+        assert!(options.format_sort().is_none());
+    }
+
+    #[test]
+    fn test_format_sort_none_by_default() {
+        assert!(DownloadOptions::default().format_sort().is_none());
+    }
+
+    // ============== Format ID Check Tests ==============
+
+    fn create_video_with_formats(ids: &[&str]) -> VideoInfo {
+        VideoInfo {
+            id: "abc123".to_string(),
+            title: "Test".to_string(),
+            description: None,
+            duration: 60,
+            thumbnail_url: None,
+            channel: None,
+            publish_date: None,
+            view_count: None,
+            streams: ids
+                .iter()
+                .map(|id| crate::youtube::StreamInfo {
+                    format_id: id.to_string(),
+                    url: format!("https://example.com/{}", id),
+                    quality: "720p".to_string(),
+                    format: "mp4".to_string(),
+                    video_codec: None,
+                    audio_codec: None,
+                    is_audio_only: false,
+                    file_size: None,
+                    bitrate: None,
+                    fps: None,
+                    width: None,
+                    height: None,
+                    dynamic_range: None,
+                })
+                .collect(),
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_check_format_id_single() {
+        let video = create_video_with_formats(&["137", "140"]);
+        assert!(Downloader::check_format_id(&video, "137").is_ok());
+    }
+
+    #[test]
+    fn test_check_format_id_merge_and_fallback() {
+        let video = create_video_with_formats(&["137", "140", "22", "18"]);
+
+        assert!(Downloader::check_format_id(&video, "137+140").is_ok());
+        assert!(Downloader::check_format_id(&video, "22/18").is_ok());
+    }
+
+    #[test]
+    fn test_check_format_id_fallback_to_available() {
+        let video = create_video_with_formats(&["18", "140"]);
+
+        assert!(Downloader::check_format_id(&video, "22/18").is_ok());
+        assert!(Downloader::check_format_id(&video, "137+140/18").is_ok());
+        assert!(matches!(
+            Downloader::check_format_id(&video, "22/37"),
+            Err(AppError::QualityNotAvailable { requested, .. }) if requested == "22"
+        ));
+    }
+
+    #[test]
+    fn test_check_format_id_passes_selectors() {
+        let video = create_video_with_formats(&["18"]);
+
+        for selection in [
+            "bv*+ba",
+            "bestvideo[height<=720]+bestaudio",
+            "best",
+            "bv.2/b",
+            "22[ext=mp4]",
+            "(137/22)+140",
+            "999/bv*+ba",
+        ] {
+            assert!(
+                Downloader::check_format_id(&video, selection).is_ok(),
+                "{}",
+                selection
+            );
+        }
+        assert!(Downloader::check_format_id(&video, "999+ba").is_err());
+    }
+
+    #[test]
+    fn test_check_format_id_unknown() {
+        let video = create_video_with_formats(&["137", "140"]);
+        let result = Downloader::check_format_id(&video, "137+999");
+
+        match result {
+            Err(AppError::QualityNotAvailable {
+                requested,
+                available,
+            }) => {
+                assert_eq!(requested, "999");
+                assert_eq!(available, vec!["137", "140"]);
+            }
+            _ => panic!("Expected QualityNotAvailable error"),
+        }
+    }
+
+    // ============== Video Format Extension Tests ==============
+
+    #[test]
+    fn test_video_format_ext_mp4() {
+        let options = DownloadOptions::default().with_video_format(VideoFormat::Mp4);
+        assert_eq!(options.video_format_ext(), "mp4");
+    }
+
+    #[test]
+    fn test_video_format_ext_mkv() {
+        let options = DownloadOptions::default().with_video_format(VideoFormat::Mkv);
+        assert_eq!(options.video_format_ext(), "mkv");
+    }
+
+    #[test]
+    fn test_video_format_ext_webm() {
+        let options = DownloadOptions::default().with_video_format(VideoFormat::Webm);
+        assert_eq!(options.video_format_ext(), "webm");
+    }
+
+    // ============== Audio Format String Tests ==============
+
+    #[test]
+    fn test_audio_format_str_mp3() {
+        let options = DownloadOptions::default().with_audio_format(AudioFormat::Mp3);
+        assert_eq!(options.audio_format_str(), "mp3");
+    }
+
+    #[test]
+    fn test_audio_format_str_m4a() {
+        let options = DownloadOptions::default().with_audio_format(AudioFormat::M4a);
+        assert_eq!(options.audio_format_str(), "m4a");
+    }
+
+    #[test]
+    fn test_audio_format_str_flac() {
+        let options = DownloadOptions::default().with_audio_format(AudioFormat::Flac);
+        assert_eq!(options.audio_format_str(), "flac");
+    }
+
+    #[test]
+    fn test_audio_format_str_wav() {
+        let options = DownloadOptions::default().with_audio_format(AudioFormat::Wav);
+        assert_eq!(options.audio_format_str(), "wav");
+    }
+
+    #[test]
+    fn test_audio_format_str_opus() {
+        let options = DownloadOptions::default().with_audio_format(AudioFormat::Opus);
+        assert_eq!(options.audio_format_str(), "opus");
+    }
+
+    // ============== Post-conversion Tests ==============
+
+    #[test]
+    fn test_native_video_formats_need_no_conversion() {
+        for format in [VideoFormat::Mp4, VideoFormat::Mkv, VideoFormat::Webm] {
+            let options = DownloadOptions::default().with_video_format(format);
+            assert!(!options.needs_video_conversion());
+            assert_eq!(options.video_format_ext(), format.extension());
+        }
+    }
+
+    #[test]
+    fn test_mov_downloads_as_mp4() {
+        let options = DownloadOptions::default().with_video_format(VideoFormat::Mov);
+
+        assert!(options.needs_video_conversion());
+        assert_eq!(options.video_format_ext(), "mp4");
+    }
+
+    #[test]
+    fn test_avi_downloads_as_mp4() {
+        let options = DownloadOptions::default().with_video_format(VideoFormat::Avi);
+
+        assert!(options.needs_video_conversion());
+        assert_eq!(options.video_format_ext(), "mp4");
+    }
+
+    #[test]
+    fn test_native_audio_formats_need_no_conversion() {
+        for format in [
+            AudioFormat::Mp3,
+            AudioFormat::M4a,
+            AudioFormat::Flac,
+            AudioFormat::Wav,
+            AudioFormat::Opus,
+        ] {
+            let options = DownloadOptions::default().with_audio_format(format);
+            assert!(!options.needs_audio_conversion());
+            assert_eq!(options.audio_format_str(), format.extension());
+        }
+    }
+
+    #[test]
+    fn test_aac_extracts_as_m4a() {
+        let options = DownloadOptions::default().with_audio_format(AudioFormat::Aac);
+
+        assert!(options.needs_audio_conversion());
+        assert_eq!(options.audio_format_str(), "m4a");
+    }
+
+    #[test]
+    fn test_ogg_extracts_as_opus() {
+        let options = DownloadOptions::default().with_audio_format(AudioFormat::Ogg);
+
+        assert!(options.needs_audio_conversion());
+        assert_eq!(options.audio_format_str(), "opus");
+    }
+
+    // ============== Downloader Creation Tests ==============
+
+    #[test]
+    fn test_downloader_new() {
+        let downloader = Downloader::new();
+        assert!(matches!(downloader.options().quality, VideoQuality::Best));
+    }
+
+    #[test]
+    fn test_downloader_default() {
+        let downloader = Downloader::default();
+        assert!(matches!(downloader.options().quality, VideoQuality::Best));
+    }
+
+    #[test]
+    fn test_downloader_with_options() {
+        let options = DownloadOptions::default()
+            .with_quality(VideoQuality::Q720p)
+            .with_silence(true);
+
+        let downloader = Downloader::with_options(options);
+
+        assert!(matches!(downloader.options().quality, VideoQuality::Q720p));
+        assert!(downloader.options().silence);
+    }
+
+    #[test]
+    fn test_downloader_from_config() {
+        let config = Config::default();
+        let downloader = Downloader::from_config(&config);
+
+        assert_eq!(
+            downloader.options().retry_attempts,
+            config.network.retry_attempts
+        );
+    }
+
+    #[test]
+    fn test_downloader_set_options() {
+        let mut downloader = Downloader::new();
+
+        let new_options = DownloadOptions::default().with_quality(VideoQuality::Q480p);
+
+        downloader.set_options(new_options);
+
+        assert!(matches!(downloader.options().quality, VideoQuality::Q480p));
+    }
+
+    // ============== Routing Tests ==============
+
+    fn music_rule() -> Rule {
+        Rule {
+            name: Some("music".to_string()),
+            channel: Some("Lofi Girl".to_string()),
+            output_dir: Some("/music/{channel}/{playlist}".to_string()),
+            audio_format: Some("flac".to_string()),
+            quality: Some("720p".to_string()),
+            vcodec: Some(vec![VideoCodec::Av1]),
+            ..Default::default()
+        }
+    }
+
+    fn lofi_video() -> VideoInfo {
+        let mut info = create_video_with_formats(&[]);
+        info.channel = Some("Lofi Girl".to_string());
+        info
+    }
+
+    #[test]
+    fn test_overrides_from_rule() {
+        let info = lofi_video();
+        let ctx = RuleContext {
+            url: "https://www.youtube.com/watch?v=abc123&list=PL1",
+            info: &info,
+            playlist_id: Some("PL1"),
+            media: MediaKind::Video,
+        };
+
+        let overrides = OptionOverrides::from_rule(&music_rule(), &ctx);
+
+        assert_eq!(
+            overrides.output_dir,
+            Some(PathBuf::from("/music/Lofi Girl/PL1"))
+        );
+        assert_eq!(overrides.audio_format, Some(AudioFormat::Flac));
+        assert!(matches!(overrides.quality, Some(VideoQuality::Q720p)));
+        assert_eq!(overrides.video_codecs, Some(vec![VideoCodec::Av1]));
+        assert_eq!(
+            overrides.keys(),
+            vec!["output_dir", "quality", "audio_format", "vcodec"]
+        );
+    }
+
+    #[test]
+    fn test_fill_placeholders_sanitizes_values() {
+        let mut info = create_video_with_formats(&[]);
+        info.channel = Some("AC/DC".to_string());
+        let ctx = RuleContext {
+            url: "",
+            info: &info,
+            playlist_id: None,
+            media: MediaKind::Video,
+        };
+
+        assert_eq!(
+            fill_placeholders("~/Music/{channel}/{id}{playlist}", &ctx),
+            "~/Music/AC_DC/abc123"
+        );
+
+        let unknown = create_video_with_formats(&[]);
+        let ctx = RuleContext {
+            info: &unknown,
+            ..ctx
+        };
+        assert_eq!(fill_placeholders("{channel}", &ctx), "Unknown");
+
+        let mut dots = create_video_with_formats(&[]);
+        dots.channel = Some("..".to_string());
+        let ctx = RuleContext {
+            info: &dots,
+            playlist_id: Some("."),
+            ..ctx
+        };
+        assert_eq!(
+            fill_placeholders("~/Music/{channel}/{playlist}", &ctx),
+            "~/Music/Unknown/Unknown"
+        );
+    }
+
+    #[test]
+    fn test_with_overrides_keeps_unset_fields() {
+        let options = DownloadOptions::default()
+            .with_video_format(VideoFormat::Mkv)
+            .with_overrides(&OptionOverrides {
+                fps: Some(60),
+                ..Default::default()
+            });
+
+        assert_eq!(options.video_format, VideoFormat::Mkv);
+        assert_eq!(options.preference.fps, Some(60));
+        assert!(OptionOverrides::default().keys().is_empty());
+    }
+
+    #[test]
+    fn test_rule_enables_sidecars() {
+        let downloader = Downloader::new().with_rules(vec![Rule {
+            channel: Some("Test Channel".to_string()),
+            write_nfo: Some(true),
+            ..Default::default()
+        }]);
+        let mut info = create_video_with_formats(&[]);
+        info.channel = Some("Test Channel".to_string());
+
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &info,
+            DownloadOptions::default(),
+            MediaKind::Video,
+        );
+
+        assert!(routed.options.write_nfo);
+        assert!(!routed.options.write_info_json);
+    }
+
+    #[test]
+    fn test_route_applies_first_matching_rule() {
+        let downloader = Downloader::new().with_rules(vec![
+            music_rule(),
+            Rule {
+                audio_format: Some("opus".to_string()),
+                ..Default::default()
+            },
+        ]);
+
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &lofi_video(),
+            downloader.options().clone(),
+            MediaKind::Audio,
+        );
+        assert_eq!(routed.options().audio_format, AudioFormat::Flac);
+        assert_eq!(
+            routed.options().output_dir,
+            PathBuf::from("/music/Lofi Girl/")
+        );
+
+        let other = create_video_with_formats(&[]);
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &other,
+            downloader.options().clone(),
+            MediaKind::Audio,
+        );
+        assert_eq!(routed.options().audio_format, AudioFormat::Opus);
+    }
+
+    #[test]
+    fn test_route_flags_win_over_rules() {
+        let downloader =
+            Downloader::new()
+                .with_rules(vec![music_rule()])
+                .with_flags(OptionOverrides {
+                    output_dir: Some(PathBuf::from("./here")),
+                    ..Default::default()
+                });
+
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &lofi_video(),
+            downloader.options().clone(),
+            MediaKind::Video,
+        );
+
+        assert_eq!(routed.options().output_dir, PathBuf::from("./here"));
+        assert_eq!(routed.options().audio_format, AudioFormat::Flac);
+    }
+
+    #[test]
+    fn test_route_without_match_keeps_options() {
+        let downloader =
+            Downloader::with_options(DownloadOptions::default().with_quality(VideoQuality::Q480p))
+                .with_rules(vec![music_rule()]);
+
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &create_video_with_formats(&[]),
+            downloader.options().clone(),
+            MediaKind::Video,
+        );
+
+        assert!(matches!(routed.options().quality, VideoQuality::Q480p));
+    }
+
+    #[test]
+    fn test_from_config_takes_rules() {
+        let mut config = Config::default();
+        config.rules.push(music_rule());
+
+        let downloader = Downloader::from_config(&config);
+
+        assert_eq!(downloader.rules, config.rules);
+    }
+
+    // ============== Playlist Template Tests ==============
+
+    fn playlist_entry(index: usize, count: usize) -> PlaylistEntry {
+        PlaylistEntry {
+            index,
+            video_id: "abc123".to_string(),
+            playlist_id: "PL1".to_string(),
+            playlist_title: "Rust Talks".to_string(),
+            playlist_count: count,
+        }
+    }
+
+    #[test]
+    fn test_output_template_without_playlist() {
+        let options = DownloadOptions::default()
+            .with_template("%(playlist_index)s-%(title)s.%(ext)s".to_string());
+        assert_eq!(
+            options.output_template(),
+            "%(playlist_index)s-%(title)s.%(ext)s"
+        );
+    }
+
+    #[test]
+    fn test_fill_playlist_fields() {
+        let entry = playlist_entry(7, 120);
+
+        assert_eq!(
+            fill_playlist_fields("%(playlist_index)s - %(title)s.%(ext)s", &entry),
+            "007 - %(title)s.%(ext)s"
+        );
+        assert_eq!(
+            fill_playlist_fields("%(playlist_index)02d of %(playlist_count)d", &entry),
+            "07 of 120"
+        );
+        assert_eq!(
+            fill_playlist_fields("%(playlist_title)s/%(playlist_id)s/%(title)s", &entry),
+            "Rust_Talks/PL1/%(title)s"
+        );
     }
 
     #[test]
-    fn test_parse_video_format_mp4() {
-        let format = DownloadOptions::parse_video_format("mp4");
-        assert!(matches!(format, VideoFormat::Mp4));
+    fn test_render_filename_with_playlist_fields() {
+        let info = create_video_with_formats(&[]);
+        let template = fill_playlist_fields(
+            "%(playlist)s/%(playlist_index)s-%(id)s.%(ext)s",
+            &playlist_entry(3, 9),
+        );
+
+        assert_eq!(
+            render_filename(&template, &info, "mp4"),
+            "Rust_Talks/3-abc123.mp4"
+        );
     }
 
     #[test]
-    fn test_parse_video_format_mkv() {
-        let format = DownloadOptions::parse_video_format("mkv");
-        assert!(matches!(format, VideoFormat::Mkv));
+    fn test_output_template_escapes_playlist_title() {
+        let mut entry = playlist_entry(7, 12);
+        entry.playlist_title = "Best of %(id)s / 100%".to_string();
+        let options = DownloadOptions::default()
+            .with_playlist_folders(true)
+            .with_playlist_entry(entry.clone());
+
+        let template = options.output_template();
+        assert_eq!(template.matches("%(").count(), 2, "{}", template);
+        assert!(template.ends_with("/07 - %(title)s.%(ext)s"));
+        assert!(!template.contains(" / "));
+
+        let options = DownloadOptions::default()
+            .with_template("%(playlist_title)s - %(title)s.%(ext)s".to_string())
+            .with_playlist_entry(entry);
+        assert_eq!(options.output_template().matches("%(").count(), 2);
     }
 
     #[test]
-    fn test_parse_video_format_webm() {
-        let format = DownloadOptions::parse_video_format("webm");
-        assert!(matches!(format, VideoFormat::Webm));
+    fn test_template_literal() {
+        assert_eq!(template_literal("100% (live)"), "100%% (live)");
+        assert_eq!(template_literal("%(id)s"), "%%(id)s");
+        assert_eq!(template_literal("a/b\\c"), "a_b_c");
     }
 
     #[test]
-    fn test_parse_video_format_case_insensitive() {
-        assert!(matches!(
-            DownloadOptions::parse_video_format("MP4"),
-            VideoFormat::Mp4
-        ));
-        assert!(matches!(
-            DownloadOptions::parse_video_format("MKV"),
-            VideoFormat::Mkv
-        ));
+    fn test_playlist_folder_dots_use_id() {
+        assert_eq!(playlist_folder("..", "PL1"), "PL1");
+        assert_eq!(playlist_folder("", "PL1"), "PL1");
+        assert_eq!(playlist_folder("v1.2", "PL1"), "v1.2");
     }
 
     #[test]
-    fn test_parse_video_format_unknown_defaults_to_mp4() {
-        let format = DownloadOptions::parse_video_format("avi");
-        assert!(matches!(format, VideoFormat::Mp4));
+    fn test_output_template_with_playlist_folders() {
+        let options = DownloadOptions::default()
+            .with_playlist_folders(true)
+            .with_playlist_entry(playlist_entry(7, 12));
+        assert_eq!(
+            options.output_template(),
+            "Rust Talks/07 - %(title)s.%(ext)s"
+        );
+
+        let options = options.with_template("%(channel)s/%(title)s.%(ext)s".to_string());
+        assert_eq!(
+            options.output_template(),
+            "Rust Talks/%(channel)s/07 - %(title)s.%(ext)s"
+        );
     }
 
     #[test]
-    fn test_parse_audio_format_mp3() {
-        let format = DownloadOptions::parse_audio_format("mp3");
-        assert!(matches!(format, AudioFormat::Mp3));
+    fn test_playlist_folder_falls_back_to_id() {
+        assert_eq!(playlist_folder("Rust Talks", "PL1"), "Rust Talks");
+        assert_eq!(playlist_folder("???", "PL1"), "PL1");
     }
 
     #[test]
-    fn test_parse_audio_format_m4a() {
-        let format = DownloadOptions::parse_audio_format("m4a");
-        assert!(matches!(format, AudioFormat::M4a));
+    fn test_renumbered_path() {
+        let entry = playlist_entry(4, 12);
+
+        assert_eq!(
+            renumbered_path(Path::new("/v/Rust Talks/03 - Intro.mp4"), &entry),
+            Some(PathBuf::from("/v/Rust Talks/04 - Intro.mp4"))
+        );
+        // The playlist grew past 99 videos
+        assert_eq!(
+            renumbered_path(Path::new("/v/Rust Talks/4 - Intro.mp4"), &entry),
+            Some(PathBuf::from("/v/Rust Talks/04 - Intro.mp4"))
+        );
+        assert_eq!(
+            renumbered_path(Path::new("/v/Rust Talks/04 - Intro.mp4"), &entry),
+            None
+        );
+        assert_eq!(
+            renumbered_path(Path::new("/v/Other/03 - Intro.mp4"), &entry),
+            None
+        );
+        assert_eq!(
+            renumbered_path(Path::new("/v/Rust Talks/Part 3 - Intro.mp4"), &entry),
+            None
+        );
     }
 
     #[test]
-    fn test_parse_audio_format_flac() {
-        let format = DownloadOptions::parse_audio_format("flac");
-        assert!(matches!(format, AudioFormat::Flac));
+    fn test_renumber_playlist_renames_moved_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let index = dir.path().join("library.json");
+        let folder = dir.path().join("Rust Talks");
+        std::fs::create_dir_all(&folder).unwrap();
+        let old = folder.join("03 - Intro.mp4");
+        std::fs::write(&old, "video").unwrap();
+        std::fs::write(folder.join("03 - Intro.nfo"), "nfo").unwrap();
+
+        let mut library = Library::default();
+        library.add("abc123", &old);
+        library.save(&index).unwrap();
+
+        let downloader = Downloader::new().with_library(index.clone());
+        let renamed = downloader
+            .renumber_playlist(&[playlist_entry(4, 12)])
+            .unwrap();
+
+        let new = folder.join("04 - Intro.mp4");
+        assert_eq!(renamed.len(), 1);
+        assert!(new.is_file());
+        assert!(!old.exists());
+        assert!(folder.join("04 - Intro.nfo").is_file());
+        assert_eq!(
+            Library::load(&index).unwrap().find("abc123", "mp4"),
+            Some(std::fs::canonicalize(&new).unwrap().as_path())
+        );
+
+        // Nothing left to rename on the next run
+        assert!(downloader
+            .renumber_playlist(&[playlist_entry(4, 12)])
+            .unwrap()
+            .is_empty());
     }
 
+    // ============== Library Reuse Tests ==============
+
     #[test]
-    fn test_parse_audio_format_wav() {
-        let format = DownloadOptions::parse_audio_format("wav");
-        assert!(matches!(format, AudioFormat::Wav));
+    fn test_render_filename() {
+        let mut info = create_video_with_formats(&[]);
+        info.title = "My Video: Part 1".to_string();
+        info.channel = Some("Some Channel".to_string());
+
+        assert_eq!(
+            render_filename("%(title)s.%(ext)s", &info, "mp4"),
+            "My_Video__Part_1.mp4"
+        );
+        assert_eq!(
+            render_filename("%(channel)s/%(title)s [%(id)s]", &info, "mp3"),
+            "Some_Channel/My_Video__Part_1 [abc123].mp3"
+        );
+        assert_eq!(
+            render_filename("%(upload_date)s-%(title)s.%(ext)s", &info, "mkv"),
+            "My_Video__Part_1.mkv"
+        );
     }
 
     #[test]
-    fn test_parse_audio_format_opus() {
-        let format = DownloadOptions::parse_audio_format("opus");
-        assert!(matches!(format, AudioFormat::Opus));
+    fn test_render_known_fields_unsupported() {
+        let info = create_video_with_formats(&[]);
+
+        assert_eq!(
+            render_known_fields("%(id)s.%(ext)s", &info, "mp4").as_deref(),
+            Some("abc123.mp4")
+        );
+        assert!(render_known_fields("%(upload_date)s-%(title)s.%(ext)s", &info, "mkv").is_none());
     }
 
     #[test]
-    fn test_parse_audio_format_case_insensitive() {
-        assert!(matches!(
-            DownloadOptions::parse_audio_format("MP3"),
-            AudioFormat::Mp3
-        ));
-        assert!(matches!(
-            DownloadOptions::parse_audio_format("FLAC"),
-            AudioFormat::Flac
-        ));
+    fn test_reuse_from_library_links_existing_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let index = dir.path().join("library.json");
+        let existing = dir.path().join("first").join("Test.mp4");
+        std::fs::create_dir_all(existing.parent().unwrap()).unwrap();
+        std::fs::write(&existing, "video").unwrap();
+
+        let mut library = Library::default();
+        library.add("abc123", &existing);
+        library.save(&index).unwrap();
+
+        let options = DownloadOptions::default()
+            .with_output_dir(dir.path().join("second"))
+            .with_silence(true);
+        let downloader = Downloader::with_options(options).with_library(index.clone());
+        let info = create_video_with_formats(&[]);
+
+        let result = downloader.reuse_from_library(&info, "mp4").unwrap();
+
+        assert_eq!(result.file_path, dir.path().join("second").join("Test.mp4"));
+        assert_eq!(result.file_size, 5);
+        assert_eq!(std::fs::read_to_string(&result.file_path).unwrap(), "video");
+        assert_eq!(Library::load(&index).unwrap().files("abc123").len(), 2);
+
+        assert!(downloader.reuse_from_library(&info, "mkv").is_none());
     }
 
     #[test]
-    fn test_parse_audio_format_unknown_defaults_to_mp3() {
-        let format = DownloadOptions::parse_audio_format("wma");
-        assert!(matches!(format, AudioFormat::Mp3));
-    }
+    fn test_reuse_from_library_writes_sidecars() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let index = dir.path().join("library.json");
+        let existing = dir.path().join("Test.mp4");
+        std::fs::write(&existing, "video").unwrap();
 
-    // ============== DownloadOptions from_config Tests ==============
+        let mut library = Library::default();
+        library.add("abc123", &existing);
+        library.save(&index).unwrap();
 
-    #[test]
-    fn test_download_options_from_config() {
-        let config = Config::default();
-        let options = DownloadOptions::from_config(&config);
+        let options = DownloadOptions::default()
+            .with_output_dir(dir.path().join("copy"))
+            .with_silence(true)
+            .with_write_info_json(true)
+            .with_write_nfo(true);
+        let downloader = Downloader::with_options(options).with_library(index);
+        let info = create_video_with_formats(&[]);
 
-        assert!(matches!(options.quality, VideoQuality::Best));
-        assert!(matches!(options.video_format, VideoFormat::Mp4));
-        assert!(matches!(options.audio_format, AudioFormat::Mp3));
-        assert_eq!(options.retry_attempts, 3);
+        let result = downloader.reuse_from_library(&info, "mp4").unwrap();
+
+        assert!(result.file_path.with_extension("info.json").is_file());
+        assert!(result.file_path.with_extension("nfo").is_file());
     }
 
-    // ============== Quality to yt-dlp Tests ==============
+    // ============== Size Check Tests ==============
 
-    #[test]
-    fn test_quality_to_ytdlp_best() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Best);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("best"));
+    fn sized_video(sizes: &[(&str, Option<u64>, Option<u64>)]) -> VideoInfo {
+        let ids: Vec<&str> = sizes.iter().map(|(id, _, _)| *id).collect();
+        let mut info = create_video_with_formats(&ids);
+        for (stream, (_, size, bitrate)) in info.streams.iter_mut().zip(sizes) {
+            stream.file_size = *size;
+            stream.bitrate = *bitrate;
+        }
+        info
     }
 
     #[test]
-    fn test_quality_to_ytdlp_worst() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Worst);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("worst"));
+    fn test_estimated_size_format_id() {
+        // 140 has no size, so it is estimated from 128 kbit/s over 60 s
+        let info = sized_video(&[("137", Some(5_000_000), None), ("140", None, Some(128_000))]);
+        let options = DownloadOptions::default().with_format_id("137+140".to_string());
+
+        assert_eq!(options.estimated_size(&info), Some(5_960_000));
+
+        let options = options.with_format_id("137+999".to_string());
+        assert_eq!(options.estimated_size(&info), None);
     }
 
     #[test]
-    fn test_quality_to_ytdlp_4k() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q4k);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("2160"));
+    fn test_check_limits_max_filesize() {
+        let info = sized_video(&[("137", Some(5_000_000), None)]);
+        let downloader = Downloader::with_options(
+            DownloadOptions::default()
+                .with_format_id("137".to_string())
+                .with_max_filesize(Some(1_000_000)),
+        );
+
+        let result = downloader.check_limits(&info, Some(5_000_000));
+        assert!(matches!(
+            result,
+            Err(AppError::FileTooLarge {
+                size: 5_000_000,
+                ..
+            })
+        ));
+
+        // Unknown sizes are never skipped
+        assert!(downloader.check_limits(&info, None).unwrap().is_none());
     }
 
     #[test]
-    fn test_quality_to_ytdlp_1440p() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q1440p);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("1440"));
+    fn test_check_limits_budget_survives_routing() {
+        let info = lofi_video();
+        let budget = Arc::new(SizeBudget::new(1000));
+        let downloader = Downloader::new()
+            .with_rules(vec![music_rule()])
+            .with_budget(Some(Arc::clone(&budget)));
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &info,
+            downloader.options().clone(),
+            MediaKind::Video,
+        );
+
+        let reservation = routed.check_limits(&info, Some(600)).unwrap();
+        assert!(reservation.is_some());
+        assert_eq!(budget.used(), 600);
+
+        let result = routed.check_limits(&info, Some(600));
+        assert!(matches!(
+            result,
+            Err(AppError::BudgetExceeded { limit: 1000, .. })
+        ));
     }
 
     #[test]
-    fn test_quality_to_ytdlp_1080p() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q1080p);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("1080"));
+    fn test_check_size_insufficient_space() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let info = sized_video(&[("137", Some(u64::MAX / 4), None)]);
+        let downloader = Downloader::with_options(
+            DownloadOptions::default()
+                .with_output_dir(temp_dir.path().to_path_buf())
+                .with_format_id("137".to_string()),
+        );
+
+        let result = downloader.check_size(&info);
+        assert!(matches!(result, Err(AppError::InsufficientSpace { .. })));
     }
 
+    // ============== Live Stream Tests ==============
+
     #[test]
-    fn test_quality_to_ytdlp_720p() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q720p);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("720"));
+    fn test_poll_delay_backoff() {
+        assert_eq!(poll_delay(0, None), Duration::from_secs(15));
+        assert_eq!(poll_delay(1, None), Duration::from_secs(30));
+        assert_eq!(poll_delay(4, None), Duration::from_secs(240));
+        assert_eq!(poll_delay(5, None), Duration::from_secs(300));
+        assert_eq!(poll_delay(40, None), Duration::from_secs(300));
     }
 
     #[test]
-    fn test_quality_to_ytdlp_480p() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q480p);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("480"));
+    fn test_poll_delay_waits_for_schedule() {
+        // A start in 10 minutes is waited for in one go
+        assert_eq!(poll_delay(0, Some(600)), Duration::from_secs(600));
+        // Far starts are checked again every hour
+        assert_eq!(poll_delay(0, Some(86400)), Duration::from_secs(3600));
+        // Late premieres fall back to the backoff
+        assert_eq!(poll_delay(2, Some(-120)), Duration::from_secs(60));
+        assert_eq!(poll_delay(2, Some(20)), Duration::from_secs(60));
     }
 
-    #[test]
-    fn test_quality_to_ytdlp_360p() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q360p);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("360"));
+    #[tokio::test]
+    async fn test_wait_for_start_fails_without_waiting() {
+        let mut info = create_video_with_formats(&[]);
+        info.live_status = LiveStatus::IsUpcoming;
+        info.release_timestamp = Some(1718000000);
+
+        let result = Downloader::new()
+            .wait_for_start("https://youtu.be/abc123", info)
+            .await;
+        match result {
+            Err(AppError::VideoUpcoming { video_id, starts }) => {
+                assert_eq!(video_id, "abc123");
+                assert!(starts.starts_with("at 2024-06-"));
+            }
+            _ => panic!("Expected VideoUpcoming error"),
+        }
     }
 
-    #[test]
-    fn test_quality_to_ytdlp_240p() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q240p);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("240"));
+    #[tokio::test]
+    async fn test_wait_for_start_passes_started_videos() {
+        let mut info = create_video_with_formats(&[]);
+        info.live_status = LiveStatus::IsLive;
+
+        let downloader =
+            Downloader::with_options(DownloadOptions::default().with_wait_for_video(true));
+        let info = downloader
+            .wait_for_start("https://youtu.be/abc123", info)
+            .await
+            .unwrap();
+        assert!(info.is_live());
     }
 
     #[test]
-    fn test_quality_to_ytdlp_144p() {
-        let options = DownloadOptions::default().with_quality(VideoQuality::Q144p);
-        let format = options.quality_to_ytdlp();
-        assert!(format.contains("144"));
-    }
+    fn test_live_ytdlp_args() {
+        let downloader =
+            Downloader::with_options(DownloadOptions::default().with_live_from_start(true));
+        let mut info = create_video_with_formats(&[]);
 
-    // ============== Video Format Extension Tests ==============
+        assert!(downloader.live_ytdlp_args(&info).unwrap().is_empty());
 
-    #[test]
-    fn test_video_format_ext_mp4() {
-        let options = DownloadOptions::default().with_video_format(VideoFormat::Mp4);
-        assert_eq!(options.video_format_ext(), "mp4");
+        info.live_status = LiveStatus::IsLive;
+        assert_eq!(
+            downloader.live_ytdlp_args(&info).unwrap(),
+            vec!["--live-from-start"]
+        );
     }
 
     #[test]
-    fn test_video_format_ext_mkv() {
-        let options = DownloadOptions::default().with_video_format(VideoFormat::Mkv);
-        assert_eq!(options.video_format_ext(), "mkv");
-    }
+    fn test_live_ytdlp_args_duration() {
+        if !FFmpeg::is_available() {
+            return;
+        }
+        let downloader =
+            Downloader::with_options(DownloadOptions::default().with_live_duration(Some(90)));
+        let mut info = create_video_with_formats(&[]);
+        info.live_status = LiveStatus::IsLive;
 
-    #[test]
-    fn test_video_format_ext_webm() {
-        let options = DownloadOptions::default().with_video_format(VideoFormat::Webm);
-        assert_eq!(options.video_format_ext(), "webm");
+        assert_eq!(
+            downloader.live_ytdlp_args(&info).unwrap(),
+            vec![
+                "--downloader",
+                "ffmpeg",
+                "--downloader-args",
+                "ffmpeg:-t 5400"
+            ]
+        );
     }
 
-    // ============== Audio Format String Tests ==============
+    // ============== Path Report Tests ==============
 
     #[test]
-    fn test_audio_format_str_mp3() {
-        let options = DownloadOptions::default().with_audio_format(AudioFormat::Mp3);
-        assert_eq!(options.audio_format_str(), "mp3");
+    fn test_path_report_reads_last_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let video = dir.path().join("Video.mp4");
+        std::fs::write(&video, "video").unwrap();
+
+        let mut args = Vec::new();
+        let report = PathReport::new("abc123", &mut args);
+        assert_eq!(args[..2], ["--print-to-file", "after_move:filepath"]);
+        assert_eq!(PathBuf::from(&args[2]), report.path);
+        assert!(report.take().is_none());
+
+        std::fs::write(&report.path, format!("{}\n", video.display())).unwrap();
+        assert_eq!(report.take(), Some(video));
+
+        let path = report.path.clone();
+        drop(report);
+        assert!(!path.exists());
     }
 
     #[test]
-    fn test_audio_format_str_m4a() {
-        let options = DownloadOptions::default().with_audio_format(AudioFormat::M4a);
-        assert_eq!(options.audio_format_str(), "m4a");
+    fn test_path_reports_are_unique() {
+        let mut args = Vec::new();
+        let first = PathReport::new("abc123", &mut args);
+        let second = PathReport::new("abc123", &mut args);
+        assert_ne!(first.path, second.path);
     }
 
     #[test]
-    fn test_audio_format_str_flac() {
-        let options = DownloadOptions::default().with_audio_format(AudioFormat::Flac);
-        assert_eq!(options.audio_format_str(), "flac");
+    fn test_reuse_without_library_is_none() {
+        let info = create_video_with_formats(&[]);
+        assert!(Downloader::new().reuse_from_library(&info, "mp4").is_none());
     }
 
     #[test]
-    fn test_audio_format_str_wav() {
-        let options = DownloadOptions::default().with_audio_format(AudioFormat::Wav);
-        assert_eq!(options.audio_format_str(), "wav");
-    }
+    fn test_route_keeps_library() {
+        let info = create_video_with_formats(&[]);
+        let downloader = Downloader::new().with_library(PathBuf::from("library.json"));
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &info,
+            DownloadOptions::default(),
+            MediaKind::Video,
+        );
 
-    #[test]
-    fn test_audio_format_str_opus() {
-        let options = DownloadOptions::default().with_audio_format(AudioFormat::Opus);
-        assert_eq!(options.audio_format_str(), "opus");
+        assert_eq!(routed.library, Some(PathBuf::from("library.json")));
     }
 
-    // ============== Downloader Creation Tests ==============
+    // ============== Monitor Tests ==============
 
     #[test]
-    fn test_downloader_new() {
-        let downloader = Downloader::new();
-        assert!(matches!(downloader.options().quality, VideoQuality::Best));
+    fn test_transfer_progress_parse() {
+        let progress =
+            TransferProgress::parse("[download]  12.3% of ~ 1.50GiB at 512.00KiB/s ETA 01:02:03")
+                .unwrap();
+        assert_eq!(progress.percent, 12.3);
+        assert_eq!(progress.total_bytes, Some(1536 * 1024 * 1024));
+        assert_eq!(progress.speed, Some(512 * 1024));
+        assert_eq!(progress.eta, Some(3723));
+
+        let done = TransferProgress::parse("[download] 100% of   10.00MiB in 00:00:04").unwrap();
+        assert_eq!(done.percent, 100.0);
+        assert_eq!(done.total_bytes, Some(10 * 1024 * 1024));
+        assert_eq!(done.eta, None);
     }
 
     #[test]
-    fn test_downloader_default() {
-        let downloader = Downloader::default();
-        assert!(matches!(downloader.options().quality, VideoQuality::Best));
+    fn test_transfer_progress_unknown_fields() {
+        let progress =
+            TransferProgress::parse("[download]   5.0% of Unknown B at Unknown B/s ETA Unknown")
+                .unwrap();
+        assert_eq!(progress.percent, 5.0);
+        assert_eq!(progress.total_bytes, None);
+        assert_eq!(progress.speed, None);
+        assert_eq!(progress.eta, None);
+
+        assert!(TransferProgress::parse("[Merger] Merging formats into \"a.mp4\"").is_none());
+        assert!(TransferProgress::parse("[youtube] abc: 100% done").is_none());
     }
 
     #[test]
-    fn test_downloader_with_options() {
-        let options = DownloadOptions::default()
-            .with_quality(VideoQuality::Q720p)
-            .with_silence(true);
-
-        let downloader = Downloader::with_options(options);
+    fn test_monitor_keeps_last_progress() {
+        let monitor = DownloadMonitor::new();
+        monitor.update("[download]  10.0% of 1.00MiB");
+        monitor.update("[info] Downloading 1 format(s): 22");
+        monitor.update("[download]  20.0% of 1.00MiB");
 
-        assert!(matches!(downloader.options().quality, VideoQuality::Q720p));
-        assert!(downloader.options().silence);
+        assert_eq!(monitor.progress().unwrap().percent, 20.0);
     }
 
     #[test]
-    fn test_downloader_from_config() {
-        let config = Config::default();
-        let downloader = Downloader::from_config(&config);
+    fn test_monitor_wait_without_process() {
+        assert!(DownloadMonitor::new().wait().is_err());
+    }
 
-        assert_eq!(
-            downloader.options().retry_attempts,
-            config.network.retry_attempts
-        );
+    #[tokio::test]
+    async fn test_monitor_cancel_wakes_waiters() {
+        let monitor = Arc::new(DownloadMonitor::new());
+        let waiter = tokio::spawn({
+            let monitor = monitor.clone();
+            async move { monitor.cancelled().await }
+        });
+
+        monitor.cancel();
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(monitor.is_cancelled());
     }
 
     #[test]
-    fn test_downloader_set_options() {
-        let mut downloader = Downloader::new();
+    fn test_route_keeps_monitor() {
+        let info = create_video_with_formats(&[]);
+        let monitor = Arc::new(DownloadMonitor::new());
+        let downloader = Downloader::new().with_monitor(monitor.clone());
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &info,
+            DownloadOptions::default(),
+            MediaKind::Video,
+        );
 
-        let new_options = DownloadOptions::default().with_quality(VideoQuality::Q480p);
+        assert!(Arc::ptr_eq(routed.monitor.as_ref().unwrap(), &monitor));
+    }
 
-        downloader.set_options(new_options);
+    #[test]
+    fn test_monitor_keeps_progress_when_silent() {
+        let options = DownloadOptions::default().with_silence(true);
+        let silent = Downloader::with_options(options.clone()).base_ytdlp_args();
+        assert!(silent.contains(&"--quiet".to_string()));
+        assert!(!silent.contains(&"--progress".to_string()));
+
+        let monitored = Downloader::with_options(options)
+            .with_monitor(Arc::new(DownloadMonitor::new()))
+            .base_ytdlp_args();
+        assert!(monitored.contains(&"--quiet".to_string()));
+        assert!(monitored.contains(&"--newline".to_string()));
+        assert!(monitored.contains(&"--progress".to_string()));
+    }
 
-        assert!(matches!(downloader.options().quality, VideoQuality::Q480p));
+    #[test]
+    fn test_base_args_shared_and_refreshed() {
+        let downloader = Downloader::with_options(DownloadOptions::default().with_silence(true))
+            .with_monitor(Arc::new(DownloadMonitor::new()));
+        let routed = downloader.route(
+            "https://youtu.be/abc123",
+            &create_video_with_formats(&[]),
+            downloader.options().clone(),
+            MediaKind::Video,
+        );
+        let entry = downloader.for_playlist_entry(PlaylistEntry {
+            index: 1,
+            video_id: "abc123".to_string(),
+            playlist_id: "PL1".to_string(),
+            playlist_title: "List".to_string(),
+            playlist_count: 1,
+        });
+
+        assert_eq!(routed.base_ytdlp_args(), downloader.base_ytdlp_args());
+        assert_eq!(entry.base_ytdlp_args(), downloader.base_ytdlp_args());
+
+        let mut downloader = downloader;
+        downloader.set_options(DownloadOptions::default());
+        assert!(!downloader
+            .base_ytdlp_args()
+            .contains(&"--quiet".to_string()));
     }
 
     // ============== DownloadResult Tests ==============
@@ -1444,12 +4301,14 @@ mod tests {
             file_size: 1024 * 1024 * 100,
             video_id: "abc123".to_string(),
             video_title: "Test Video".to_string(),
+            channel: Some("Test Channel".to_string()),
         };
 
         assert_eq!(result.file_path, PathBuf::from("/downloads/video.mp4"));
         assert_eq!(result.file_size, 104857600);
         assert_eq!(result.video_id, "abc123");
         assert_eq!(result.video_title, "Test Video");
+        assert_eq!(result.channel.as_deref(), Some("Test Channel"));
     }
 
     // ============== Multiple Downloader Instances ==============
@@ -1512,6 +4371,7 @@ mod tests {
             file_size: 1000,
             video_id: "test".to_string(),
             video_title: "Test".to_string(),
+            channel: None,
         };
 
         let debug_str = format!("{:?}", result);
@@ -1548,7 +4408,13 @@ mod tests {
 
     #[test]
     fn test_all_video_format_variants() {
-        let formats = vec![VideoFormat::Mp4, VideoFormat::Mkv, VideoFormat::Webm];
+        let formats = vec![
+            VideoFormat::Mp4,
+            VideoFormat::Mkv,
+            VideoFormat::Webm,
+            VideoFormat::Avi,
+            VideoFormat::Mov,
+        ];
 
         for format in formats {
             let options = DownloadOptions::default().with_video_format(format);
@@ -1564,9 +4430,11 @@ mod tests {
         let formats = vec![
             AudioFormat::Mp3,
             AudioFormat::M4a,
+            AudioFormat::Aac,
             AudioFormat::Flac,
             AudioFormat::Wav,
             AudioFormat::Opus,
+            AudioFormat::Ogg,
         ];
 
         for format in formats {
@@ -50,6 +50,8 @@
 use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::utils::format_bytes;
+
 /// Result type alias using [`AppError`] as the error type.
 ///
 /// This is the standard result type used throughout the application for
@@ -123,6 +125,10 @@ pub enum AppError {
     #[error("Video is unavailable in your region: {video_id}")]
     RegionBlocked { video_id: String },
 
+    /// The video is a live stream or premiere that has not started yet.
+    #[error("Video has not started yet: {video_id} (starts {starts}); pass --wait-for-video to wait for it")]
+    VideoUpcoming { video_id: String, starts: String },
+
     /// Playlist with the given ID was not found.
     #[error("Playlist not found: {playlist_id}")]
     PlaylistNotFound { playlist_id: String },
@@ -243,6 +249,34 @@ pub enum AppError {
     #[error("Download failed after {attempts} attempts: {message}")]
     MaxRetriesExceeded { attempts: u32, message: String },
 
+    /// Not enough free space on the output filesystem for a download.
+    #[error(
+        "Not enough disk space in {path}: about {} needed, {} free",
+        format_bytes(*.needed),
+        format_bytes(*.available)
+    )]
+    InsufficientSpace {
+        path: PathBuf,
+        needed: u64,
+        available: u64,
+    },
+
+    /// The video's estimated size is above `--max-filesize`.
+    #[error(
+        "Skipped {title}: about {}, larger than --max-filesize {}",
+        format_bytes(*.size),
+        format_bytes(*.limit)
+    )]
+    FileTooLarge {
+        title: String,
+        size: u64,
+        limit: u64,
+    },
+
+    /// The video does not fit in what is left of `--max-total`.
+    #[error("Skipped {title}: it would exceed --max-total {}", format_bytes(*.limit))]
+    BudgetExceeded { title: String, limit: u64 },
+
     // ============== Validation Errors ==============
     /// Invalid command-line argument provided.
     #[error("Invalid argument: {argument} - {message}")]
@@ -529,6 +563,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_video_upcoming_display() {
+        let error = AppError::VideoUpcoming {
+            video_id: "abc123".to_string(),
+            starts: "at 2024-06-10 08:13".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Video has not started yet: abc123 (starts at 2024-06-10 08:13); pass --wait-for-video to wait for it"
+        );
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_insufficient_space_display() {
+        let error = AppError::InsufficientSpace {
+            path: PathBuf::from("/videos"),
+            needed: 3 * 1024 * 1024 * 1024,
+            available: 512 * 1024 * 1024,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Not enough disk space in /videos: about 3.00 GB needed, 512.00 MB free"
+        );
+    }
+
+    #[test]
+    fn test_size_limit_display() {
+        let error = AppError::FileTooLarge {
+            title: "Long talk".to_string(),
+            size: 2048 * 1024 * 1024,
+            limit: 500 * 1024 * 1024,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Skipped Long talk: about 2.00 GB, larger than --max-filesize 500.00 MB"
+        );
+
+        let error = AppError::BudgetExceeded {
+            title: "Long talk".to_string(),
+            limit: 10 * 1024 * 1024 * 1024,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Skipped Long talk: it would exceed --max-total 10.00 GB"
+        );
+        assert!(!error.is_retryable());
+    }
+
     #[test]
     fn test_cancelled_display() {
         let error = AppError::Cancelled;
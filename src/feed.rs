@@ -0,0 +1,837 @@
+//! Podcast feeds for download directories.
+//!
+//! `ytdl feed build <dir> --base-url <url>` writes an RSS 2.0 feed with
+//! iTunes tags for every media file under a directory, so a folder of
+//! downloaded lectures can be subscribed to in any podcast app once the
+//! directory is served over HTTP at the base URL.
+//!
+//! Item metadata comes from the `.info.json` sidecar next to each file
+//! (written by yt-dlp's `--write-info-json` or by this tool), then from the
+//! file's embedded tags when ffprobe is available, and finally from the file
+//! itself (name and modification time).
+//!
+//! Builds are incremental: the metadata of every file is cached in
+//! `.feed-cache.json` inside the directory, and only files that are new or
+//! whose size or modification time changed are read again. Running the
+//! command after each download (or from cron) keeps the feed current.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use rust_yt_downloader::feed::{build_feed, FeedOptions};
+//!
+//! let options = FeedOptions::new("https://example.com/lectures")
+//!     .with_title(Some("Lectures".to_string()));
+//! let report = build_feed(Path::new("/srv/lectures"), &options)?;
+//! println!("{} episodes", report.items);
+//! # Ok::<(), rust_yt_downloader::error::AppError>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::library::collect_media_files;
+use crate::media::FFmpeg;
+use crate::utils::{extract_video_id, format_duration};
+
+/// Default file name of the feed, inside the scanned directory.
+pub const FEED_FILE: &str = "feed.xml";
+
+/// File name of the incremental build cache, inside the scanned directory.
+pub const CACHE_FILE: &str = ".feed-cache.json";
+
+/// Image extensions checked for a local thumbnail next to each file.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// Channel-level settings of a feed.
+#[derive(Debug, Clone, Default)]
+pub struct FeedOptions {
+    /// Public URL the directory is served at.
+    pub base_url: String,
+    /// Podcast title (defaults to the directory name).
+    pub title: Option<String>,
+    /// Podcast description (defaults to the title).
+    pub description: Option<String>,
+    /// Podcast author.
+    pub author: Option<String>,
+    /// Podcast cover image URL.
+    pub image: Option<String>,
+    /// Where to write the feed (defaults to `<dir>/feed.xml`).
+    pub output: Option<PathBuf>,
+}
+
+impl FeedOptions {
+    /// Creates options for a directory served at `base_url`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the podcast title.
+    pub fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Sets the podcast description.
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    /// Sets the podcast author.
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    /// Sets the podcast cover image URL.
+    pub fn with_image(mut self, image: Option<String>) -> Self {
+        self.image = image;
+        self
+    }
+
+    /// Sets where the feed is written.
+    pub fn with_output(mut self, output: Option<PathBuf>) -> Self {
+        self.output = output;
+        self
+    }
+}
+
+/// One episode of the feed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedItem {
+    /// Path of the media file relative to the directory, with `/` separators.
+    pub file: String,
+    /// Stable identifier: the video ID, or the relative path.
+    pub guid: String,
+    /// Episode title.
+    pub title: String,
+    /// Episode description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Publication date, RFC 3339.
+    pub published: String,
+    /// Duration in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+    /// File size in bytes.
+    pub length: u64,
+    /// MIME type of the file.
+    pub mime: String,
+    /// Episode image: a path relative to the directory, or an absolute URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+/// Result of [`build_feed`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedReport {
+    /// Where the feed was written.
+    pub output: PathBuf,
+    /// Number of items in the feed.
+    pub items: usize,
+    /// Files read for the first time.
+    pub added: usize,
+    /// Files read again because they changed.
+    pub updated: usize,
+    /// Files taken from the cache.
+    pub unchanged: usize,
+    /// Cached files that no longer exist.
+    pub removed: usize,
+}
+
+/// A cached item with the file state it was read from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    modified: u64,
+    item: FeedItem,
+}
+
+/// Builds (or rebuilds) the feed for `dir`.
+///
+/// # Errors
+///
+/// Returns `AppError::FileRead` if `dir` cannot be read, `AppError::InvalidArgument`
+/// if the base URL is not an HTTP(S) URL, and `AppError::FileWrite` if the
+/// feed or its cache cannot be written.
+pub fn build_feed(dir: &Path, options: &FeedOptions) -> AppResult<FeedReport> {
+    check_base_url(&options.base_url)?;
+
+    let cache_path = dir.join(CACHE_FILE);
+    let mut cache = load_cache(&cache_path);
+    let mut report = FeedReport {
+        output: options
+            .output
+            .clone()
+            .unwrap_or_else(|| dir.join(FEED_FILE)),
+        ..Default::default()
+    };
+
+    let mut files = Vec::new();
+    collect_media_files(dir, &mut files)?;
+
+    let probe = FFmpeg::is_probe_available();
+    let mut fresh = BTreeMap::new();
+    for path in files {
+        let Ok(meta) = fs::metadata(&path) else {
+            continue;
+        };
+        let size = meta.len();
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        let relative = relative_path(dir, &path);
+
+        let entry = match cache.remove(&relative) {
+            Some(entry) if entry.size == size && entry.modified == modified => {
+                report.unchanged += 1;
+                entry
+            }
+            previous => {
+                if previous.is_some() {
+                    report.updated += 1;
+                } else {
+                    report.added += 1;
+                }
+                CacheEntry {
+                    size,
+                    modified,
+                    item: read_item(dir, &path, &relative, size, modified, probe),
+                }
+            }
+        };
+        fresh.insert(relative, entry);
+    }
+    report.removed = cache.len();
+
+    let mut items: Vec<&FeedItem> = fresh.values().map(|entry| &entry.item).collect();
+    items.sort_by(|a, b| b.published.cmp(&a.published).then(a.file.cmp(&b.file)));
+    report.items = items.len();
+
+    let title = options.title.clone().unwrap_or_else(|| {
+        dir.canonicalize()
+            .ok()
+            .and_then(|d| d.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "Podcast".to_string())
+    });
+    let xml = render_feed(&title, options, &items, Utc::now());
+
+    write_file(&report.output, &xml)?;
+    let cache_json = serde_json::to_string_pretty(&fresh)
+        .map_err(|e| AppError::Other(format!("Could not serialize feed cache: {}", e)))?;
+    write_file(&cache_path, &cache_json)?;
+
+    Ok(report)
+}
+
+/// Renders the RSS document.
+fn render_feed(
+    title: &str,
+    options: &FeedOptions,
+    items: &[&FeedItem],
+    built: DateTime<Utc>,
+) -> String {
+    let base = &options.base_url;
+    let description = options.description.as_deref().unwrap_or(title);
+    let feed_name = options
+        .output
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map_or(FEED_FILE.to_string(), |n| n.to_string_lossy().into_owned());
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(
+        "<rss version=\"2.0\" \
+         xmlns:itunes=\"http://www.itunes.com/dtds/podcast-1.0.dtd\" \
+         xmlns:atom=\"http://www.w3.org/2005/Atom\">\n",
+    );
+    xml.push_str("  <channel>\n");
+    push_tag(&mut xml, 4, "title", title);
+    push_tag(&mut xml, 4, "link", &format!("{}/", base));
+    push_tag(&mut xml, 4, "description", description);
+    xml.push_str(&format!(
+        "    <atom:link href=\"{}/{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        escape(base),
+        escape(&encode_path(&feed_name))
+    ));
+    push_tag(&mut xml, 4, "lastBuildDate", &built.to_rfc2822());
+    push_tag(&mut xml, 4, "generator", "ytdl");
+    push_tag(&mut xml, 4, "itunes:summary", description);
+    if let Some(author) = &options.author {
+        push_tag(&mut xml, 4, "itunes:author", author);
+    }
+    if let Some(image) = &options.image {
+        xml.push_str(&format!("    <itunes:image href=\"{}\"/>\n", escape(image)));
+        xml.push_str("    <image>\n");
+        push_tag(&mut xml, 6, "url", image);
+        push_tag(&mut xml, 6, "title", title);
+        push_tag(&mut xml, 6, "link", &format!("{}/", base));
+        xml.push_str("    </image>\n");
+    }
+    push_tag(&mut xml, 4, "itunes:explicit", "false");
+
+    for item in items {
+        let url = format!("{}/{}", base, encode_path(&item.file));
+        let published = DateTime::parse_from_rfc3339(&item.published)
+            .map(|d| d.to_rfc2822())
+            .unwrap_or_default();
+
+        xml.push_str("    <item>\n");
+        push_tag(&mut xml, 6, "title", &item.title);
+        if let Some(description) = &item.description {
+            push_tag(&mut xml, 6, "description", description);
+            push_tag(&mut xml, 6, "itunes:summary", description);
+        }
+        xml.push_str(&format!(
+            "      <enclosure url=\"{}\" length=\"{}\" type=\"{}\"/>\n",
+            escape(&url),
+            item.length,
+            escape(&item.mime)
+        ));
+        xml.push_str(&format!(
+            "      <guid isPermaLink=\"false\">{}</guid>\n",
+            escape(&item.guid)
+        ));
+        push_tag(&mut xml, 6, "pubDate", &published);
+        if let Some(duration) = item.duration {
+            push_tag(&mut xml, 6, "itunes:duration", &format_duration(duration));
+        }
+        if let Some(image) = &item.image {
+            let href = if image.starts_with("http://") || image.starts_with("https://") {
+                image.clone()
+            } else {
+                format!("{}/{}", base, encode_path(image))
+            };
+            xml.push_str(&format!(
+                "      <itunes:image href=\"{}\"/>\n",
+                escape(&href)
+            ));
+        }
+        xml.push_str("    </item>\n");
+    }
+
+    xml.push_str("  </channel>\n");
+    xml.push_str("</rss>\n");
+    xml
+}
+
+/// Reads the metadata of one media file into a feed item.
+fn read_item(
+    dir: &Path,
+    path: &Path,
+    relative: &str,
+    size: u64,
+    modified: u64,
+    probe: bool,
+) -> FeedItem {
+    let mut meta = read_sidecar(path).unwrap_or_default();
+    if probe {
+        if let Some(tags) = FFmpeg::probe(path).ok().and_then(|p| parse_probe(&p)) {
+            meta.fill_from(tags);
+        }
+    }
+
+    let published = meta
+        .published
+        .or_else(|| Utc.timestamp_opt(modified as i64, 0).single())
+        .unwrap_or_else(Utc::now);
+    let title = meta.title.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| relative.to_string())
+    });
+    let image = local_image(dir, path).or(meta.thumbnail);
+
+    FeedItem {
+        file: relative.to_string(),
+        guid: meta.id.unwrap_or_else(|| relative.to_string()),
+        title,
+        description: meta.description.filter(|d| !d.trim().is_empty()),
+        published: published.to_rfc3339(),
+        duration: meta.duration,
+        length: size,
+        mime: mime_type(path).to_string(),
+        image,
+    }
+}
+
+/// Metadata gathered for one file from its sidecar or tags.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Metadata {
+    id: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    published: Option<DateTime<Utc>>,
+    duration: Option<u64>,
+    thumbnail: Option<String>,
+}
+
+impl Metadata {
+    /// Fills the fields still missing from `other`.
+    fn fill_from(&mut self, other: Metadata) {
+        self.id = self.id.take().or(other.id);
+        self.title = self.title.take().or(other.title);
+        self.description = self.description.take().or(other.description);
+        self.published = self.published.or(other.published);
+        self.duration = self.duration.or(other.duration);
+        self.thumbnail = self.thumbnail.take().or(other.thumbnail);
+    }
+}
+
+/// Reads `<name>.info.json` next to `path`.
+///
+/// Both yt-dlp's info JSON (`upload_date`, `thumbnail`) and a serialized
+/// [`VideoInfo`](crate::youtube::VideoInfo) (`publish_date`,
+/// `thumbnail_url`) are understood.
+fn read_sidecar(path: &Path) -> Option<Metadata> {
+    let content = fs::read_to_string(path.with_extension("info.json")).ok()?;
+    parse_sidecar(&content)
+}
+
+fn parse_sidecar(content: &str) -> Option<Metadata> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let text = |key: &str| {
+        json.get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+    };
+
+    let published = json
+        .get("timestamp")
+        .and_then(|v| v.as_i64())
+        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+        .or_else(|| text("upload_date").and_then(|d| parse_date(&d)))
+        .or_else(|| text("publish_date").and_then(|d| parse_date(&d)));
+
+    Some(Metadata {
+        id: text("id"),
+        title: text("title"),
+        description: text("description"),
+        published,
+        duration: json
+            .get("duration")
+            .and_then(|v| v.as_f64())
+            .filter(|d| *d > 0.0)
+            .map(|d| d.round() as u64),
+        thumbnail: text("thumbnail").or_else(|| text("thumbnail_url")),
+    })
+}
+
+/// Reads metadata from ffprobe's JSON output (format tags and duration).
+fn parse_probe(probe: &str) -> Option<Metadata> {
+    let json: serde_json::Value = serde_json::from_str(probe).ok()?;
+    let format = json.get("format")?;
+    let tags = format.get("tags").and_then(|t| t.as_object());
+
+    let tag = |wanted: &str| {
+        tags.and_then(|tags| {
+            tags.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(wanted))
+                .and_then(|(_, v)| v.as_str())
+                .filter(|s| !s.trim().is_empty())
+                .map(String::from)
+        })
+    };
+
+    let id = ["purl", "comment", "url"]
+        .iter()
+        .filter_map(|key| tag(key))
+        .find_map(|value| extract_video_id(&value));
+    let description = ["description", "synopsis", "comment"]
+        .iter()
+        .filter_map(|key| tag(key))
+        .find(|value| extract_video_id(value).is_none());
+
+    Some(Metadata {
+        id,
+        title: tag("title"),
+        description,
+        published: tag("date").and_then(|d| parse_date(&d)),
+        duration: format
+            .get("duration")
+            .and_then(|d| d.as_str())
+            .and_then(|d| d.parse::<f64>().ok())
+            .filter(|d| *d > 0.0)
+            .map(|d| d.round() as u64),
+        thumbnail: None,
+    })
+}
+
+/// Parses `YYYYMMDD`, `YYYY-MM-DD` or an RFC 3339 timestamp.
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    ["%Y%m%d", "%Y-%m-%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+/// Returns the relative path of an image with the same name as `path`.
+fn local_image(dir: &Path, path: &Path) -> Option<String> {
+    IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|image| image.is_file())
+        .map(|image| relative_path(dir, &image))
+}
+
+/// Returns the MIME type podcast apps expect for a media file.
+fn mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/x-m4a",
+        "aac" => "audio/aac",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "ogg" | "opus" => "audio/ogg",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        "avi" => "video/x-msvideo",
+        _ => "application/octet-stream",
+    }
+}
+
+fn relative_path(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Percent-encodes every segment of a `/`-separated relative path.
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            segment
+                .bytes()
+                .map(|b| {
+                    if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                        (b as char).to_string()
+                    } else {
+                        format!("%{:02X}", b)
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Escapes text for XML content and attribute values.
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn push_tag(xml: &mut String, indent: usize, tag: &str, text: &str) {
+    xml.push_str(&format!(
+        "{}<{}>{}</{}>\n",
+        " ".repeat(indent),
+        tag,
+        escape(text),
+        tag
+    ));
+}
+
+fn check_base_url(base_url: &str) -> AppResult<()> {
+    if base_url.starts_with("http://") || base_url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(AppError::invalid_arg(
+            "base-url",
+            format!("'{}' must start with http:// or https://", base_url),
+        ))
+    }
+}
+
+fn load_cache(path: &Path) -> BTreeMap<String, CacheEntry> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_file(path: &Path, content: &str) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+        }
+    }
+    fs::write(path, content).map_err(|e| AppError::file_write(path, e))
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, content).unwrap();
+    }
+
+    fn item(file: &str, published: &str) -> FeedItem {
+        FeedItem {
+            file: file.to_string(),
+            guid: file.to_string(),
+            title: "Episode <1> & more".to_string(),
+            description: Some("About \"things\"".to_string()),
+            published: published.to_string(),
+            duration: Some(3725),
+            length: 1234,
+            mime: "audio/mpeg".to_string(),
+            image: Some("covers/ep 1.jpg".to_string()),
+        }
+    }
+
+    // ============== Metadata Tests ==============
+
+    #[test]
+    fn test_parse_sidecar_ytdlp() {
+        let meta = parse_sidecar(
+            r#"{"id": "abc123", "title": "Lecture 1", "description": "Intro",
+                "upload_date": "20240501", "duration": 3600.4,
+                "thumbnail": "https://i.ytimg.com/vi/abc123/maxresdefault.jpg"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(meta.id.as_deref(), Some("abc123"));
+        assert_eq!(meta.title.as_deref(), Some("Lecture 1"));
+        assert_eq!(meta.duration, Some(3600));
+        assert_eq!(meta.published, parse_date("2024-05-01"));
+        assert!(meta.thumbnail.unwrap().ends_with("maxresdefault.jpg"));
+    }
+
+    #[test]
+    fn test_parse_sidecar_video_info() {
+        let meta = parse_sidecar(
+            r#"{"id": "abc123", "title": "Lecture 2", "description": null,
+                "duration": 60, "thumbnail_url": "https://example.com/t.jpg",
+                "channel": "Uni", "publish_date": "2024-05-02", "view_count": 3,
+                "streams": []}"#,
+        )
+        .unwrap();
+
+        assert_eq!(meta.published, parse_date("20240502"));
+        assert_eq!(meta.thumbnail.as_deref(), Some("https://example.com/t.jpg"));
+        assert!(meta.description.is_none());
+    }
+
+    #[test]
+    fn test_parse_sidecar_prefers_timestamp() {
+        let meta =
+            parse_sidecar(r#"{"timestamp": 1714564800, "upload_date": "20200101"}"#).unwrap();
+        assert_eq!(
+            meta.published.unwrap().to_rfc3339(),
+            "2024-05-01T12:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_probe_tags() {
+        let meta = parse_probe(
+            r#"{"format": {"duration": "125.6", "tags": {
+                "TITLE": "Tagged", "comment": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                "description": "A talk", "date": "20240503"}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(meta.id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(meta.title.as_deref(), Some("Tagged"));
+        assert_eq!(meta.description.as_deref(), Some("A talk"));
+        assert_eq!(meta.duration, Some(126));
+        assert_eq!(meta.published, parse_date("2024-05-03"));
+    }
+
+    #[test]
+    fn test_fill_from_keeps_existing_fields() {
+        let mut meta = Metadata {
+            title: Some("Sidecar".to_string()),
+            ..Default::default()
+        };
+        meta.fill_from(Metadata {
+            title: Some("Tag".to_string()),
+            duration: Some(10),
+            ..Default::default()
+        });
+
+        assert_eq!(meta.title.as_deref(), Some("Sidecar"));
+        assert_eq!(meta.duration, Some(10));
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        assert!(parse_date("20240501").is_some());
+        assert!(parse_date("2024-05-01").is_some());
+        assert!(parse_date("2024-05-01T10:00:00Z").is_some());
+        assert!(parse_date("May 1st").is_none());
+    }
+
+    // ============== Rendering Tests ==============
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(
+            encode_path("sub dir/Ep #1 é.mp3"),
+            "sub%20dir/Ep%20%231%20%C3%A9.mp3"
+        );
+        assert_eq!(encode_path("plain-file_1.m4a"), "plain-file_1.m4a");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("a & <b> \"c\" 'd'"),
+            "a &amp; &lt;b&gt; &quot;c&quot; &apos;d&apos;"
+        );
+        assert_eq!(escape("bell\u{7}"), "bell");
+    }
+
+    #[test]
+    fn test_mime_type() {
+        assert_eq!(mime_type(Path::new("a.mp3")), "audio/mpeg");
+        assert_eq!(mime_type(Path::new("a.M4A")), "audio/x-m4a");
+        assert_eq!(mime_type(Path::new("a.opus")), "audio/ogg");
+        assert_eq!(mime_type(Path::new("a.mp4")), "video/mp4");
+    }
+
+    #[test]
+    fn test_render_feed() {
+        let options = FeedOptions::new("https://example.com/pod/")
+            .with_author(Some("Uni".to_string()))
+            .with_image(Some("https://example.com/cover.jpg".to_string()));
+        let first = item("ep 1.mp3", "2024-05-01T00:00:00+00:00");
+        let built = Utc.with_ymd_and_hms(2024, 5, 2, 0, 0, 0).unwrap();
+
+        let xml = render_feed("Lectures & Talks", &options, &[&first], built);
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(xml.contains("xmlns:itunes=\"http://www.itunes.com/dtds/podcast-1.0.dtd\""));
+        assert!(xml.contains("<title>Lectures &amp; Talks</title>"));
+        assert!(xml.contains("<link>https://example.com/pod/</link>"));
+        assert!(xml.contains("<itunes:author>Uni</itunes:author>"));
+        assert!(xml.contains(
+            "<enclosure url=\"https://example.com/pod/ep%201.mp3\" length=\"1234\" type=\"audio/mpeg\"/>"
+        ));
+        assert!(xml.contains("<title>Episode &lt;1&gt; &amp; more</title>"));
+        assert!(xml.contains("<pubDate>Wed, 1 May 2024 00:00:00 +0000</pubDate>"));
+        assert!(xml.contains("<itunes:duration>01:02:05</itunes:duration>"));
+        assert!(xml.contains("<itunes:image href=\"https://example.com/pod/covers/ep%201.jpg\"/>"));
+        assert!(xml.contains("<guid isPermaLink=\"false\">ep 1.mp3</guid>"));
+        assert!(xml.trim_end().ends_with("</rss>"));
+    }
+
+    // ============== Build Tests ==============
+
+    #[test]
+    fn test_build_feed_uses_sidecars_and_images() {
+        let dir = TempDir::new().unwrap();
+        write(&dir.path().join("one.mp3"), "audio-one");
+        write(
+            &dir.path().join("one.info.json"),
+            r#"{"id": "vid1", "title": "First", "upload_date": "20240501", "duration": 61}"#,
+        );
+        write(&dir.path().join("one.jpg"), "img");
+        write(&dir.path().join("sub/two.m4a"), "audio-two!");
+        write(
+            &dir.path().join("sub/two.info.json"),
+            r#"{"id": "vid2", "title": "Second", "upload_date": "20240502"}"#,
+        );
+
+        let report = build_feed(dir.path(), &FeedOptions::new("http://host/pod")).unwrap();
+
+        assert_eq!(report.items, 2);
+        assert_eq!(report.added, 2);
+        let xml = fs::read_to_string(dir.path().join(FEED_FILE)).unwrap();
+        assert!(xml.contains("<guid isPermaLink=\"false\">vid1</guid>"));
+        assert!(xml.contains("url=\"http://host/pod/sub/two.m4a\" length=\"10\""));
+        assert!(xml.contains("<itunes:image href=\"http://host/pod/one.jpg\"/>"));
+        assert!(xml.find("Second").unwrap() < xml.find("First").unwrap());
+    }
+
+    #[test]
+    fn test_build_feed_is_incremental() {
+        let dir = TempDir::new().unwrap();
+        write(&dir.path().join("one.mp3"), "audio");
+        let options = FeedOptions::new("http://host/pod");
+
+        build_feed(dir.path(), &options).unwrap();
+        write(&dir.path().join("two.mp3"), "audio");
+        let report = build_feed(dir.path(), &options).unwrap();
+
+        assert_eq!(report.added, 1);
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(report.items, 2);
+
+        fs::remove_file(dir.path().join("one.mp3")).unwrap();
+        write(&dir.path().join("two.mp3"), "longer audio");
+        let report = build_feed(dir.path(), &options).unwrap();
+
+        assert_eq!(report.removed, 1);
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.items, 1);
+    }
+
+    #[test]
+    fn test_build_feed_rejects_bad_base_url() {
+        let dir = TempDir::new().unwrap();
+        let result = build_feed(dir.path(), &FeedOptions::new("host/pod"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_build_feed_custom_output() {
+        let dir = TempDir::new().unwrap();
+        let output = dir.path().join("public").join("lectures.xml");
+        let options = FeedOptions::new("http://host/pod").with_output(Some(output.clone()));
+
+        let report = build_feed(dir.path(), &options).unwrap();
+
+        assert_eq!(report.output, output);
+        let xml = fs::read_to_string(&output).unwrap();
+        assert!(xml.contains("href=\"http://host/pod/lectures.xml\""));
+    }
+}
@@ -0,0 +1,838 @@
+//! Filter expressions for choosing which videos to download.
+//!
+//! `--filter` takes conditions joined with `&`. Each condition compares one
+//! field of a video with a value:
+//!
+//! ```text
+//! upload_date = 2024 & duration >= 5m & duration <= 60m & title !~= '#shorts'
+//! ```
+//!
+//! | Field | Values | Operators |
+//! |-------|--------|-----------|
+//! | `title` | text or regular expression | `=` `!=` `~=` `!~=` |
+//! | `upload_date` | `2024`, `202403`, `20240315`, `2024-03-15`, `today`, `today-2weeks` | `=` `!=` `<` `<=` `>` `>=` |
+//! | `duration` | `90`, `5m`, `1h30m`, `04:30`, `01:04:30` | `=` `!=` `<` `<=` `>` `>=` |
+//! | `view_count` | `500`, `10k`, `1.5M` | `=` `!=` `<` `<=` `>` `>=` |
+//! | `live_status` | `not_live`, `is_live`, `is_upcoming`, `was_live`, `post_live` | `=` `!=` `~=` `!~=` |
+//! | `availability` | `public`, `unlisted`, `private`, `needs_auth`, `subscriber_only`, `premium_only` | `=` `!=` `~=` `!~=` |
+//!
+//! A partial date covers the whole period: `upload_date = 2024` matches any
+//! day of 2024, and `upload_date > 2024` starts in 2025.
+//!
+//! Playlist listings only carry some of these fields. A condition on a field
+//! the listing lacks leaves the verdict [`Verdict::Unknown`], and only then
+//! does [`Filter::resolve()`] fetch the video's full info.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Local, Months, NaiveDate};
+use regex::Regex;
+
+use crate::error::{AppError, AppResult};
+use crate::utils::parse_duration;
+use crate::youtube::VideoSummary;
+
+/// Operators, longest first so that `<=` is not read as `<`.
+const OPERATORS: [(&str, Op); 9] = [
+    ("!~=", Op::NotMatches),
+    ("~=", Op::Matches),
+    ("==", Op::Eq),
+    ("!=", Op::Ne),
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("=", Op::Eq),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+];
+
+/// A video field that conditions can test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    UploadDate,
+    Duration,
+    ViewCount,
+    LiveStatus,
+    Availability,
+}
+
+impl Field {
+    const ALL: [Field; 6] = [
+        Field::Title,
+        Field::UploadDate,
+        Field::Duration,
+        Field::ViewCount,
+        Field::LiveStatus,
+        Field::Availability,
+    ];
+
+    /// Returns the name used in expressions.
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::UploadDate => "upload_date",
+            Field::Duration => "duration",
+            Field::ViewCount => "view_count",
+            Field::LiveStatus => "live_status",
+            Field::Availability => "availability",
+        }
+    }
+
+    /// Returns `true` for fields compared as text.
+    fn is_text(self) -> bool {
+        matches!(self, Field::Title | Field::LiveStatus | Field::Availability)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Field::ALL
+            .into_iter()
+            .find(|field| field.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Field::ALL.iter().map(|field| field.name()).collect();
+                format!(
+                    "unknown field '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// A comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// The regular expression is found in the text
+    Matches,
+    /// The regular expression is not found in the text
+    NotMatches,
+}
+
+impl Op {
+    /// Returns the symbol used in expressions.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Matches => "~=",
+            Op::NotMatches => "!~=",
+        }
+    }
+
+    fn is_ordering(self) -> bool {
+        matches!(self, Op::Lt | Op::Le | Op::Gt | Op::Ge)
+    }
+
+    fn compare<T: PartialOrd>(self, actual: T, expected: T) -> bool {
+        match self {
+            Op::Eq => actual == expected,
+            Op::Ne => actual != expected,
+            Op::Lt => actual < expected,
+            Op::Le => actual <= expected,
+            Op::Gt => actual > expected,
+            Op::Ge => actual >= expected,
+            Op::Matches | Op::NotMatches => false,
+        }
+    }
+}
+
+/// The value a condition compares against, parsed for its field.
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Pattern(Regex),
+    Number(u64),
+    /// First and last day of a period, as `YYYYMMDD`
+    Dates {
+        first: String,
+        last: String,
+    },
+}
+
+/// One `field op value` comparison.
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+    /// The condition as written, for messages
+    text: String,
+}
+
+impl Condition {
+    fn new(field: Field, op: Op, value: &str) -> Result<Self, String> {
+        let text = if field.is_text() {
+            format!("{} {} '{}'", field, op.symbol(), value)
+        } else {
+            format!("{} {} {}", field, op.symbol(), value)
+        };
+
+        let value = match (field, op) {
+            (_, Op::Matches | Op::NotMatches) if field.is_text() => Value::Pattern(
+                Regex::new(value).map_err(|e| format!("invalid pattern in '{}': {}", text, e))?,
+            ),
+            (_, Op::Matches | Op::NotMatches) => {
+                return Err(format!(
+                    "'{}': {} only works on title, live_status and availability",
+                    text,
+                    op.symbol()
+                ))
+            }
+            (_, _) if field.is_text() && op.is_ordering() => {
+                return Err(format!(
+                    "'{}': {} only supports =, !=, ~= and !~=",
+                    text, field
+                ))
+            }
+            (Field::UploadDate, _) => {
+                let (first, last) = parse_date_range(value)
+                    .ok_or_else(|| format!("'{}': '{}' is not a date", text, value))?;
+                Value::Dates { first, last }
+            }
+            (Field::Duration, _) => Value::Number(
+                parse_length(value)
+                    .ok_or_else(|| format!("'{}': '{}' is not a duration", text, value))?,
+            ),
+            (Field::ViewCount, _) => Value::Number(
+                parse_count(value)
+                    .ok_or_else(|| format!("'{}': '{}' is not a number", text, value))?,
+            ),
+            (_, _) => Value::Text(value.to_string()),
+        };
+
+        Ok(Self {
+            field,
+            op,
+            value,
+            text,
+        })
+    }
+
+    /// Tests the condition, or returns `None` if the field is unknown.
+    fn test(&self, summary: &VideoSummary) -> Option<bool> {
+        let text = match self.field {
+            Field::Title => summary.title.as_deref(),
+            Field::LiveStatus => summary.live_status.as_deref(),
+            Field::Availability => summary.availability.as_deref(),
+            Field::UploadDate => summary.upload_date.as_deref(),
+            Field::Duration | Field::ViewCount => None,
+        };
+        let number = match self.field {
+            Field::Duration => summary.duration,
+            Field::ViewCount => summary.view_count,
+            _ => None,
+        };
+
+        Some(match &self.value {
+            Value::Text(expected) => self.op.compare(text?, expected.as_str()),
+            Value::Pattern(pattern) => pattern.is_match(text?) == (self.op == Op::Matches),
+            Value::Number(expected) => self.op.compare(number?, *expected),
+            Value::Dates { first, last } => {
+                let date = text?;
+                match self.op {
+                    Op::Eq => first.as_str() <= date && date <= last.as_str(),
+                    Op::Ne => date < first.as_str() || last.as_str() < date,
+                    Op::Lt => date < first.as_str(),
+                    Op::Le => date <= last.as_str(),
+                    Op::Gt => date > last.as_str(),
+                    Op::Ge => date >= first.as_str(),
+                    Op::Matches | Op::NotMatches => false,
+                }
+            }
+        })
+    }
+}
+
+/// The outcome of testing a video against a [`Filter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Every condition holds
+    Match,
+    /// A condition does not hold; carries the reason
+    Skip(String),
+    /// A condition tests a field the summary does not have
+    Unknown,
+}
+
+/// Conditions that must all hold for a video to be downloaded.
+///
+/// An empty filter matches every video. Several `--filter` flags are
+/// combined with [`FromIterator`], which requires all of them.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::filter::{Filter, Verdict};
+/// use rust_yt_downloader::youtube::VideoSummary;
+///
+/// let filter: Filter = "duration >= 5m & title !~= '#shorts'".parse().unwrap();
+///
+/// let summary = VideoSummary {
+///     title: Some("Conference talk".to_string()),
+///     duration: Some(1800),
+///     ..VideoSummary::new("abc123")
+/// };
+/// assert_eq!(filter.evaluate(&summary), Verdict::Match);
+///
+/// // The duration is not known yet
+/// let flat = VideoSummary::new("def456");
+/// assert_eq!(filter.evaluate(&flat), Verdict::Unknown);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+impl Filter {
+    /// Creates a filter that matches every video.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a condition to the filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if `value` does not suit the
+    /// field or the operator.
+    pub fn with_condition(mut self, field: Field, op: Op, value: &str) -> AppResult<Self> {
+        let condition =
+            Condition::new(field, op, value).map_err(|e| AppError::invalid_arg("--filter", e))?;
+        self.conditions.push(condition);
+        Ok(self)
+    }
+
+    /// Returns `true` if the filter has no conditions.
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Tests the video against every condition.
+    ///
+    /// A failing condition decides the verdict even when other fields are
+    /// unknown. A field missing from a `complete` summary will never be
+    /// known, so it makes the video skip rather than stay `Unknown`.
+    pub fn evaluate(&self, summary: &VideoSummary) -> Verdict {
+        let mut missing = None;
+        for condition in &self.conditions {
+            match condition.test(summary) {
+                Some(true) => {}
+                Some(false) => return Verdict::Skip(condition.text.clone()),
+                None => missing = missing.or(Some(condition.field)),
+            }
+        }
+
+        match missing {
+            None => Verdict::Match,
+            Some(field) if summary.complete => Verdict::Skip(format!("{} is unknown", field)),
+            Some(_) => Verdict::Unknown,
+        }
+    }
+
+    /// Tests the video, calling `fetch` for its full info only if the
+    /// summary lacks a field a condition needs.
+    ///
+    /// Returns the verdict, which is never `Unknown`, and the summary it
+    /// was based on.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from `fetch`.
+    pub fn resolve(
+        &self,
+        summary: VideoSummary,
+        fetch: impl FnOnce() -> AppResult<VideoSummary>,
+    ) -> AppResult<(Verdict, VideoSummary)> {
+        match self.evaluate(&summary) {
+            Verdict::Unknown => {
+                let full = VideoSummary {
+                    complete: true,
+                    ..fetch()?
+                };
+                Ok((self.evaluate(&full), full))
+            }
+            verdict => Ok((verdict, summary)),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = AppError;
+
+    /// Parses conditions joined with `&`. Values may be quoted with `'` or
+    /// `"` to contain spaces or `&`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| AppError::invalid_arg("--filter", message);
+
+        let parts = split_conditions(s).map_err(invalid)?;
+        if parts.is_empty() {
+            return Err(invalid("the filter is empty".to_string()));
+        }
+
+        let conditions = parts
+            .iter()
+            .map(|part| parse_condition(part))
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?;
+        Ok(Self { conditions })
+    }
+}
+
+impl FromIterator<Filter> for Filter {
+    fn from_iter<I: IntoIterator<Item = Filter>>(iter: I) -> Self {
+        Self {
+            conditions: iter.into_iter().flat_map(|f| f.conditions).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let texts: Vec<&str> = self.conditions.iter().map(|c| c.text.as_str()).collect();
+        f.write_str(&texts.join(" & "))
+    }
+}
+
+/// Splits an expression on the `&`s that are outside quotes, dropping
+/// empty parts so that `&&` works too.
+fn split_conditions(expression: &str) -> Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+
+    for c in expression.chars() {
+        match (quote, c) {
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (Some(open), _) if c == open => {
+                quote = None;
+                current.push(c);
+            }
+            (None, '&') => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("unclosed quote in '{}'", expression.trim()));
+    }
+    parts.push(current);
+
+    Ok(parts
+        .into_iter()
+        .map(|part| part.trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect())
+}
+
+/// Parses one `field op value` condition.
+fn parse_condition(text: &str) -> Result<Condition, String> {
+    let name_end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let field: Field = text[..name_end].parse()?;
+
+    let rest = text[name_end..].trim_start();
+    let (op, value) = OPERATORS
+        .iter()
+        .find_map(|(symbol, op)| rest.strip_prefix(symbol).map(|value| (*op, value)))
+        .ok_or_else(|| format!("'{}' has no operator (e.g. =, !=, <, >=, ~=)", text))?;
+
+    let value = unquote(value.trim());
+    if value.is_empty() {
+        return Err(format!("'{}' has no value", text));
+    }
+
+    Condition::new(field, op, value)
+}
+
+/// Removes one pair of matching quotes around `value`.
+fn unquote(value: &str) -> &str {
+    for quote in ['\'', '"'] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Parses a date or partial date into the first and last day it covers.
+///
+/// Accepts `YYYY`, `YYYYMM`, `YYYYMMDD`, the same with dashes, `today`,
+/// and `today-N` followed by `day(s)`, `week(s)`, `month(s)` or `year(s)`.
+fn parse_date_range(value: &str) -> Option<(String, String)> {
+    let day = |date: NaiveDate| date.format("%Y%m%d").to_string();
+
+    if let Some(rest) = value.strip_prefix("today") {
+        let today = Local::now().date_naive();
+        let date = match rest.strip_prefix('-') {
+            None if rest.is_empty() => today,
+            None => return None,
+            Some(offset) => {
+                let split = offset.find(|c: char| !c.is_ascii_digit())?;
+                let amount: u32 = offset[..split].parse().ok()?;
+                match offset[split..].trim_end_matches('s') {
+                    "day" => today.checked_sub_days(chrono::Days::new(amount.into()))?,
+                    "week" => today.checked_sub_days(chrono::Days::new(u64::from(amount) * 7))?,
+                    "month" => today.checked_sub_months(Months::new(amount))?,
+                    "year" => today.checked_sub_months(Months::new(amount.checked_mul(12)?))?,
+                    _ => return None,
+                }
+            }
+        };
+        return Some((day(date), day(date)));
+    }
+
+    let digits = value.replace('-', "");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    match digits.len() {
+        4 => {
+            let year: i32 = digits.parse().ok()?;
+            let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+            let last = NaiveDate::from_ymd_opt(year, 12, 31)?;
+            Some((day(first), day(last)))
+        }
+        6 => {
+            let first = NaiveDate::parse_from_str(&format!("{}01", digits), "%Y%m%d").ok()?;
+            let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+            Some((day(first), day(last)))
+        }
+        8 => {
+            let date = NaiveDate::parse_from_str(&digits, "%Y%m%d").ok()?;
+            (date.year() > 0).then(|| (day(date), day(date)))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a duration: plain seconds, `HH:MM:SS`, `MM:SS`, or amounts with
+/// `h`, `m` and `s` units such as `1h30m`.
+fn parse_length(value: &str) -> Option<u64> {
+    if value.contains(':') || value.bytes().all(|b| b.is_ascii_digit()) {
+        return parse_duration(value).ok();
+    }
+
+    let mut total: u64 = 0;
+    let mut amount = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            amount.push(c);
+            continue;
+        }
+        let scale = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let number: u64 = std::mem::take(&mut amount).parse().ok()?;
+        total = total.checked_add(number.checked_mul(scale)?)?;
+    }
+
+    amount.is_empty().then_some(total)
+}
+
+/// Parses a count with an optional `k`, `m` or `b` suffix, e.g. `1.5M`.
+fn parse_count(value: &str) -> Option<u64> {
+    let (number, scale) = match value.char_indices().last()? {
+        (split, 'k' | 'K') => (&value[..split], 1e3),
+        (split, 'm' | 'M') => (&value[..split], 1e6),
+        (split, 'b' | 'B') => (&value[..split], 1e9),
+        _ => return value.parse().ok(),
+    };
+
+    let number: f64 = number.parse().ok()?;
+    (number.is_finite() && number >= 0.0).then(|| (number * scale).round() as u64)
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> VideoSummary {
+        VideoSummary {
+            id: "abc123".to_string(),
+            title: Some("Rust in 2024 #shorts".to_string()),
+            duration: Some(600),
+            upload_date: Some("20240315".to_string()),
+            view_count: Some(12_500),
+            live_status: Some("not_live".to_string()),
+            availability: Some("public".to_string()),
+            complete: false,
+        }
+    }
+
+    fn verdict(expression: &str) -> Verdict {
+        expression.parse::<Filter>().unwrap().evaluate(&summary())
+    }
+
+    fn invalid(expression: &str) -> String {
+        expression.parse::<Filter>().unwrap_err().to_string()
+    }
+
+    // ============== Parsing Tests ==============
+
+    #[test]
+    fn test_parse_conditions() {
+        let filter: Filter = "duration>=5m&title !~= '#shorts'  &  view_count < 1M"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            filter.to_string(),
+            "duration >= 5m & title !~= '#shorts' & view_count < 1M"
+        );
+    }
+
+    #[test]
+    fn test_parse_quoted_values_keep_ampersands() {
+        let filter: Filter = "title = \"Q&A\" && availability = public".parse().unwrap();
+        assert_eq!(
+            filter.to_string(),
+            "title = 'Q&A' & availability = 'public'"
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(invalid("").contains("empty"));
+        assert!(invalid("likes > 5").contains("unknown field 'likes'"));
+        assert!(invalid("duration 5m").contains("no operator"));
+        assert!(invalid("duration >=").contains("no value"));
+        assert!(invalid("title = 'open").contains("unclosed quote"));
+        assert!(invalid("duration >= soon").contains("not a duration"));
+        assert!(invalid("upload_date > 2024-13").contains("not a date"));
+        assert!(invalid("view_count > many").contains("not a number"));
+        assert!(invalid("title ~= '('").contains("invalid pattern"));
+        assert!(invalid("duration ~= 5").contains("only works on"));
+        assert!(invalid("title > a").contains("only supports"));
+    }
+
+    #[test]
+    fn test_error_names_the_flag() {
+        assert!(invalid("likes > 5").starts_with("Invalid argument: --filter"));
+    }
+
+    // ============== Value Tests ==============
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!(parse_length("90"), Some(90));
+        assert_eq!(parse_length("5m"), Some(300));
+        assert_eq!(parse_length("1h30m"), Some(5400));
+        assert_eq!(parse_length("04:30"), Some(270));
+        assert_eq!(parse_length("01:00:00"), Some(3600));
+        assert_eq!(parse_length("5"), Some(5));
+        assert_eq!(parse_length("m5"), None);
+        assert_eq!(parse_length("5x"), None);
+        assert_eq!(parse_length("1h30"), None);
+    }
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("500"), Some(500));
+        assert_eq!(parse_count("10k"), Some(10_000));
+        assert_eq!(parse_count("1.5M"), Some(1_500_000));
+        assert_eq!(parse_count("2b"), Some(2_000_000_000));
+        assert_eq!(parse_count("-1k"), None);
+        assert_eq!(parse_count("k"), None);
+    }
+
+    #[test]
+    fn test_parse_date_range() {
+        let range = |first: &str, last: &str| Some((first.to_string(), last.to_string()));
+
+        assert_eq!(parse_date_range("2024"), range("20240101", "20241231"));
+        assert_eq!(parse_date_range("202402"), range("20240201", "20240229"));
+        assert_eq!(parse_date_range("2023-12"), range("20231201", "20231231"));
+        assert_eq!(
+            parse_date_range("2024-03-15"),
+            range("20240315", "20240315")
+        );
+        assert_eq!(parse_date_range("20240230"), None);
+        assert_eq!(parse_date_range("24"), None);
+        assert_eq!(parse_date_range("yesterday"), None);
+    }
+
+    #[test]
+    fn test_parse_relative_dates() {
+        let today = Local::now().date_naive();
+        let day = |date: NaiveDate| date.format("%Y%m%d").to_string();
+
+        let (first, last) = parse_date_range("today").unwrap();
+        assert_eq!(first, day(today));
+        assert_eq!(last, day(today));
+
+        let (first, _) = parse_date_range("today-2weeks").unwrap();
+        assert_eq!(first, day(today - chrono::Duration::days(14)));
+
+        let (first, _) = parse_date_range("today-1day").unwrap();
+        assert_eq!(first, day(today - chrono::Duration::days(1)));
+
+        assert!(parse_date_range("today-3months").is_some());
+        assert!(parse_date_range("today-1year").is_some());
+        assert!(parse_date_range("today-1fortnight").is_none());
+        assert!(parse_date_range("todayish").is_none());
+    }
+
+    // ============== Evaluation Tests ==============
+
+    #[test]
+    fn test_text_conditions() {
+        assert_eq!(verdict("title ~= 'Rust'"), Verdict::Match);
+        assert_eq!(verdict("title ~= '(?i)rust in'"), Verdict::Match);
+        assert_eq!(
+            verdict("title !~= '#shorts'"),
+            Verdict::Skip("title !~= '#shorts'".to_string())
+        );
+        assert_eq!(verdict("availability != subscriber_only"), Verdict::Match);
+        assert_eq!(verdict("live_status = not_live"), Verdict::Match);
+    }
+
+    #[test]
+    fn test_number_conditions() {
+        assert_eq!(verdict("duration >= 5m & duration <= 60m"), Verdict::Match);
+        assert_eq!(verdict("duration = 10:00"), Verdict::Match);
+        assert!(matches!(verdict("duration < 5m"), Verdict::Skip(_)));
+        assert_eq!(verdict("view_count > 10k"), Verdict::Match);
+        assert!(matches!(verdict("view_count >= 1M"), Verdict::Skip(_)));
+    }
+
+    #[test]
+    fn test_date_conditions_cover_periods() {
+        assert_eq!(verdict("upload_date = 2024"), Verdict::Match);
+        assert_eq!(verdict("upload_date = 202403"), Verdict::Match);
+        assert_eq!(verdict("upload_date >= 2024-03-15"), Verdict::Match);
+        assert_eq!(verdict("upload_date <= 2024"), Verdict::Match);
+        assert!(matches!(verdict("upload_date < 2024"), Verdict::Skip(_)));
+        assert!(matches!(verdict("upload_date > 202403"), Verdict::Skip(_)));
+        assert!(matches!(verdict("upload_date != 2024"), Verdict::Skip(_)));
+        assert_eq!(verdict("upload_date != 2023"), Verdict::Match);
+    }
+
+    #[test]
+    fn test_empty_filter_matches() {
+        assert_eq!(
+            Filter::new().evaluate(&VideoSummary::new("abc")),
+            Verdict::Match
+        );
+    }
+
+    #[test]
+    fn test_missing_fields_are_unknown() {
+        let filter: Filter = "upload_date = 2024 & duration > 5m".parse().unwrap();
+        let flat = VideoSummary {
+            upload_date: None,
+            ..summary()
+        };
+        assert_eq!(filter.evaluate(&flat), Verdict::Unknown);
+
+        let complete = VideoSummary {
+            complete: true,
+            ..flat
+        };
+        assert_eq!(
+            filter.evaluate(&complete),
+            Verdict::Skip("upload_date is unknown".to_string())
+        );
+    }
+
+    #[test]
+    fn test_known_failure_beats_unknown_field() {
+        let filter: Filter = "upload_date = 2024 & duration > 1h".parse().unwrap();
+        let flat = VideoSummary {
+            upload_date: None,
+            ..summary()
+        };
+        assert_eq!(
+            filter.evaluate(&flat),
+            Verdict::Skip("duration > 1h".to_string())
+        );
+    }
+
+    #[test]
+    fn test_combined_filters_require_all() {
+        let filter: Filter = ["duration > 5m", "title ~= Go"]
+            .iter()
+            .map(|expression| expression.parse::<Filter>().unwrap())
+            .collect();
+        assert_eq!(
+            filter.evaluate(&summary()),
+            Verdict::Skip("title ~= 'Go'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_condition() {
+        let filter = Filter::new()
+            .with_condition(Field::Duration, Op::Ge, "60")
+            .unwrap()
+            .with_condition(Field::Title, Op::Matches, "Rust")
+            .unwrap();
+        assert_eq!(filter.to_string(), "duration >= 60 & title ~= 'Rust'");
+        assert_eq!(filter.evaluate(&summary()), Verdict::Match);
+
+        assert!(Filter::new()
+            .with_condition(Field::Title, Op::Matches, "(")
+            .is_err());
+    }
+
+    // ============== Resolve Tests ==============
+
+    #[test]
+    fn test_resolve_skips_fetch_when_listing_suffices() {
+        let filter: Filter = "duration > 5m".parse().unwrap();
+        let (verdict, _) = filter
+            .resolve(summary(), || panic!("should not fetch"))
+            .unwrap();
+        assert_eq!(verdict, Verdict::Match);
+    }
+
+    #[test]
+    fn test_resolve_fetches_missing_fields() {
+        let filter: Filter = "upload_date = 2024".parse().unwrap();
+        let flat = VideoSummary::new("abc123");
+
+        let (verdict, full) = filter.resolve(flat.clone(), || Ok(summary())).unwrap();
+        assert_eq!(verdict, Verdict::Match);
+        assert!(full.complete);
+
+        let (verdict, _) = filter
+            .resolve(flat.clone(), || Ok(VideoSummary::new("abc123")))
+            .unwrap();
+        assert_eq!(verdict, Verdict::Skip("upload_date is unknown".to_string()));
+
+        let failed = filter.resolve(flat, || Err(AppError::Other("offline".to_string())));
+        assert!(failed.is_err());
+    }
+}
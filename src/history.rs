@@ -0,0 +1,816 @@
+//! Persistent download history.
+//!
+//! Every finished download, successful or not, is appended to a history file
+//! as one JSON object per line (`history.jsonl`). The file lives next to the
+//! user configuration file, so `--config` and `YTDL_CONFIG` move it too.
+//!
+//! The history is append-only during downloads. `ytdl history` reads it back
+//! with optional filters, and `ytdl history prune` rewrites it without stale
+//! entries.
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::history::{History, HistoryEntry, HistoryQuery};
+//!
+//! # fn example() -> rust_yt_downloader::error::AppResult<()> {
+//! let dir = std::env::temp_dir().join("ytdl-history-doc");
+//! let history = History::new(dir.join("history.jsonl"));
+//!
+//! history.append(&[HistoryEntry::failure("https://youtu.be/abc", "Video unavailable")])?;
+//!
+//! let failed = history.search(&HistoryQuery::new().with_failed_only(true))?;
+//! assert!(!failed.is_empty());
+//! # std::fs::remove_dir_all(&dir).ok();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::downloader::DownloadResult;
+use crate::error::{AppError, AppResult};
+use crate::progress::messages;
+
+/// File name of the history store, relative to the configuration directory.
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// Whether a recorded download succeeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// The file was downloaded and saved.
+    Success,
+    /// The download failed after all retries.
+    Failed,
+}
+
+/// One recorded download.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// When the download finished, in RFC 3339 format (UTC).
+    pub timestamp: String,
+
+    /// The URL as it was requested.
+    pub url: String,
+
+    /// YouTube video ID, when the video could be resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_id: Option<String>,
+
+    /// Video title, when the video could be resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Uploader channel, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+
+    /// Where the file was saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    /// Size of the saved file in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Container or audio format of the saved file (its extension).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    /// Whether the download succeeded.
+    pub outcome: Outcome,
+
+    /// The error message of a failed download.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Records a successful download of `url`, timestamped now.
+    pub fn success(url: &str, result: &DownloadResult) -> Self {
+        Self {
+            timestamp: now(),
+            url: url.to_string(),
+            video_id: Some(result.video_id.clone()),
+            title: Some(result.video_title.clone()),
+            channel: result.channel.clone(),
+            path: Some(result.file_path.clone()),
+            size: Some(result.file_size),
+            format: result
+                .file_path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase()),
+            outcome: Outcome::Success,
+            error: None,
+        }
+    }
+
+    /// Records a failed download of `url`, timestamped now.
+    pub fn failure(url: &str, error: &str) -> Self {
+        Self {
+            timestamp: now(),
+            url: url.to_string(),
+            video_id: None,
+            title: None,
+            channel: None,
+            path: None,
+            size: None,
+            format: None,
+            outcome: Outcome::Failed,
+            error: Some(error.to_string()),
+        }
+    }
+
+    /// Sets the timestamp of the entry.
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+        self
+    }
+
+    /// Returns the parsed timestamp, or `None` if it is malformed.
+    pub fn recorded_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Returns `true` if the download failed.
+    pub fn is_failed(&self) -> bool {
+        self.outcome == Outcome::Failed
+    }
+
+    /// Returns `true` if `text` appears (case-insensitively) in the title,
+    /// channel, URL, video ID or path of the entry.
+    pub fn contains(&self, text: &str) -> bool {
+        let needle = text.to_lowercase();
+        let path = self.path.as_ref().map(|p| p.to_string_lossy().into_owned());
+
+        let fields = [
+            Some(self.url.as_str()),
+            self.video_id.as_deref(),
+            self.title.as_deref(),
+            self.channel.as_deref(),
+            path.as_deref(),
+        ];
+
+        fields
+            .iter()
+            .flatten()
+            .any(|field| field.to_lowercase().contains(&needle))
+    }
+}
+
+/// Filters applied by `ytdl history`.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// Free text matched against title, channel, URL, ID and path.
+    pub search: Option<String>,
+    /// Only entries recorded at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+    /// Only failed downloads.
+    pub failed_only: bool,
+}
+
+impl HistoryQuery {
+    /// Creates a query that matches every entry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the free-text search.
+    pub fn with_search(mut self, search: Option<String>) -> Self {
+        self.search = search;
+        self
+    }
+
+    /// Sets the earliest timestamp to include.
+    pub fn with_since(mut self, since: Option<DateTime<Utc>>) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// Restricts the query to failed downloads.
+    pub fn with_failed_only(mut self, failed_only: bool) -> Self {
+        self.failed_only = failed_only;
+        self
+    }
+
+    /// Returns `true` if `entry` passes every filter of the query.
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        if self.failed_only && !entry.is_failed() {
+            return false;
+        }
+
+        if let Some(since) = self.since {
+            match entry.recorded_at() {
+                Some(at) if at >= since => {}
+                _ => return false,
+            }
+        }
+
+        match &self.search {
+            Some(text) => entry.contains(text),
+            None => true,
+        }
+    }
+}
+
+/// What `ytdl history prune` removes.
+///
+/// By default only stale entries go: successful downloads whose file no
+/// longer exists, and failures that were later retried successfully.
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    /// Also remove every entry recorded before this instant.
+    pub before: Option<DateTime<Utc>>,
+    /// Remove every entry.
+    pub all: bool,
+}
+
+/// Result of a prune.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Number of entries removed.
+    pub removed: usize,
+    /// Number of entries kept.
+    pub kept: usize,
+}
+
+/// The history file on disk.
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// Opens the history stored at `path`. The file is created on first write.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Opens the history that sits next to the configuration file at
+    /// `config_path`.
+    pub fn beside(config_path: &Path) -> Self {
+        Self::new(config_path.with_file_name(HISTORY_FILE))
+    }
+
+    /// Returns the default history path, next to [`Config::config_path`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration directory cannot be determined.
+    pub fn default_path() -> AppResult<PathBuf> {
+        Ok(Config::config_path()?.with_file_name(HISTORY_FILE))
+    }
+
+    /// Returns the path of the history file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends entries to the history, creating the file and its directory
+    /// if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::DirectoryCreation` or `AppError::FileWrite` if the
+    /// history file cannot be written.
+    pub fn append(&self, entries: &[HistoryEntry]) -> AppResult<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        self.create_parent()?;
+
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&to_line(entry)?);
+            content.push('\n');
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| AppError::file_write(&self.path, e))
+    }
+
+    /// Reads every entry, oldest first.
+    ///
+    /// A missing file is an empty history. Lines that cannot be parsed are
+    /// skipped with a warning so one bad line does not hide the rest.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::FileRead` if the file exists but cannot be read.
+    pub fn load(&self) -> AppResult<Vec<HistoryEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content =
+            fs::read_to_string(&self.path).map_err(|e| AppError::file_read(&self.path, e))?;
+
+        let mut entries = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => messages::warning(&format!(
+                    "{}: skipping unreadable line {}: {}",
+                    self.path.display(),
+                    index + 1,
+                    e
+                )),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the entries matching `query`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history cannot be read.
+    pub fn search(&self, query: &HistoryQuery) -> AppResult<Vec<HistoryEntry>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|entry| query.matches(entry))
+            .collect())
+    }
+
+    /// Removes stale entries (see [`PruneOptions`]) and rewrites the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history cannot be read or rewritten.
+    pub fn prune(&self, options: &PruneOptions) -> AppResult<PruneReport> {
+        let entries = self.load()?;
+        let total = entries.len();
+        let kept = prune_entries(entries, options, |path| path.exists());
+
+        let report = PruneReport {
+            removed: total - kept.len(),
+            kept: kept.len(),
+        };
+
+        if report.removed > 0 {
+            self.rewrite(&kept)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Replaces the history with `entries`, via a temporary file so an
+    /// interrupted write never leaves a truncated history behind.
+    fn rewrite(&self, entries: &[HistoryEntry]) -> AppResult<()> {
+        self.create_parent()?;
+
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&to_line(entry)?);
+            content.push('\n');
+        }
+
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, content).map_err(|e| AppError::file_write(&tmp, e))?;
+        fs::rename(&tmp, &self.path).map_err(|e| AppError::file_write(&self.path, e))
+    }
+
+    fn create_parent(&self) -> AppResult<()> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the entries that survive `options`. `exists` tells whether a
+/// saved file is still on disk.
+fn prune_entries(
+    entries: Vec<HistoryEntry>,
+    options: &PruneOptions,
+    exists: impl Fn(&Path) -> bool,
+) -> Vec<HistoryEntry> {
+    if options.all {
+        return Vec::new();
+    }
+
+    // A failure is resolved once the same URL succeeds later on.
+    let mut succeeded_later = HashSet::new();
+    let mut resolved = vec![false; entries.len()];
+    for (index, entry) in entries.iter().enumerate().rev() {
+        if entry.is_failed() {
+            resolved[index] = succeeded_later.contains(entry.url.as_str());
+        } else {
+            succeeded_later.insert(entry.url.as_str());
+        }
+    }
+
+    entries
+        .iter()
+        .zip(resolved)
+        .filter(|(entry, resolved)| {
+            if *resolved {
+                return false;
+            }
+            if let Some(before) = options.before {
+                if entry.recorded_at().map_or(true, |at| at < before) {
+                    return false;
+                }
+            }
+            match (&entry.outcome, &entry.path) {
+                (Outcome::Success, Some(path)) => exists(path),
+                _ => true,
+            }
+        })
+        .map(|(entry, _)| entry.clone())
+        .collect()
+}
+
+/// Parses a date given to `--since` or `--before`.
+///
+/// Accepts a calendar date (`2024-05-01`, midnight UTC), an RFC 3339
+/// timestamp (`2024-05-01T12:00:00Z`), or a duration ago such as `30m`,
+/// `12h`, `7d` or `2w`.
+///
+/// # Errors
+///
+/// Returns `AppError::InvalidArgument` if the value matches none of these.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::history::parse_date;
+///
+/// assert!(parse_date("2024-05-01").is_ok());
+/// assert!(parse_date("7d").is_ok());
+/// assert!(parse_date("last tuesday").is_err());
+/// ```
+pub fn parse_date(value: &str) -> AppResult<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    if let Some(ago) = parse_relative(value) {
+        return Ok(Utc::now() - ago);
+    }
+
+    Err(AppError::invalid_arg(
+        "date",
+        format!(
+            "'{}' is not a date (use YYYY-MM-DD, an RFC 3339 timestamp, or a duration such as 12h, 7d, 2w)",
+            value
+        ),
+    ))
+}
+
+/// Parses `<number><unit>` with unit `m`, `h`, `d` or `w`.
+fn parse_relative(value: &str) -> Option<Duration> {
+    let unit = value.chars().last()?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    if amount < 0 {
+        return None;
+    }
+
+    match unit {
+        'm' => Duration::try_minutes(amount),
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => None,
+    }
+}
+
+fn to_line(entry: &HistoryEntry) -> AppResult<String> {
+    serde_json::to_string(entry)
+        .map_err(|e| AppError::Other(format!("Could not serialize history entry: {}", e)))
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap()
+    }
+
+    fn sample_result(path: &str) -> DownloadResult {
+        DownloadResult {
+            file_path: PathBuf::from(path),
+            file_size: 2048,
+            video_id: "abc123".to_string(),
+            video_title: "Rust in Production".to_string(),
+            channel: Some("Ferris Talks".to_string()),
+        }
+    }
+
+    fn success(url: &str, path: &str, day: u32) -> HistoryEntry {
+        HistoryEntry::success(url, &sample_result(path)).with_timestamp(at(day))
+    }
+
+    fn failure(url: &str, day: u32) -> HistoryEntry {
+        HistoryEntry::failure(url, "Video unavailable").with_timestamp(at(day))
+    }
+
+    // ============== HistoryEntry Tests ==============
+
+    #[test]
+    fn test_entry_from_download_result() {
+        let entry = HistoryEntry::success("https://youtu.be/abc123", &sample_result("/d/a.MP4"));
+
+        assert_eq!(entry.outcome, Outcome::Success);
+        assert_eq!(entry.video_id.as_deref(), Some("abc123"));
+        assert_eq!(entry.channel.as_deref(), Some("Ferris Talks"));
+        assert_eq!(entry.size, Some(2048));
+        assert_eq!(entry.format.as_deref(), Some("mp4"));
+        assert!(entry.error.is_none());
+        assert!(entry.recorded_at().is_some());
+    }
+
+    #[test]
+    fn test_entry_failure() {
+        let entry = HistoryEntry::failure("https://youtu.be/x", "boom");
+
+        assert!(entry.is_failed());
+        assert_eq!(entry.error.as_deref(), Some("boom"));
+        assert!(entry.path.is_none());
+    }
+
+    #[test]
+    fn test_entry_json_round_trip() {
+        let entry = success("https://youtu.be/abc123", "/d/a.mp4", 1);
+        let line = to_line(&entry).unwrap();
+
+        assert!(line.contains("\"outcome\":\"success\""));
+        assert!(line.contains("\"timestamp\":\"2024-05-01T12:00:00Z\""));
+        assert!(!line.contains("\"error\""));
+        assert_eq!(serde_json::from_str::<HistoryEntry>(&line).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_entry_contains_is_case_insensitive() {
+        let entry = success("https://youtu.be/abc123", "/d/a.mp4", 1);
+
+        assert!(entry.contains("ferris"));
+        assert!(entry.contains("PRODUCTION"));
+        assert!(entry.contains("abc123"));
+        assert!(entry.contains("/d/a"));
+        assert!(!entry.contains("python"));
+    }
+
+    // ============== HistoryQuery Tests ==============
+
+    #[test]
+    fn test_query_default_matches_everything() {
+        let query = HistoryQuery::new();
+        assert!(query.matches(&success("u", "/a.mp4", 1)));
+        assert!(query.matches(&failure("u", 1)));
+    }
+
+    #[test]
+    fn test_query_failed_only() {
+        let query = HistoryQuery::new().with_failed_only(true);
+        assert!(!query.matches(&success("u", "/a.mp4", 1)));
+        assert!(query.matches(&failure("u", 1)));
+    }
+
+    #[test]
+    fn test_query_since() {
+        let query = HistoryQuery::new().with_since(Some(at(10)));
+        assert!(!query.matches(&failure("u", 9)));
+        assert!(query.matches(&failure("u", 10)));
+        assert!(query.matches(&failure("u", 11)));
+    }
+
+    #[test]
+    fn test_query_combines_filters() {
+        let query = HistoryQuery::new()
+            .with_search(Some("youtu.be/x".to_string()))
+            .with_failed_only(true);
+
+        assert!(query.matches(&failure("https://youtu.be/x", 1)));
+        assert!(!query.matches(&failure("https://youtu.be/y", 1)));
+    }
+
+    // ============== History Store Tests ==============
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let dir = TempDir::new().unwrap();
+        let history = History::new(dir.path().join("history.jsonl"));
+
+        assert!(history.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = TempDir::new().unwrap();
+        let history = History::new(dir.path().join("nested").join("history.jsonl"));
+
+        history.append(&[failure("u1", 1)]).unwrap();
+        history
+            .append(&[success("u2", "/a.mp4", 2), failure("u3", 3)])
+            .unwrap();
+
+        let entries = history.load().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].url, "u1");
+        assert_eq!(entries[2].url, "u3");
+    }
+
+    #[test]
+    fn test_load_skips_bad_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let good = to_line(&failure("u1", 1)).unwrap();
+        fs::write(&path, format!("{}\nnot json\n\n{}\n", good, good)).unwrap();
+
+        assert_eq!(History::new(&path).load().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_search_filters_entries() {
+        let dir = TempDir::new().unwrap();
+        let history = History::new(dir.path().join("history.jsonl"));
+        history
+            .append(&[success("u1", "/a.mp4", 1), failure("u2", 2)])
+            .unwrap();
+
+        let failed = history
+            .search(&HistoryQuery::new().with_failed_only(true))
+            .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].url, "u2");
+    }
+
+    #[test]
+    fn test_beside_config_path() {
+        let history = History::beside(Path::new("/etc/ytdl/config.toml"));
+        assert_eq!(history.path(), Path::new("/etc/ytdl/history.jsonl"));
+    }
+
+    #[test]
+    fn test_default_path_is_next_to_config() {
+        if let (Ok(history), Ok(config)) = (History::default_path(), Config::config_path()) {
+            assert_eq!(history.parent(), config.parent());
+            assert!(history.ends_with(HISTORY_FILE));
+        }
+    }
+
+    // ============== Prune Tests ==============
+
+    #[test]
+    fn test_prune_removes_missing_files() {
+        let entries = vec![success("u1", "/kept.mp4", 1), success("u2", "/gone.mp4", 2)];
+
+        let kept = prune_entries(entries, &PruneOptions::default(), |p| {
+            p == Path::new("/kept.mp4")
+        });
+
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].url, "u1");
+    }
+
+    #[test]
+    fn test_prune_removes_failures_that_later_succeeded() {
+        let entries = vec![
+            failure("u1", 1),
+            success("u1", "/a.mp4", 2),
+            failure("u2", 3),
+            success("u3", "/b.mp4", 4),
+            failure("u3", 5),
+        ];
+
+        let kept = prune_entries(entries, &PruneOptions::default(), |_| true);
+        let urls: Vec<_> = kept.iter().map(|e| (e.url.as_str(), e.outcome)).collect();
+
+        assert_eq!(
+            urls,
+            vec![
+                ("u1", Outcome::Success),
+                ("u2", Outcome::Failed),
+                ("u3", Outcome::Success),
+                ("u3", Outcome::Failed),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune_before() {
+        let entries = vec![failure("u1", 1), failure("u2", 5)];
+        let options = PruneOptions {
+            before: Some(at(3)),
+            all: false,
+        };
+
+        let kept = prune_entries(entries, &options, |_| true);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].url, "u2");
+    }
+
+    #[test]
+    fn test_prune_all() {
+        let entries = vec![failure("u1", 1), success("u2", "/a.mp4", 2)];
+        let options = PruneOptions {
+            before: None,
+            all: true,
+        };
+
+        assert!(prune_entries(entries, &options, |_| true).is_empty());
+    }
+
+    #[test]
+    fn test_prune_rewrites_file() {
+        let dir = TempDir::new().unwrap();
+        let kept_file = dir.path().join("kept.mp4");
+        fs::write(&kept_file, b"data").unwrap();
+
+        let history = History::new(dir.path().join("history.jsonl"));
+        history
+            .append(&[
+                success("u1", kept_file.to_str().unwrap(), 1),
+                success("u2", dir.path().join("gone.mp4").to_str().unwrap(), 2),
+            ])
+            .unwrap();
+
+        let report = history.prune(&PruneOptions::default()).unwrap();
+
+        assert_eq!(
+            report,
+            PruneReport {
+                removed: 1,
+                kept: 1
+            }
+        );
+        assert_eq!(history.load().unwrap().len(), 1);
+        assert!(!dir.path().join("history.jsonl.tmp").exists());
+    }
+
+    // ============== Date Parsing Tests ==============
+
+    #[test]
+    fn test_parse_date_calendar() {
+        assert_eq!(
+            parse_date("2024-05-01").unwrap(),
+            Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_date_rfc3339() {
+        assert_eq!(
+            parse_date("2024-05-01T14:30:00+02:00").unwrap(),
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_date_relative() {
+        let before = Utc::now();
+        let week = parse_date("1w").unwrap();
+        let after = Utc::now();
+
+        assert!(week >= before - Duration::days(7));
+        assert!(week <= after - Duration::days(7));
+        assert!(parse_date("12h").is_ok());
+        assert!(parse_date("30m").is_ok());
+        assert!(parse_date("0d").is_ok());
+    }
+
+    #[test]
+    fn test_parse_date_invalid() {
+        for value in ["", "yesterday", "7y", "-3d", "2024-13-01", "d"] {
+            assert!(parse_date(value).is_err(), "{value:?} should be rejected");
+        }
+    }
+}
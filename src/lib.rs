@@ -27,21 +27,45 @@
 //!
 //! # Modules
 //!
+//! - [`batch`] - Multi-URL input, retries and run summaries
 //! - [`cli`] - Command-line interface and argument parsing
 //! - [`config`] - Configuration file management
 //! - [`downloader`] - Core download functionality
 //! - [`error`] - Error types and handling
+//! - [`feed`] - Podcast RSS feeds for download directories
+//! - [`filter`] - `--filter` expressions over video metadata
+//! - [`history`] - Persistent download history
+//! - [`library`] - Index of downloaded videos for reuse
+//! - [`m3u`] - M3U8 playlist files for downloaded playlists
 //! - [`media`] - FFmpeg integration for media processing
+//! - [`mirror`] - Playlist snapshots for mirroring
+//! - [`plan`] - Download plans printed by `--dry-run`
 //! - [`progress`] - Progress tracking and display
+//! - [`server`] - Local HTTP API for `ytdl serve`
+//! - [`sidecar`] - Info JSON and NFO files for media servers
+//! - [`space`] - Disk-space checks and size limits
+//! - [`subscriptions`] - Followed channels and playlists for `ytdl sync`
 //! - [`utils`] - Utility functions and helpers
 //! - [`youtube`] - YouTube API client and metadata extraction
 
+pub mod batch;
 pub mod cli;
 pub mod config;
 pub mod downloader;
 pub mod error;
+pub mod feed;
+pub mod filter;
+pub mod history;
+pub mod library;
+pub mod m3u;
 pub mod media;
+pub mod mirror;
+pub mod plan;
 pub mod progress;
+pub mod server;
+pub mod sidecar;
+pub mod space;
+pub mod subscriptions;
 pub mod utils;
 pub mod youtube;
 
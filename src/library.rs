@@ -0,0 +1,706 @@
+//! Library index of downloaded videos.
+//!
+//! The library maps YouTube video IDs to the files that hold them, so a
+//! video that appears in several playlists is only fetched once. Further
+//! copies are hard-linked to the first one, or copied when the target is on
+//! another filesystem (on Linux the copy is a reflink where the filesystem
+//! supports it).
+//!
+//! The index is stored as JSON in `library.json`, next to the user
+//! configuration file. Every download adds its file, and
+//! `ytdl library scan <dir>` indexes existing files from their embedded tags
+//! or the `.info.json` sidecar yt-dlp writes next to them.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::Path;
+//! use rust_yt_downloader::library::Library;
+//!
+//! let mut library = Library::default();
+//! library.add("dQw4w9WgXcQ", Path::new("/music/song.mp3"));
+//!
+//! assert_eq!(library.files("dQw4w9WgXcQ").len(), 1);
+//! assert!(library.files("other").is_empty());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use clap::ValueEnum;
+use fs4::fs_std::FileExt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::media::{AudioFormat, FFmpeg, VideoFormat};
+use crate::utils::extract_video_id;
+
+/// File name of the library index, relative to the configuration directory.
+pub const LIBRARY_FILE: &str = "library.json";
+
+/// Tags that yt-dlp's `--embed-metadata` (and most taggers) use for the
+/// source URL of a file.
+const URL_TAGS: &[&str] = &["purl", "comment", "url", "description", "synopsis"];
+
+/// Index of video IDs to the files that contain them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+    /// Files per video ID, in the order they were added.
+    #[serde(default)]
+    videos: BTreeMap<String, Vec<PathBuf>>,
+}
+
+/// How a file already in the library was placed at a new path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reuse {
+    /// The target already was the library file.
+    Existing,
+    /// A hard link to the library file was created.
+    HardLink,
+    /// The file was copied (or reflinked) from the library file.
+    Copy,
+}
+
+impl std::fmt::Display for Reuse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reuse::Existing => write!(f, "already in place"),
+            Reuse::HardLink => write!(f, "hard-linked"),
+            Reuse::Copy => write!(f, "copied"),
+        }
+    }
+}
+
+/// Result of [`Library::scan`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanReport {
+    /// Media files found under the scanned directory.
+    pub scanned: usize,
+    /// Files whose video ID was found and that were not indexed yet.
+    pub added: usize,
+    /// Files that were already indexed.
+    pub known: usize,
+    /// Media files without a recognizable video ID.
+    pub unidentified: Vec<PathBuf>,
+    /// Index entries dropped because their file no longer exists.
+    pub removed: usize,
+}
+
+impl Library {
+    /// Returns the index path next to the configuration file at `config_path`.
+    pub fn path_beside(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(LIBRARY_FILE)
+    }
+
+    /// Loads the index at `path`. A missing file is an empty library.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::FileRead` if the file cannot be read and
+    /// `AppError::Other` if it is not a library index.
+    pub fn load(path: &Path) -> AppResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| AppError::file_read(path, e))?;
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::Other(format!(
+                "Library index {} is corrupt: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Writes the index to `path`, creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::DirectoryCreation` or `AppError::FileWrite` if the
+    /// index cannot be written.
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+            }
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Other(format!("Could not serialize library: {}", e)))?;
+
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let tmp = path.with_extension(format!(
+            "json.{}-{}.tmp",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp, content).map_err(|e| AppError::file_write(&tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| AppError::file_write(path, e))
+    }
+
+    /// Loads the index at `path`, lets `change` edit it and saves it if it
+    /// changed, returning what `change` returns.
+    ///
+    /// The whole update holds an exclusive lock on `library.json.lock`, so
+    /// concurrent downloads, in this process or another, don't lose each
+    /// other's entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock cannot be taken, the index cannot be
+    /// loaded or saved, or `change` fails (the index is then left as it was).
+    pub fn update<T>(path: &Path, change: impl FnOnce(&mut Self) -> AppResult<T>) -> AppResult<T> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+            }
+        }
+
+        let lock_path = path.with_extension("json.lock");
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| AppError::file_write(&lock_path, e))?;
+        lock.lock_exclusive()
+            .map_err(|e| AppError::file_write(&lock_path, e))?;
+
+        let mut library = Self::load(path)?;
+        let before = library.clone();
+        let value = change(&mut library)?;
+        if library != before {
+            library.save(path)?;
+        }
+
+        Ok(value)
+    }
+
+    /// Records that `path` holds `video_id`. Returns `false` if it was
+    /// already recorded.
+    pub fn add(&mut self, video_id: &str, path: &Path) -> bool {
+        let path = absolute(path);
+        let files = self.videos.entry(video_id.to_string()).or_default();
+
+        if files.contains(&path) {
+            false
+        } else {
+            files.push(path);
+            true
+        }
+    }
+
+    /// Returns every file recorded for `video_id`.
+    pub fn files(&self, video_id: &str) -> &[PathBuf] {
+        self.videos.get(video_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the first recorded file for `video_id` that still exists and
+    /// has the extension `ext`.
+    pub fn find(&self, video_id: &str, ext: &str) -> Option<&Path> {
+        self.files(video_id)
+            .iter()
+            .filter(|path| has_extension(path, ext))
+            .find(|path| path.is_file())
+            .map(PathBuf::as_path)
+    }
+
+    /// Places the library file `source` of `video_id` at `target`, like
+    /// [`link_or_copy`].
+    ///
+    /// A file already at `target` is replaced only if the library records
+    /// it under `video_id` (a stale or partial copy of the same video).
+    /// Anything else there, such as another video with a colliding title,
+    /// is left alone.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::FileWrite` if another file is at `target` or the
+    /// stale copy cannot be removed, and `AppError::DirectoryCreation` if
+    /// the target directory cannot be created.
+    pub fn link_or_copy(&self, video_id: &str, source: &Path, target: &Path) -> AppResult<Reuse> {
+        if target.exists()
+            && !same_file(source, target)
+            && self.files(video_id).contains(&absolute(target))
+        {
+            fs::remove_file(target).map_err(|e| AppError::file_write(target, e))?;
+        }
+
+        link_or_copy(source, target)
+    }
+
+    /// Returns the number of indexed videos.
+    pub fn len(&self) -> usize {
+        self.videos.len()
+    }
+
+    /// Returns `true` if no video is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.videos.is_empty()
+    }
+
+    /// Drops entries whose file no longer exists. Returns how many were dropped.
+    pub fn remove_missing(&mut self) -> usize {
+        let mut removed = 0;
+
+        for files in self.videos.values_mut() {
+            let before = files.len();
+            files.retain(|path| path.is_file());
+            removed += before - files.len();
+        }
+        self.videos.retain(|_, files| !files.is_empty());
+
+        removed
+    }
+
+    /// Indexes every media file under `dir`, recursively.
+    ///
+    /// The video ID of each file is read from its `.info.json` sidecar or,
+    /// when ffprobe is available, from the source URL in its embedded tags.
+    /// Entries whose files have been deleted are dropped at the same time.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::FileRead` if `dir` cannot be read.
+    pub fn scan(&mut self, dir: &Path) -> AppResult<ScanReport> {
+        let mut report = ScanReport {
+            removed: self.remove_missing(),
+            ..Default::default()
+        };
+
+        let mut files = Vec::new();
+        collect_media_files(dir, &mut files)?;
+        files.sort();
+
+        let probe = FFmpeg::is_probe_available();
+        for file in files {
+            report.scanned += 1;
+
+            let id = read_sidecar_id(&file).or_else(|| probe.then(|| read_tag_id(&file)).flatten());
+
+            match id {
+                Some(id) if self.add(&id, &file) => report.added += 1,
+                Some(_) => report.known += 1,
+                None => report.unidentified.push(file),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Places `source` at `target` without downloading it again.
+///
+/// Tries a hard link first and falls back to copying, for example when the
+/// two paths are on different filesystems. A file already at `target` is
+/// never overwritten; use [`Library::link_or_copy`] to replace one the
+/// library knows about.
+///
+/// # Errors
+///
+/// Returns `AppError::DirectoryCreation` or `AppError::FileWrite` if the
+/// target cannot be created or something else is already there.
+pub fn link_or_copy(source: &Path, target: &Path) -> AppResult<Reuse> {
+    if same_file(source, target) {
+        return Ok(Reuse::Existing);
+    }
+
+    if target.exists() {
+        return Err(AppError::file_write(
+            target,
+            io::Error::new(io::ErrorKind::AlreadyExists, "another file is in the way"),
+        ));
+    }
+
+    if let Some(parent) = target.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+        }
+    }
+
+    if fs::hard_link(source, target).is_ok() {
+        return Ok(Reuse::HardLink);
+    }
+
+    fs::copy(source, target).map_err(|e| AppError::file_write(target, e))?;
+    Ok(Reuse::Copy)
+}
+
+/// Reads the video ID from the yt-dlp sidecar next to `file`
+/// (`name.info.json` for `name.mp4`).
+pub fn read_sidecar_id(file: &Path) -> Option<String> {
+    let sidecar = file.with_extension("info.json");
+    let content = fs::read_to_string(sidecar).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    json.get("id")?
+        .as_str()
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+}
+
+/// Reads the video ID from the source URL in the embedded tags of `file`.
+fn read_tag_id(file: &Path) -> Option<String> {
+    let probe = FFmpeg::probe(file).ok()?;
+    id_from_probe(&probe)
+}
+
+/// Finds a YouTube URL among the format tags of ffprobe JSON output.
+fn id_from_probe(probe: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(probe).ok()?;
+    let tags = json.get("format")?.get("tags")?.as_object()?;
+
+    URL_TAGS.iter().find_map(|wanted| {
+        tags.iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(wanted))
+            .filter_map(|(_, value)| value.as_str())
+            .find_map(extract_video_id)
+    })
+}
+
+/// Returns `true` if `path` has a video or audio extension this tool writes.
+pub(crate) fn is_media_file(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    let ext = ext.to_lowercase();
+
+    VideoFormat::value_variants()
+        .iter()
+        .map(VideoFormat::extension)
+        .chain(
+            AudioFormat::value_variants()
+                .iter()
+                .map(AudioFormat::extension),
+        )
+        .any(|known| known == ext)
+}
+
+/// Collects every media file under `dir`, recursively.
+pub(crate) fn collect_media_files(dir: &Path, files: &mut Vec<PathBuf>) -> AppResult<()> {
+    let entries = fs::read_dir(dir).map_err(|e| AppError::file_read(dir, e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            collect_media_files(&path, files)?;
+        } else if file_type.is_file() && is_media_file(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    })
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(path: &Path, content: &str) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, content).unwrap();
+    }
+
+    // ============== Index Tests ==============
+
+    #[test]
+    fn test_add_deduplicates_paths() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.mp4");
+        touch(&file, "video");
+
+        let mut library = Library::default();
+        assert!(library.add("abc", &file));
+        assert!(!library.add("abc", &file));
+        assert_eq!(library.files("abc").len(), 1);
+        assert_eq!(library.len(), 1);
+    }
+
+    #[test]
+    fn test_find_matches_extension_and_existence() {
+        let dir = TempDir::new().unwrap();
+        let mp4 = dir.path().join("a.mp4");
+        let mp3 = dir.path().join("a.mp3");
+        touch(&mp3, "audio");
+
+        let mut library = Library::default();
+        library.add("abc", &mp4);
+        library.add("abc", &mp3);
+
+        assert_eq!(library.find("abc", "MP3"), Some(absolute(&mp3).as_path()));
+        assert!(library.find("abc", "mp4").is_none());
+        assert!(library.find("other", "mp3").is_none());
+    }
+
+    #[test]
+    fn test_remove_missing() {
+        let dir = TempDir::new().unwrap();
+        let kept = dir.path().join("kept.mp4");
+        touch(&kept, "video");
+
+        let mut library = Library::default();
+        library.add("kept", &kept);
+        library.add("gone", &dir.path().join("gone.mp4"));
+
+        assert_eq!(library.remove_missing(), 1);
+        assert_eq!(library.len(), 1);
+        assert!(library.files("gone").is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let index = dir.path().join("nested").join(LIBRARY_FILE);
+
+        let mut library = Library::default();
+        library.add("abc", Path::new("/videos/a.mp4"));
+        library.save(&index).unwrap();
+
+        assert_eq!(Library::load(&index).unwrap(), library);
+        let files: Vec<_> = fs::read_dir(index.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, vec![LIBRARY_FILE]);
+    }
+
+    #[test]
+    fn test_update_concurrent_keeps_every_entry() {
+        let dir = TempDir::new().unwrap();
+        let index = dir.path().join(LIBRARY_FILE);
+
+        let threads: Vec<_> = (0..8)
+            .map(|n| {
+                let index = index.clone();
+                std::thread::spawn(move || {
+                    for i in 0..5 {
+                        let path = PathBuf::from(format!("/videos/{}-{}.mp4", n, i));
+                        Library::update(&index, |library| {
+                            library.add(&format!("id{}-{}", n, i), &path);
+                            Ok(())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(Library::load(&index).unwrap().len(), 40);
+    }
+
+    #[test]
+    fn test_update_failure_keeps_index() {
+        let dir = TempDir::new().unwrap();
+        let index = dir.path().join(LIBRARY_FILE);
+        let mut library = Library::default();
+        library.add("abc", Path::new("/videos/a.mp4"));
+        library.save(&index).unwrap();
+
+        let result: AppResult<()> = Library::update(&index, |library| {
+            library.add("other", Path::new("/videos/b.mp4"));
+            Err(AppError::Other("failed".to_string()))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(Library::load(&index).unwrap(), library);
+    }
+
+    #[test]
+    fn test_load_missing_is_empty() {
+        let dir = TempDir::new().unwrap();
+        assert!(Library::load(&dir.path().join(LIBRARY_FILE))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_load_corrupt_fails() {
+        let dir = TempDir::new().unwrap();
+        let index = dir.path().join(LIBRARY_FILE);
+        touch(&index, "not json");
+
+        assert!(Library::load(&index).is_err());
+    }
+
+    #[test]
+    fn test_path_beside_config() {
+        assert_eq!(
+            Library::path_beside(Path::new("/etc/ytdl/config.toml")),
+            PathBuf::from("/etc/ytdl/library.json")
+        );
+    }
+
+    // ============== Scan Tests ==============
+
+    #[test]
+    fn test_read_sidecar_id() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("song.mp3");
+        touch(&file, "audio");
+        touch(
+            &dir.path().join("song.info.json"),
+            r#"{"id": "dQw4w9WgXcQ", "title": "Song"}"#,
+        );
+
+        assert_eq!(read_sidecar_id(&file).as_deref(), Some("dQw4w9WgXcQ"));
+        assert!(read_sidecar_id(&dir.path().join("other.mp3")).is_none());
+    }
+
+    #[test]
+    fn test_id_from_probe_tags() {
+        let probe = r#"{"format": {"tags": {
+            "title": "Song",
+            "PURL": "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+        }}}"#;
+        assert_eq!(id_from_probe(probe).as_deref(), Some("dQw4w9WgXcQ"));
+
+        let probe = r#"{"format": {"tags": {"comment": "ripped from a CD"}}}"#;
+        assert!(id_from_probe(probe).is_none());
+        assert!(id_from_probe("{}").is_none());
+    }
+
+    #[test]
+    fn test_is_media_file() {
+        assert!(is_media_file(Path::new("a.mp4")));
+        assert!(is_media_file(Path::new("a.FLAC")));
+        assert!(!is_media_file(Path::new("a.info.json")));
+        assert!(!is_media_file(Path::new("README")));
+    }
+
+    #[test]
+    fn test_scan_indexes_sidecars_recursively() {
+        let dir = TempDir::new().unwrap();
+        touch(&dir.path().join("a/one.mp4"), "video");
+        touch(&dir.path().join("a/one.info.json"), r#"{"id": "one"}"#);
+        touch(&dir.path().join("b/two.mp3"), "audio");
+        touch(&dir.path().join("b/two.info.json"), r#"{"id": "two"}"#);
+        touch(&dir.path().join("b/notes.txt"), "text");
+
+        let mut library = Library::default();
+        let report = library.scan(dir.path()).unwrap();
+
+        assert_eq!(report.scanned, 2);
+        assert_eq!(report.added, 2);
+        assert!(library.find("one", "mp4").is_some());
+        assert!(library.find("two", "mp3").is_some());
+
+        let again = library.scan(dir.path()).unwrap();
+        assert_eq!(again.added, 0);
+        assert_eq!(again.known, 2);
+    }
+
+    #[test]
+    fn test_scan_missing_dir_fails() {
+        let dir = TempDir::new().unwrap();
+        let mut library = Library::default();
+
+        assert!(library.scan(&dir.path().join("missing")).is_err());
+    }
+
+    // ============== Reuse Tests ==============
+
+    #[test]
+    fn test_link_or_copy_creates_target() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.mp4");
+        let target = dir.path().join("playlist").join("copy.mp4");
+        touch(&source, "video");
+
+        let reuse = link_or_copy(&source, &target).unwrap();
+
+        assert!(matches!(reuse, Reuse::HardLink | Reuse::Copy));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "video");
+    }
+
+    #[test]
+    fn test_link_or_copy_same_file() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.mp4");
+        touch(&source, "video");
+
+        assert_eq!(link_or_copy(&source, &source).unwrap(), Reuse::Existing);
+        assert_eq!(fs::read_to_string(&source).unwrap(), "video");
+    }
+
+    #[test]
+    fn test_link_or_copy_keeps_existing_target() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.mp4");
+        let target = dir.path().join("target.mp4");
+        touch(&source, "video");
+        touch(&target, "mine");
+
+        assert!(link_or_copy(&source, &target).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "mine");
+    }
+
+    #[test]
+    fn test_library_link_or_copy_replaces_stale_copy() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.mp4");
+        let target = dir.path().join("target.mp4");
+        touch(&source, "video");
+        touch(&target, "partial");
+
+        let mut library = Library::default();
+        library.add("abc", &source);
+        library.add("abc", &target);
+
+        library.link_or_copy("abc", &source, &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "video");
+    }
+
+    #[test]
+    fn test_library_link_or_copy_keeps_other_video() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.mp4");
+        let target = dir.path().join("target.mp4");
+        touch(&source, "video");
+        touch(&target, "other video");
+
+        let mut library = Library::default();
+        library.add("abc", &source);
+        library.add("other", &target);
+
+        assert!(library.link_or_copy("abc", &source, &target).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "other video");
+    }
+}
@@ -0,0 +1,466 @@
+//! Extended M3U playlists for downloaded YouTube playlists.
+//!
+//! After `ytdl playlist` downloads a playlist, a `<playlist title>.m3u8` file
+//! is written to the output directory so the videos can be played in order
+//! in any media player. Entries follow the original playlist positions, carry
+//! an `#EXTINF` line with the duration and title, and point at the files with
+//! paths relative to the playlist file. Videos that failed are left out.
+//!
+//! The file is rewritten on every run. Entries from earlier runs are kept as
+//! long as their file still exists and the video is still in the playlist,
+//! so downloading new uploads later, or another `--items` selection, adds to
+//! the file instead of replacing it. To recognize them, every entry is
+//! preceded by a `#YTDL-ID:` comment, which players ignore.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use rust_yt_downloader::m3u::{M3uEntry, M3uPlaylist};
+//!
+//! let mut playlist = M3uPlaylist::new("Talks");
+//! playlist.update([M3uEntry {
+//!     index: 1,
+//!     video_id: "abc123".to_string(),
+//!     title: "Opening keynote".to_string(),
+//!     duration: Some(754),
+//!     path: PathBuf::from("/videos/Opening keynote.mp4"),
+//! }]);
+//!
+//! let text = playlist.render(&PathBuf::from("/videos"));
+//! assert!(text.contains("#EXTINF:754,Opening keynote\nOpening keynote.mp4"));
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::AppResult;
+use crate::utils::sanitize_filename;
+use crate::youtube::PlaylistEntry;
+
+/// Extension of the playlist files.
+pub const EXTENSION: &str = "m3u8";
+
+/// Comment holding the video ID of the entry that follows.
+const ID_TAG: &str = "#YTDL-ID:";
+
+/// One video of an M3U playlist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct M3uEntry {
+    /// Position in the YouTube playlist, starting at 1
+    pub index: usize,
+    /// Video ID
+    pub video_id: String,
+    /// Title shown by players
+    pub title: String,
+    /// Duration in seconds, when known
+    pub duration: Option<u64>,
+    /// Location of the downloaded file
+    pub path: PathBuf,
+}
+
+/// An extended M3U playlist, ordered by playlist position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct M3uPlaylist {
+    /// Title written in the `#PLAYLIST` tag
+    pub title: String,
+    entries: Vec<M3uEntry>,
+}
+
+impl M3uPlaylist {
+    /// Creates an empty playlist.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Reads a playlist written by [`M3uPlaylist::save()`].
+    ///
+    /// A missing file gives an empty playlist. Entries without a
+    /// `#YTDL-ID:` comment are dropped, and the positions of the others are
+    /// only known again after [`M3uPlaylist::retain_listed()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load(path: &Path) -> AppResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let text = fs::read_to_string(path)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut playlist = Self::default();
+        let mut video_id = None;
+        let mut info = None;
+
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(title) = line.strip_prefix("#PLAYLIST:") {
+                playlist.title = title.to_string();
+            } else if let Some(id) = line.strip_prefix(ID_TAG) {
+                video_id = Some(id.trim().to_string());
+            } else if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+                info = Some(parse_extinf(extinf));
+            } else if !line.starts_with('#') {
+                let (duration, title) = info.take().unwrap_or_default();
+                if let Some(video_id) = video_id.take() {
+                    playlist.entries.push(M3uEntry {
+                        index: 0,
+                        video_id,
+                        title,
+                        duration,
+                        path: dir.join(line),
+                    });
+                }
+            }
+        }
+
+        Ok(playlist)
+    }
+
+    /// Returns the entries, ordered by position.
+    pub fn entries(&self) -> &[M3uEntry] {
+        &self.entries
+    }
+
+    /// Adds entries, replacing any with the same video ID.
+    pub fn update(&mut self, entries: impl IntoIterator<Item = M3uEntry>) {
+        for entry in entries {
+            self.entries.retain(|e| e.video_id != entry.video_id);
+            self.entries.push(entry);
+        }
+        self.entries.sort_by_key(|entry| entry.index);
+    }
+
+    /// Keeps the entries whose video is still in the playlist and whose file
+    /// still exists, renumbered from the current playlist positions.
+    pub fn retain_listed(&mut self, listing: &[PlaylistEntry]) {
+        let positions: HashMap<&str, usize> = listing
+            .iter()
+            .map(|entry| (entry.video_id.as_str(), entry.index))
+            .collect();
+
+        self.entries
+            .retain_mut(|entry| match positions.get(entry.video_id.as_str()) {
+                Some(&index) if entry.path.exists() => {
+                    entry.index = index;
+                    true
+                }
+                _ => false,
+            });
+        self.entries.sort_by_key(|entry| entry.index);
+    }
+
+    /// Renders the playlist with paths relative to `dir`, the directory the
+    /// file is written to.
+    pub fn render(&self, dir: &Path) -> String {
+        let mut text = String::from("#EXTM3U\n");
+        if !self.title.is_empty() {
+            text.push_str(&format!("#PLAYLIST:{}\n", one_line(&self.title)));
+        }
+
+        for entry in &self.entries {
+            let duration = entry
+                .duration
+                .map_or_else(|| "-1".to_string(), |d| d.to_string());
+            text.push_str(&format!("{}{}\n", ID_TAG, entry.video_id));
+            text.push_str(&format!(
+                "#EXTINF:{},{}\n",
+                duration,
+                one_line(&entry.title)
+            ));
+            text.push_str(&format!("{}\n", relative_path(&entry.path, dir)));
+        }
+
+        text
+    }
+
+    /// Writes the playlist to `path`, with paths relative to its directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        if !dir.as_os_str().is_empty() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.render(dir))?;
+        Ok(())
+    }
+}
+
+/// Returns where the playlist file of a playlist titled `title` goes.
+pub fn playlist_path(dir: &Path, title: &str) -> PathBuf {
+    dir.join(format!("{}.{}", sanitize_filename(title), EXTENSION))
+}
+
+/// Rewrites the playlist file at `path` with the videos just downloaded and
+/// the ones still present from earlier runs.
+///
+/// `listing` is the whole playlist, used to order the entries and to drop
+/// videos that were removed from it. No file is created when there is
+/// nothing to list.
+///
+/// Returns the number of entries written.
+///
+/// # Errors
+///
+/// Returns an error if the existing file cannot be read or the new one
+/// cannot be written.
+pub fn write_playlist(
+    path: &Path,
+    title: &str,
+    listing: &[PlaylistEntry],
+    downloaded: Vec<M3uEntry>,
+) -> AppResult<usize> {
+    let mut playlist = M3uPlaylist::load(path)?;
+    playlist.title = title.to_string();
+    playlist.update(downloaded);
+    playlist.retain_listed(listing);
+
+    if playlist.entries.is_empty() && !path.exists() {
+        return Ok(0);
+    }
+    playlist.save(path)?;
+    Ok(playlist.entries.len())
+}
+
+/// Parses the `duration,title` part of an `#EXTINF` line.
+fn parse_extinf(extinf: &str) -> (Option<u64>, String) {
+    let (duration, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+    (duration.trim().parse().ok(), title.to_string())
+}
+
+/// Keeps a title on one line, as M3U requires.
+fn one_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+/// Returns `path` relative to `dir` with `/` separators, or the path as is
+/// when they share no root.
+fn relative_path(path: &Path, dir: &Path) -> String {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+
+    let to: Vec<Component> = path.components().collect();
+    let from: Vec<Component> = dir.components().collect();
+    let common = to.iter().zip(&from).take_while(|(a, b)| a == b).count();
+
+    if common == 0 && path.is_absolute() {
+        return path.to_string_lossy().into_owned();
+    }
+
+    let parents = (common..from.len()).map(|_| "..".to_string());
+    let rest = to[common..]
+        .iter()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned());
+    parents.chain(rest).collect::<Vec<_>>().join("/")
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(index: usize, id: &str, path: PathBuf) -> M3uEntry {
+        M3uEntry {
+            index,
+            video_id: id.to_string(),
+            title: format!("Video {}", id),
+            duration: Some(60 * index as u64),
+            path,
+        }
+    }
+
+    fn listing(ids: &[&str]) -> Vec<PlaylistEntry> {
+        ids.iter()
+            .enumerate()
+            .map(|(position, id)| PlaylistEntry {
+                index: position + 1,
+                video_id: id.to_string(),
+                playlist_id: "PL1".to_string(),
+                playlist_title: "Talks".to_string(),
+                playlist_count: ids.len(),
+            })
+            .collect()
+    }
+
+    fn touch(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "data").unwrap();
+        path
+    }
+
+    // ============== Render Tests ==============
+
+    #[test]
+    fn test_render_orders_by_index() {
+        let mut playlist = M3uPlaylist::new("Talks");
+        playlist.update([
+            entry(3, "ccc", PathBuf::from("/v/c.mp4")),
+            entry(1, "aaa", PathBuf::from("/v/a.mp4")),
+        ]);
+
+        assert_eq!(
+            playlist.render(Path::new("/v")),
+            "#EXTM3U\n#PLAYLIST:Talks\n\
+             #YTDL-ID:aaa\n#EXTINF:60,Video aaa\na.mp4\n\
+             #YTDL-ID:ccc\n#EXTINF:180,Video ccc\nc.mp4\n"
+        );
+    }
+
+    #[test]
+    fn test_render_unknown_duration_and_multiline_title() {
+        let mut playlist = M3uPlaylist::new("");
+        playlist.update([M3uEntry {
+            duration: None,
+            title: "Line one\nline two".to_string(),
+            ..entry(1, "aaa", PathBuf::from("/v/a.mp4"))
+        }]);
+
+        let text = playlist.render(Path::new("/v"));
+        assert!(!text.contains("#PLAYLIST"));
+        assert!(text.contains("#EXTINF:-1,Line one line two\n"));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("/v/a.mp4"), Path::new("/v")),
+            "a.mp4"
+        );
+        assert_eq!(
+            relative_path(Path::new("/v/Channel/a.mp4"), Path::new("/v")),
+            "Channel/a.mp4"
+        );
+        assert_eq!(
+            relative_path(Path::new("/music/a.m4a"), Path::new("/v/lists")),
+            "../../music/a.m4a"
+        );
+    }
+
+    #[test]
+    fn test_update_replaces_same_video() {
+        let mut playlist = M3uPlaylist::new("Talks");
+        playlist.update([entry(1, "aaa", PathBuf::from("/v/old.mp4"))]);
+        playlist.update([entry(1, "aaa", PathBuf::from("/v/new.mp4"))]);
+
+        assert_eq!(playlist.entries().len(), 1);
+        assert_eq!(playlist.entries()[0].path, PathBuf::from("/v/new.mp4"));
+    }
+
+    #[test]
+    fn test_playlist_path() {
+        assert_eq!(
+            playlist_path(Path::new("/v"), "Rust: Talks"),
+            PathBuf::from(format!("/v/{}.m3u8", sanitize_filename("Rust: Talks")))
+        );
+    }
+
+    // ============== File Tests ==============
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let file = touch(dir.path(), "Channel/a.mp4");
+        let path = dir.path().join("Talks.m3u8");
+
+        let mut playlist = M3uPlaylist::new("Talks");
+        playlist.update([entry(1, "aaa", file.clone())]);
+        playlist.save(&path).unwrap();
+
+        let loaded = M3uPlaylist::load(&path).unwrap();
+        assert_eq!(loaded.title, "Talks");
+        assert_eq!(loaded.entries().len(), 1);
+        assert_eq!(loaded.entries()[0].video_id, "aaa");
+        assert_eq!(loaded.entries()[0].title, "Video aaa");
+        assert_eq!(loaded.entries()[0].duration, Some(60));
+        assert_eq!(
+            fs::canonicalize(&loaded.entries()[0].path).unwrap(),
+            fs::canonicalize(&file).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_missing_and_foreign_entries() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("list.m3u8");
+        assert_eq!(M3uPlaylist::load(&path).unwrap(), M3uPlaylist::default());
+
+        fs::write(&path, "#EXTM3U\n#EXTINF:10,Hand-made\nother.mp4\n").unwrap();
+        assert!(M3uPlaylist::load(&path).unwrap().entries().is_empty());
+    }
+
+    #[test]
+    fn test_write_playlist_keeps_earlier_entries() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Talks.m3u8");
+        let a = touch(dir.path(), "a.mp4");
+        let b = touch(dir.path(), "b.mp4");
+        let c = touch(dir.path(), "c.mp4");
+
+        let first = listing(&["aaa", "bbb"]);
+        let written =
+            write_playlist(&path, "Talks", &first, vec![entry(2, "bbb", b.clone())]).unwrap();
+        assert_eq!(written, 1);
+
+        // A new upload at the top shifts everything down; "aaa" was removed
+        let second = listing(&["ccc", "aaa", "bbb"]);
+        write_playlist(&path, "Talks", &second, vec![entry(1, "ccc", c)]).unwrap();
+        fs::remove_file(&a).unwrap();
+
+        let loaded = M3uPlaylist::load(&path).unwrap();
+        let ids: Vec<&str> = loaded
+            .entries()
+            .iter()
+            .map(|e| e.video_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["ccc", "bbb"]);
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.find("c.mp4").unwrap() < text.find("b.mp4").unwrap());
+    }
+
+    #[test]
+    fn test_write_playlist_drops_missing_and_unlisted() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Talks.m3u8");
+        let a = touch(dir.path(), "a.mp4");
+        let b = touch(dir.path(), "b.mp4");
+
+        write_playlist(
+            &path,
+            "Talks",
+            &listing(&["aaa", "bbb"]),
+            vec![entry(1, "aaa", a.clone()), entry(2, "bbb", b)],
+        )
+        .unwrap();
+
+        fs::remove_file(&a).unwrap();
+        let written = write_playlist(&path, "Talks", &listing(&["aaa"]), Vec::new()).unwrap();
+        assert_eq!(written, 0);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "#EXTM3U\n#PLAYLIST:Talks\n"
+        );
+    }
+
+    #[test]
+    fn test_write_playlist_without_entries_creates_nothing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Talks.m3u8");
+
+        let written = write_playlist(&path, "Talks", &listing(&["aaa"]), Vec::new()).unwrap();
+        assert_eq!(written, 0);
+        assert!(!path.exists());
+    }
+}
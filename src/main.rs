@@ -52,26 +52,44 @@
 //! - **FFmpeg**: Required for audio extraction and format conversion
 //! - **yt-dlp**: Used internally for YouTube API access
 
-mod cli;
-mod config;
-mod downloader;
-mod error;
-mod media;
-mod progress;
-mod utils;
-mod youtube;
-
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
 
+use chrono::Local;
 use clap::Parser;
 use colored::Colorize;
 
-use cli::{Cli, Commands, ConfigCommands};
-use config::Config;
-use downloader::{DownloadOptions, Downloader};
-use error::{AppError, AppResult};
-use progress::messages;
-use youtube::YtDlpClient;
+use rust_yt_downloader::batch::{BatchFailure, BatchReport, BatchRunner};
+use rust_yt_downloader::cli::{
+    self, Cli, Commands, ConfigCommands, FeedCommands, HistoryCommands, LibraryCommands,
+    SubscribeCommands,
+};
+use rust_yt_downloader::config::{validate_file, validate_str, Config, ConfigLoader};
+use rust_yt_downloader::downloader::{
+    playlist_folder, DownloadOptions, DownloadResult, Downloader, OptionOverrides,
+};
+use rust_yt_downloader::error::{AppError, AppResult};
+use rust_yt_downloader::feed::{build_feed, FeedOptions};
+use rust_yt_downloader::filter::{Filter, Verdict};
+use rust_yt_downloader::history::{History, HistoryEntry, HistoryQuery, Outcome, PruneOptions};
+use rust_yt_downloader::library::Library;
+use rust_yt_downloader::m3u::{self, M3uEntry};
+use rust_yt_downloader::mirror::{move_files, removed_files, Snapshot, REMOVED_DIR};
+use rust_yt_downloader::plan::{Plan, PlanFormat};
+use rust_yt_downloader::progress::messages;
+use rust_yt_downloader::server::Server;
+use rust_yt_downloader::subscriptions::{
+    video_url, Subscription, SubscriptionFilters, Subscriptions, SyncJob, SyncUpdate,
+};
+use rust_yt_downloader::utils;
+use rust_yt_downloader::youtube::{
+    ItemOrder, LiveStatus, PlaylistEntry, PlaylistInfo, PlaylistSelection, VideoInfo, VideoSummary,
+    YtDlpClient,
+};
 
 /// Application entry point.
 ///
@@ -94,51 +112,70 @@ async fn main() {
 /// Returns an error if any command handler fails.
 async fn run() -> AppResult<()> {
     let cli = Cli::parse();
+    let loader = ConfigLoader::new()
+        .with_config_path(cli.config)
+        .with_profile(cli.profile);
+    let dry_run = cli.dry_run;
+
+    if dry_run.is_some()
+        && !matches!(
+            cli.command,
+            Commands::Download(_) | Commands::Audio(_) | Commands::Playlist(_) | Commands::Sync(_)
+        )
+    {
+        return Err(AppError::invalid_arg(
+            "--dry-run",
+            "only download, audio, playlist and sync can be simulated",
+        ));
+    }
 
     match cli.command {
         Commands::Download(args) => {
-            handle_download(
-                &args.common.url,
-                args.common.output,
-                args.quality,
-                args.format,
-                args.common.silence,
-                args.common.verbose,
-            )
-            .await?;
+            handle_download(args, &loader, dry_run).await?;
         }
 
         Commands::Audio(args) => {
-            handle_audio(
-                &args.common.url,
-                args.common.output,
-                args.format,
-                args.common.silence,
-                args.common.verbose,
-            )
-            .await?;
+            handle_audio(args, &loader, dry_run).await?;
         }
 
         Commands::Playlist(args) => {
-            handle_playlist(
-                &args.urls,
-                args.output,
-                args.quality,
-                args.format,
-                args.audio_only,
-                args.audio_format,
-                args.silence,
-                args.verbose,
-            )
-            .await?;
+            handle_playlist(args, &loader, dry_run).await?;
         }
 
         Commands::Info(args) => {
-            handle_info(&args.url).await?;
+            handle_info(args, &loader).await?;
+        }
+
+        Commands::Formats(args) => {
+            handle_formats(args).await?;
         }
 
         Commands::Config { command } => {
-            handle_config(command)?;
+            handle_config(command, &loader)?;
+        }
+
+        Commands::History(args) => {
+            handle_history(args, &loader)?;
+        }
+
+        Commands::Library { command } => {
+            handle_library(command, &loader)?;
+        }
+
+        Commands::Feed { command } => {
+            handle_feed(command)?;
+        }
+
+        Commands::Subscribe { command } => {
+            handle_subscribe(command, &loader)?;
+        }
+
+        Commands::Sync(args) => {
+            handle_sync(args, &loader, dry_run).await?;
+        }
+
+        Commands::Serve(args) => {
+            handle_serve(args, &loader).await?;
         }
     }
 
@@ -147,158 +184,647 @@ async fn run() -> AppResult<()> {
 
 /// Handles the `download` command for video downloads.
 ///
-/// Downloads a complete YouTube video with both video and audio streams,
-/// merging them into the specified format.
+/// Downloads complete YouTube videos with both video and audio streams,
+/// merging them into the specified format. Every URL from the command line
+/// and from `--batch-file` goes through the same retry and summary logic.
+/// With `--dry-run`, prints the plan instead.
 async fn handle_download(
-    url: &str,
-    output: PathBuf,
-    quality: cli::VideoQuality,
-    format: cli::VideoFormat,
-    silence: bool,
-    verbose: bool,
+    args: cli::DownloadArgs,
+    loader: &ConfigLoader,
+    dry_run: Option<PlanFormat>,
 ) -> AppResult<()> {
-    let config = Config::load()?;
+    let urls = args.common.resolve_urls()?;
+    let (urls, unchecked) = select_batch(urls, &args.common);
+    let config = loader.load()?;
+    let silence = args.common.silence || dry_run == Some(PlanFormat::Json);
 
     let options = DownloadOptions::from_config(&config)
-        .with_output_dir(output)
-        .with_quality(quality)
-        .with_video_format(format)
+        .with_max_filesize(args.sizes.max_filesize)
+        .with_live_from_start(args.live.live_from_start)
+        .with_wait_for_video(args.live.wait_for_video)
+        .with_live_duration(args.live.live_duration)
         .with_silence(silence)
-        .with_verbose(verbose);
+        .with_verbose(args.common.verbose);
 
-    let downloader = Downloader::with_options(options);
-    let result = downloader.download(url).await?;
+    let flags = OptionOverrides {
+        output_dir: args.common.output,
+        quality: args.quality,
+        format_id: args.format_id,
+        video_format: args.format,
+        write_info_json: args.sidecars.write_info_json.then_some(true),
+        write_nfo: args.sidecars.write_nfo.then_some(true),
+        ..args.preference.into()
+    };
 
-    if !silence {
-        println!();
-        messages::success(&format!("Downloaded: {}", result.file_path.display()));
-        messages::info(&format!("Size: {}", utils::format_bytes(result.file_size)));
+    let downloader = Downloader::with_options(options)
+        .with_rules(config.rules)
+        .with_flags(flags)
+        .with_library(Library::path_beside(&loader.user_path()?))
+        .with_budget(args.sizes.budget())
+        .with_explain(args.common.explain);
+
+    if let Some(format) = dry_run {
+        return plan_batch(&downloader, &urls, &unchecked).finish(format);
     }
 
-    Ok(())
+    let runner = BatchRunner::new(downloader.options().retry_attempts).with_silence(silence);
+
+    let mut report = runner
+        .run(&urls, |url| {
+            let downloader = &downloader;
+            async move {
+                let result = downloader.download(&url).await?;
+                print_download_result(&result, silence);
+                Ok(result)
+            }
+        })
+        .await;
+    report.failed.extend(unchecked);
+
+    record_history(&report, loader);
+    finish_batch(report, silence)
 }
 
 /// Handles the `audio` command for audio-only downloads.
 ///
-/// Extracts and downloads only the audio stream, converting it to the
-/// specified format using FFmpeg.
+/// Extracts and downloads only the audio stream of every URL, converting it
+/// to the specified format using FFmpeg. With `--dry-run`, prints the plan
+/// instead.
 async fn handle_audio(
-    url: &str,
-    output: PathBuf,
-    format: cli::AudioFormat,
-    silence: bool,
-    verbose: bool,
+    args: cli::AudioArgs,
+    loader: &ConfigLoader,
+    dry_run: Option<PlanFormat>,
 ) -> AppResult<()> {
-    let config = Config::load()?;
+    let urls = args.common.resolve_urls()?;
+    let (urls, unchecked) = select_batch(urls, &args.common);
+    let config = loader.load()?;
+    let silence = args.common.silence || dry_run == Some(PlanFormat::Json);
 
     let options = DownloadOptions::from_config(&config)
-        .with_output_dir(output)
         .with_audio_only(true)
-        .with_audio_format(format)
+        .with_max_filesize(args.sizes.max_filesize)
+        .with_live_from_start(args.live.live_from_start)
+        .with_wait_for_video(args.live.wait_for_video)
+        .with_live_duration(args.live.live_duration)
         .with_silence(silence)
-        .with_verbose(verbose);
+        .with_verbose(args.common.verbose);
 
-    let downloader = Downloader::with_options(options);
-    let result = downloader.download_audio(url).await?;
+    let flags = OptionOverrides {
+        output_dir: args.common.output,
+        audio_format: args.format,
+        write_info_json: args.sidecars.write_info_json.then_some(true),
+        write_nfo: args.sidecars.write_nfo.then_some(true),
+        ..Default::default()
+    };
 
-    if !silence {
-        println!();
-        messages::success(&format!("Downloaded: {}", result.file_path.display()));
-        messages::info(&format!("Size: {}", utils::format_bytes(result.file_size)));
+    let downloader = Downloader::with_options(options)
+        .with_rules(config.rules)
+        .with_flags(flags)
+        .with_library(Library::path_beside(&loader.user_path()?))
+        .with_budget(args.sizes.budget())
+        .with_explain(args.common.explain);
+
+    if let Some(format) = dry_run {
+        return plan_batch(&downloader, &urls, &unchecked).finish(format);
     }
 
-    Ok(())
+    let runner = BatchRunner::new(downloader.options().retry_attempts).with_silence(silence);
+
+    let mut report = runner
+        .run(&urls, |url| {
+            let downloader = &downloader;
+            async move {
+                let result = downloader.download_audio(&url).await?;
+                print_download_result(&result, silence);
+                Ok(result)
+            }
+        })
+        .await;
+    report.failed.extend(unchecked);
+
+    record_history(&report, loader);
+    finish_batch(report, silence)
 }
 
 /// Handles the `playlist` command for downloading multiple videos.
 ///
-/// Downloads all videos from one or more playlists, with support for
-/// both video and audio-only modes. Continues downloading even if some
-/// videos fail, reporting a final summary.
+/// Enumerates each playlist, applies `--items`, `--reverse`, `--shuffle`
+/// and `--filter`, then downloads the selected videos one by one. Continues
+/// downloading even if some videos fail, reporting a final summary. With
+/// `--dry-run`, prints the plan instead.
 async fn handle_playlist(
-    urls: &[String],
-    output: PathBuf,
-    quality: cli::VideoQuality,
-    format: cli::VideoFormat,
-    audio_only: bool,
-    audio_format: cli::AudioFormat,
-    silence: bool,
-    verbose: bool,
+    args: cli::PlaylistArgs,
+    loader: &ConfigLoader,
+    dry_run: Option<PlanFormat>,
 ) -> AppResult<()> {
-    let config = Config::load()?;
+    let config = loader.load()?;
+    let urls = args.urls;
+    let audio_only = args.audio_only;
+    let silence = args.silence || dry_run == Some(PlanFormat::Json);
+    let verbose = args.verbose;
+    let filter: Filter = args.filter.into_iter().collect();
+
+    let order = if args.shuffle {
+        let seed = args.seed.unwrap_or_else(|| {
+            let seed = random_seed();
+            if !silence {
+                messages::info(&format!(
+                    "Shuffling with seed {} (pass --seed {} to repeat this order)",
+                    seed, seed
+                ));
+            }
+            seed
+        });
+        ItemOrder::Shuffle(seed)
+    } else if args.reverse {
+        ItemOrder::Reverse
+    } else {
+        ItemOrder::Playlist
+    };
+    let selection = PlaylistSelection::new()
+        .with_items(args.items)
+        .with_order(order);
 
     let options = DownloadOptions::from_config(&config)
-        .with_output_dir(output)
-        .with_quality(quality)
-        .with_video_format(format)
         .with_audio_only(audio_only)
-        .with_audio_format(audio_format)
+        .with_playlist_folders(args.playlist_folder)
+        .with_max_filesize(args.sizes.max_filesize)
         .with_silence(silence)
         .with_verbose(verbose);
 
-    let downloader = Downloader::with_options(options);
+    let flags = OptionOverrides {
+        output_dir: args.output,
+        quality: args.quality,
+        video_format: args.format,
+        audio_format: args.audio_format,
+        write_info_json: args.sidecars.write_info_json.then_some(true),
+        write_nfo: args.sidecars.write_nfo.then_some(true),
+        ..args.preference.into()
+    };
+
+    let downloader = Downloader::with_options(options)
+        .with_rules(config.rules)
+        .with_flags(flags)
+        .with_library(Library::path_beside(&loader.user_path()?))
+        .with_budget(args.sizes.budget())
+        .with_explain(args.explain);
+    let runner = BatchRunner::new(downloader.options().retry_attempts).with_silence(silence);
+    let client = YtDlpClient::new();
+
+    let mut report = BatchReport {
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+    };
+    let mut plan = Plan::default();
 
-    let mut success_count = 0;
-    let mut error_count = 0;
+    for url in urls {
+        let (playlist, summaries) = match client.get_playlist_entries(&url) {
+            Ok(listing) => listing,
+            Err(error) => {
+                messages::error(&format!("{}: {}", url, error));
+                report.failed.push(BatchFailure {
+                    url,
+                    error,
+                    attempts: 1,
+                });
+                continue;
+            }
+        };
+
+        let unavailable = if args.mirror {
+            let dir = &downloader.options().output_dir;
+            match mirror_playlist(
+                &playlist,
+                &summaries,
+                dir,
+                loader,
+                args.move_removed,
+                dry_run.is_some(),
+                silence,
+            ) {
+                Ok(unavailable) => unavailable,
+                Err(error) => {
+                    messages::error(&format!("{}: {}", url, error));
+                    report.failed.push(BatchFailure {
+                        url,
+                        error,
+                        attempts: 1,
+                    });
+                    continue;
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        let mut summaries: HashMap<String, VideoSummary> = summaries
+            .into_iter()
+            .map(|summary| (summary.id.clone(), summary))
+            .collect();
+        let listing = playlist.entries();
+        let mut entries = match selection.apply(listing.clone()) {
+            Ok(entries) => entries,
+            Err(error) => {
+                messages::error(&format!("{}: {}", url, error));
+                report.failed.push(BatchFailure {
+                    url,
+                    error,
+                    attempts: 1,
+                });
+                continue;
+            }
+        };
+        entries.retain(|entry| !unavailable.contains(&entry.video_id));
+        let videos = entries
+            .iter()
+            .map(|entry| {
+                let summary = summaries
+                    .remove(&entry.video_id)
+                    .unwrap_or_else(|| VideoSummary::new(&entry.video_id));
+                (entry.url(), summary)
+            })
+            .collect();
+        let (videos, unchecked) = filter_videos(&filter, videos, verbose);
+        report.failed.extend(unchecked);
+
+        let positions: HashMap<String, PlaylistEntry> = entries
+            .into_iter()
+            .map(|entry| (entry.url(), entry))
+            .collect();
+
+        if dry_run.is_some() {
+            for (url, _) in &videos {
+                let downloader = downloader.for_playlist_entry(positions[url].clone());
+                match downloader.plan(url) {
+                    Ok(planned) => plan.downloads.push(planned),
+                    Err(error) => plan.push_failure(url.as_str(), error),
+                }
+            }
+            continue;
+        }
+
+        if args.playlist_folder {
+            match downloader.renumber_playlist(&listing) {
+                Ok(renamed) if verbose => {
+                    for (from, to) in renamed {
+                        messages::info(&format!(
+                            "Renumbered {} -> {}",
+                            from.display(),
+                            to.display()
+                        ));
+                    }
+                }
+                Ok(renamed) if !silence && !renamed.is_empty() => messages::info(&format!(
+                    "Renumbered {} files that moved in the playlist",
+                    renamed.len()
+                )),
+                Ok(_) => {}
+                Err(e) => messages::warning(&format!("Could not renumber files: {}", e)),
+            }
+        }
 
-    for (index, url) in urls.iter().enumerate() {
         if !silence {
             messages::info(&format!(
-                "Processing playlist {} of {}...",
-                index + 1,
-                urls.len()
+                "{}: downloading {} of {} videos",
+                playlist.title,
+                videos.len(),
+                playlist.len()
             ));
         }
 
-        let result = if audio_only {
-            downloader.download_audio(url).await
-        } else {
-            downloader.download(url).await
-        };
+        let durations: HashMap<String, Option<u64>> = videos
+            .iter()
+            .map(|(url, summary)| (url.clone(), summary.duration))
+            .collect();
+        let video_urls: Vec<String> = videos.into_iter().map(|(url, _)| url).collect();
+
+        let downloads = runner
+            .run(&video_urls, |url| {
+                let downloader = downloader.for_playlist_entry(positions[&url].clone());
+                async move {
+                    let result = if audio_only {
+                        downloader.download_audio(&url).await?
+                    } else {
+                        downloader.download(&url).await?
+                    };
+                    if verbose {
+                        messages::success(&format!("Downloaded: {}", result.file_path.display()));
+                    }
+                    Ok(result)
+                }
+            })
+            .await;
+
+        if !args.no_m3u {
+            let downloaded = downloads
+                .succeeded
+                .iter()
+                .map(|(url, result)| M3uEntry {
+                    index: positions[url].index,
+                    video_id: positions[url].video_id.clone(),
+                    title: result.video_title.clone(),
+                    duration: durations[url],
+                    path: result.file_path.clone(),
+                })
+                .collect();
+            let mut dir = downloader.options().output_dir.clone();
+            if args.playlist_folder {
+                dir.push(playlist_folder(&playlist.title, &playlist.id));
+            }
+            let path = m3u::playlist_path(&dir, &playlist.title);
+            match m3u::write_playlist(&path, &playlist.title, &listing, downloaded) {
+                Ok(0) => {}
+                Ok(count) if !silence => messages::info(&format!(
+                    "Playlist file: {} ({} videos)",
+                    path.display(),
+                    count
+                )),
+                Ok(_) => {}
+                Err(e) => messages::warning(&format!("Could not write {}: {}", path.display(), e)),
+            }
+        }
+
+        report.succeeded.extend(downloads.succeeded);
+        report.failed.extend(downloads.failed);
+    }
+
+    if let Some(format) = dry_run {
+        for failure in &report.failed {
+            plan.push_failure(failure.url.as_str(), &failure.error);
+        }
+        return plan.finish(format);
+    }
+
+    record_history(&report, loader);
+
+    if !silence {
+        report.print_summary("Playlist");
+    }
+
+    if report.succeeded.is_empty() && !report.failed.is_empty() {
+        return Err(AppError::Other("All downloads failed".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Returns a seed for `--shuffle` when none was given.
+fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default()
+        ^ u64::from(std::process::id())
+}
+
+/// Applies `--filter` to batch input.
+///
+/// Returns the URLs to download and the failures of URLs whose info could
+/// not be fetched.
+fn select_batch(urls: Vec<String>, common: &cli::CommonArgs) -> (Vec<String>, Vec<BatchFailure>) {
+    let filter: Filter = common.filter.iter().cloned().collect();
+    let videos = urls
+        .into_iter()
+        .map(|url| {
+            let id = utils::extract_video_id(&url).unwrap_or_default();
+            (url, VideoSummary::new(id))
+        })
+        .collect();
+    let (videos, unchecked) = filter_videos(&filter, videos, common.verbose);
+
+    (videos.into_iter().map(|(url, _)| url).collect(), unchecked)
+}
+
+/// Works out the `--dry-run` plan of every URL, adding the URLs that could
+/// not be checked earlier.
+fn plan_batch(downloader: &Downloader, urls: &[String], unchecked: &[BatchFailure]) -> Plan {
+    let mut plan = Plan::default();
+
+    for url in urls {
+        match downloader.plan(url) {
+            Ok(planned) => plan.downloads.push(planned),
+            Err(error) => plan.push_failure(url.as_str(), error),
+        }
+    }
+    for failure in unchecked {
+        plan.push_failure(failure.url.as_str(), &failure.error);
+    }
 
-        match result {
-            Ok(r) => {
-                success_count += 1;
+    plan
+}
+
+/// Keeps the videos `filter` matches.
+///
+/// A video's full info is fetched only when its summary lacks a field the
+/// filter tests. Videos whose info cannot be fetched are returned as
+/// failures; with `verbose`, skipped videos are reported with the reason.
+fn filter_videos(
+    filter: &Filter,
+    videos: Vec<(String, VideoSummary)>,
+    verbose: bool,
+) -> (Vec<(String, VideoSummary)>, Vec<BatchFailure>) {
+    if filter.is_empty() {
+        return (videos, Vec::new());
+    }
+
+    let client = YtDlpClient::new();
+    let mut kept = Vec::new();
+    let mut unchecked = Vec::new();
+
+    for (url, summary) in videos {
+        match filter.resolve(summary, || client.get_video_summary(&url)) {
+            Ok((Verdict::Skip(reason), summary)) => {
                 if verbose {
-                    messages::success(&format!("Downloaded: {}", r.file_path.display()));
+                    let title = summary.title.as_deref().unwrap_or(&url);
+                    messages::info(&format!("Skipping {}: {}", title, reason));
                 }
             }
-            Err(e) => {
-                error_count += 1;
-                if !silence {
-                    messages::error(&format!("Failed to download {}: {}", url, e));
+            Ok((_, summary)) => kept.push((url, summary)),
+            Err(error) => unchecked.push(BatchFailure {
+                url,
+                error,
+                attempts: 1,
+            }),
+        }
+    }
+
+    (kept, unchecked)
+}
+
+/// Compares a playlist with its last `--mirror` snapshot and reports what
+/// changed.
+///
+/// Saves the new snapshot and, with `--move-removed`, moves the files of
+/// removed and unavailable videos under `dir` to `_removed/`. A dry run
+/// only reports. Returns the IDs of the videos that cannot be downloaded.
+fn mirror_playlist(
+    playlist: &PlaylistInfo,
+    summaries: &[VideoSummary],
+    dir: &Path,
+    loader: &ConfigLoader,
+    move_removed: bool,
+    dry_run: bool,
+    silence: bool,
+) -> AppResult<Vec<String>> {
+    let config_path = loader.user_path()?;
+    let path = Snapshot::path_beside(&config_path, &playlist.id);
+    let previous = Snapshot::load(&path)?;
+    let snapshot = Snapshot::of_playlist(playlist, summaries);
+    let diff = snapshot.diff(previous.as_ref());
+
+    match &previous {
+        None if !silence => messages::info(&format!(
+            "{}: first mirror snapshot ({} videos)",
+            playlist.title,
+            snapshot.entries.len()
+        )),
+        Some(_) if diff.is_empty() && !silence => {
+            messages::info(&format!("{}: no changes since last run", playlist.title))
+        }
+        Some(previous) if !diff.is_empty() => {
+            if !silence {
+                messages::info(&format!(
+                    "{}: changes since {}",
+                    playlist.title, previous.taken_at
+                ));
+                for entry in &diff.added {
+                    println!("  {} {}", "+".green(), entry.label());
                 }
             }
+            for entry in &diff.removed {
+                messages::warning(&format!("Removed from playlist: {}", entry.label()));
+            }
+            for entry in &diff.unavailable {
+                messages::warning(&format!("No longer available: {}", entry.label()));
+            }
         }
+        _ => {}
     }
 
-    if !silence {
-        println!();
+    let index = Library::path_beside(&config_path);
+    let library = Library::load(&index)?;
+    let moves = removed_files(&library, &diff, dir);
+
+    if !moves.is_empty() && !move_removed {
         messages::info(&format!(
-            "Playlist complete: {} succeeded, {} failed",
-            success_count, error_count
+            "Kept {} local files of removed videos (--move-removed moves them to {}/)",
+            moves.len(),
+            REMOVED_DIR
         ));
+    } else if dry_run {
+        for (file, target) in &moves {
+            println!("  Would move {} -> {}", file.display(), target.display());
+        }
+    } else if !moves.is_empty() {
+        let moved = Library::update(&index, |library| move_files(&moves, library, &diff))?;
+        for target in moved {
+            messages::info(&format!("Moved to {}", target.display()));
+        }
     }
 
-    if error_count > 0 && success_count == 0 {
-        return Err(AppError::Other("All downloads failed".to_string()));
+    if !dry_run {
+        snapshot.save(&path)?;
     }
 
-    Ok(())
+    Ok(snapshot
+        .unavailable_ids()
+        .into_iter()
+        .map(String::from)
+        .collect())
+}
+
+/// Prints where a finished download was saved and how large it is.
+fn print_download_result(result: &DownloadResult, silence: bool) {
+    if !silence {
+        println!();
+        messages::success(&format!("Downloaded: {}", result.file_path.display()));
+        messages::info(&format!("Size: {}", utils::format_bytes(result.file_size)));
+    }
+}
+
+/// Appends the outcome of every URL in `report` to the download history.
+///
+/// A history that cannot be written only produces a warning: the downloads
+/// themselves have already finished.
+fn record_history(report: &BatchReport<DownloadResult>, loader: &ConfigLoader) {
+    let entries: Vec<HistoryEntry> = report
+        .succeeded
+        .iter()
+        .map(|(url, result)| HistoryEntry::success(url, result))
+        .chain(
+            report
+                .failed
+                .iter()
+                .map(|failure| HistoryEntry::failure(&failure.url, &failure.error.to_string())),
+        )
+        .collect();
+
+    let written = loader
+        .user_path()
+        .and_then(|path| History::beside(&path).append(&entries));
+
+    if let Err(e) = written {
+        messages::warning(&format!("Could not update download history: {}", e));
+    }
+}
+
+/// Turns a batch report into the command's result.
+///
+/// A single URL fails with its own error, exactly as before batch input
+/// existed. With several URLs the summary is printed and the command fails
+/// if any of them failed.
+fn finish_batch<T>(mut report: BatchReport<T>, silence: bool) -> AppResult<()> {
+    if report.total() == 1 {
+        return match report.failed.pop() {
+            Some(failure) => Err(failure.error),
+            None => Ok(()),
+        };
+    }
+
+    if !silence {
+        report.print_summary("Batch");
+    }
+
+    if report.is_success() {
+        Ok(())
+    } else {
+        Err(AppError::Other(format!(
+            "{} of {} URLs failed",
+            report.failed.len(),
+            report.total()
+        )))
+    }
 }
 
 /// Handles the `info` command for displaying video metadata.
 ///
-/// Fetches and displays detailed information about a YouTube video
+/// Fetches and displays detailed information about each YouTube video
 /// including title, duration, channel, views, available qualities, and audio streams.
-async fn handle_info(url: &str) -> AppResult<()> {
+async fn handle_info(args: cli::InfoArgs, loader: &ConfigLoader) -> AppResult<()> {
+    let urls = args.resolve_urls()?;
+    let config = loader.load()?;
     let client = YtDlpClient::new();
+    let runner = BatchRunner::new(config.network.retry_attempts);
 
-    messages::info("Fetching video information...");
-    println!();
+    let report = runner
+        .run(&urls, |url| {
+            let client = &client;
+            async move {
+                messages::info("Fetching video information...");
+                println!();
 
-    let video = client.get_video_info(url)?;
+                let video = client.get_video_info(&url)?;
+                print_video_info(&video);
+                Ok(())
+            }
+        })
+        .await;
 
+    finish_batch(report, false)
+}
+
+/// Prints the metadata shown by the `info` command.
+fn print_video_info(video: &VideoInfo) {
     println!("{}: {}", "Title".cyan().bold(), video.title);
     println!("{}: {}", "ID".cyan().bold(), video.id);
     println!(
@@ -315,6 +841,17 @@ async fn handle_info(url: &str) -> AppResult<()> {
         println!("{}: {}", "Views".cyan().bold(), format_views(views));
     }
 
+    if video.live_status != LiveStatus::NotLive {
+        let mut live = video.live_status.label().to_string();
+        if let Some(time) = video.release_time().filter(|_| video.is_upcoming()) {
+            live.push_str(&format!(
+                ", starts {}",
+                time.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ));
+        }
+        println!("{}: {}", "Live".cyan().bold(), live);
+    }
+
     if let Some(desc) = &video.description {
         let short_desc: String = desc.chars().take(200).collect();
         println!("{}: {}...", "Description".cyan().bold(), short_desc);
@@ -348,6 +885,58 @@ async fn handle_info(url: &str) -> AppResult<()> {
             println!("  • {} ({})", stream.format, bitrate);
         }
     }
+}
+
+/// Handles the `formats` command for listing every available stream.
+///
+/// Prints one row per stream with its format ID, extension, resolution, fps,
+/// codecs, bitrate and size, sorted by the requested column.
+async fn handle_formats(args: cli::FormatsArgs) -> AppResult<()> {
+    let client = YtDlpClient::new();
+
+    messages::info("Fetching available formats...");
+    println!();
+
+    let video = client.get_video_info(&args.url)?;
+    let streams = video.sorted_streams(args.sort, args.reverse);
+
+    if streams.is_empty() {
+        return Err(AppError::NoStreamsAvailable {
+            video_id: video.id.clone(),
+        });
+    }
+
+    println!("{}: {}", "Title".cyan().bold(), video.title);
+    println!();
+
+    let header = format!(
+        "{:<12} {:<5} {:<11} {:>4}  {:<14} {:<14} {:>8} {:>10}",
+        "ID", "EXT", "RESOLUTION", "FPS", "VCODEC", "ACODEC", "BITRATE", "SIZE"
+    );
+    println!("{}", header.yellow().bold());
+
+    for stream in streams {
+        let resolution = if stream.is_audio_only {
+            "audio only".to_string()
+        } else {
+            stream.resolution()
+        };
+
+        println!(
+            "{:<12} {:<5} {:<11} {:>4}  {:<14} {:<14} {:>8} {:>10}",
+            stream.format_id,
+            stream.format,
+            resolution,
+            stream.fps.map(|f| f.to_string()).unwrap_or_default(),
+            stream.video_codec.as_deref().unwrap_or("-"),
+            stream.audio_codec.as_deref().unwrap_or("-"),
+            stream
+                .bitrate
+                .map(|b| format!("{}k", b / 1000))
+                .unwrap_or_default(),
+            stream.formatted_size().unwrap_or_default(),
+        );
+    }
 
     Ok(())
 }
@@ -356,28 +945,73 @@ async fn handle_info(url: &str) -> AppResult<()> {
 ///
 /// Manages the application configuration file, supporting operations
 /// to show, get, set, reset configuration values, and display the config file path.
-fn handle_config(command: ConfigCommands) -> AppResult<()> {
+fn handle_config(command: ConfigCommands, loader: &ConfigLoader) -> AppResult<()> {
     match command {
-        ConfigCommands::Show => {
-            let config = Config::load()?;
+        ConfigCommands::Show { origin } => {
+            let loaded = loader.load_with_origins()?;
 
-            println!("{}", "Current Configuration:".yellow().bold());
+            match &loaded.profile {
+                Some(name) => println!(
+                    "{}",
+                    format!("Current Configuration (profile: {}):", name)
+                        .yellow()
+                        .bold()
+                ),
+                None => println!("{}", "Current Configuration:".yellow().bold()),
+            }
             println!();
 
             for key in Config::keys() {
-                if let Some(value) = config.get(key) {
-                    println!("  {}: {}", key.cyan(), value);
+                let value = match loaded.config.get(key) {
+                    Some(value) => value,
+                    None => "(not set)".dimmed().to_string(),
+                };
+
+                if origin {
+                    let source = format!("({})", loaded.origin(key));
+                    println!("  {}: {}  {}", key.cyan(), value, source.dimmed());
                 } else {
-                    println!("  {}: {}", key.cyan(), "(not set)".dimmed());
+                    println!("  {}: {}", key.cyan(), value);
+                }
+            }
+
+            let profiles = loaded.config.profile_names();
+            if !profiles.is_empty() {
+                println!();
+                println!("{}: {}", "Profiles".yellow().bold(), profiles.join(", "));
+            }
+
+            if !loaded.config.rules.is_empty() {
+                println!();
+                println!("{}", "Rules:".yellow().bold());
+                for (index, rule) in loaded.config.rules.iter().enumerate() {
+                    let describe = |entries: Vec<(&str, String)>| {
+                        entries
+                            .iter()
+                            .map(|(key, value)| format!("{} = {}", key, value))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    let conditions = match describe(rule.conditions()) {
+                        conditions if conditions.is_empty() => "every video".to_string(),
+                        conditions => conditions,
+                    };
+                    println!(
+                        "  {} {} -> {}",
+                        rule.label(index).cyan(),
+                        conditions,
+                        describe(rule.overrides())
+                    );
                 }
             }
         }
 
         ConfigCommands::Get { key } => {
-            let config = Config::load()?;
+            let config = loader.load()?;
 
             match config.get(&key) {
                 Some(value) => println!("{}", value),
+                None if Config::keys().contains(&key.as_str()) => {}
                 None => {
                     return Err(AppError::ConfigInvalid {
                         field: key,
@@ -388,27 +1022,792 @@ fn handle_config(command: ConfigCommands) -> AppResult<()> {
         }
 
         ConfigCommands::Set { key, value } => {
-            let mut config = Config::load()?;
-            config.set(&key, &value)?;
-            config.save()?;
+            Config::set_in_file(&loader.user_path()?, loader.profile(), &key, &value)?;
 
-            messages::success(&format!("{} set to {}", key, value));
+            match loader.profile() {
+                Some(name) => {
+                    messages::success(&format!("{} set to {} in profile {}", key, value, name))
+                }
+                None => messages::success(&format!("{} set to {}", key, value)),
+            }
         }
 
         ConfigCommands::Reset => {
-            Config::reset()?;
+            Config::default().save_to(&loader.user_path()?)?;
             messages::success("Configuration reset to defaults");
         }
 
         ConfigCommands::Path => {
-            let path = Config::config_path()?;
-            println!("{}", path.display());
+            println!("{}", loader.user_path()?.display());
+        }
+
+        ConfigCommands::Validate => {
+            let mut paths = vec![loader.user_path()?];
+            paths.extend(loader.project_path());
+
+            let mut problems = 0;
+            for path in paths.iter().filter(|path| path.exists()) {
+                let issues = validate_file(path)?;
+
+                if issues.is_empty() {
+                    messages::success(&format!("{} is valid", path.display()));
+                }
+                for issue in &issues {
+                    messages::error(&format!("{}: {}", path.display(), issue));
+                }
+                problems += issues.len();
+            }
+
+            if problems > 0 {
+                return Err(AppError::ConfigInvalid {
+                    field: "config".to_string(),
+                    message: format!("{} problem(s) found", problems),
+                });
+            }
+        }
+
+        ConfigCommands::Edit => {
+            edit_config(&loader.user_path()?)?;
         }
     }
 
     Ok(())
 }
 
+/// Opens the configuration file in the user's editor and saves it only if valid.
+///
+/// Edits happen on a draft next to the file, so the real configuration is
+/// never left in an invalid state. When the draft has problems they are
+/// listed and, on an interactive terminal, the editor can be reopened.
+fn edit_config(path: &Path) -> AppResult<()> {
+    let original = if path.exists() {
+        fs::read_to_string(path).map_err(|e| AppError::file_read(path, e))?
+    } else {
+        toml::to_string_pretty(&Config::default())?
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+    }
+
+    let draft = path.with_extension("edit.toml");
+    fs::write(&draft, &original).map_err(|e| AppError::file_write(&draft, e))?;
+
+    loop {
+        run_editor(&draft)?;
+
+        let edited = fs::read_to_string(&draft).map_err(|e| AppError::file_read(&draft, e))?;
+        if edited == original {
+            fs::remove_file(&draft).map_err(|e| AppError::file_write(&draft, e))?;
+            messages::info("No changes made");
+            return Ok(());
+        }
+
+        let issues = validate_str(&edited);
+        if issues.is_empty() {
+            fs::rename(&draft, path).map_err(|e| AppError::file_write(path, e))?;
+            messages::success(&format!("Saved {}", path.display()));
+            return Ok(());
+        }
+
+        for issue in &issues {
+            messages::error(&issue.to_string());
+        }
+
+        if !std::io::stdin().is_terminal() || !confirm("Edit again?")? {
+            fs::remove_file(&draft).map_err(|e| AppError::file_write(&draft, e))?;
+            return Err(AppError::ConfigInvalid {
+                field: path.display().to_string(),
+                message: format!("not saved, {} problem(s) found", issues.len()),
+            });
+        }
+    }
+}
+
+/// Runs `$VISUAL` or `$EDITOR` (falling back to `vi`) on `path` and waits for it.
+fn run_editor(path: &Path) -> AppResult<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+
+    // Allow editors with arguments, such as "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| AppError::invalid_arg("EDITOR", "is empty"))?;
+
+    let status = Command::new(program).args(parts).arg(path).status()?;
+    if !status.success() {
+        return Err(AppError::Other(format!(
+            "Editor '{}' exited with {}",
+            editor, status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Handles the `history` command: lists matching entries or prunes the store.
+///
+/// The history file sits next to the user configuration file, so `--config`
+/// and `YTDL_CONFIG` select a different history as well.
+fn handle_history(args: cli::HistoryArgs, loader: &ConfigLoader) -> AppResult<()> {
+    let history = History::beside(&loader.user_path()?);
+
+    if let Some(HistoryCommands::Prune { before, all }) = args.command {
+        let report = history.prune(&PruneOptions { before, all })?;
+        messages::success(&format!(
+            "Removed {} history entries, {} kept",
+            report.removed, report.kept
+        ));
+        return Ok(());
+    }
+
+    let query = HistoryQuery::new()
+        .with_search(args.search)
+        .with_since(args.since)
+        .with_failed_only(args.failed);
+    let entries = history.search(&query)?;
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&entries)
+            .map_err(|e| AppError::Other(format!("Could not serialize history: {}", e)))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        messages::info("No matching downloads in history");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        print_history_entry(entry);
+    }
+
+    println!();
+    messages::info(&format!("{} entries", entries.len()));
+
+    Ok(())
+}
+
+/// Handles the `library` command and its subcommands.
+fn handle_library(command: LibraryCommands, loader: &ConfigLoader) -> AppResult<()> {
+    let index = Library::path_beside(&loader.user_path()?);
+
+    match command {
+        LibraryCommands::Scan { dir } => {
+            let (report, videos) = Library::update(&index, |library| {
+                library.scan(&dir).map(|report| (report, library.len()))
+            })?;
+
+            messages::success(&format!(
+                "Scanned {} media files: {} added, {} already indexed",
+                report.scanned, report.added, report.known
+            ));
+
+            if report.removed > 0 {
+                messages::info(&format!(
+                    "Dropped {} entries whose files no longer exist",
+                    report.removed
+                ));
+            }
+
+            if !report.unidentified.is_empty() {
+                messages::warning(&format!(
+                    "{} files have no video ID (no .info.json sidecar or source URL tag):",
+                    report.unidentified.len()
+                ));
+                for path in &report.unidentified {
+                    println!("  {}", path.display());
+                }
+            }
+
+            messages::info(&format!(
+                "Library: {} videos in {}",
+                videos,
+                index.display()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the `feed` command and its subcommands.
+fn handle_feed(command: FeedCommands) -> AppResult<()> {
+    match command {
+        FeedCommands::Build(args) => {
+            let options = FeedOptions::new(args.base_url)
+                .with_title(args.title)
+                .with_description(args.description)
+                .with_author(args.author)
+                .with_image(args.image)
+                .with_output(args.output);
+
+            let report = build_feed(&args.dir, &options)?;
+
+            messages::success(&format!(
+                "Wrote {} ({} episodes)",
+                report.output.display(),
+                report.items
+            ));
+            messages::info(&format!(
+                "{} new, {} changed, {} unchanged, {} removed",
+                report.added, report.updated, report.unchanged, report.removed
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the `subscribe` command and its subcommands.
+///
+/// The store sits next to the user configuration file. The global
+/// `--profile` given with `subscribe add` is saved with the subscription.
+fn handle_subscribe(command: SubscribeCommands, loader: &ConfigLoader) -> AppResult<()> {
+    let path = Subscriptions::path_beside(&loader.user_path()?);
+    let mut store = Subscriptions::load(&path)?;
+
+    match command {
+        SubscribeCommands::Add(args) => {
+            let mut subscription = Subscription::new(args.url)
+                .with_profile(loader.profile().map(String::from))
+                .with_audio_only(args.audio_only)
+                .with_filters(SubscriptionFilters {
+                    title: args.title,
+                    min_duration: args.min_duration,
+                    max_duration: args.max_duration,
+                })
+                .with_include_existing(args.all);
+            if let Some(name) = args.name {
+                subscription = subscription.with_name(name);
+            }
+
+            let name = subscription.name.clone();
+            store.add(subscription)?;
+            store.save(&path)?;
+
+            messages::success(&format!("Subscribed to {}", name));
+            if !args.all {
+                messages::info(
+                    "Existing videos will be skipped; run `ytdl sync` to start following",
+                );
+            }
+        }
+
+        SubscribeCommands::List { json } => {
+            if json {
+                let subscriptions: Vec<_> = store.iter().collect();
+                let json = serde_json::to_string_pretty(&subscriptions).map_err(|e| {
+                    AppError::Other(format!("Could not serialize subscriptions: {}", e))
+                })?;
+                println!("{}", json);
+                return Ok(());
+            }
+
+            if store.is_empty() {
+                messages::info("No subscriptions. Add one with `ytdl subscribe add <URL>`");
+                return Ok(());
+            }
+
+            for subscription in store.iter() {
+                print_subscription(subscription);
+            }
+        }
+
+        SubscribeCommands::Remove { subscription } => {
+            let removed = store.remove(&subscription)?;
+            store.save(&path)?;
+            messages::success(&format!("Unsubscribed from {}", removed.name));
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints one subscription: its name and URL, then its settings.
+fn print_subscription(subscription: &Subscription) {
+    println!(
+        "{}  {}",
+        subscription.name.bold(),
+        subscription.url.dimmed()
+    );
+
+    let mut details = vec![if subscription.audio_only {
+        "audio".to_string()
+    } else {
+        "video".to_string()
+    }];
+    if let Some(profile) = &subscription.profile {
+        details.push(format!("profile {}", profile));
+    }
+    if !subscription.filters.is_empty() {
+        details.push(subscription.filters.describe());
+    }
+    details.push(format!("{} seen", subscription.seen.len()));
+    details.push(match &subscription.last_sync {
+        Some(time) => format!("last sync {}", time),
+        None => "never synced".to_string(),
+    });
+
+    println!("    {}", details.join(" · "));
+}
+
+/// Handles the `sync` command: downloads new uploads once, or repeatedly
+/// with `--watch`.
+///
+/// In watch mode a failed sync is reported and retried at the next interval.
+async fn handle_sync(
+    args: cli::SyncArgs,
+    loader: &ConfigLoader,
+    dry_run: Option<PlanFormat>,
+) -> AppResult<()> {
+    if dry_run.is_some() && args.watch.is_some() {
+        return Err(AppError::invalid_arg(
+            "--dry-run",
+            "cannot be used with --watch",
+        ));
+    }
+    let path = Subscriptions::path_beside(&loader.user_path()?);
+
+    loop {
+        let synced = sync_subscriptions(&args, loader, &path, dry_run).await;
+
+        let Some(interval) = args.watch else {
+            return synced;
+        };
+        if let Err(e) = synced {
+            messages::error(&e.to_string());
+        }
+
+        if !args.silence {
+            let next = chrono::Duration::from_std(interval)
+                .map(|delay| (Local::now() + delay).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            messages::info(&format!("Next sync at {}", next));
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// URLs to download in one round of a sync, and the job of each with the
+/// index of the subscription it is for.
+type SyncRound = (Vec<String>, HashMap<String, (usize, SyncJob)>);
+
+/// Enumerates the selected subscriptions, downloads their unseen videos and
+/// records what was seen.
+///
+/// The store is read again before saving, so subscriptions added or removed
+/// while downloading are kept. Failed downloads stay unseen and are retried
+/// on the next sync. With `--dry-run`, prints the plan of the new videos
+/// instead, without marking anything as seen.
+async fn sync_subscriptions(
+    args: &cli::SyncArgs,
+    loader: &ConfigLoader,
+    path: &Path,
+    dry_run: Option<PlanFormat>,
+) -> AppResult<()> {
+    let store = Subscriptions::load(path)?;
+    let selected = store.select(&args.subscriptions)?;
+    let silence = args.silence || dry_run == Some(PlanFormat::Json);
+
+    if selected.is_empty() {
+        messages::info("No subscriptions. Add one with `ytdl subscribe add <URL>`");
+        return Ok(());
+    }
+
+    YtDlpClient::require()?;
+    let config = loader.load()?;
+    let jobs = args.jobs.unwrap_or(config.general.max_parallel_downloads) as usize;
+    let client = YtDlpClient::new();
+    let budget = args.sizes.budget();
+
+    let filter: Filter = args.filter.iter().cloned().collect();
+    let mut updates = Vec::new();
+    // A video several subscriptions list is downloaded once for each, with
+    // its own profile and folder, in a later round
+    let mut rounds: Vec<SyncRound> = Vec::new();
+    let mut filtered = 0;
+    let mut unchecked = Vec::new();
+    let mut plan = Plan::default();
+
+    for subscription in selected {
+        let mut update = SyncUpdate::new(&subscription.url);
+
+        let (playlist, summaries) = match client.get_playlist_entries(&subscription.url) {
+            Ok(listing) => listing,
+            Err(e) => {
+                messages::error(&format!("{}: {}", subscription.name, e));
+                updates.push(update);
+                continue;
+            }
+        };
+        let unseen = subscription.unseen(&playlist.video_ids);
+
+        if subscription.needs_baseline() {
+            if !silence {
+                let marked = if dry_run.is_some() {
+                    "would be marked"
+                } else {
+                    "marked"
+                };
+                messages::info(&format!(
+                    "{}: {} existing videos {} as seen",
+                    subscription.name,
+                    unseen.len(),
+                    marked
+                ));
+            }
+            update.seen = unseen;
+            update.synced = true;
+            updates.push(update);
+            continue;
+        }
+
+        if !silence && dry_run.is_none() {
+            messages::info(&format!(
+                "{}: {} new videos",
+                subscription.name,
+                unseen.len()
+            ));
+        }
+
+        if !unseen.is_empty() {
+            // Progress bars of parallel downloads would overwrite each other
+            let quiet = silence || jobs > 1;
+            let prepared = subscription_downloader(
+                subscription,
+                loader,
+                quiet,
+                args.verbose,
+                args.sizes.max_filesize,
+            )
+            .map(|downloader| downloader.with_budget(budget.clone()))
+            .and_then(|downloader| Ok((downloader, subscription.filters.to_filter()?)));
+            let (downloader, subscription_filter) = match prepared {
+                Ok((downloader, subscription_filter)) => {
+                    (Arc::new(downloader), subscription_filter)
+                }
+                Err(e) => {
+                    messages::error(&format!("{}: {}", subscription.name, e));
+                    updates.push(update);
+                    continue;
+                }
+            };
+
+            let mut summaries: HashMap<String, VideoSummary> = summaries
+                .into_iter()
+                .map(|summary| (summary.id.clone(), summary))
+                .collect();
+            let mut planned = Vec::new();
+
+            for id in &unseen {
+                let summary = summaries
+                    .remove(id)
+                    .unwrap_or_else(|| VideoSummary::new(id));
+                let url = video_url(id);
+
+                match check_new_video(&subscription_filter, &filter, summary, &client) {
+                    Ok(NewVideo::Download(summary)) => planned.push((url, summary)),
+                    Ok(NewVideo::Filtered(reason)) => {
+                        if args.verbose {
+                            messages::info(&format!("Filtered out {}: {}", url, reason));
+                        }
+                        update.seen.push(id.clone());
+                        filtered += 1;
+                    }
+                    Ok(NewVideo::Skipped(reason)) => {
+                        if args.verbose {
+                            messages::info(&format!("Skipping {}: {}", url, reason));
+                        }
+                        filtered += 1;
+                    }
+                    Err(error) => unchecked.push(BatchFailure {
+                        url,
+                        error,
+                        attempts: 1,
+                    }),
+                }
+            }
+
+            if dry_run.is_some() {
+                if !silence {
+                    messages::info(&format!(
+                        "{}: {} of {} new videos would be downloaded",
+                        subscription.name,
+                        planned.len(),
+                        unseen.len()
+                    ));
+                }
+                for (url, _) in &planned {
+                    match downloader.plan(url) {
+                        Ok(planned) => plan.downloads.push(planned),
+                        Err(error) => plan.push_failure(url.as_str(), error),
+                    }
+                }
+            } else {
+                for (url, summary) in planned {
+                    let job = SyncJob {
+                        video_id: summary.id,
+                        downloader: Arc::clone(&downloader),
+                        audio_only: subscription.audio_only,
+                    };
+                    let round = match rounds
+                        .iter()
+                        .position(|(_, pending)| !pending.contains_key(&url))
+                    {
+                        Some(round) => round,
+                        None => {
+                            rounds.push(Default::default());
+                            rounds.len() - 1
+                        }
+                    };
+                    let (urls, pending) = &mut rounds[round];
+                    urls.push(url.clone());
+                    pending.insert(url, (updates.len(), job));
+                }
+            }
+        } else if dry_run.is_some() && !silence {
+            messages::info(&format!("{}: no new videos", subscription.name));
+        }
+
+        update.synced = true;
+        updates.push(update);
+    }
+
+    if let Some(format) = dry_run {
+        for failure in &unchecked {
+            plan.push_failure(failure.url.as_str(), &failure.error);
+        }
+        return plan.finish(format);
+    }
+
+    let runner = BatchRunner::new(config.network.retry_attempts).with_silence(silence);
+    let mut downloads = BatchReport {
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+    };
+    for (urls, pending) in rounds {
+        let pending = Arc::new(pending);
+        let report = {
+            let pending = Arc::clone(&pending);
+            runner
+                .run_parallel(&urls, jobs, move |url| {
+                    let pending = Arc::clone(&pending);
+                    async move {
+                        // run_parallel already gives every download a blocking thread
+                        let result = pending[&url].1.run(&url).await?;
+
+                        if !silence {
+                            messages::success(&format!(
+                                "Downloaded: {}",
+                                result.file_path.display()
+                            ));
+                        }
+                        Ok(result)
+                    }
+                })
+                .await
+        };
+
+        downloads.failed.extend(report.failed);
+        for (url, result) in report.succeeded {
+            let (owner, job) = &pending[&url];
+            updates[*owner].seen.push(job.video_id.clone());
+            downloads.succeeded.push((url, result));
+        }
+    }
+    downloads.failed.extend(unchecked);
+
+    record_history(&downloads, loader);
+
+    let mut store = Subscriptions::load(path)?;
+    store.apply(&updates);
+    store.save(path)?;
+
+    if !silence {
+        println!();
+        messages::info(&format!(
+            "Sync complete: {} downloaded, {} filtered out, {} failed",
+            downloads.succeeded.len(),
+            filtered,
+            downloads.failed.len()
+        ));
+    }
+    for failure in &downloads.failed {
+        messages::error(&format!("{}: {}", failure.url, failure.error));
+    }
+
+    if !downloads.failed.is_empty() {
+        return Err(AppError::Other(format!(
+            "{} downloads failed; they will be retried on the next sync",
+            downloads.failed.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Where a new video of a subscription goes after filtering.
+enum NewVideo {
+    /// Download it
+    Download(VideoSummary),
+    /// Rejected by the subscription's filters: mark it as seen
+    Filtered(String),
+    /// Rejected by `--filter`: leave it unseen for later syncs
+    Skipped(String),
+}
+
+/// Applies the subscription's filters, then `--filter`, fetching the
+/// video's full info at most once and only if a filter needs it.
+fn check_new_video(
+    subscription_filter: &Filter,
+    filter: &Filter,
+    summary: VideoSummary,
+    client: &YtDlpClient,
+) -> AppResult<NewVideo> {
+    let url = video_url(&summary.id);
+    let fetch = || client.get_video_summary(&url);
+
+    let (verdict, summary) = subscription_filter.resolve(summary, fetch)?;
+    if let Verdict::Skip(reason) = verdict {
+        return Ok(NewVideo::Filtered(reason));
+    }
+
+    // A summary fetched above is complete, so this never fetches it again
+    let (verdict, summary) = filter.resolve(summary, fetch)?;
+    Ok(match verdict {
+        Verdict::Skip(reason) => NewVideo::Skipped(reason),
+        _ => NewVideo::Download(summary),
+    })
+}
+
+/// Builds the downloader for a subscription from its profile, or from the
+/// global `--profile` when it has none.
+fn subscription_downloader(
+    subscription: &Subscription,
+    loader: &ConfigLoader,
+    silence: bool,
+    verbose: bool,
+    max_filesize: Option<u64>,
+) -> AppResult<Downloader> {
+    let profile = subscription
+        .profile
+        .clone()
+        .or_else(|| loader.profile().map(String::from));
+    let loader = loader.clone().with_profile(profile);
+    let config = loader.load()?;
+
+    let options = DownloadOptions::from_config(&config)
+        .with_audio_only(subscription.audio_only)
+        .with_max_filesize(max_filesize)
+        .with_silence(silence)
+        .with_verbose(verbose);
+
+    Ok(Downloader::with_options(options)
+        .with_rules(config.rules)
+        .with_library(Library::path_beside(&loader.user_path()?)))
+}
+
+/// Handles the `serve` command: runs the HTTP API until Ctrl+C.
+///
+/// The token comes from `--token`, then `YTDL_SERVE_TOKEN`. Listening on a
+/// non-local address without one only produces a warning.
+async fn handle_serve(args: cli::ServeArgs, loader: &ConfigLoader) -> AppResult<()> {
+    YtDlpClient::require()?;
+    let config = loader.load()?;
+    let jobs = args.jobs.unwrap_or(config.general.max_parallel_downloads) as usize;
+    let token = args
+        .token
+        .or_else(|| std::env::var("YTDL_SERVE_TOKEN").ok())
+        .filter(|token| !token.is_empty());
+
+    if token.is_none() && !args.listen.ip().is_loopback() {
+        messages::warning(&format!(
+            "Listening on {} without a token: anyone who can reach it can start downloads",
+            args.listen
+        ));
+    }
+
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    messages::success(&format!(
+        "Listening on http://{} ({} jobs at once{})",
+        listener.local_addr()?,
+        jobs,
+        if token.is_some() {
+            ", token required"
+        } else {
+            ""
+        }
+    ));
+
+    Server::new(loader.clone())
+        .with_token(token)
+        .with_jobs(jobs)
+        .run(listener, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+}
+
+/// Prints one history entry: a status line followed by the saved file or
+/// the error.
+fn print_history_entry(entry: &HistoryEntry) {
+    let when = entry
+        .recorded_at()
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| entry.timestamp.clone());
+    let title = entry.title.as_deref().unwrap_or(&entry.url);
+    let indent = " ".repeat(when.len() + 2);
+
+    match entry.outcome {
+        Outcome::Success => {
+            let channel = entry
+                .channel
+                .as_ref()
+                .map(|c| format!(" ({})", c))
+                .unwrap_or_default();
+            println!(
+                "{}  {} {}{}",
+                when.dimmed(),
+                "✓".green(),
+                title,
+                channel.dimmed()
+            );
+
+            if let Some(path) = &entry.path {
+                let size = entry.size.map(utils::format_bytes).unwrap_or_default();
+                println!("{}  {}  {}", indent, path.display(), size.dimmed());
+            }
+        }
+        Outcome::Failed => {
+            println!("{}  {} {}", when.dimmed(), "✗".red(), title);
+            if let Some(error) = &entry.error {
+                println!("{}  {}", indent, error.red());
+            }
+        }
+    }
+}
+
+/// Asks a yes/no question on the terminal, defaulting to yes.
+fn confirm(question: &str) -> AppResult<bool> {
+    print!("{} [Y/n] ", question);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(!answer.trim().to_lowercase().starts_with('n'))
+}
+
 /// Formats a view count into a human-readable string with K/M/B suffixes.
 ///
 /// # Examples
@@ -31,16 +31,19 @@
 
 use std::path::{Path, PathBuf};
 
-use crate::error::{AppError, AppResult};
+use clap::ValueEnum;
+
+use crate::error::AppResult;
 use crate::media::ffmpeg::{AudioBitrate, AudioCodec, FFmpeg};
 
 /// Supported audio formats for extraction and conversion.
 ///
 /// Each variant represents a specific audio format with its associated
 /// codec, file extension, and encoding characteristics.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
 pub enum AudioFormat {
     /// MP3 format (lossy, widely compatible).
+    #[default]
     Mp3,
     /// M4A format (AAC in MP4 container, lossy).
     M4a,
@@ -33,15 +33,18 @@
 
 use std::path::{Path, PathBuf};
 
-use crate::error::{AppError, AppResult};
+use clap::ValueEnum;
+
+use crate::error::AppResult;
 use crate::media::ffmpeg::FFmpeg;
 
 /// Supported video container formats.
 ///
 /// Each format represents a container that can hold various video and audio codecs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
 pub enum VideoFormat {
     /// MP4 container (H.264/AAC, widely compatible).
+    #[default]
     Mp4,
     /// Matroska container (supports all codecs).
     Mkv,
@@ -811,8 +811,6 @@ mod tests {
 
     mod integration {
         use super::*;
-        use std::fs;
-        use tempfile::TempDir;
 
         fn skip_if_no_ffmpeg() -> bool {
             if !FFmpeg::is_available() {
@@ -926,7 +924,7 @@ mod tests {
         let input = "input.mp4";
         let output = "output.mp3";
 
-        let args = vec!["-i", input, "-vn", "-acodec", "copy", output];
+        let args = ["-i", input, "-vn", "-acodec", "copy", output];
 
         assert_eq!(args[0], "-i");
         assert_eq!(args[2], "-vn");
@@ -0,0 +1,493 @@
+//! Playlist mirroring with removal detection.
+//!
+//! `ytdl playlist --mirror` keeps a snapshot of every mirrored playlist and
+//! compares each new listing with the previous one. Videos that appeared,
+//! disappeared, or became private or deleted upstream are reported, so a
+//! curated archive never loses track of them silently.
+//!
+//! Nothing is ever deleted. With `--move-removed`, local files of removed
+//! and unavailable videos are moved to a `_removed/` folder next to them;
+//! without it they are only reported.
+//!
+//! Snapshots are stored as JSON in `mirrors/<playlist id>.json`, next to the
+//! user configuration file.
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::mirror::{MirrorEntry, Snapshot};
+//!
+//! let entry = |id: &str, available| MirrorEntry {
+//!     id: id.to_string(),
+//!     title: None,
+//!     available,
+//! };
+//! let before = Snapshot::new("PL1", "Talks", vec![entry("a", true), entry("b", true)]);
+//! let after = Snapshot::new("PL1", "Talks", vec![entry("b", false), entry("c", true)]);
+//!
+//! let diff = after.diff(Some(&before));
+//! assert_eq!(diff.added[0].id, "c");
+//! assert_eq!(diff.removed[0].id, "a");
+//! assert_eq!(diff.unavailable[0].id, "b");
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::library::Library;
+use crate::utils::sanitize_filename;
+use crate::youtube::{PlaylistInfo, VideoSummary};
+
+/// Directory of the snapshots, relative to the configuration directory.
+pub const MIRRORS_DIR: &str = "mirrors";
+
+/// Folder that `--move-removed` moves files to, next to the files.
+pub const REMOVED_DIR: &str = "_removed";
+
+/// Titles YouTube gives videos that can no longer be watched in listings.
+const UNAVAILABLE_TITLES: &[&str] = &["[Private video]", "[Deleted video]"];
+
+/// Availability values of videos that cannot be downloaded without an account.
+const UNAVAILABLE: &[&str] = &["private", "needs_auth", "subscriber_only", "premium_only"];
+
+/// One video of a playlist snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorEntry {
+    /// Video ID
+    pub id: String,
+    /// Title from the listing, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// `false` when the video is private, deleted or needs an account
+    pub available: bool,
+}
+
+impl MirrorEntry {
+    /// Creates the entry of a video from its listing summary.
+    pub fn from_summary(summary: &VideoSummary) -> Self {
+        let title = summary.title.clone();
+        let hidden = title
+            .as_deref()
+            .is_some_and(|title| UNAVAILABLE_TITLES.contains(&title));
+        let restricted = summary
+            .availability
+            .as_deref()
+            .is_some_and(|availability| UNAVAILABLE.contains(&availability));
+
+        Self {
+            id: summary.id.clone(),
+            title,
+            available: !hidden && !restricted,
+        }
+    }
+
+    /// Returns the title, or the ID when the title is unknown.
+    pub fn label(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// The videos of a playlist at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Playlist ID
+    pub playlist_id: String,
+    /// Playlist title
+    pub title: String,
+    /// When the snapshot was taken (RFC 3339)
+    pub taken_at: String,
+    /// Videos in playlist order
+    pub entries: Vec<MirrorEntry>,
+}
+
+/// Changes between two snapshots of a playlist.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MirrorDiff {
+    /// Videos that were not in the previous snapshot
+    pub added: Vec<MirrorEntry>,
+    /// Videos that are no longer in the playlist
+    pub removed: Vec<MirrorEntry>,
+    /// Videos still listed that became private, deleted or restricted
+    pub unavailable: Vec<MirrorEntry>,
+}
+
+impl MirrorDiff {
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.unavailable.is_empty()
+    }
+
+    /// Returns the videos whose local files `--move-removed` moves away.
+    pub fn gone(&self) -> impl Iterator<Item = &MirrorEntry> {
+        self.removed.iter().chain(&self.unavailable)
+    }
+}
+
+impl Snapshot {
+    /// Creates a snapshot taken now.
+    pub fn new(
+        playlist_id: impl Into<String>,
+        title: impl Into<String>,
+        entries: Vec<MirrorEntry>,
+    ) -> Self {
+        Self {
+            playlist_id: playlist_id.into(),
+            title: title.into(),
+            taken_at: Utc::now().to_rfc3339(),
+            entries,
+        }
+    }
+
+    /// Creates a snapshot of a playlist listing.
+    ///
+    /// `summaries` carry the titles and availability of the videos; videos
+    /// without a summary are taken to be available.
+    pub fn of_playlist(playlist: &PlaylistInfo, summaries: &[VideoSummary]) -> Self {
+        let summaries: HashMap<&str, &VideoSummary> = summaries
+            .iter()
+            .map(|summary| (summary.id.as_str(), summary))
+            .collect();
+        let entries = playlist
+            .video_ids
+            .iter()
+            .map(|id| match summaries.get(id.as_str()) {
+                Some(summary) => MirrorEntry::from_summary(summary),
+                None => MirrorEntry::from_summary(&VideoSummary::new(id)),
+            })
+            .collect();
+
+        Self::new(&playlist.id, &playlist.title, entries)
+    }
+
+    /// Returns the path of the snapshot of `playlist_id` next to the
+    /// configuration file at `config_path`.
+    pub fn path_beside(config_path: &Path, playlist_id: &str) -> PathBuf {
+        config_path
+            .with_file_name(MIRRORS_DIR)
+            .join(format!("{}.json", sanitize_filename(playlist_id)))
+    }
+
+    /// Loads the snapshot at `path`, or `None` if there is none yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::FileRead` if the file cannot be read and
+    /// `AppError::Other` if it is not a snapshot.
+    pub fn load(path: &Path) -> AppResult<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| AppError::file_read(path, e))?;
+        serde_json::from_str(&content).map(Some).map_err(|e| {
+            AppError::Other(format!(
+                "Mirror snapshot {} is corrupt: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Writes the snapshot to `path` through a temporary file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::DirectoryCreation` or `AppError::FileWrite` if the
+    /// snapshot cannot be written.
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+            }
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Other(format!("Could not serialize snapshot: {}", e)))?;
+
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, content).map_err(|e| AppError::file_write(&tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| AppError::file_write(path, e))
+    }
+
+    /// Compares this snapshot with the `previous` one.
+    ///
+    /// Without a previous snapshot every video counts as added. A video that
+    /// was already unavailable is not reported again.
+    pub fn diff(&self, previous: Option<&Snapshot>) -> MirrorDiff {
+        let before: HashMap<&str, &MirrorEntry> = previous
+            .map(|snapshot| {
+                snapshot
+                    .entries
+                    .iter()
+                    .map(|entry| (entry.id.as_str(), entry))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let now: HashMap<&str, &MirrorEntry> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.id.as_str(), entry))
+            .collect();
+
+        let mut diff = MirrorDiff::default();
+        for entry in &self.entries {
+            match before.get(entry.id.as_str()) {
+                None => diff.added.push(entry.clone()),
+                Some(old) if old.available && !entry.available => {
+                    // Listings only keep a placeholder title for hidden videos
+                    diff.unavailable.push(MirrorEntry {
+                        title: old.title.clone().or_else(|| entry.title.clone()),
+                        ..entry.clone()
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(previous) = previous {
+            diff.removed = previous
+                .entries
+                .iter()
+                .filter(|entry| !now.contains_key(entry.id.as_str()))
+                .cloned()
+                .collect();
+        }
+
+        diff
+    }
+
+    /// Returns the IDs of the videos that cannot be downloaded.
+    pub fn unavailable_ids(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.available)
+            .map(|entry| entry.id.as_str())
+            .collect()
+    }
+}
+
+/// Finds the local files of the videos that left the playlist.
+///
+/// Only files the library knows under `dir` are returned, so copies of the
+/// same video in other folders are not touched. Each file is paired with
+/// its place in the `_removed/` folder next to it.
+pub fn removed_files(library: &Library, diff: &MirrorDiff, dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+
+    diff.gone()
+        .flat_map(|entry| library.files(&entry.id))
+        .filter(|file| file.is_file() && file.starts_with(&dir))
+        .filter(|file| {
+            !file
+                .parent()
+                .is_some_and(|parent| parent.ends_with(REMOVED_DIR))
+        })
+        .filter_map(|file| {
+            let target = file.parent()?.join(REMOVED_DIR).join(file.file_name()?);
+            Some((file.clone(), target))
+        })
+        .collect()
+}
+
+/// Moves files to their `_removed/` folder, with their `.info.json` and
+/// `.nfo` sidecars, and records the new places in the library.
+///
+/// Files whose target already exists are left in place. Returns the files
+/// that were moved.
+///
+/// # Errors
+///
+/// Returns `AppError::DirectoryCreation` or `AppError::FileWrite` if a
+/// folder cannot be created or a file cannot be moved.
+pub fn move_files(
+    moves: &[(PathBuf, PathBuf)],
+    library: &mut Library,
+    diff: &MirrorDiff,
+) -> AppResult<Vec<PathBuf>> {
+    let mut moved = Vec::new();
+
+    for (file, target) in moves {
+        if target.exists() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+        }
+
+        fs::rename(file, target).map_err(|e| AppError::file_write(target, e))?;
+        for ext in ["info.json", "nfo"] {
+            let sidecar = file.with_extension(ext);
+            if sidecar.is_file() {
+                fs::rename(&sidecar, target.with_extension(ext))
+                    .map_err(|e| AppError::file_write(&sidecar, e))?;
+            }
+        }
+
+        if let Some(entry) = diff
+            .gone()
+            .find(|entry| library.files(&entry.id).contains(file))
+        {
+            library.add(&entry.id, target);
+        }
+        moved.push(target.clone());
+    }
+
+    library.remove_missing();
+    Ok(moved)
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(id: &str, available: bool) -> MirrorEntry {
+        MirrorEntry {
+            id: id.to_string(),
+            title: Some(format!("Video {}", id)),
+            available,
+        }
+    }
+
+    fn snapshot(entries: Vec<MirrorEntry>) -> Snapshot {
+        Snapshot::new("PL1", "Talks", entries)
+    }
+
+    // ============== Entry Tests ==============
+
+    #[test]
+    fn test_entry_from_summary() {
+        let mut summary = VideoSummary::new("abc");
+        assert!(MirrorEntry::from_summary(&summary).available);
+
+        summary.title = Some("[Private video]".to_string());
+        assert!(!MirrorEntry::from_summary(&summary).available);
+
+        summary.title = Some("Talk".to_string());
+        summary.availability = Some("subscriber_only".to_string());
+        assert!(!MirrorEntry::from_summary(&summary).available);
+
+        summary.availability = Some("unlisted".to_string());
+        assert!(MirrorEntry::from_summary(&summary).available);
+    }
+
+    #[test]
+    fn test_of_playlist() {
+        let playlist = PlaylistInfo {
+            id: "PL1".to_string(),
+            title: "Talks".to_string(),
+            description: None,
+            channel: None,
+            video_count: 2,
+            video_ids: vec!["a".to_string(), "b".to_string()],
+        };
+        let summaries = vec![VideoSummary {
+            title: Some("[Deleted video]".to_string()),
+            ..VideoSummary::new("b")
+        }];
+
+        let snapshot = Snapshot::of_playlist(&playlist, &summaries);
+        assert_eq!(snapshot.entries.len(), 2);
+        assert!(snapshot.entries[0].available);
+        assert_eq!(snapshot.unavailable_ids(), vec!["b"]);
+    }
+
+    // ============== Diff Tests ==============
+
+    #[test]
+    fn test_diff_first_snapshot() {
+        let diff = snapshot(vec![entry("a", true)]).diff(None);
+        assert_eq!(diff.added.len(), 1);
+        assert!(diff.removed.is_empty());
+        assert!(diff.unavailable.is_empty());
+    }
+
+    #[test]
+    fn test_diff_changes() {
+        let before = snapshot(vec![entry("a", true), entry("b", true), entry("c", false)]);
+        let after = snapshot(vec![
+            MirrorEntry {
+                title: Some("[Private video]".to_string()),
+                ..entry("b", false)
+            },
+            entry("c", false),
+            entry("d", true),
+        ]);
+
+        let diff = after.diff(Some(&before));
+        assert_eq!(diff.added, vec![entry("d", true)]);
+        assert_eq!(diff.removed, vec![entry("a", true)]);
+        // The old title is kept, and "c" was already unavailable
+        assert_eq!(diff.unavailable, vec![entry("b", false)]);
+        assert_eq!(diff.gone().count(), 2);
+    }
+
+    #[test]
+    fn test_diff_unchanged() {
+        let before = snapshot(vec![entry("a", true)]);
+        assert!(snapshot(vec![entry("a", true)])
+            .diff(Some(&before))
+            .is_empty());
+    }
+
+    // ============== File Tests ==============
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let path = Snapshot::path_beside(&dir.path().join("config.toml"), "PL1");
+        assert_eq!(path, dir.path().join("mirrors").join("PL1.json"));
+        assert_eq!(Snapshot::load(&path).unwrap(), None);
+
+        let snapshot = snapshot(vec![entry("a", true)]);
+        snapshot.save(&path).unwrap();
+        assert_eq!(Snapshot::load(&path).unwrap(), Some(snapshot));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(Snapshot::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_move_removed_files() {
+        let dir = TempDir::new().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let kept = root.join("Talks").join("b.mp4");
+        let gone = root.join("Talks").join("a.mp4");
+        let elsewhere = root.join("other").join("a.mp4");
+        for file in [&kept, &gone, &elsewhere] {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "video").unwrap();
+        }
+        fs::write(gone.with_extension("nfo"), "nfo").unwrap();
+
+        let mut library = Library::default();
+        library.add("a", &gone);
+        library.add("a", &elsewhere);
+        library.add("b", &kept);
+
+        let diff = snapshot(vec![entry("b", true)])
+            .diff(Some(&snapshot(vec![entry("a", true), entry("b", true)])));
+        let moves = removed_files(&library, &diff, &root.join("Talks"));
+        assert_eq!(moves.len(), 1);
+
+        let moved = move_files(&moves, &mut library, &diff).unwrap();
+        let target = root.join("Talks").join(REMOVED_DIR).join("a.mp4");
+        assert_eq!(moved, vec![target.clone()]);
+        assert!(!gone.exists());
+        assert!(target.is_file());
+        assert!(target.with_extension("nfo").is_file());
+        assert!(elsewhere.is_file() && kept.is_file());
+        assert!(library.files("a").contains(&target));
+
+        // Files already in `_removed/` stay where they are
+        assert!(removed_files(&library, &diff, &root.join("Talks")).is_empty());
+    }
+}
@@ -0,0 +1,420 @@
+//! Download plans for the global `--dry-run` flag.
+//!
+//! With `--dry-run` (or `--simulate`), `download`, `audio`, `playlist` and
+//! `sync` go through their whole pipeline (batch files, filters, playlist
+//! selection, routing rules and filename templates) but stop before yt-dlp
+//! downloads anything. Every video becomes a [`PlannedDownload`] describing
+//! the options it would be downloaded with, and the [`Plan`] is printed as
+//! text or, with `--dry-run=json`, as JSON.
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::plan::Plan;
+//!
+//! let mut plan = Plan::default();
+//! plan.push_failure("https://youtu.be/aaaaaaaaaaa", "Video unavailable");
+//!
+//! assert!(plan.render().contains("Video unavailable"));
+//! assert!(plan.to_json().unwrap().contains("\"failed\""));
+//! ```
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::utils::format_bytes;
+
+/// How `--dry-run` prints the plan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PlanFormat {
+    /// Readable text, one block per video
+    #[default]
+    Human,
+    /// A JSON document with every field
+    Json,
+}
+
+/// What a download would do, worked out without downloading.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedDownload {
+    /// Video URL
+    pub url: String,
+    /// Video ID
+    pub video_id: String,
+    /// Video title
+    pub title: String,
+    /// Routing rule that matched, e.g. `#2 (music)`
+    pub rule: Option<String>,
+    /// `true` for audio extractions
+    pub audio_only: bool,
+    /// Quality preset
+    pub quality: String,
+    /// yt-dlp `-f` selector, if one is passed
+    pub format: Option<String>,
+    /// yt-dlp `-S` sort order, if any
+    pub sort: Option<String>,
+    /// Extension of the final file
+    pub container: String,
+    /// Extension yt-dlp produces when the file is converted with FFmpeg afterwards
+    pub convert_from: Option<String>,
+    /// Output directory after routing
+    pub output_dir: PathBuf,
+    /// yt-dlp output template, with the playlist fields filled in
+    pub filename_template: String,
+    /// Expected path of the final file
+    pub output_path: PathBuf,
+    /// `true` when the template uses fields only yt-dlp fills in, so
+    /// `output_path` is a guess
+    pub output_path_approximate: bool,
+    /// `true` when `output_path` already exists, so the video would be
+    /// skipped
+    pub already_downloaded: bool,
+    /// Library file that would be linked instead of downloading
+    pub library_file: Option<PathBuf>,
+    /// Estimated size in bytes, when yt-dlp reports the stream sizes
+    pub estimated_size: Option<u64>,
+    /// Whether `<name>.info.json` would be written
+    pub write_info_json: bool,
+    /// Whether `<name>.nfo` would be written
+    pub write_nfo: bool,
+}
+
+impl PlannedDownload {
+    /// Returns whether yt-dlp would fetch the video, rather than it being
+    /// linked from the library or skipped.
+    pub fn is_fetched(&self) -> bool {
+        self.library_file.is_none() && !self.already_downloaded
+    }
+}
+
+/// A video whose plan could not be worked out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlanFailure {
+    /// Video or playlist URL
+    pub url: String,
+    /// Why it failed
+    pub error: String,
+}
+
+/// Everything a `--dry-run` would download.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Plan {
+    /// Planned downloads, in the order they would run
+    pub downloads: Vec<PlannedDownload>,
+    /// URLs that could not be checked
+    pub failed: Vec<PlanFailure>,
+}
+
+impl Plan {
+    /// Adds a URL that could not be checked.
+    pub fn push_failure(&mut self, url: impl Into<String>, error: impl ToString) {
+        self.failed.push(PlanFailure {
+            url: url.into(),
+            error: error.to_string(),
+        });
+    }
+
+    /// Returns the number of videos that would be fetched from YouTube,
+    /// leaving out those linked from the library or already downloaded.
+    pub fn to_download(&self) -> usize {
+        self.downloads
+            .iter()
+            .filter(|download| download.is_fetched())
+            .count()
+    }
+
+    /// Returns the number of videos that would be linked from the library.
+    pub fn from_library(&self) -> usize {
+        self.downloads
+            .iter()
+            .filter(|download| download.library_file.is_some() && !download.already_downloaded)
+            .count()
+    }
+
+    /// Returns the number of videos that would be skipped because their
+    /// file already exists.
+    pub fn already_downloaded(&self) -> usize {
+        self.downloads
+            .iter()
+            .filter(|download| download.already_downloaded)
+            .count()
+    }
+
+    /// Returns the estimated size of the videos that would be fetched, and
+    /// whether every one of them had a known size.
+    pub fn estimated_size(&self) -> (u64, bool) {
+        self.downloads
+            .iter()
+            .filter(|download| download.is_fetched())
+            .fold((0, true), |(total, complete), download| {
+                match download.estimated_size {
+                    Some(size) => (total + size, complete),
+                    None => (total, false),
+                }
+            })
+    }
+
+    /// Serializes the plan as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::Other` if serialization fails.
+    pub fn to_json(&self) -> AppResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Other(format!("Could not serialize plan: {}", e)))
+    }
+
+    /// Renders the plan as text.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+
+        for (position, download) in self.downloads.iter().enumerate() {
+            let _ = writeln!(text, "{:>4}. {}", position + 1, download.title);
+            let _ = writeln!(text, "      URL:     {}", download.url);
+            if let Some(rule) = &download.rule {
+                let _ = writeln!(text, "      Rule:    {}", rule);
+            }
+
+            let kind = if download.audio_only {
+                "audio"
+            } else {
+                "video"
+            };
+            let mut options = format!("{} {}, {}", kind, download.container, download.quality);
+            if let Some(from) = &download.convert_from {
+                let _ = write!(options, " (converted from {})", from);
+            }
+            let _ = writeln!(text, "      Options: {}", options);
+
+            if let Some(format) = &download.format {
+                let _ = writeln!(text, "      Format:  {}", format);
+            }
+            if let Some(sort) = &download.sort {
+                let _ = writeln!(text, "      Sort:    {}", sort);
+            }
+            let approximate = if download.output_path_approximate {
+                " (approximate)"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                text,
+                "      Output:  {}{}",
+                download.output_path.display(),
+                approximate
+            );
+
+            let mut sidecars = Vec::new();
+            if download.write_info_json {
+                sidecars.push("info.json");
+            }
+            if download.write_nfo {
+                sidecars.push("nfo");
+            }
+            if !sidecars.is_empty() {
+                let _ = writeln!(text, "      Sidecar: {}", sidecars.join(", "));
+            }
+
+            match (&download.library_file, download.estimated_size) {
+                _ if download.already_downloaded => {
+                    let _ = writeln!(text, "      Skip:    already downloaded");
+                }
+                (Some(file), _) => {
+                    let _ = writeln!(text, "      Library: linked from {}", file.display());
+                }
+                (None, Some(size)) => {
+                    let _ = writeln!(text, "      Size:    ~{}", format_bytes(size));
+                }
+                (None, None) => {
+                    let _ = writeln!(text, "      Size:    unknown");
+                }
+            }
+        }
+
+        for failure in &self.failed {
+            let _ = writeln!(text, "      Failed:  {}: {}", failure.url, failure.error);
+        }
+
+        let (size, complete) = self.estimated_size();
+        let size = match (size, complete) {
+            (0, false) => "unknown size".to_string(),
+            (size, true) => format!("~{}", format_bytes(size)),
+            (size, false) => format!("more than {}", format_bytes(size)),
+        };
+        let skipped = match self.already_downloaded() {
+            0 => String::new(),
+            count => format!(", {} already downloaded", count),
+        };
+        let _ = writeln!(
+            text,
+            "\n{} videos planned: {} to download ({}), {} from the library{}, {} failed",
+            self.downloads.len(),
+            self.to_download(),
+            size,
+            self.from_library(),
+            skipped,
+            self.failed.len()
+        );
+
+        text
+    }
+
+    /// Prints the plan in `format`, then fails if some URLs could not be
+    /// checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::Other` if the plan has failures or cannot be
+    /// serialized.
+    pub fn finish(&self, format: PlanFormat) -> AppResult<()> {
+        match format {
+            PlanFormat::Human => print!("{}", self.render()),
+            PlanFormat::Json => println!("{}", self.to_json()?),
+        }
+
+        if self.failed.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Other(format!(
+                "{} URLs could not be checked",
+                self.failed.len()
+            )))
+        }
+    }
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned(id: &str, size: Option<u64>, library_file: Option<&str>) -> PlannedDownload {
+        PlannedDownload {
+            url: format!("https://www.youtube.com/watch?v={}", id),
+            video_id: id.to_string(),
+            title: format!("Video {}", id),
+            rule: None,
+            audio_only: false,
+            quality: "1080p".to_string(),
+            format: Some("bv*[height<=1080]+ba/b[height<=1080]".to_string()),
+            sort: None,
+            container: "mp4".to_string(),
+            convert_from: None,
+            output_dir: PathBuf::from("/videos"),
+            filename_template: "%(title)s.%(ext)s".to_string(),
+            output_path: PathBuf::from(format!("/videos/Video_{}.mp4", id)),
+            output_path_approximate: false,
+            already_downloaded: false,
+            library_file: library_file.map(PathBuf::from),
+            estimated_size: size,
+            write_info_json: false,
+            write_nfo: false,
+        }
+    }
+
+    #[test]
+    fn test_estimated_size_skips_library_files() {
+        let plan = Plan {
+            downloads: vec![
+                planned("a", Some(1000), None),
+                planned("b", Some(5000), Some("/old/b.mp4")),
+                planned("c", Some(500), None),
+            ],
+            failed: Vec::new(),
+        };
+
+        assert_eq!(plan.to_download(), 2);
+        assert_eq!(plan.estimated_size(), (1500, true));
+    }
+
+    #[test]
+    fn test_estimated_size_incomplete() {
+        let plan = Plan {
+            downloads: vec![planned("a", Some(1000), None), planned("b", None, None)],
+            failed: Vec::new(),
+        };
+
+        assert_eq!(plan.estimated_size(), (1000, false));
+        assert!(plan.render().contains("more than"));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut download = planned("a", Some(2048), None);
+        download.rule = Some("#1 (music)".to_string());
+        download.convert_from = Some("mp4".to_string());
+        download.container = "avi".to_string();
+        download.write_nfo = true;
+
+        let mut plan = Plan {
+            downloads: vec![download, planned("b", None, Some("/old/b.mp4"))],
+            failed: Vec::new(),
+        };
+        plan.push_failure("https://youtu.be/ccccccccccc", "Video unavailable");
+
+        let text = plan.render();
+        assert!(text.contains("   1. Video a\n"));
+        assert!(text.contains("Rule:    #1 (music)"));
+        assert!(text.contains("Options: video avi, 1080p (converted from mp4)"));
+        assert!(text.contains("Output:  /videos/Video_a.mp4"));
+        assert!(text.contains("Sidecar: nfo"));
+        assert!(text.contains("Library: linked from /old/b.mp4"));
+        assert!(text.contains("https://youtu.be/ccccccccccc: Video unavailable"));
+        assert!(text.contains("2 videos planned: 1 to download"));
+        assert!(text.contains("1 from the library, 1 failed"));
+    }
+
+    #[test]
+    fn test_already_downloaded_is_skipped() {
+        let mut skipped = planned("b", Some(5000), None);
+        skipped.already_downloaded = true;
+        let mut guessed = planned("c", Some(500), None);
+        guessed.output_path_approximate = true;
+        let plan = Plan {
+            downloads: vec![planned("a", Some(1000), None), skipped, guessed],
+            failed: Vec::new(),
+        };
+
+        assert_eq!(plan.to_download(), 2);
+        assert_eq!(plan.already_downloaded(), 1);
+        assert_eq!(plan.estimated_size(), (1500, true));
+
+        let text = plan.render();
+        assert!(text.contains("Skip:    already downloaded"));
+        assert!(text.contains("Output:  /videos/Video_c.mp4 (approximate)"));
+        assert!(text.contains("0 from the library, 1 already downloaded, 0 failed"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let plan = Plan {
+            downloads: vec![planned("a", Some(10), None)],
+            failed: Vec::new(),
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&plan.to_json().unwrap()).unwrap();
+        assert_eq!(json["downloads"][0]["video_id"], "a");
+        assert_eq!(json["downloads"][0]["estimated_size"], 10);
+        assert_eq!(
+            json["downloads"][0]["library_file"],
+            serde_json::Value::Null
+        );
+        assert!(json["failed"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_finish_fails_with_failures() {
+        let mut plan = Plan::default();
+        assert!(plan.finish(PlanFormat::Json).is_ok());
+
+        plan.push_failure("https://youtu.be/aaaaaaaaaaa", "boom");
+        assert!(plan.finish(PlanFormat::Json).is_err());
+    }
+}
@@ -0,0 +1,825 @@
+//! Local HTTP API for `ytdl serve`.
+//!
+//! Other programs queue downloads by posting JSON instead of running `ytdl`.
+//! Every job is downloaded with a [`Downloader`] built like the one of
+//! `ytdl download`: config file, routing rules and library, with the job's
+//! own options on top, as if they were command-line flags.
+//!
+//! | Method   | Path         | Does                                            |
+//! |----------|--------------|-------------------------------------------------|
+//! | `POST`   | `/jobs`      | Queues a [`JobRequest`], answers `202` and the job |
+//! | `GET`    | `/jobs`      | Lists every job since the server started        |
+//! | `GET`    | `/jobs/{id}` | Returns a [`JobStatus`] with live progress      |
+//! | `DELETE` | `/jobs/{id}` | Cancels a queued or running job                 |
+//! | `GET`    | `/history`   | Searches the download history                   |
+//!
+//! Errors are answered as `{"error": "..."}`. With a token, every request
+//! needs an `Authorization: Bearer <token>` header.
+//!
+//! Jobs are kept in memory only; finished jobs are also appended to the
+//! download history, like any other download.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rust_yt_downloader::config::ConfigLoader;
+//! use rust_yt_downloader::server::Server;
+//!
+//! # async fn example() -> rust_yt_downloader::error::AppResult<()> {
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:8765").await?;
+//! let server = Server::new(ConfigLoader::new())
+//!     .with_token(Some("s3cret".to_string()))
+//!     .with_jobs(2);
+//!
+//! server.run(listener, async {
+//!     let _ = tokio::signal::ctrl_c().await;
+//! })
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Component, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+
+use crate::cli::VideoQuality;
+use crate::config::ConfigLoader;
+use crate::downloader::{
+    DownloadMonitor, DownloadOptions, DownloadResult, Downloader, OptionOverrides, TransferProgress,
+};
+use crate::error::{AppError, AppResult};
+use crate::history::{parse_date, History, HistoryEntry, HistoryQuery};
+use crate::library::Library;
+use crate::media::{AudioFormat, VideoFormat};
+use crate::progress::messages;
+use crate::space::parse_size;
+use crate::youtube::{validate_url, AudioCodec, HdrMode, VideoCodec};
+
+/// Body of `POST /jobs`: a URL and the options to change for it.
+///
+/// The option names are those of `[[rules]]` in the config file. Options
+/// left out keep their configured value.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::server::JobRequest;
+/// use std::path::Path;
+///
+/// let request: JobRequest = serde_json::from_str(
+///     r#"{"url": "https://youtu.be/dQw4w9WgXcQ", "quality": "720p", "vcodec": ["av1"]}"#,
+/// )
+/// .unwrap();
+///
+/// let overrides = request.overrides(Path::new("/srv/videos")).unwrap();
+/// assert_eq!(overrides.keys(), vec!["quality", "vcodec"]);
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobRequest {
+    /// Video URL
+    pub url: String,
+    /// Output directory, relative to the configured one
+    pub output_dir: Option<String>,
+    /// Quality preset, e.g. `1080p` or `best`
+    pub quality: Option<String>,
+    /// Explicit yt-dlp format selection, e.g. `137+140`
+    pub format_id: Option<String>,
+    /// Container of video downloads, e.g. `mkv`
+    pub video_format: Option<String>,
+    /// Format of audio downloads, e.g. `flac`
+    pub audio_format: Option<String>,
+    /// Extracts the audio only
+    pub audio_only: Option<bool>,
+    /// yt-dlp output template, relative to the output directory
+    pub filename_template: Option<String>,
+    /// Preferred video codecs, best first
+    pub vcodec: Option<Vec<VideoCodec>>,
+    /// Preferred audio codecs, best first
+    pub acodec: Option<Vec<AudioCodec>>,
+    /// Preferred frame rate
+    pub fps: Option<u32>,
+    /// HDR preference
+    pub hdr: Option<HdrMode>,
+    /// Prefers vertical videos
+    pub vertical: Option<bool>,
+    /// Writes `<name>.info.json`
+    pub write_info_json: Option<bool>,
+    /// Writes `<name>.nfo`
+    pub write_nfo: Option<bool>,
+    /// Skips the video above this size, e.g. `500M`
+    pub max_filesize: Option<String>,
+    /// Records a live stream from its beginning
+    pub live_from_start: Option<bool>,
+    /// Waits for an upcoming live stream or premiere to start
+    pub wait_for_video: Option<bool>,
+    /// Stops recording a live stream after this many minutes
+    pub live_duration: Option<u32>,
+}
+
+impl JobRequest {
+    /// Returns the download options set by the request.
+    ///
+    /// `output_dir` is resolved against `base`, the configured output
+    /// directory, so that clients cannot write anywhere else.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` for an unknown quality or format,
+    /// or for an `output_dir` or `filename_template` that is absolute or
+    /// contains `..`.
+    pub fn overrides(&self, base: &std::path::Path) -> AppResult<OptionOverrides> {
+        let output_dir = self
+            .output_dir
+            .as_deref()
+            .map(|dir| relative_path("output_dir", dir).map(|dir| base.join(dir)))
+            .transpose()?;
+        if let Some(template) = &self.filename_template {
+            relative_path("filename_template", template)?;
+        }
+        let quality = self
+            .quality
+            .as_deref()
+            .map(|quality| {
+                VideoQuality::from_str(quality, true).map_err(|_| {
+                    AppError::invalid_arg("quality", format!("unknown quality '{}'", quality))
+                })
+            })
+            .transpose()?;
+        let video_format = self
+            .video_format
+            .as_deref()
+            .map(|format| {
+                VideoFormat::from_extension(format).ok_or_else(|| {
+                    AppError::invalid_arg("video_format", format!("unknown format '{}'", format))
+                })
+            })
+            .transpose()?;
+        let audio_format = self
+            .audio_format
+            .as_deref()
+            .map(|format| {
+                AudioFormat::from_extension(format).ok_or_else(|| {
+                    AppError::invalid_arg("audio_format", format!("unknown format '{}'", format))
+                })
+            })
+            .transpose()?;
+
+        Ok(OptionOverrides {
+            output_dir,
+            quality,
+            format_id: self.format_id.clone(),
+            video_format,
+            audio_format,
+            audio_only: self.audio_only,
+            filename_template: self.filename_template.clone(),
+            video_codecs: self.vcodec.clone(),
+            audio_codecs: self.acodec.clone(),
+            fps: self.fps,
+            hdr: self.hdr,
+            prefer_vertical: self.vertical,
+            write_info_json: self.write_info_json,
+            write_nfo: self.write_nfo,
+        })
+    }
+
+    /// Returns `options` with the request's size and live stream settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if `max_filesize` is not a size.
+    fn apply(&self, mut options: DownloadOptions) -> AppResult<DownloadOptions> {
+        if let Some(size) = &self.max_filesize {
+            options = options.with_max_filesize(Some(parse_size(size)?));
+        }
+        if let Some(enabled) = self.live_from_start {
+            options = options.with_live_from_start(enabled);
+        }
+        if let Some(enabled) = self.wait_for_video {
+            options = options.with_wait_for_video(enabled);
+        }
+        if self.live_duration.is_some() {
+            options = options.with_live_duration(self.live_duration);
+        }
+
+        Ok(options)
+    }
+}
+
+/// Where a job is in its life.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for a free download slot
+    Queued,
+    /// Being downloaded
+    Running,
+    /// Downloaded successfully
+    Finished,
+    /// Stopped by an error
+    Failed,
+    /// Cancelled with `DELETE /jobs/{id}`
+    Cancelled,
+}
+
+impl JobState {
+    /// Returns `true` once the job can no longer change.
+    pub fn is_done(self) -> bool {
+        matches!(self, Self::Finished | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A job as returned by the API.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    /// Job ID, unique while the server runs
+    pub id: u64,
+    /// Video URL
+    pub url: String,
+    /// Current state
+    pub state: JobState,
+    /// When the job was queued, in RFC 3339 format
+    pub created: String,
+    /// Last progress reported by yt-dlp, while running
+    pub progress: Option<TransferProgress>,
+    /// Video ID, once finished
+    pub video_id: Option<String>,
+    /// Video title, once finished
+    pub title: Option<String>,
+    /// Path of the downloaded file, once finished
+    pub file: Option<PathBuf>,
+    /// Size of the downloaded file in bytes, once finished
+    pub size: Option<u64>,
+    /// Why the job failed
+    pub error: Option<String>,
+}
+
+/// A job and the monitor of its download.
+struct Job {
+    status: JobStatus,
+    monitor: Arc<DownloadMonitor>,
+}
+
+impl Job {
+    /// Returns the job's status with the current progress.
+    fn snapshot(&self) -> JobStatus {
+        let mut status = self.status.clone();
+        if status.state == JobState::Running {
+            status.progress = self.monitor.progress();
+        }
+        status
+    }
+}
+
+/// State shared by every request.
+struct ServerState {
+    loader: ConfigLoader,
+    token: Option<String>,
+    slots: Semaphore,
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, Job>>,
+}
+
+/// Checks that a path sent by a client stays inside the directory it is
+/// resolved against: it must be relative and must not contain `..`.
+fn relative_path<'a>(field: &str, path: &'a str) -> AppResult<&'a std::path::Path> {
+    let path = std::path::Path::new(path);
+    let inside = path
+        .components()
+        .all(|part| matches!(part, Component::Normal(_) | Component::CurDir));
+
+    if inside {
+        Ok(path)
+    } else {
+        Err(AppError::invalid_arg(
+            field,
+            format!("'{}' must be a relative path without '..'", path.display()),
+        ))
+    }
+}
+
+impl ServerState {
+    fn jobs(&self) -> MutexGuard<'_, BTreeMap<u64, Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Builds the downloader for a job, as `ytdl download` would with the
+    /// request's options as flags.
+    fn downloader(
+        &self,
+        request: &JobRequest,
+        monitor: Arc<DownloadMonitor>,
+    ) -> AppResult<Downloader> {
+        let config = self.loader.load()?;
+        let options = DownloadOptions::from_config(&config).with_silence(true);
+        let flags = request.overrides(&options.output_dir)?;
+        let options = request.apply(options)?;
+
+        Ok(Downloader::with_options(options)
+            .with_rules(config.rules)
+            .with_flags(flags)
+            .with_library(Library::path_beside(&self.loader.user_path()?))
+            .with_monitor(monitor))
+    }
+
+    /// Changes a job, if it still exists.
+    fn update(&self, id: u64, change: impl FnOnce(&mut JobStatus)) {
+        if let Some(job) = self.jobs().get_mut(&id) {
+            change(&mut job.status);
+        }
+    }
+
+    /// Cancels every job that has not ended.
+    fn cancel_all(&self) {
+        for job in self.jobs().values_mut() {
+            if !job.status.state.is_done() {
+                job.monitor.cancel();
+                job.status.state = JobState::Cancelled;
+            }
+        }
+    }
+
+    /// Appends a finished job to the download history.
+    fn record(&self, url: &str, result: &AppResult<DownloadResult>) {
+        let entry = match result {
+            Ok(result) => HistoryEntry::success(url, result),
+            Err(e) => HistoryEntry::failure(url, &e.to_string()),
+        };
+        let written = self
+            .loader
+            .user_path()
+            .and_then(|path| History::beside(&path).append(&[entry]));
+
+        if let Err(e) = written {
+            messages::warning(&format!("Could not update download history: {}", e));
+        }
+    }
+}
+
+/// The `ytdl serve` HTTP server.
+#[derive(Debug, Clone)]
+pub struct Server {
+    loader: ConfigLoader,
+    token: Option<String>,
+    jobs: usize,
+}
+
+impl Server {
+    /// Creates a server reading the configuration with `loader`, without a
+    /// token and downloading one job at a time.
+    pub fn new(loader: ConfigLoader) -> Self {
+        Self {
+            loader,
+            token: None,
+            jobs: 1,
+        }
+    }
+
+    /// Requires `Authorization: Bearer <token>` on every request.
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Downloads up to `jobs` jobs at once; the others wait in the queue.
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Serves the API on `listener` until `shutdown` completes, then cancels
+    /// the jobs still queued or running.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::Io` if the server fails.
+    pub async fn run(
+        self,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> AppResult<()> {
+        let state = Arc::new(ServerState {
+            loader: self.loader,
+            token: self.token,
+            slots: Semaphore::new(self.jobs),
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(BTreeMap::new()),
+        });
+
+        let served = axum::serve(listener, router(state.clone()))
+            .with_graceful_shutdown(shutdown)
+            .await;
+        state.cancel_all();
+
+        Ok(served?)
+    }
+}
+
+/// Returns the routes of the API.
+fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/jobs", get(list_jobs).post(create_job))
+        .route("/jobs/{id}", get(get_job).delete(cancel_job))
+        .route("/history", get(get_history))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// An error answered as `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl From<AppError> for ApiError {
+    fn from(error: AppError) -> Self {
+        let status = match error {
+            AppError::InvalidUrl(_) | AppError::InvalidArgument { .. } => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self(status, error.to_string())
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self(rejection.status(), rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.1 }));
+        (self.0, body).into_response()
+    }
+}
+
+/// Rejects requests without the server's token, if it has one.
+async fn require_token(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = &state.token else {
+        return next.run(request).await;
+    };
+
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    let matches = given.is_some_and(|given| bool::from(given.as_bytes().ct_eq(token.as_bytes())));
+    if matches {
+        next.run(request).await
+    } else {
+        let mut response = ApiError(
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong bearer token".to_string(),
+        )
+        .into_response();
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+        response
+    }
+}
+
+/// `POST /jobs`
+async fn create_job(
+    State(state): State<Arc<ServerState>>,
+    request: Result<Json<JobRequest>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(request) = request?;
+    let url = validate_url(&request.url)?;
+    let monitor = Arc::new(DownloadMonitor::new());
+    let downloader = state.downloader(&request, monitor.clone())?;
+
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+    let status = JobStatus {
+        id,
+        url: url.clone(),
+        state: JobState::Queued,
+        created: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        progress: None,
+        video_id: None,
+        title: None,
+        file: None,
+        size: None,
+        error: None,
+    };
+    state.jobs().insert(
+        id,
+        Job {
+            status: status.clone(),
+            monitor: monitor.clone(),
+        },
+    );
+    messages::info(&format!("Job {} queued: {}", id, url));
+
+    tokio::spawn(run_job(state, id, url, downloader, monitor));
+
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/jobs/{}", id))],
+        Json(status),
+    ))
+}
+
+/// Downloads a job once a slot is free and records the outcome.
+async fn run_job(
+    state: Arc<ServerState>,
+    id: u64,
+    url: String,
+    downloader: Downloader,
+    monitor: Arc<DownloadMonitor>,
+) {
+    let Ok(_slot) = state.slots.acquire().await else {
+        return;
+    };
+    if monitor.is_cancelled() {
+        return;
+    }
+    state.update(id, |status| status.state = JobState::Running);
+
+    // The download waits on yt-dlp, so it runs off the async workers
+    let runtime = tokio::runtime::Handle::current();
+    let result = tokio::task::spawn_blocking({
+        let url = url.clone();
+        move || runtime.block_on(downloader.download(&url))
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::Other(format!("Download task failed: {}", e))));
+
+    // A job cancelled while yt-dlp was finishing stays cancelled: the client
+    // has already been told so
+    let mut cancelled = false;
+    state.update(id, |status| {
+        if status.state == JobState::Cancelled {
+            cancelled = true;
+            return;
+        }
+        match &result {
+            Ok(result) => {
+                status.state = JobState::Finished;
+                status.video_id = Some(result.video_id.clone());
+                status.title = Some(result.video_title.clone());
+                status.file = Some(result.file_path.clone());
+                status.size = Some(result.file_size);
+            }
+            Err(AppError::Cancelled) => {
+                cancelled = true;
+                status.state = JobState::Cancelled;
+            }
+            Err(e) => {
+                status.state = JobState::Failed;
+                status.error = Some(e.to_string());
+            }
+        }
+    });
+
+    if cancelled {
+        messages::info(&format!("Job {} cancelled", id));
+        return;
+    }
+    match &result {
+        Ok(result) => messages::success(&format!(
+            "Job {} finished: {}",
+            id,
+            result.file_path.display()
+        )),
+        Err(e) => messages::warning(&format!("Job {} failed: {}", id, e)),
+    }
+    state.record(&url, &result);
+}
+
+/// `GET /jobs`
+async fn list_jobs(State(state): State<Arc<ServerState>>) -> Json<Vec<JobStatus>> {
+    Json(state.jobs().values().map(Job::snapshot).collect())
+}
+
+/// `GET /jobs/{id}`
+async fn get_job(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<JobStatus>, ApiError> {
+    state
+        .jobs()
+        .get(&id)
+        .map(|job| Json(job.snapshot()))
+        .ok_or_else(|| job_not_found(id))
+}
+
+/// `DELETE /jobs/{id}`
+async fn cancel_job(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<u64>,
+) -> Result<Json<JobStatus>, ApiError> {
+    let mut jobs = state.jobs();
+    let job = jobs.get_mut(&id).ok_or_else(|| job_not_found(id))?;
+
+    if job.status.state.is_done() {
+        return Err(ApiError(
+            StatusCode::CONFLICT,
+            format!("Job {} has already ended", id),
+        ));
+    }
+
+    job.monitor.cancel();
+    job.status.state = JobState::Cancelled;
+    Ok(Json(job.snapshot()))
+}
+
+fn job_not_found(id: u64) -> ApiError {
+    ApiError(StatusCode::NOT_FOUND, format!("No job {}", id))
+}
+
+/// Query of `GET /history`, with the filters of `ytdl history`.
+#[derive(Debug, Default, Deserialize)]
+struct HistoryParams {
+    search: Option<String>,
+    since: Option<String>,
+    #[serde(default)]
+    failed: bool,
+}
+
+/// `GET /history`
+async fn get_history(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<Vec<HistoryEntry>>, ApiError> {
+    let since = params.since.as_deref().map(parse_date).transpose()?;
+    let query = HistoryQuery::new()
+        .with_search(params.search)
+        .with_since(since)
+        .with_failed_only(params.failed);
+
+    let history = History::beside(&state.loader.user_path()?);
+    Ok(Json(history.search(&query)?))
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> PathBuf {
+        PathBuf::from("/srv/videos")
+    }
+
+    fn request(json: &str) -> JobRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    // ============== JobRequest Tests ==============
+
+    #[test]
+    fn test_request_overrides() {
+        let request = request(
+            r#"{
+                "url": "https://youtu.be/dQw4w9WgXcQ",
+                "quality": "720p",
+                "video_format": "MKV",
+                "audio_only": true,
+                "hdr": "never",
+                "write_nfo": true
+            }"#,
+        );
+
+        let overrides = request.overrides(&base()).unwrap();
+        assert!(matches!(overrides.quality, Some(VideoQuality::Q720p)));
+        assert_eq!(overrides.video_format, Some(VideoFormat::Mkv));
+        assert_eq!(overrides.audio_only, Some(true));
+        assert_eq!(overrides.hdr, Some(HdrMode::Never));
+        assert_eq!(
+            overrides.keys(),
+            vec!["quality", "video_format", "audio_only", "hdr", "write_nfo"]
+        );
+    }
+
+    #[test]
+    fn test_request_url_only() {
+        let request = request(r#"{"url": "https://youtu.be/dQw4w9WgXcQ"}"#);
+        assert!(request.overrides(&base()).unwrap().keys().is_empty());
+    }
+
+    #[test]
+    fn test_request_rejects_unknown_values() {
+        let bad_quality = request(r#"{"url": "u", "quality": "8k"}"#);
+        assert!(matches!(
+            bad_quality.overrides(&base()),
+            Err(AppError::InvalidArgument { .. })
+        ));
+
+        let bad_format = request(r#"{"url": "u", "audio_format": "wma"}"#);
+        assert!(bad_format.overrides(&base()).is_err());
+
+        let unknown_field = serde_json::from_str::<JobRequest>(r#"{"url": "u", "qualty": "720p"}"#);
+        assert!(unknown_field.is_err());
+    }
+
+    #[test]
+    fn test_request_paths_stay_in_output_dir() {
+        let nested = request(
+            r#"{"url": "u", "output_dir": "music/./live", "filename_template": "%(channel)s/%(title)s.%(ext)s"}"#,
+        );
+        let overrides = nested.overrides(&base()).unwrap();
+        assert_eq!(
+            overrides.output_dir,
+            Some(PathBuf::from("/srv/videos/music/./live"))
+        );
+
+        for body in [
+            r#"{"url": "u", "output_dir": "/etc"}"#,
+            r#"{"url": "u", "output_dir": "../elsewhere"}"#,
+            r#"{"url": "u", "output_dir": "music/../../elsewhere"}"#,
+            r#"{"url": "u", "filename_template": "/tmp/%(title)s.%(ext)s"}"#,
+            r#"{"url": "u", "filename_template": "../%(title)s.%(ext)s"}"#,
+        ] {
+            assert!(
+                matches!(
+                    request(body).overrides(&base()),
+                    Err(AppError::InvalidArgument { .. })
+                ),
+                "{}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn test_request_apply() {
+        let request = request(
+            r#"{"url": "u", "max_filesize": "1G", "wait_for_video": true, "live_duration": 30}"#,
+        );
+
+        let options = request.apply(DownloadOptions::default()).unwrap();
+        assert_eq!(options.max_filesize, Some(1024 * 1024 * 1024));
+        assert!(options.wait_for_video);
+        assert!(!options.live_from_start);
+        assert_eq!(options.live_duration, Some(30));
+
+        let bad_size = self::request(r#"{"url": "u", "max_filesize": "big"}"#);
+        assert!(bad_size.apply(DownloadOptions::default()).is_err());
+    }
+
+    // ============== Job Tests ==============
+
+    #[test]
+    fn test_job_state_is_done() {
+        assert!(!JobState::Queued.is_done());
+        assert!(!JobState::Running.is_done());
+        assert!(JobState::Finished.is_done());
+        assert!(JobState::Failed.is_done());
+        assert!(JobState::Cancelled.is_done());
+    }
+
+    #[test]
+    fn test_job_status_json() {
+        let status = JobStatus {
+            id: 7,
+            url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            state: JobState::Running,
+            created: "2024-05-01T12:00:00Z".to_string(),
+            progress: TransferProgress::parse("[download]  50.0% of 2.00MiB"),
+            video_id: None,
+            title: None,
+            file: None,
+            size: None,
+            error: None,
+        };
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["state"], "running");
+        assert_eq!(json["progress"]["percent"], 50.0);
+        assert_eq!(json["progress"]["total_bytes"], 2 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_api_error_status() {
+        let ApiError(status, _) = AppError::InvalidUrl("x".to_string()).into();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let ApiError(status, _) = AppError::Other("x".to_string()).into();
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
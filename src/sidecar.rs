@@ -0,0 +1,328 @@
+//! Metadata files written next to downloads for media servers.
+//!
+//! Two kinds of sidecar are supported, both enabled per download:
+//!
+//! - **`<name>.info.json`**: the video's [`VideoInfo`] serialized as JSON.
+//!   `ytdl library scan` and `ytdl feed build` read it back.
+//! - **`<name>.nfo`**: Kodi/Jellyfin XML with title, plot, premiered date,
+//!   studio (the channel), runtime, the YouTube ID and thumbnail references.
+//!
+//! When a file is saved in a folder named after its channel (for example
+//! with a rule `output_dir = "~/Videos/{channel}"`), the folder is treated as
+//! a show: the NFO uses `<episodedetails>` and a `tvshow.nfo` describing the
+//! channel is written in the folder. Otherwise the NFO is a `<movie>`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use rust_yt_downloader::sidecar;
+//! use rust_yt_downloader::youtube::YtDlpClient;
+//!
+//! let info = YtDlpClient::new().get_video_info("https://youtu.be/dQw4w9WgXcQ")?;
+//! let video = Path::new("/media/Rick Astley/Never Gonna Give You Up.mp4");
+//!
+//! sidecar::write_info_json(&info, video)?;
+//! sidecar::write_nfo(&info, video)?;
+//! # Ok::<(), rust_yt_downloader::error::AppError>(())
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::utils::sanitize_filename;
+use crate::youtube::VideoInfo;
+
+/// Name of the show description written in a channel folder.
+pub const TVSHOW_NFO: &str = "tvshow.nfo";
+
+/// Image extensions checked for a local thumbnail next to the media file.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// Writes `<name>.info.json` next to `media` and returns its path.
+///
+/// # Errors
+///
+/// Returns `AppError::FileWrite` if the file cannot be written.
+pub fn write_info_json(info: &VideoInfo, media: &Path) -> AppResult<PathBuf> {
+    let path = media.with_extension("info.json");
+    let json = serde_json::to_string_pretty(info)
+        .map_err(|e| AppError::Other(format!("Could not serialize video info: {}", e)))?;
+
+    fs::write(&path, json).map_err(|e| AppError::file_write(&path, e))?;
+    Ok(path)
+}
+
+/// Writes `<name>.nfo` next to `media`, and `tvshow.nfo` in its folder when
+/// the folder is the channel's. Returns the paths written.
+///
+/// # Errors
+///
+/// Returns `AppError::FileWrite` if a file cannot be written.
+pub fn write_nfo(info: &VideoInfo, media: &Path) -> AppResult<Vec<PathBuf>> {
+    let folder = media.parent().unwrap_or(Path::new(""));
+    let thumb = local_thumbnail(media);
+    let show = is_channel_folder(folder, info.channel.as_deref());
+
+    let nfo = media.with_extension("nfo");
+    let xml = if show {
+        episode_nfo(info, thumb.as_deref())
+    } else {
+        movie_nfo(info, thumb.as_deref())
+    };
+    fs::write(&nfo, xml).map_err(|e| AppError::file_write(&nfo, e))?;
+
+    let mut written = vec![nfo];
+    if let (true, Some(channel)) = (show, info.channel.as_deref()) {
+        let tvshow = folder.join(TVSHOW_NFO);
+        fs::write(&tvshow, tvshow_nfo(channel)).map_err(|e| AppError::file_write(&tvshow, e))?;
+        written.push(tvshow);
+    }
+
+    Ok(written)
+}
+
+/// Returns `true` if `folder` is named after `channel`, as the `{channel}`
+/// placeholder of rules and the `%(channel)s` template field name it.
+pub fn is_channel_folder(folder: &Path, channel: Option<&str>) -> bool {
+    let (Some(name), Some(channel)) = (folder.file_name(), channel) else {
+        return false;
+    };
+    let name = name.to_string_lossy();
+    let sanitized = sanitize_filename(channel);
+
+    name == channel || name == sanitized || name == sanitized.replace(' ', "_")
+}
+
+/// Renders a `<movie>` NFO.
+pub fn movie_nfo(info: &VideoInfo, local_thumb: Option<&str>) -> String {
+    nfo_document("movie", info, local_thumb)
+}
+
+/// Renders an `<episodedetails>` NFO.
+pub fn episode_nfo(info: &VideoInfo, local_thumb: Option<&str>) -> String {
+    nfo_document("episodedetails", info, local_thumb)
+}
+
+/// Renders the `tvshow.nfo` of a channel folder.
+pub fn tvshow_nfo(channel: &str) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    xml.push_str("<tvshow>\n");
+    push_tag(&mut xml, "title", channel);
+    push_tag(&mut xml, "studio", channel);
+    xml.push_str("</tvshow>\n");
+    xml
+}
+
+fn nfo_document(root: &str, info: &VideoInfo, local_thumb: Option<&str>) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    xml.push_str(&format!("<{}>\n", root));
+
+    push_tag(&mut xml, "title", &info.title);
+    if let Some(plot) = info.description.as_deref().filter(|d| !d.is_empty()) {
+        push_tag(&mut xml, "plot", plot);
+    }
+    if let Some(date) = &info.publish_date {
+        push_tag(&mut xml, "premiered", date);
+        if root == "episodedetails" {
+            push_tag(&mut xml, "aired", date);
+        }
+    }
+    if let Some(channel) = &info.channel {
+        push_tag(&mut xml, "studio", channel);
+    }
+    if info.duration > 0 {
+        push_tag(&mut xml, "runtime", &info.duration.div_ceil(60).to_string());
+    }
+    xml.push_str(&format!(
+        "  <uniqueid type=\"youtube\" default=\"true\">{}</uniqueid>\n",
+        escape(&info.id)
+    ));
+    if let Some(thumb) = local_thumb {
+        xml.push_str(&format!(
+            "  <thumb aspect=\"thumb\">{}</thumb>\n",
+            escape(thumb)
+        ));
+    }
+    if let Some(url) = &info.thumbnail_url {
+        xml.push_str(&format!(
+            "  <thumb aspect=\"thumb\">{}</thumb>\n",
+            escape(url)
+        ));
+    }
+
+    xml.push_str(&format!("</{}>\n", root));
+    xml
+}
+
+/// Returns the file name of an image saved next to `media` with the same
+/// name, if any.
+fn local_thumbnail(media: &Path) -> Option<String> {
+    IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| media.with_extension(ext))
+        .find(|image| image.is_file())
+        .and_then(|image| image.file_name().map(|n| n.to_string_lossy().into_owned()))
+}
+
+fn push_tag(xml: &mut String, tag: &str, text: &str) {
+    xml.push_str(&format!("  <{}>{}</{}>\n", tag, escape(text), tag));
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::youtube::LiveStatus;
+    use tempfile::TempDir;
+
+    fn sample_info() -> VideoInfo {
+        VideoInfo {
+            id: "abc123".to_string(),
+            title: "Tom & Jerry <Live>".to_string(),
+            description: Some("A \"classic\" show".to_string()),
+            duration: 125,
+            thumbnail_url: Some("https://i.ytimg.com/vi/abc123/hq.jpg".to_string()),
+            channel: Some("Cartoon Hits".to_string()),
+            publish_date: Some("2024-05-01".to_string()),
+            view_count: Some(10),
+            streams: Vec::new(),
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
+        }
+    }
+
+    // ============== Rendering Tests ==============
+
+    #[test]
+    fn test_movie_nfo() {
+        let xml = movie_nfo(&sample_info(), None);
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>"));
+        assert!(xml.contains("<movie>\n"));
+        assert!(xml.contains("<title>Tom &amp; Jerry &lt;Live&gt;</title>"));
+        assert!(xml.contains("<plot>A &quot;classic&quot; show</plot>"));
+        assert!(xml.contains("<premiered>2024-05-01</premiered>"));
+        assert!(!xml.contains("<aired>"));
+        assert!(xml.contains("<studio>Cartoon Hits</studio>"));
+        assert!(xml.contains("<runtime>3</runtime>"));
+        assert!(xml.contains("<uniqueid type=\"youtube\" default=\"true\">abc123</uniqueid>"));
+        assert!(
+            xml.contains("<thumb aspect=\"thumb\">https://i.ytimg.com/vi/abc123/hq.jpg</thumb>")
+        );
+        assert!(xml.trim_end().ends_with("</movie>"));
+    }
+
+    #[test]
+    fn test_episode_nfo_with_local_thumb() {
+        let xml = episode_nfo(&sample_info(), Some("Tom_Jerry.jpg"));
+
+        assert!(xml.contains("<episodedetails>\n"));
+        assert!(xml.contains("<aired>2024-05-01</aired>"));
+        let local = xml.find("Tom_Jerry.jpg").unwrap();
+        let remote = xml.find("hq.jpg").unwrap();
+        assert!(local < remote);
+    }
+
+    #[test]
+    fn test_nfo_skips_missing_fields() {
+        let mut info = sample_info();
+        info.description = None;
+        info.publish_date = None;
+        info.channel = None;
+        info.thumbnail_url = None;
+        info.duration = 0;
+
+        let xml = movie_nfo(&info, None);
+        for tag in ["<plot>", "<premiered>", "<studio>", "<runtime>", "<thumb"] {
+            assert!(!xml.contains(tag), "unexpected {tag}");
+        }
+    }
+
+    #[test]
+    fn test_tvshow_nfo() {
+        let xml = tvshow_nfo("AC/DC & Friends");
+        assert!(xml.contains("<tvshow>"));
+        assert!(xml.contains("<title>AC/DC &amp; Friends</title>"));
+        assert!(xml.contains("<studio>AC/DC &amp; Friends</studio>"));
+    }
+
+    #[test]
+    fn test_is_channel_folder() {
+        assert!(is_channel_folder(
+            Path::new("/v/Cartoon Hits"),
+            Some("Cartoon Hits")
+        ));
+        assert!(is_channel_folder(
+            Path::new("/v/Cartoon_Hits"),
+            Some("Cartoon Hits")
+        ));
+        assert!(is_channel_folder(Path::new("/v/AC_DC"), Some("AC/DC")));
+        assert!(!is_channel_folder(
+            Path::new("/v/Downloads"),
+            Some("Cartoon Hits")
+        ));
+        assert!(!is_channel_folder(Path::new("/v/Cartoon Hits"), None));
+    }
+
+    // ============== Writing Tests ==============
+
+    #[test]
+    fn test_write_info_json_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let media = dir.path().join("video.mp4");
+
+        let path = write_info_json(&sample_info(), &media).unwrap();
+
+        assert_eq!(path, dir.path().join("video.info.json"));
+        let info: VideoInfo = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(info.id, "abc123");
+        assert_eq!(info.channel.as_deref(), Some("Cartoon Hits"));
+    }
+
+    #[test]
+    fn test_write_nfo_movie() {
+        let dir = TempDir::new().unwrap();
+        let media = dir.path().join("video.mp4");
+
+        let written = write_nfo(&sample_info(), &media).unwrap();
+
+        assert_eq!(written, vec![dir.path().join("video.nfo")]);
+        assert!(fs::read_to_string(&written[0]).unwrap().contains("<movie>"));
+        assert!(!dir.path().join(TVSHOW_NFO).exists());
+    }
+
+    #[test]
+    fn test_write_nfo_in_channel_folder() {
+        let dir = TempDir::new().unwrap();
+        let folder = dir.path().join("Cartoon Hits");
+        fs::create_dir_all(&folder).unwrap();
+        let media = folder.join("video.mp4");
+        fs::write(folder.join("video.jpg"), "img").unwrap();
+
+        let written = write_nfo(&sample_info(), &media).unwrap();
+
+        assert_eq!(written.len(), 2);
+        let episode = fs::read_to_string(folder.join("video.nfo")).unwrap();
+        assert!(episode.contains("<episodedetails>"));
+        assert!(episode.contains("<thumb aspect=\"thumb\">video.jpg</thumb>"));
+        let show = fs::read_to_string(folder.join(TVSHOW_NFO)).unwrap();
+        assert!(show.contains("<title>Cartoon Hits</title>"));
+    }
+}
@@ -0,0 +1,306 @@
+//! Disk-space checks and size limits.
+//!
+//! Before yt-dlp starts, the downloader estimates the size of the streams it
+//! selected (see [`StreamInfo::estimated_size`]) and checks it against:
+//!
+//! - the free space on the output filesystem ([`check_free_space`]);
+//! - `--max-filesize`, which skips a single video that is too large;
+//! - `--max-total`, a [`SizeBudget`] shared by every download of one run.
+//!
+//! Videos whose size cannot be estimated are downloaded anyway; their real
+//! size still counts towards the budget once they finish.
+//!
+//! [`StreamInfo::estimated_size`]: crate::youtube::StreamInfo::estimated_size
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::space::{parse_size, SizeBudget};
+//!
+//! let budget = SizeBudget::new(parse_size("1G").unwrap());
+//!
+//! let reservation = budget.reserve(Some(parse_size("600M").unwrap())).unwrap();
+//! reservation.commit(parse_size("700M").unwrap());
+//!
+//! // 324 MB are left, so a 500 MB video no longer fits
+//! assert!(budget.reserve(Some(parse_size("500M").unwrap())).is_none());
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{AppError, AppResult};
+
+/// Parses a size such as `500M`, `1.5G`, `700MB` or `2GiB`.
+///
+/// Units are powers of 1024 (`K`, `M`, `G`, `T`), with an optional `B` or
+/// `iB` suffix, in any case. A plain number is a size in bytes.
+///
+/// # Errors
+///
+/// Returns `AppError::InvalidArgument` if the value is not a positive size.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::space::parse_size;
+///
+/// assert_eq!(parse_size("500").unwrap(), 500);
+/// assert_eq!(parse_size("2K").unwrap(), 2048);
+/// assert_eq!(parse_size("1.5GB").unwrap(), 1536 * 1024 * 1024);
+/// assert!(parse_size("lots").is_err());
+/// ```
+pub fn parse_size(value: &str) -> AppResult<u64> {
+    let invalid = || {
+        AppError::invalid_arg(
+            "size",
+            format!("'{}' is not a size (e.g. 500M, 1.5G, 2GB)", value),
+        )
+    };
+
+    let upper = value.trim().to_ascii_uppercase();
+    let number = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (number, scale) = match number.char_indices().last() {
+        Some((split, unit @ ('K' | 'M' | 'G' | 'T'))) => {
+            let power = match unit {
+                'K' => 10,
+                'M' => 20,
+                'G' => 30,
+                _ => 40,
+            };
+            (&number[..split], 1u64 << power)
+        }
+        _ => (number, 1),
+    };
+
+    let amount: f64 = number.trim().parse().map_err(|_| invalid())?;
+    let bytes = amount * scale as f64;
+    if !bytes.is_finite() || bytes < 1.0 || bytes >= u64::MAX as f64 {
+        return Err(invalid());
+    }
+
+    Ok(bytes as u64)
+}
+
+/// Returns the free space in bytes on the filesystem holding `path`.
+///
+/// `path` does not need to exist yet: its closest existing ancestor is
+/// checked instead. Returns `None` if the space cannot be read.
+pub fn available_space(path: &Path) -> Option<u64> {
+    path.ancestors()
+        .map(|dir| {
+            if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            }
+        })
+        .find(|dir| dir.exists())
+        .and_then(|dir| fs4::available_space(dir).ok())
+}
+
+/// Checks that `needed` bytes are free on the filesystem holding `dir`.
+///
+/// Passes when the free space cannot be read, so that an unusual filesystem
+/// never blocks a download.
+///
+/// # Errors
+///
+/// Returns `AppError::InsufficientSpace` if less than `needed` bytes are free.
+pub fn check_free_space(dir: &Path, needed: u64) -> AppResult<()> {
+    match available_space(dir) {
+        Some(available) if available < needed => Err(AppError::InsufficientSpace {
+            path: PathBuf::from(dir),
+            needed,
+            available,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The `--max-total` limit shared by every download of one run.
+///
+/// Each download reserves its estimated size before starting and, once it
+/// finishes, replaces the reservation with the real size of its file. A
+/// download that fails releases its reservation.
+#[derive(Debug)]
+pub struct SizeBudget {
+    limit: u64,
+    used: Mutex<u64>,
+}
+
+impl SizeBudget {
+    /// Creates a budget of `limit` bytes.
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: Mutex::new(0),
+        }
+    }
+
+    /// Returns the limit in bytes.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the bytes downloaded or reserved so far.
+    pub fn used(&self) -> u64 {
+        *self.used.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Reserves `estimate` bytes for a download.
+    ///
+    /// Returns `None` if the download does not fit in what is left. A
+    /// download of unknown size reserves nothing and fits as long as the
+    /// budget is not used up.
+    pub fn reserve(&self, estimate: Option<u64>) -> Option<Reservation<'_>> {
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let size = estimate.unwrap_or(0);
+        let fits = match estimate {
+            Some(size) => used.saturating_add(size) <= self.limit,
+            None => *used < self.limit,
+        };
+        if !fits {
+            return None;
+        }
+
+        *used += size;
+        Some(Reservation {
+            budget: self,
+            size,
+            committed: false,
+        })
+    }
+}
+
+/// Bytes reserved in a [`SizeBudget`] by a running download.
+///
+/// Dropping it without [`Reservation::commit`] releases the bytes.
+#[derive(Debug)]
+pub struct Reservation<'a> {
+    budget: &'a SizeBudget,
+    size: u64,
+    committed: bool,
+}
+
+impl Reservation<'_> {
+    /// Replaces the reservation with the real size of the downloaded file.
+    pub fn commit(mut self, actual: u64) {
+        let mut used = self.budget.used.lock().unwrap_or_else(|e| e.into_inner());
+        *used = used.saturating_sub(self.size).saturating_add(actual);
+        self.committed = true;
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let mut used = self.budget.used.lock().unwrap_or_else(|e| e.into_inner());
+            *used = used.saturating_sub(self.size);
+        }
+    }
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MB: u64 = 1024 * 1024;
+
+    // ============== parse_size Tests ==============
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("500K").unwrap(), 500 * 1024);
+        assert_eq!(parse_size("500m").unwrap(), 500 * MB);
+        assert_eq!(parse_size("2G").unwrap(), 2048 * MB);
+        assert_eq!(parse_size("1T").unwrap(), 1024 * 1024 * MB);
+    }
+
+    #[test]
+    fn test_parse_size_suffixes() {
+        assert_eq!(parse_size("700MB").unwrap(), 700 * MB);
+        assert_eq!(parse_size("700MiB").unwrap(), 700 * MB);
+        assert_eq!(parse_size(" 1.5 GB ").unwrap(), 1536 * MB);
+        assert_eq!(parse_size("12B").unwrap(), 12);
+    }
+
+    #[test]
+    fn test_parse_size_invalid() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("-5M").is_err());
+        assert!(parse_size("5 MB/s").is_err());
+        assert!(parse_size("huge").is_err());
+        assert!(parse_size("99999999T").is_err());
+    }
+
+    // ============== Free Space Tests ==============
+
+    #[test]
+    fn test_available_space_missing_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("a").join("b");
+
+        assert!(available_space(&missing).is_some());
+        assert!(available_space(Path::new("not-created-yet")).is_some());
+    }
+
+    #[test]
+    fn test_check_free_space() {
+        let temp_dir = TempDir::new().unwrap();
+
+        assert!(check_free_space(temp_dir.path(), 1).is_ok());
+
+        let err = check_free_space(temp_dir.path(), u64::MAX).unwrap_err();
+        assert!(matches!(err, AppError::InsufficientSpace { needed, .. } if needed == u64::MAX));
+    }
+
+    // ============== SizeBudget Tests ==============
+
+    #[test]
+    fn test_budget_reserve_and_commit() {
+        let budget = SizeBudget::new(100);
+
+        let first = budget.reserve(Some(60)).unwrap();
+        assert_eq!(budget.used(), 60);
+        assert!(budget.reserve(Some(50)).is_none());
+
+        first.commit(70);
+        assert_eq!(budget.used(), 70);
+        assert!(budget.reserve(Some(30)).is_some());
+        assert!(budget.reserve(Some(31)).is_none());
+    }
+
+    #[test]
+    fn test_budget_drop_releases() {
+        let budget = SizeBudget::new(100);
+
+        drop(budget.reserve(Some(80)).unwrap());
+        assert_eq!(budget.used(), 0);
+        assert!(budget.reserve(Some(100)).is_some());
+    }
+
+    #[test]
+    fn test_budget_unknown_size() {
+        let budget = SizeBudget::new(100);
+
+        let unknown = budget.reserve(None).unwrap();
+        assert_eq!(budget.used(), 0);
+        unknown.commit(120);
+
+        assert_eq!(budget.used(), 120);
+        assert!(budget.reserve(None).is_none());
+        assert!(budget.reserve(Some(1)).is_none());
+    }
+}
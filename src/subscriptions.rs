@@ -0,0 +1,774 @@
+//! Channel and playlist subscriptions.
+//!
+//! A subscription remembers a channel or playlist URL together with the
+//! profile and filters to download it with, and the IDs of the videos it has
+//! already seen. `ytdl sync` enumerates every subscription and downloads only
+//! the videos that are not in that list yet.
+//!
+//! The first sync of a subscription only records the videos that already
+//! exist, so following a channel does not download its whole back catalogue;
+//! subscriptions added with `--all` download them instead.
+//!
+//! Subscriptions are stored as JSON in `subscriptions.json`, next to the user
+//! configuration file.
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::subscriptions::{Subscription, Subscriptions};
+//!
+//! let mut store = Subscriptions::default();
+//! store.add(Subscription::new("https://www.youtube.com/@LofiGirl"))?;
+//!
+//! let lofi = store.find("@LofiGirl").unwrap();
+//! assert!(lofi.needs_baseline());
+//! # Ok::<(), rust_yt_downloader::error::AppError>(())
+//! ```
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::downloader::{DownloadResult, Downloader};
+use crate::error::{AppError, AppResult};
+use crate::filter::{Field, Filter, Op};
+use crate::utils::parse_duration;
+use crate::youtube::YouTubeUrl;
+
+/// File name of the subscription store, relative to the configuration directory.
+pub const SUBSCRIPTIONS_FILE: &str = "subscriptions.json";
+
+/// Shortest interval accepted by `ytdl sync --watch`.
+pub const MIN_WATCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Conditions a new video must meet to be downloaded.
+///
+/// Videos that do not match are still marked as seen, so they are not
+/// checked again on the next sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionFilters {
+    /// Regular expression searched for in the title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Minimum duration in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_duration: Option<u64>,
+
+    /// Maximum duration in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<u64>,
+}
+
+impl SubscriptionFilters {
+    /// Returns `true` if no filter is set.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.min_duration.is_none() && self.max_duration.is_none()
+    }
+
+    /// Converts the filters into a [`Filter`] expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if the title pattern is invalid.
+    pub fn to_filter(&self) -> AppResult<Filter> {
+        let mut filter = Filter::new();
+        if let Some(pattern) = &self.title {
+            filter = filter.with_condition(Field::Title, Op::Matches, pattern)?;
+        }
+        if let Some(min) = self.min_duration {
+            filter = filter.with_condition(Field::Duration, Op::Ge, &min.to_string())?;
+        }
+        if let Some(max) = self.max_duration {
+            filter = filter.with_condition(Field::Duration, Op::Le, &max.to_string())?;
+        }
+        Ok(filter)
+    }
+
+    /// Checks the title pattern and the duration range.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` naming the offending flag.
+    pub fn check(&self) -> AppResult<()> {
+        if let Some(pattern) = &self.title {
+            Regex::new(pattern).map_err(|e| AppError::invalid_arg("--title", e.to_string()))?;
+        }
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                return Err(AppError::invalid_arg(
+                    "--min-duration",
+                    format!("{} is longer than --max-duration {}", min, max),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Describes the filters for `subscribe list`, e.g. `title /live/, >= 60s`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(title) = &self.title {
+            parts.push(format!("title /{}/", title));
+        }
+        if let Some(min) = self.min_duration {
+            parts.push(format!(">= {}s", min));
+        }
+        if let Some(max) = self.max_duration {
+            parts.push(format!("<= {}s", max));
+        }
+        parts.join(", ")
+    }
+}
+
+/// A followed channel or playlist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subscription {
+    /// Name used by `subscribe remove` and `sync <NAME>`
+    pub name: String,
+
+    /// Channel or playlist URL
+    pub url: String,
+
+    /// Configuration profile used for its downloads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Extract audio instead of downloading the video
+    #[serde(default)]
+    pub audio_only: bool,
+
+    /// Conditions for downloading a new video
+    #[serde(default, skip_serializing_if = "SubscriptionFilters::is_empty")]
+    pub filters: SubscriptionFilters,
+
+    /// Download the videos that already existed on the first sync
+    #[serde(default)]
+    pub include_existing: bool,
+
+    /// When the subscription was last synced (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_sync: Option<String>,
+
+    /// IDs of the videos already downloaded, filtered out or skipped
+    #[serde(default)]
+    pub seen: BTreeSet<String>,
+}
+
+impl Subscription {
+    /// Creates a subscription named after its URL (see [`default_name`]).
+    pub fn new(url: impl Into<String>) -> Self {
+        let url = url.into();
+        Self {
+            name: default_name(&url),
+            url,
+            profile: None,
+            audio_only: false,
+            filters: SubscriptionFilters::default(),
+            include_existing: false,
+            last_sync: None,
+            seen: BTreeSet::new(),
+        }
+    }
+
+    /// Sets the name used to refer to the subscription.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the configuration profile used for its downloads.
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Sets whether only the audio is extracted.
+    pub fn with_audio_only(mut self, audio_only: bool) -> Self {
+        self.audio_only = audio_only;
+        self
+    }
+
+    /// Sets the conditions for downloading a new video.
+    pub fn with_filters(mut self, filters: SubscriptionFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Sets whether the first sync downloads the videos that already exist.
+    pub fn with_include_existing(mut self, include_existing: bool) -> Self {
+        self.include_existing = include_existing;
+        self
+    }
+
+    /// Returns `true` if the next sync should only record the existing
+    /// videos as seen instead of downloading them.
+    pub fn needs_baseline(&self) -> bool {
+        self.last_sync.is_none() && !self.include_existing
+    }
+
+    /// Returns the IDs in `ids` that have not been seen yet, in order and
+    /// without duplicates.
+    pub fn unseen(&self, ids: &[String]) -> Vec<String> {
+        let mut listed = BTreeSet::new();
+        ids.iter()
+            .filter(|id| !self.seen.contains(*id) && listed.insert(*id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// What one sync learned about a subscription, applied with
+/// [`Subscriptions::apply()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncUpdate {
+    /// URL of the subscription
+    pub url: String,
+    /// IDs to mark as seen
+    pub seen: Vec<String>,
+    /// Whether the subscription was enumerated successfully
+    pub synced: bool,
+}
+
+impl SyncUpdate {
+    /// Creates an empty update for the subscription at `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// All subscriptions, in the order they were added.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subscriptions {
+    #[serde(default)]
+    subscriptions: Vec<Subscription>,
+}
+
+impl Subscriptions {
+    /// Returns the store path next to the configuration file at `config_path`.
+    pub fn path_beside(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(SUBSCRIPTIONS_FILE)
+    }
+
+    /// Loads the store at `path`. A missing file has no subscriptions.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::FileRead` if the file cannot be read and
+    /// `AppError::Other` if it is not a subscription store.
+    pub fn load(path: &Path) -> AppResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| AppError::file_read(path, e))?;
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::Other(format!(
+                "Subscription store {} is corrupt: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Writes the store to `path` through a temporary file, so an
+    /// interrupted write never leaves a truncated store.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::DirectoryCreation` or `AppError::FileWrite` if the
+    /// store cannot be written.
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).map_err(|e| AppError::dir_create(parent, e))?;
+            }
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Other(format!("Could not serialize subscriptions: {}", e)))?;
+
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, content).map_err(|e| AppError::file_write(&tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| AppError::file_write(path, e))
+    }
+
+    /// Adds a subscription.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if its URL or name is already used
+    /// or its filters are invalid.
+    pub fn add(&mut self, subscription: Subscription) -> AppResult<()> {
+        subscription.filters.check()?;
+
+        if let Some(existing) = self
+            .subscriptions
+            .iter()
+            .find(|s| s.url == subscription.url)
+        {
+            return Err(AppError::invalid_arg(
+                "URL",
+                format!("already subscribed as '{}'", existing.name),
+            ));
+        }
+        if self.find(&subscription.name).is_some() {
+            return Err(AppError::invalid_arg(
+                "--name",
+                format!(
+                    "'{}' is already used by another subscription",
+                    subscription.name
+                ),
+            ));
+        }
+
+        self.subscriptions.push(subscription);
+        Ok(())
+    }
+
+    /// Removes the subscription with the given name or URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if there is no such subscription.
+    pub fn remove(&mut self, key: &str) -> AppResult<Subscription> {
+        let index = self
+            .subscriptions
+            .iter()
+            .position(|s| s.name == key || s.url == key)
+            .ok_or_else(|| unknown_subscription(key))?;
+
+        Ok(self.subscriptions.remove(index))
+    }
+
+    /// Returns the subscription with the given name or URL.
+    pub fn find(&self, key: &str) -> Option<&Subscription> {
+        self.subscriptions
+            .iter()
+            .find(|s| s.name == key || s.url == key)
+    }
+
+    /// Returns the subscriptions named in `keys` (names or URLs), or every
+    /// subscription when `keys` is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` for the first unknown key.
+    pub fn select(&self, keys: &[String]) -> AppResult<Vec<&Subscription>> {
+        if keys.is_empty() {
+            return Ok(self.subscriptions.iter().collect());
+        }
+
+        keys.iter()
+            .map(|key| self.find(key).ok_or_else(|| unknown_subscription(key)))
+            .collect()
+    }
+
+    /// Records the outcome of a sync. Updates for subscriptions removed in
+    /// the meantime are ignored.
+    pub fn apply(&mut self, updates: &[SyncUpdate]) {
+        let now = Utc::now().to_rfc3339();
+
+        for update in updates {
+            let Some(subscription) = self.subscriptions.iter_mut().find(|s| s.url == update.url)
+            else {
+                continue;
+            };
+
+            subscription.seen.extend(update.seen.iter().cloned());
+            if update.synced {
+                subscription.last_sync = Some(now.clone());
+            }
+        }
+    }
+
+    /// Returns the subscriptions, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Subscription> {
+        self.subscriptions.iter()
+    }
+
+    /// Returns the number of subscriptions.
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    /// Returns `true` if there are no subscriptions.
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+}
+
+/// A new video found by `ytdl sync`, with what is needed to download it.
+///
+/// Filters have already been applied when the job is created.
+pub struct SyncJob {
+    /// ID of the video
+    pub video_id: String,
+    /// Downloader configured with the subscription's profile
+    pub downloader: Arc<Downloader>,
+    /// Extract audio instead of downloading the video
+    pub audio_only: bool,
+}
+
+impl SyncJob {
+    /// Downloads the video at `url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the download error.
+    pub async fn run(&self, url: &str) -> AppResult<DownloadResult> {
+        if self.audio_only {
+            self.downloader.download_audio(url).await
+        } else {
+            self.downloader.download(url).await
+        }
+    }
+}
+
+/// Returns the watch URL of a video ID.
+pub fn video_url(id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={}", id)
+}
+
+/// Returns the default name of a subscription: the channel handle or name
+/// (`@LofiGirl`, `UC…`), the playlist ID, or the URL itself.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::subscriptions::default_name;
+///
+/// assert_eq!(default_name("https://www.youtube.com/@LofiGirl/videos"), "@LofiGirl");
+/// assert_eq!(default_name("https://youtube.com/playlist?list=PL123"), "PL123");
+/// ```
+pub fn default_name(url: &str) -> String {
+    match YouTubeUrl::parse(url) {
+        Ok(YouTubeUrl::Channel { channel, .. }) => match channel.split_once('/') {
+            Some((_, name)) => name.to_string(),
+            None => channel,
+        },
+        Ok(parsed) => parsed
+            .playlist_id()
+            .map(String::from)
+            .unwrap_or_else(|| url.to_string()),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Parses a `--watch` interval: a number with an `s`, `m`, `h` or `d`
+/// suffix (`30m`, `6h`), or seconds as `SS`, `MM:SS` or `HH:MM:SS`.
+///
+/// # Errors
+///
+/// Returns `AppError::InvalidArgument` if the value cannot be parsed or is
+/// shorter than [`MIN_WATCH_INTERVAL`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use rust_yt_downloader::subscriptions::parse_interval;
+///
+/// assert_eq!(parse_interval("30m").unwrap(), Duration::from_secs(1800));
+/// assert_eq!(parse_interval("01:00:00").unwrap(), Duration::from_secs(3600));
+/// assert!(parse_interval("10s").is_err());
+/// ```
+pub fn parse_interval(value: &str) -> AppResult<Duration> {
+    let invalid = || {
+        AppError::invalid_arg(
+            "--watch",
+            format!("'{}' is not an interval (e.g. 90s, 30m, 6h, 1d)", value),
+        )
+    };
+
+    let value = value.trim();
+    let seconds = match value.char_indices().last() {
+        Some((split, unit @ ('s' | 'm' | 'h' | 'd'))) => {
+            let amount: u64 = value[..split].parse().map_err(|_| invalid())?;
+            let scale = match unit {
+                's' => 1,
+                'm' => 60,
+                'h' => 3600,
+                _ => 86400,
+            };
+            amount.checked_mul(scale).ok_or_else(invalid)?
+        }
+        _ => parse_duration(value).map_err(|_| invalid())?,
+    };
+
+    let interval = Duration::from_secs(seconds);
+    if interval < MIN_WATCH_INTERVAL {
+        return Err(AppError::invalid_arg(
+            "--watch",
+            format!(
+                "'{}' is too short; the minimum is {}s",
+                value,
+                MIN_WATCH_INTERVAL.as_secs()
+            ),
+        ));
+    }
+
+    Ok(interval)
+}
+
+fn unknown_subscription(key: &str) -> AppError {
+    AppError::invalid_arg("subscription", format!("no subscription named '{}'", key))
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Verdict;
+    use crate::youtube::VideoSummary;
+    use tempfile::TempDir;
+
+    fn video(title: &str, duration: u64) -> VideoSummary {
+        VideoSummary {
+            title: Some(title.to_string()),
+            duration: Some(duration),
+            complete: true,
+            ..VideoSummary::new("abc123")
+        }
+    }
+
+    fn matches(filters: &SubscriptionFilters, video: &VideoSummary) -> bool {
+        filters.to_filter().unwrap().evaluate(video) == Verdict::Match
+    }
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    // ============== Filter Tests ==============
+
+    #[test]
+    fn test_filters_empty_match_everything() {
+        let filters = SubscriptionFilters::default();
+        assert!(filters.is_empty());
+        assert!(filters.to_filter().unwrap().is_empty());
+        assert!(matches(&filters, &video("Anything", 10)));
+    }
+
+    #[test]
+    fn test_filters_match() {
+        let filters = SubscriptionFilters {
+            title: Some("(?i)episode".to_string()),
+            min_duration: Some(60),
+            max_duration: Some(3600),
+        };
+
+        assert!(matches(&filters, &video("Episode 12", 1200)));
+        assert!(!matches(&filters, &video("Trailer", 1200)));
+        assert!(!matches(&filters, &video("Episode 13", 30)));
+        assert!(!matches(&filters, &video("Episode 14", 7200)));
+        assert_eq!(filters.describe(), "title /(?i)episode/, >= 60s, <= 3600s");
+    }
+
+    #[test]
+    fn test_filters_check() {
+        let bad_pattern = SubscriptionFilters {
+            title: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_pattern.check().is_err());
+        assert!(bad_pattern.to_filter().is_err());
+
+        let bad_range = SubscriptionFilters {
+            min_duration: Some(100),
+            max_duration: Some(10),
+            ..Default::default()
+        };
+        assert!(bad_range.check().is_err());
+    }
+
+    // ============== Subscription Tests ==============
+
+    #[test]
+    fn test_default_name() {
+        assert_eq!(
+            default_name("https://www.youtube.com/@LofiGirl"),
+            "@LofiGirl"
+        );
+        assert_eq!(
+            default_name("https://www.youtube.com/channel/UCabc/videos"),
+            "UCabc"
+        );
+        assert_eq!(default_name("https://www.youtube.com/c/Ferris"), "Ferris");
+        assert_eq!(
+            default_name("https://www.youtube.com/playlist?list=PLxyz"),
+            "PLxyz"
+        );
+        assert_eq!(default_name("not a url"), "not a url");
+    }
+
+    #[test]
+    fn test_unseen_skips_seen_and_duplicates() {
+        let mut subscription = Subscription::new("https://www.youtube.com/@a");
+        subscription.seen.insert("old".to_string());
+
+        assert_eq!(
+            subscription.unseen(&ids(&["new1", "old", "new2", "new1"])),
+            ids(&["new1", "new2"])
+        );
+    }
+
+    #[test]
+    fn test_needs_baseline() {
+        let subscription = Subscription::new("https://www.youtube.com/@a");
+        assert!(subscription.needs_baseline());
+        assert!(!subscription
+            .clone()
+            .with_include_existing(true)
+            .needs_baseline());
+
+        let synced = Subscription {
+            last_sync: Some("2024-05-01T00:00:00+00:00".to_string()),
+            ..subscription
+        };
+        assert!(!synced.needs_baseline());
+    }
+
+    // ============== Store Tests ==============
+
+    #[test]
+    fn test_add_rejects_duplicates() {
+        let mut store = Subscriptions::default();
+        store
+            .add(Subscription::new("https://www.youtube.com/@a"))
+            .unwrap();
+
+        assert!(store
+            .add(Subscription::new("https://www.youtube.com/@a"))
+            .is_err());
+        assert!(store
+            .add(Subscription::new("https://www.youtube.com/@b").with_name("@a"))
+            .is_err());
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_by_name_or_url() {
+        let mut store = Subscriptions::default();
+        store
+            .add(Subscription::new("https://www.youtube.com/@a"))
+            .unwrap();
+        store
+            .add(Subscription::new("https://www.youtube.com/@b"))
+            .unwrap();
+
+        assert_eq!(store.remove("@a").unwrap().name, "@a");
+        assert_eq!(
+            store.remove("https://www.youtube.com/@b").unwrap().name,
+            "@b"
+        );
+        assert!(store.remove("@c").is_err());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_select() {
+        let mut store = Subscriptions::default();
+        store
+            .add(Subscription::new("https://www.youtube.com/@a"))
+            .unwrap();
+        store
+            .add(Subscription::new("https://www.youtube.com/@b"))
+            .unwrap();
+
+        assert_eq!(store.select(&[]).unwrap().len(), 2);
+        assert_eq!(store.select(&ids(&["@b"])).unwrap()[0].name, "@b");
+        assert!(store.select(&ids(&["@missing"])).is_err());
+    }
+
+    #[test]
+    fn test_apply_updates() {
+        let mut store = Subscriptions::default();
+        store
+            .add(Subscription::new("https://www.youtube.com/@a"))
+            .unwrap();
+        store
+            .add(Subscription::new("https://www.youtube.com/@b"))
+            .unwrap();
+
+        store.apply(&[
+            SyncUpdate {
+                url: "https://www.youtube.com/@a".to_string(),
+                seen: ids(&["v1", "v2"]),
+                synced: true,
+            },
+            SyncUpdate {
+                url: "https://www.youtube.com/@b".to_string(),
+                seen: Vec::new(),
+                synced: false,
+            },
+            SyncUpdate::new("https://www.youtube.com/@removed"),
+        ]);
+
+        let a = store.find("@a").unwrap();
+        assert_eq!(a.seen.len(), 2);
+        assert!(a.last_sync.is_some());
+        assert!(store.find("@b").unwrap().last_sync.is_none());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join(SUBSCRIPTIONS_FILE);
+
+        let mut store = Subscriptions::default();
+        store
+            .add(
+                Subscription::new("https://www.youtube.com/@a")
+                    .with_profile(Some("music".to_string()))
+                    .with_audio_only(true)
+                    .with_filters(SubscriptionFilters {
+                        min_duration: Some(60),
+                        ..Default::default()
+                    }),
+            )
+            .unwrap();
+        store.save(&path).unwrap();
+
+        assert_eq!(Subscriptions::load(&path).unwrap(), store);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_load_missing_and_corrupt() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(SUBSCRIPTIONS_FILE);
+        assert!(Subscriptions::load(&path).unwrap().is_empty());
+
+        fs::write(&path, "not json").unwrap();
+        assert!(Subscriptions::load(&path).is_err());
+    }
+
+    // ============== Interval Tests ==============
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_interval("6h").unwrap(), Duration::from_secs(21600));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86400));
+        assert_eq!(parse_interval("120").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_interval("05:00").unwrap(), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_parse_interval_invalid() {
+        for value in ["", "soon", "5x", "m", "30s", "-5m"] {
+            assert!(parse_interval(value).is_err(), "{value}");
+        }
+    }
+}
@@ -8,10 +8,10 @@
 //! - Template-based filename generation
 
 use chrono::{DateTime, Utc};
-use reqwest::Url;
 use std::path::PathBuf;
 
 use crate::error::{AppError, AppResult};
+use crate::youtube::YouTubeUrl;
 
 /// Metadata for a YouTube video used in template-based filename generation.
 ///
@@ -246,10 +246,12 @@ pub fn parse_duration(seconds: &str) -> AppResult<u64> {
 
 /// Extracts the video ID from a YouTube URL.
 ///
-/// Supports multiple YouTube URL formats:
+/// Accepts every shape recognized by [`YouTubeUrl`]:
 /// - Standard: `https://www.youtube.com/watch?v=VIDEO_ID`
-/// - Short: `https://youtu.be/VIDEO_ID`
-/// - Embed: `https://www.youtube.com/embed/VIDEO_ID`
+/// - Short link: `https://youtu.be/VIDEO_ID`
+/// - Embed: `https://www.youtube.com/embed/VIDEO_ID`, `/v/VIDEO_ID`, `youtube-nocookie.com`
+/// - Shorts and live streams: `/shorts/VIDEO_ID`, `/live/VIDEO_ID`
+/// - The `m.` and `music.` sites
 ///
 /// # Arguments
 ///
@@ -257,7 +259,7 @@ pub fn parse_duration(seconds: &str) -> AppResult<u64> {
 ///
 /// # Returns
 ///
-/// The video ID if the URL is valid, or `None` if it cannot be parsed.
+/// The video ID if the URL points to a video, or `None` otherwise.
 ///
 /// # Examples
 ///
@@ -269,28 +271,17 @@ pub fn parse_duration(seconds: &str) -> AppResult<u64> {
 ///
 /// let id = extract_video_id("https://youtu.be/dQw4w9WgXcQ");
 /// assert_eq!(id, Some("dQw4w9WgXcQ".to_string()));
+///
+/// let id = extract_video_id("https://www.youtube.com/shorts/dQw4w9WgXcQ");
+/// assert_eq!(id, Some("dQw4w9WgXcQ".to_string()));
 /// ```
 pub fn extract_video_id(url: &str) -> Option<String> {
-    let parsed_url = Url::parse(url).ok()?;
-    let domain = parsed_url.domain()?;
-
-    if domain.ends_with("youtube.com") {
-        if let Some(pair) = parsed_url.query_pairs().find(|(key, _)| key == "v") {
-            return Some(pair.1.to_string());
-        }
-
-        if parsed_url.path().starts_with("/embed/") {
-            return parsed_url.path_segments()?.nth(1).map(|s| s.to_string());
-        }
-    } else if domain.ends_with("youtu.be") {
-        return parsed_url.path_segments()?.next().map(|s| s.to_string());
-    }
-    None
+    YouTubeUrl::parse(url).ok()?.video_id().map(String::from)
 }
 
 /// Extracts the playlist ID from a YouTube URL.
 ///
-/// Parses the `list` query parameter from YouTube URLs to extract playlist IDs.
+/// Reads the `list` parameter of playlist and video URLs.
 ///
 /// # Arguments
 ///
@@ -312,11 +303,10 @@ pub fn extract_video_id(url: &str) -> Option<String> {
 /// assert_eq!(id, Some("PLrAXtmErZgOe".to_string()));
 /// ```
 pub fn extract_playlist_id(url_str: &str) -> Option<String> {
-    let parsed_url = Url::parse(url_str).ok()?;
-    parsed_url
-        .query_pairs()
-        .find(|(key, _)| key == "list")
-        .map(|(_, value)| value.to_string())
+    YouTubeUrl::parse(url_str)
+        .ok()?
+        .playlist_id()
+        .map(String::from)
 }
 
 /// Applies a filename template using video metadata.
@@ -360,13 +350,11 @@ pub fn apply_template(template: &str, meta: &VideoMetadata) -> String {
 
     let safe_title = sanitize_filename(meta.title);
 
-    let filename = template
+    template
         .replace("{title}", &safe_title)
         .replace("{id}", meta.id)
         .replace("{date}", &date_str)
-        .replace("{duration}", meta.duration.unwrap_or(""));
-
-    filename
+        .replace("{duration}", meta.duration.unwrap_or(""))
 }
 
 // ==================================================
@@ -766,6 +754,33 @@ mod tests {
         assert_eq!(result, Some("dQw4w9WgXcQ".to_string()));
     }
 
+    #[test]
+    fn test_extract_video_id_shorts_and_live() {
+        let result = extract_video_id("https://www.youtube.com/shorts/dQw4w9WgXcQ");
+        assert_eq!(result, Some("dQw4w9WgXcQ".to_string()));
+
+        let result = extract_video_id("https://www.youtube.com/live/dQw4w9WgXcQ?si=x");
+        assert_eq!(result, Some("dQw4w9WgXcQ".to_string()));
+    }
+
+    #[test]
+    fn test_extract_video_id_other_hosts() {
+        for url in [
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/v/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(extract_video_id(url), Some("dQw4w9WgXcQ".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_extract_video_id_channel_url() {
+        assert!(extract_video_id("https://www.youtube.com/@LofiGirl").is_none());
+        assert!(extract_video_id("https://www.youtube.com/channel/UCabc").is_none());
+    }
+
     #[test]
     fn test_extract_video_id_invalid_url() {
         let result = extract_video_id("not a url");
@@ -0,0 +1,448 @@
+//! Format preferences beyond plain resolution.
+//!
+//! [`FormatPreference`] describes which codecs, frame rate, dynamic range and
+//! orientation a user would rather get. The same preference is used in two
+//! places:
+//!
+//! - compiled into a yt-dlp `-f` fallback chain and `-S` sort string for
+//!   downloads ([`FormatPreference::ytdlp_format`], [`FormatPreference::ytdlp_sort`])
+//! - applied to already-fetched metadata through
+//!   [`VideoInfo::stream_by_filter_with`](crate::youtube::VideoInfo::stream_by_filter_with)
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::youtube::{FormatPreference, HdrMode, VideoCodec};
+//!
+//! let pref = FormatPreference::default()
+//!     .with_video_codecs(vec![VideoCodec::Av1, VideoCodec::Vp9, VideoCodec::H264])
+//!     .with_fps(60)
+//!     .with_hdr(HdrMode::Never);
+//!
+//! assert_eq!(pref.ytdlp_sort().as_deref(), Some("res,fps:60,hdr:sdr"));
+//! ```
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use super::metadata::StreamInfo;
+
+/// Video codec families that can be preferred when selecting a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    /// AV1 (`av01`)
+    Av1,
+    /// VP9 (`vp9`, `vp09`)
+    Vp9,
+    /// H.264/AVC (`avc1`, `h264`)
+    H264,
+}
+
+impl VideoCodec {
+    /// Returns the codec name prefixes yt-dlp reports for this family.
+    pub fn prefixes(&self) -> &'static [&'static str] {
+        match self {
+            VideoCodec::Av1 => &["av01"],
+            VideoCodec::Vp9 => &["vp9", "vp09"],
+            VideoCodec::H264 => &["avc1", "h264"],
+        }
+    }
+
+    /// Returns `true` if the codec string belongs to this family.
+    pub fn matches(&self, codec: &str) -> bool {
+        matches_prefix(codec, self.prefixes())
+    }
+}
+
+/// Audio codec families that can be preferred when selecting a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    /// Opus (`opus`)
+    Opus,
+    /// AAC (`mp4a`, `aac`)
+    Aac,
+}
+
+impl AudioCodec {
+    /// Returns the codec name prefixes yt-dlp reports for this family.
+    pub fn prefixes(&self) -> &'static [&'static str] {
+        match self {
+            AudioCodec::Opus => &["opus"],
+            AudioCodec::Aac => &["mp4a", "aac"],
+        }
+    }
+
+    /// Returns `true` if the codec string belongs to this family.
+    pub fn matches(&self, codec: &str) -> bool {
+        matches_prefix(codec, self.prefixes())
+    }
+}
+
+/// How HDR streams should be treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HdrMode {
+    /// No preference (default)
+    #[default]
+    Any,
+    /// Prefer HDR when available
+    Prefer,
+    /// Never select HDR streams
+    Never,
+}
+
+/// Preferences applied on top of a quality filter when choosing a stream.
+///
+/// Every field is optional; the default preference changes nothing, so
+/// selection falls back to resolution only.
+///
+/// Preferences are applied in order: codec first, then orientation, then
+/// resolution, frame rate and dynamic range. `HdrMode::Never` is the only
+/// hard constraint — everything else only reorders candidates.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatPreference {
+    /// Video codecs in order of preference. Empty means no preference.
+    pub video_codecs: Vec<VideoCodec>,
+
+    /// Audio codecs in order of preference. Empty means no preference.
+    pub audio_codecs: Vec<AudioCodec>,
+
+    /// Preferred maximum frame rate, e.g. `60` for "60 fps if available".
+    pub fps: Option<u32>,
+
+    /// HDR handling.
+    pub hdr: HdrMode,
+
+    /// Prefer portrait (taller than wide) streams, e.g. for Shorts.
+    pub prefer_vertical: bool,
+}
+
+impl FormatPreference {
+    /// Sets the preferred video codecs, most preferred first.
+    pub fn with_video_codecs(mut self, codecs: Vec<VideoCodec>) -> Self {
+        self.video_codecs = codecs;
+        self
+    }
+
+    /// Sets the preferred audio codecs, most preferred first.
+    pub fn with_audio_codecs(mut self, codecs: Vec<AudioCodec>) -> Self {
+        self.audio_codecs = codecs;
+        self
+    }
+
+    /// Sets the preferred maximum frame rate.
+    pub fn with_fps(mut self, fps: u32) -> Self {
+        self.fps = Some(fps);
+        self
+    }
+
+    /// Sets the HDR handling.
+    pub fn with_hdr(mut self, hdr: HdrMode) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    /// Sets whether portrait streams are preferred.
+    pub fn with_prefer_vertical(mut self, prefer_vertical: bool) -> Self {
+        self.prefer_vertical = prefer_vertical;
+        self
+    }
+
+    /// Returns `true` if no preference is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Builds a yt-dlp `-f` selector honouring the codec, HDR and orientation
+    /// preferences.
+    ///
+    /// The result is a `/`-separated fallback chain: the most preferred
+    /// codec/orientation combination comes first and the plain
+    /// `bestvideo+bestaudio/best` selector (with the same height cap) comes
+    /// last, so a download never fails just because a preference cannot be met.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_height` - Height cap from the quality preset, if any
+    /// * `worst` - Select the lowest quality instead of the highest
+    pub fn ytdlp_format(&self, max_height: Option<u32>, worst: bool) -> String {
+        let (video, audio, combined) = if worst {
+            ("worstvideo", "worstaudio", "worst")
+        } else {
+            ("bestvideo", "bestaudio", "best")
+        };
+
+        let height = max_height
+            .map(|h| format!("[height<={}]", h))
+            .unwrap_or_default();
+        let hdr = if self.hdr == HdrMode::Never {
+            "[dynamic_range=?SDR]"
+        } else {
+            ""
+        };
+
+        let mut codec_filters: Vec<String> = self
+            .video_codecs
+            .iter()
+            .map(|c| regex_filter("vcodec", c.prefixes()))
+            .collect();
+        codec_filters.push(String::new());
+
+        let orientations: &[&str] = if self.prefer_vertical {
+            &["[aspect_ratio<1]", ""]
+        } else {
+            &[""]
+        };
+
+        let mut audio_selectors: Vec<String> = self
+            .audio_codecs
+            .iter()
+            .map(|c| format!("{}{}", audio, regex_filter("acodec", c.prefixes())))
+            .collect();
+        audio_selectors.push(audio.to_string());
+
+        let mut chain = Vec::new();
+        for codec in &codec_filters {
+            for orientation in orientations {
+                for audio_selector in &audio_selectors {
+                    chain.push(format!(
+                        "{}{}{}{}{}+{}",
+                        video, height, hdr, codec, orientation, audio_selector
+                    ));
+                }
+            }
+        }
+        chain.push(format!("{}{}{}", combined, height, hdr));
+
+        chain.join("/")
+    }
+
+    /// Builds a yt-dlp audio-only `-f` selector honouring the audio codec
+    /// preference, or `None` when no audio codec is preferred.
+    pub fn ytdlp_audio_format(&self) -> Option<String> {
+        if self.audio_codecs.is_empty() {
+            return None;
+        }
+
+        let mut chain: Vec<String> = self
+            .audio_codecs
+            .iter()
+            .map(|c| format!("bestaudio{}", regex_filter("acodec", c.prefixes())))
+            .collect();
+        chain.push("bestaudio/best".to_string());
+
+        Some(chain.join("/"))
+    }
+
+    /// Builds a yt-dlp `-S` sort string for the frame rate and HDR
+    /// preferences, or `None` when neither is set.
+    ///
+    /// Resolution is always listed first so these preferences only break
+    /// ties between streams of the same height.
+    pub fn ytdlp_sort(&self) -> Option<String> {
+        let mut fields = Vec::new();
+
+        if let Some(fps) = self.fps {
+            fields.push(format!("fps:{}", fps));
+        }
+
+        match self.hdr {
+            HdrMode::Any => {}
+            HdrMode::Prefer => fields.push("hdr".to_string()),
+            HdrMode::Never => fields.push("hdr:sdr".to_string()),
+        }
+
+        if fields.is_empty() {
+            return None;
+        }
+
+        Some(format!("res,{}", fields.join(",")))
+    }
+
+    /// Returns `true` if the stream is allowed at all under this preference.
+    pub(crate) fn allows(&self, stream: &StreamInfo) -> bool {
+        !(self.hdr == HdrMode::Never && stream.is_hdr())
+    }
+
+    /// Ranks a stream's video codec: higher is better, 0 if not preferred.
+    pub(crate) fn video_codec_rank(&self, stream: &StreamInfo) -> usize {
+        let codec = stream.video_codec.as_deref().unwrap_or("");
+        self.video_codecs
+            .iter()
+            .position(|c| c.matches(codec))
+            .map(|i| self.video_codecs.len() - i)
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if the stream satisfies the orientation preference.
+    pub(crate) fn orientation_matches(&self, stream: &StreamInfo) -> bool {
+        self.prefer_vertical && stream.is_vertical()
+    }
+
+    /// Scores a stream's frame rate: streams at or below the preferred rate
+    /// come first, highest rate first within each group.
+    pub(crate) fn fps_score(&self, stream: &StreamInfo) -> (bool, u32) {
+        match self.fps {
+            Some(max) => {
+                let fps = stream.fps.unwrap_or(0);
+                (fps <= max, fps)
+            }
+            None => (true, 0),
+        }
+    }
+
+    /// Scores a stream's dynamic range under `HdrMode::Prefer`.
+    pub(crate) fn hdr_score(&self, stream: &StreamInfo) -> bool {
+        self.hdr == HdrMode::Prefer && stream.is_hdr()
+    }
+}
+
+/// Returns `true` if `codec` starts with any of the prefixes (case-insensitive).
+fn matches_prefix(codec: &str, prefixes: &[&str]) -> bool {
+    let codec = codec.to_lowercase();
+    prefixes.iter().any(|p| codec.starts_with(p))
+}
+
+/// Builds a yt-dlp regex filter matching any of the prefixes, e.g.
+/// `[vcodec~='^(vp9|vp09)']`.
+fn regex_filter(field: &str, prefixes: &[&str]) -> String {
+    format!("[{}~='^({})']", field, prefixes.join("|"))
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ============== Codec Matching Tests ==============
+
+    #[test]
+    fn test_video_codec_matches() {
+        assert!(VideoCodec::Av1.matches("av01.0.08M.08"));
+        assert!(VideoCodec::Vp9.matches("vp9"));
+        assert!(VideoCodec::Vp9.matches("vp09.00.40.08"));
+        assert!(VideoCodec::H264.matches("avc1.640028"));
+        assert!(VideoCodec::H264.matches("H264"));
+        assert!(!VideoCodec::Av1.matches("vp9"));
+    }
+
+    #[test]
+    fn test_audio_codec_matches() {
+        assert!(AudioCodec::Opus.matches("opus"));
+        assert!(AudioCodec::Aac.matches("mp4a.40.2"));
+        assert!(!AudioCodec::Aac.matches("opus"));
+    }
+
+    // ============== Default Tests ==============
+
+    #[test]
+    fn test_default_is_empty() {
+        assert!(FormatPreference::default().is_empty());
+        assert!(!FormatPreference::default().with_fps(60).is_empty());
+    }
+
+    #[test]
+    fn test_hdr_mode_default() {
+        assert_eq!(HdrMode::default(), HdrMode::Any);
+    }
+
+    // ============== ytdlp_format Tests ==============
+
+    #[test]
+    fn test_ytdlp_format_without_preferences() {
+        let pref = FormatPreference::default();
+
+        assert_eq!(
+            pref.ytdlp_format(Some(720), false),
+            "bestvideo[height<=720]+bestaudio/best[height<=720]"
+        );
+        assert_eq!(pref.ytdlp_format(None, true), "worstvideo+worstaudio/worst");
+    }
+
+    #[test]
+    fn test_ytdlp_format_codec_order() {
+        let pref =
+            FormatPreference::default().with_video_codecs(vec![VideoCodec::Av1, VideoCodec::H264]);
+        let format = pref.ytdlp_format(Some(1080), false);
+        let chain: Vec<&str> = format.split('/').collect();
+
+        assert_eq!(
+            chain[0],
+            "bestvideo[height<=1080][vcodec~='^(av01)']+bestaudio"
+        );
+        assert_eq!(
+            chain[1],
+            "bestvideo[height<=1080][vcodec~='^(avc1|h264)']+bestaudio"
+        );
+        assert_eq!(chain[2], "bestvideo[height<=1080]+bestaudio");
+        assert_eq!(chain[3], "best[height<=1080]");
+    }
+
+    #[test]
+    fn test_ytdlp_format_audio_codecs() {
+        let pref = FormatPreference::default().with_audio_codecs(vec![AudioCodec::Opus]);
+        let format = pref.ytdlp_format(None, false);
+
+        assert_eq!(
+            format,
+            "bestvideo+bestaudio[acodec~='^(opus)']/bestvideo+bestaudio/best"
+        );
+    }
+
+    #[test]
+    fn test_ytdlp_format_never_hdr() {
+        let pref = FormatPreference::default().with_hdr(HdrMode::Never);
+        let format = pref.ytdlp_format(None, false);
+
+        assert_eq!(
+            format,
+            "bestvideo[dynamic_range=?SDR]+bestaudio/best[dynamic_range=?SDR]"
+        );
+    }
+
+    #[test]
+    fn test_ytdlp_format_vertical() {
+        let pref = FormatPreference::default().with_prefer_vertical(true);
+        let format = pref.ytdlp_format(None, false);
+
+        assert!(format.starts_with("bestvideo[aspect_ratio<1]+bestaudio/bestvideo+bestaudio"));
+    }
+
+    // ============== ytdlp_audio_format Tests ==============
+
+    #[test]
+    fn test_ytdlp_audio_format() {
+        assert!(FormatPreference::default().ytdlp_audio_format().is_none());
+
+        let pref =
+            FormatPreference::default().with_audio_codecs(vec![AudioCodec::Opus, AudioCodec::Aac]);
+        assert_eq!(
+            pref.ytdlp_audio_format().unwrap(),
+            "bestaudio[acodec~='^(opus)']/bestaudio[acodec~='^(mp4a|aac)']/bestaudio/best"
+        );
+    }
+
+    // ============== ytdlp_sort Tests ==============
+
+    #[test]
+    fn test_ytdlp_sort_empty() {
+        assert!(FormatPreference::default().ytdlp_sort().is_none());
+        assert!(FormatPreference::default()
+            .with_video_codecs(vec![VideoCodec::Vp9])
+            .ytdlp_sort()
+            .is_none());
+    }
+
+    #[test]
+    fn test_ytdlp_sort_fps_and_hdr() {
+        let pref = FormatPreference::default()
+            .with_fps(60)
+            .with_hdr(HdrMode::Prefer);
+        assert_eq!(pref.ytdlp_sort().as_deref(), Some("res,fps:60,hdr"));
+    }
+}
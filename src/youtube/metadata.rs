@@ -5,8 +5,15 @@
 //! It also provides utility methods for filtering and selecting streams based on
 //! quality preferences.
 
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
+use super::format::FormatPreference;
+use super::url::YouTubeUrl;
+
 /// Complete metadata for a YouTube video.
 ///
 /// Contains all relevant information about a video including its metadata,
@@ -51,6 +58,68 @@ pub struct VideoInfo {
     pub view_count: Option<u64>,
     /// Available streams at different qualities and formats
     pub streams: Vec<StreamInfo>,
+    /// Whether the video is a live stream or premiere, and its state
+    #[serde(default)]
+    pub live_status: LiveStatus,
+    /// Scheduled start of an upcoming live stream or premiere, as a Unix timestamp
+    #[serde(default)]
+    pub release_timestamp: Option<i64>,
+}
+
+/// Live state of a video, as reported by yt-dlp's `live_status`.
+///
+/// Premieres count as live streams: they are upcoming until they start and
+/// live while they play.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveStatus {
+    /// A regular upload
+    #[default]
+    NotLive,
+    /// A live stream on air now
+    IsLive,
+    /// A live stream or premiere that has not started yet
+    IsUpcoming,
+    /// A live stream that has ended
+    WasLive,
+    /// A live stream that just ended and is still being processed
+    PostLive,
+}
+
+impl LiveStatus {
+    /// Parses yt-dlp's `live_status`, falling back to its `is_live` flag
+    /// when the status is missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::youtube::LiveStatus;
+    ///
+    /// assert_eq!(LiveStatus::from_ytdlp(Some("is_upcoming"), None), LiveStatus::IsUpcoming);
+    /// assert_eq!(LiveStatus::from_ytdlp(None, Some(true)), LiveStatus::IsLive);
+    /// assert_eq!(LiveStatus::from_ytdlp(None, None), LiveStatus::NotLive);
+    /// ```
+    pub fn from_ytdlp(live_status: Option<&str>, is_live: Option<bool>) -> Self {
+        match live_status {
+            Some("is_live") => Self::IsLive,
+            Some("is_upcoming") => Self::IsUpcoming,
+            Some("was_live") => Self::WasLive,
+            Some("post_live") => Self::PostLive,
+            None if is_live == Some(true) => Self::IsLive,
+            _ => Self::NotLive,
+        }
+    }
+
+    /// Returns a short description for display, e.g. "live now".
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NotLive => "not live",
+            Self::IsLive => "live now",
+            Self::IsUpcoming => "upcoming",
+            Self::WasLive => "was live",
+            Self::PostLive => "ended, still processing",
+        }
+    }
 }
 
 /// Information about a specific video or audio stream.
@@ -78,6 +147,9 @@ pub struct VideoInfo {
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamInfo {
+    /// yt-dlp format identifier (e.g., "137", "140"), usable with `--format-id`
+    #[serde(default)]
+    pub format_id: String,
     /// Direct URL to the stream (may expire)
     pub url: String,
     /// Quality label (e.g., "1080p", "720p", "audio")
@@ -96,6 +168,15 @@ pub struct StreamInfo {
     pub bitrate: Option<u64>,
     /// Frames per second (video streams only)
     pub fps: Option<u32>,
+    /// Frame width in pixels (video streams only)
+    #[serde(default)]
+    pub width: Option<u32>,
+    /// Frame height in pixels (video streams only)
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Dynamic range as reported by yt-dlp (e.g., "SDR", "HDR10", "HLG")
+    #[serde(default)]
+    pub dynamic_range: Option<String>,
 }
 
 /// Metadata for a YouTube playlist.
@@ -145,7 +226,46 @@ pub enum QualityFilter {
     MaxHeight(u32),
 }
 
+/// Column used to order the stream table printed by `ytdl formats`.
+///
+/// Numeric columns sort highest first; text columns sort alphabetically.
+/// Streams missing the sorted value always come last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum StreamSortKey {
+    /// yt-dlp format ID (numeric IDs in numeric order)
+    Id,
+    /// Frame size, largest first
+    #[default]
+    Resolution,
+    /// Frames per second, highest first
+    Fps,
+    /// Average bitrate, highest first
+    Bitrate,
+    /// File size, largest first
+    Size,
+    /// Video codec (audio codec for audio-only streams)
+    Codec,
+    /// Container extension
+    Ext,
+}
+
 impl VideoInfo {
+    /// Returns `true` if the video is a live stream on air now.
+    pub fn is_live(&self) -> bool {
+        self.live_status == LiveStatus::IsLive
+    }
+
+    /// Returns `true` if the video is a live stream or premiere that has
+    /// not started yet.
+    pub fn is_upcoming(&self) -> bool {
+        self.live_status == LiveStatus::IsUpcoming
+    }
+
+    /// Returns the scheduled start of an upcoming live stream or premiere.
+    pub fn release_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.release_timestamp?, 0)
+    }
+
     /// Returns the highest quality video stream available.
     ///
     /// Filters out audio-only streams and selects the stream with the highest
@@ -235,22 +355,123 @@ impl VideoInfo {
     /// # }
     /// ```
     pub fn stream_by_filter(&self, filter: QualityFilter) -> Option<&StreamInfo> {
+        self.stream_by_filter_with(filter, &FormatPreference::default())
+    }
+
+    /// Selects a video stream based on a quality filter and format preferences.
+    ///
+    /// Among the streams allowed by `filter`, picks the one that best matches
+    /// `preference`: preferred codec first, then orientation, then resolution
+    /// (highest, or lowest for `QualityFilter::Worst`), frame rate and dynamic
+    /// range. With the default preference this behaves exactly like
+    /// [`stream_by_filter`](Self::stream_by_filter).
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Quality filter criteria to apply
+    /// * `preference` - Codec, fps, HDR and orientation preferences
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use rust_yt_downloader::youtube::{FormatPreference, QualityFilter, VideoCodec, YtDlpClient};
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = YtDlpClient::new();
+    /// let info = client.get_video_info("https://www.youtube.com/watch?v=dQw4w9WgXcQ")?;
+    ///
+    /// let pref = FormatPreference::default()
+    ///     .with_video_codecs(vec![VideoCodec::Av1, VideoCodec::Vp9])
+    ///     .with_fps(60);
+    ///
+    /// if let Some(stream) = info.stream_by_filter_with(QualityFilter::MaxHeight(1080), &pref) {
+    ///     println!("Selected: {}", stream.description());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stream_by_filter_with(
+        &self,
+        filter: QualityFilter,
+        preference: &FormatPreference,
+    ) -> Option<&StreamInfo> {
+        let worst = filter == QualityFilter::Worst;
+
+        let candidates = self
+            .streams
+            .iter()
+            .filter(|s| !s.is_audio_only)
+            .filter(|s| preference.allows(s))
+            .filter(|s| match filter {
+                QualityFilter::Best | QualityFilter::Worst => true,
+                QualityFilter::Exact(height) => {
+                    s.quality.eq_ignore_ascii_case(&format!("{}p", height))
+                }
+                QualityFilter::MaxHeight(max) => Self::quality_to_height(&s.quality) <= max,
+            });
+
+        let compare = |a: &&StreamInfo, b: &&StreamInfo| {
+            let height_a = Self::quality_to_height(&a.quality);
+            let height_b = Self::quality_to_height(&b.quality);
+            let height = if worst {
+                height_b.cmp(&height_a)
+            } else {
+                height_a.cmp(&height_b)
+            };
+
+            preference
+                .video_codec_rank(a)
+                .cmp(&preference.video_codec_rank(b))
+                .then(
+                    preference
+                        .orientation_matches(a)
+                        .cmp(&preference.orientation_matches(b)),
+                )
+                .then(height)
+                .then(preference.fps_score(a).cmp(&preference.fps_score(b)))
+                .then(preference.hdr_score(a).cmp(&preference.hdr_score(b)))
+        };
+
+        // `max_by` keeps the last of equal elements; Worst and Exact have
+        // always returned the first one, so walk those in reverse.
         match filter {
-            QualityFilter::Best => self.best_video_stream(),
-            QualityFilter::Worst => self.worst_video_stream(),
-            QualityFilter::Exact(height) => {
-                let quality = format!("{}p", height);
-                self.stream_by_quality(&quality)
-            }
-            QualityFilter::MaxHeight(max) => self
-                .streams
-                .iter()
-                .filter(|s| !s.is_audio_only)
-                .filter(|s| Self::quality_to_height(&s.quality) <= max)
-                .max_by_key(|s| Self::quality_to_height(&s.quality)),
+            QualityFilter::Worst | QualityFilter::Exact(_) => candidates
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .max_by(compare),
+            _ => candidates.max_by(compare),
         }
     }
 
+    /// Finds the stream with the given yt-dlp format ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `format_id` - Format identifier as listed by `ytdl formats` (e.g., "137")
+    pub fn stream_by_format_id(&self, format_id: &str) -> Option<&StreamInfo> {
+        self.streams.iter().find(|s| s.format_id == format_id)
+    }
+
+    /// Returns every stream ordered by the given column.
+    ///
+    /// Ties keep the order reported by yt-dlp. When `reverse` is true the
+    /// whole ordering is flipped, including where missing values end up.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Column to sort by
+    /// * `reverse` - Reverse the resulting order
+    pub fn sorted_streams(&self, key: StreamSortKey, reverse: bool) -> Vec<&StreamInfo> {
+        let mut streams: Vec<&StreamInfo> = self.streams.iter().collect();
+        streams.sort_by(|a, b| a.compare_by(b, key));
+
+        if reverse {
+            streams.reverse();
+        }
+
+        streams
+    }
+
     /// Returns a sorted, deduplicated list of available video quality labels.
     ///
     /// The qualities are sorted in descending order (highest quality first).
@@ -352,6 +573,96 @@ impl StreamInfo {
     pub fn formatted_size(&self) -> Option<String> {
         self.file_size.map(crate::utils::format_bytes)
     }
+
+    /// Returns the size of the stream in bytes, or an estimate from its
+    /// average bitrate over `duration` seconds when yt-dlp reports no size.
+    ///
+    /// Returns `None` when neither is known, or for a zero duration.
+    pub fn estimated_size(&self, duration: u64) -> Option<u64> {
+        self.file_size.or_else(|| match (self.bitrate, duration) {
+            (Some(bitrate), duration) if duration > 0 => Some(bitrate.saturating_mul(duration) / 8),
+            _ => None,
+        })
+    }
+
+    /// Returns the frame size as `WIDTHxHEIGHT`, or the quality label
+    /// when the dimensions are unknown.
+    pub fn resolution(&self) -> String {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => format!("{}x{}", w, h),
+            (None, Some(h)) => format!("{}p", h),
+            _ => self.quality.clone(),
+        }
+    }
+
+    /// Returns `true` if the stream uses a high dynamic range (HDR10, HLG, ...).
+    pub fn is_hdr(&self) -> bool {
+        self.dynamic_range
+            .as_deref()
+            .is_some_and(|r| !r.eq_ignore_ascii_case("sdr"))
+    }
+
+    /// Returns `true` if the frame is taller than it is wide.
+    pub fn is_vertical(&self) -> bool {
+        matches!((self.width, self.height), (Some(w), Some(h)) if h > w)
+    }
+
+    /// Returns the codec most relevant to this stream: the video codec for
+    /// video streams, the audio codec for audio-only streams.
+    pub fn codec(&self) -> Option<&str> {
+        if self.is_audio_only {
+            self.audio_codec.as_deref()
+        } else {
+            self.video_codec.as_deref()
+        }
+    }
+
+    /// Compares two streams by the given sort column.
+    fn compare_by(&self, other: &StreamInfo, key: StreamSortKey) -> Ordering {
+        match key {
+            StreamSortKey::Id => Self::compare_format_ids(&self.format_id, &other.format_id),
+            StreamSortKey::Resolution => Self::descending(
+                self.height.map(|h| (h, self.width.unwrap_or(0))),
+                other.height.map(|h| (h, other.width.unwrap_or(0))),
+            ),
+            StreamSortKey::Fps => Self::descending(self.fps, other.fps),
+            StreamSortKey::Bitrate => Self::descending(self.bitrate, other.bitrate),
+            StreamSortKey::Size => Self::descending(self.file_size, other.file_size),
+            StreamSortKey::Codec => Self::ascending(self.codec(), other.codec()),
+            StreamSortKey::Ext => self.format.cmp(&other.format),
+        }
+    }
+
+    /// Orders present values highest first, with missing values last.
+    fn descending<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => b.cmp(&a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    /// Orders present values lowest first, with missing values last.
+    fn ascending<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    /// Compares format IDs numerically when both are numbers, so that
+    /// "18" sorts before "137", and lexicographically otherwise.
+    fn compare_format_ids(a: &str, b: &str) -> Ordering {
+        match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        }
+    }
 }
 
 impl PlaylistInfo {
@@ -367,6 +678,107 @@ impl PlaylistInfo {
     pub fn len(&self) -> usize {
         self.video_ids.len()
     }
+
+    /// Returns the videos of the playlist with their positions, starting at 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::youtube::PlaylistInfo;
+    ///
+    /// let playlist = PlaylistInfo {
+    ///     id: "PL1".to_string(),
+    ///     title: "Talks".to_string(),
+    ///     description: None,
+    ///     channel: None,
+    ///     video_count: 2,
+    ///     video_ids: vec!["aaa".to_string(), "bbb".to_string()],
+    /// };
+    ///
+    /// let entries = playlist.entries();
+    /// assert_eq!(entries[1].index, 2);
+    /// assert_eq!(entries[1].url(), "https://www.youtube.com/watch?v=bbb");
+    /// ```
+    pub fn entries(&self) -> Vec<PlaylistEntry> {
+        self.video_ids
+            .iter()
+            .enumerate()
+            .map(|(position, id)| PlaylistEntry {
+                index: position + 1,
+                video_id: id.clone(),
+                playlist_id: self.id.clone(),
+                playlist_title: self.title.clone(),
+                playlist_count: (self.video_count as usize).max(self.video_ids.len()),
+            })
+            .collect()
+    }
+}
+
+/// One video of a playlist, with its original position.
+///
+/// The position survives item selection, reversing and shuffling, so
+/// `%(playlist_index)s` in filename templates always refers to the
+/// playlist as published.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistEntry {
+    /// Position in the playlist, starting at 1
+    pub index: usize,
+    /// Video ID
+    pub video_id: String,
+    /// ID of the playlist
+    pub playlist_id: String,
+    /// Title of the playlist
+    pub playlist_title: String,
+    /// Number of videos in the playlist, from `video_count` when larger
+    pub playlist_count: usize,
+}
+
+impl PlaylistEntry {
+    /// Returns the watch URL of the video.
+    pub fn url(&self) -> String {
+        YouTubeUrl::Video {
+            id: self.video_id.clone(),
+            start: None,
+            list: None,
+        }
+        .to_string()
+    }
+}
+
+/// The fields of a video that `--filter` expressions can test.
+///
+/// Playlist listings only carry some of them (yt-dlp's "flat" metadata);
+/// the rest are `None` until the video's own info is fetched, which sets
+/// `complete`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VideoSummary {
+    /// Video ID
+    pub id: String,
+    /// Video title
+    pub title: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<u64>,
+    /// Upload date as `YYYYMMDD`
+    pub upload_date: Option<String>,
+    /// Total view count
+    pub view_count: Option<u64>,
+    /// `not_live`, `is_live`, `is_upcoming`, `was_live` or `post_live`
+    pub live_status: Option<String>,
+    /// `public`, `unlisted`, `private`, `needs_auth`, `subscriber_only` or
+    /// `premium_only`
+    pub availability: Option<String>,
+    /// `true` when read from the video's own info rather than a listing
+    pub complete: bool,
+}
+
+impl VideoSummary {
+    /// Creates a summary that knows nothing but the video ID.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
 }
 
 // ==================================================
@@ -381,6 +793,7 @@ mod tests {
 
     fn create_test_stream(quality: &str, format: &str, is_audio_only: bool) -> StreamInfo {
         StreamInfo {
+            format_id: format!("v{}", quality),
             url: format!("https://example.com/stream_{}", quality),
             quality: quality.to_string(),
             format: format.to_string(),
@@ -394,11 +807,15 @@ mod tests {
             file_size: Some(1000000),
             bitrate: Some(128),
             fps: Some(30),
+            width: None,
+            height: None,
+            dynamic_range: None,
         }
     }
 
     fn create_test_audio_stream(bitrate: u64) -> StreamInfo {
         StreamInfo {
+            format_id: format!("a{}", bitrate),
             url: format!("https://example.com/audio_{}", bitrate),
             quality: "audio".to_string(),
             format: "m4a".to_string(),
@@ -408,6 +825,9 @@ mod tests {
             file_size: Some(500000),
             bitrate: Some(bitrate),
             fps: None,
+            width: None,
+            height: None,
+            dynamic_range: None,
         }
     }
 
@@ -429,6 +849,8 @@ mod tests {
                 create_test_audio_stream(320),
                 create_test_audio_stream(128),
             ],
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
         }
     }
 
@@ -456,6 +878,8 @@ mod tests {
             publish_date: None,
             view_count: None,
             streams: vec![],
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
         };
 
         assert_eq!(video.id, "xyz789");
@@ -533,6 +957,8 @@ mod tests {
             publish_date: None,
             view_count: None,
             streams: vec![],
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
         };
 
         assert!(video.best_video_stream().is_none());
@@ -550,6 +976,8 @@ mod tests {
             publish_date: None,
             view_count: None,
             streams: vec![create_test_audio_stream(320), create_test_audio_stream(128)],
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
         };
 
         assert!(video.best_video_stream().is_none());
@@ -598,6 +1026,8 @@ mod tests {
             publish_date: None,
             view_count: None,
             streams: vec![],
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
         };
 
         assert!(video.worst_video_stream().is_none());
@@ -638,6 +1068,8 @@ mod tests {
                 create_test_stream("1080p", "mp4", false),
                 create_test_stream("720p", "mp4", false),
             ],
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
         };
 
         assert!(video.best_audio_stream().is_none());
@@ -805,6 +1237,8 @@ mod tests {
             publish_date: None,
             view_count: None,
             streams: vec![],
+            live_status: LiveStatus::NotLive,
+            release_timestamp: None,
         };
 
         let qualities = video.available_qualities();
@@ -865,8 +1299,354 @@ mod tests {
         assert_eq!(VideoInfo::quality_to_height(""), 0);
     }
 
+    // ============== stream_by_filter_with Tests ==============
+
+    use crate::youtube::{HdrMode, VideoCodec};
+
+    fn create_codec_stream(quality: &str, codec: &str, fps: u32) -> StreamInfo {
+        let mut stream = create_test_stream(quality, "mp4", false);
+        stream.format_id = format!("{}-{}-{}", quality, codec, fps);
+        stream.video_codec = Some(codec.to_string());
+        stream.fps = Some(fps);
+        stream
+    }
+
+    fn create_preference_video_info() -> VideoInfo {
+        let mut video = create_test_video_info();
+        video.streams = vec![
+            create_codec_stream("1080p", "avc1.640028", 30),
+            create_codec_stream("1080p", "vp09.00.40.08", 60),
+            create_codec_stream("720p", "av01.0.08M.08", 30),
+            create_codec_stream("720p", "av01.0.08M.08", 60),
+            create_test_audio_stream(128),
+        ];
+        video
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_default_matches_stream_by_filter() {
+        let video = create_test_video_info();
+        let pref = FormatPreference::default();
+
+        for filter in [
+            QualityFilter::Best,
+            QualityFilter::Worst,
+            QualityFilter::Exact(720),
+            QualityFilter::MaxHeight(480),
+        ] {
+            assert_eq!(
+                video.stream_by_filter_with(filter, &pref).map(|s| &s.url),
+                video.stream_by_filter(filter).map(|s| &s.url)
+            );
+        }
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_codec_preference() {
+        let video = create_preference_video_info();
+        let pref = FormatPreference::default().with_video_codecs(vec![VideoCodec::Av1]);
+
+        let stream = video
+            .stream_by_filter_with(QualityFilter::Best, &pref)
+            .unwrap();
+        assert!(stream.video_codec.as_deref().unwrap().starts_with("av01"));
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_codec_order() {
+        let video = create_preference_video_info();
+        let pref =
+            FormatPreference::default().with_video_codecs(vec![VideoCodec::H264, VideoCodec::Vp9]);
+
+        let stream = video
+            .stream_by_filter_with(QualityFilter::Best, &pref)
+            .unwrap();
+        assert_eq!(stream.format_id, "1080p-avc1.640028-30");
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_fps_breaks_ties() {
+        let video = create_preference_video_info();
+        let pref = FormatPreference::default()
+            .with_video_codecs(vec![VideoCodec::Av1])
+            .with_fps(60);
+
+        let stream = video
+            .stream_by_filter_with(QualityFilter::Best, &pref)
+            .unwrap();
+        assert_eq!(stream.fps, Some(60));
+
+        let pref = pref.with_fps(30);
+        let stream = video
+            .stream_by_filter_with(QualityFilter::Best, &pref)
+            .unwrap();
+        assert_eq!(stream.fps, Some(30));
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_never_hdr() {
+        let mut video = create_test_video_info();
+        video.streams[0].dynamic_range = Some("HDR10".to_string());
+        let pref = FormatPreference::default().with_hdr(HdrMode::Never);
+
+        let stream = video
+            .stream_by_filter_with(QualityFilter::Best, &pref)
+            .unwrap();
+        assert_eq!(stream.quality, "720p");
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_prefer_hdr() {
+        let mut video = create_test_video_info();
+        video
+            .streams
+            .push(create_test_stream("1080p", "webm", false));
+        video.streams[0].dynamic_range = Some("HDR10".to_string());
+        let pref = FormatPreference::default().with_hdr(HdrMode::Prefer);
+
+        let stream = video
+            .stream_by_filter_with(QualityFilter::Best, &pref)
+            .unwrap();
+        assert!(stream.is_hdr());
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_prefer_vertical() {
+        let mut video = create_test_video_info();
+        video.streams[2].width = Some(480);
+        video.streams[2].height = Some(854);
+        let pref = FormatPreference::default().with_prefer_vertical(true);
+
+        let stream = video
+            .stream_by_filter_with(QualityFilter::Best, &pref)
+            .unwrap();
+        assert!(stream.is_vertical());
+    }
+
+    #[test]
+    fn test_stream_by_filter_with_respects_quality_filter() {
+        let video = create_preference_video_info();
+        let pref = FormatPreference::default().with_video_codecs(vec![VideoCodec::Vp9]);
+
+        let stream = video
+            .stream_by_filter_with(QualityFilter::MaxHeight(720), &pref)
+            .unwrap();
+        assert_eq!(stream.quality, "720p");
+    }
+
+    // ============== stream_by_format_id Tests ==============
+
+    #[test]
+    fn test_stream_by_format_id_found() {
+        let video = create_test_video_info();
+        let stream = video.stream_by_format_id("v720p");
+
+        assert!(stream.is_some());
+        assert_eq!(stream.unwrap().quality, "720p");
+    }
+
+    #[test]
+    fn test_stream_by_format_id_includes_audio() {
+        let video = create_test_video_info();
+        let stream = video.stream_by_format_id("a320");
+
+        assert!(stream.is_some());
+        assert!(stream.unwrap().is_audio_only);
+    }
+
+    #[test]
+    fn test_stream_by_format_id_not_found() {
+        let video = create_test_video_info();
+        assert!(video.stream_by_format_id("137").is_none());
+    }
+
+    // ============== sorted_streams Tests ==============
+
+    fn create_sized_stream(format_id: &str, height: Option<u32>, size: Option<u64>) -> StreamInfo {
+        let mut stream = create_test_stream("unknown", "mp4", height.is_none());
+        stream.format_id = format_id.to_string();
+        stream.height = height;
+        stream.width = height.map(|h| h * 16 / 9);
+        stream.file_size = size;
+        stream
+    }
+
+    fn create_sortable_video_info() -> VideoInfo {
+        let mut video = create_test_video_info();
+        video.streams = vec![
+            create_sized_stream("18", Some(360), Some(10)),
+            create_sized_stream("140", None, Some(5)),
+            create_sized_stream("137", Some(1080), None),
+            create_sized_stream("hls-720", Some(720), Some(30)),
+        ];
+        video
+    }
+
+    fn ids(streams: &[&StreamInfo]) -> Vec<String> {
+        streams.iter().map(|s| s.format_id.clone()).collect()
+    }
+
+    #[test]
+    fn test_sorted_streams_by_resolution() {
+        let video = create_sortable_video_info();
+        let sorted = video.sorted_streams(StreamSortKey::Resolution, false);
+
+        assert_eq!(ids(&sorted), vec!["137", "hls-720", "18", "140"]);
+    }
+
+    #[test]
+    fn test_sorted_streams_by_id_numeric_first() {
+        let video = create_sortable_video_info();
+        let sorted = video.sorted_streams(StreamSortKey::Id, false);
+
+        assert_eq!(ids(&sorted), vec!["18", "137", "140", "hls-720"]);
+    }
+
+    #[test]
+    fn test_sorted_streams_by_size_missing_last() {
+        let video = create_sortable_video_info();
+        let sorted = video.sorted_streams(StreamSortKey::Size, false);
+
+        assert_eq!(ids(&sorted), vec!["hls-720", "18", "140", "137"]);
+    }
+
+    #[test]
+    fn test_sorted_streams_reverse() {
+        let video = create_sortable_video_info();
+        let sorted = video.sorted_streams(StreamSortKey::Resolution, true);
+
+        assert_eq!(ids(&sorted), vec!["140", "18", "hls-720", "137"]);
+    }
+
+    #[test]
+    fn test_sorted_streams_includes_every_stream() {
+        let video = create_test_video_info();
+        let sorted = video.sorted_streams(StreamSortKey::Codec, false);
+
+        assert_eq!(sorted.len(), video.streams.len());
+    }
+
+    #[test]
+    fn test_stream_sort_key_default() {
+        assert_eq!(StreamSortKey::default(), StreamSortKey::Resolution);
+    }
+
+    // ============== Live Status Tests ==============
+
+    #[test]
+    fn test_live_status_from_ytdlp() {
+        assert_eq!(
+            LiveStatus::from_ytdlp(Some("is_live"), Some(false)),
+            LiveStatus::IsLive
+        );
+        assert_eq!(
+            LiveStatus::from_ytdlp(Some("was_live"), None),
+            LiveStatus::WasLive
+        );
+        assert_eq!(
+            LiveStatus::from_ytdlp(Some("post_live"), None),
+            LiveStatus::PostLive
+        );
+        assert_eq!(
+            LiveStatus::from_ytdlp(Some("not_live"), Some(true)),
+            LiveStatus::NotLive
+        );
+        assert_eq!(
+            LiveStatus::from_ytdlp(None, Some(false)),
+            LiveStatus::NotLive
+        );
+    }
+
+    #[test]
+    fn test_release_time() {
+        let mut video = create_test_video_info();
+        assert!(!video.is_upcoming());
+        assert_eq!(video.release_time(), None);
+
+        video.live_status = LiveStatus::IsUpcoming;
+        video.release_timestamp = Some(1718000000);
+        assert!(video.is_upcoming());
+        assert!(!video.is_live());
+        assert_eq!(
+            video.release_time().unwrap().to_rfc3339(),
+            "2024-06-10T06:13:20+00:00"
+        );
+    }
+
+    #[test]
+    fn test_live_fields_default_when_missing() {
+        let json = serde_json::to_value(create_test_video_info()).unwrap();
+        let mut object = json.as_object().unwrap().clone();
+        object.remove("live_status");
+        object.remove("release_timestamp");
+
+        let video: VideoInfo = serde_json::from_value(object.into()).unwrap();
+        assert_eq!(video.live_status, LiveStatus::NotLive);
+        assert_eq!(video.release_timestamp, None);
+    }
+
     // ============== StreamInfo Tests ==============
 
+    #[test]
+    fn test_estimated_size() {
+        let mut stream = create_test_stream("720p", "mp4", false);
+        assert_eq!(stream.estimated_size(60), Some(1000000));
+
+        stream.file_size = None;
+        stream.bitrate = Some(2_000_000);
+        assert_eq!(stream.estimated_size(60), Some(15_000_000));
+        assert_eq!(stream.estimated_size(0), None);
+
+        stream.bitrate = None;
+        assert_eq!(stream.estimated_size(60), None);
+    }
+
+    #[test]
+    fn test_stream_info_resolution_with_dimensions() {
+        let mut stream = create_test_stream("1080p", "mp4", false);
+        stream.width = Some(1920);
+        stream.height = Some(1080);
+
+        assert_eq!(stream.resolution(), "1920x1080");
+    }
+
+    #[test]
+    fn test_stream_info_resolution_falls_back_to_quality() {
+        let stream = create_test_stream("720p", "mp4", false);
+        assert_eq!(stream.resolution(), "720p");
+    }
+
+    #[test]
+    fn test_stream_info_is_hdr() {
+        let mut stream = create_test_stream("1080p", "webm", false);
+        assert!(!stream.is_hdr());
+
+        stream.dynamic_range = Some("SDR".to_string());
+        assert!(!stream.is_hdr());
+
+        stream.dynamic_range = Some("HLG".to_string());
+        assert!(stream.is_hdr());
+    }
+
+    #[test]
+    fn test_stream_info_is_vertical() {
+        let mut stream = create_test_stream("1080p", "mp4", false);
+        assert!(!stream.is_vertical());
+
+        stream.width = Some(1080);
+        stream.height = Some(1920);
+        assert!(stream.is_vertical());
+    }
+
+    #[test]
+    fn test_stream_info_codec() {
+        let video = create_test_stream("720p", "mp4", false);
+        let audio = create_test_audio_stream(128);
+
+        assert_eq!(video.codec(), Some("h264"));
+        assert_eq!(audio.codec(), Some("aac"));
+    }
+
     #[test]
     fn test_stream_info_creation() {
         let stream = create_test_stream("1080p", "mp4", false);
@@ -1004,6 +1784,27 @@ mod tests {
         assert_eq!(playlist.len(), 0);
     }
 
+    #[test]
+    fn test_playlist_entries_keep_positions() {
+        let playlist = PlaylistInfo {
+            id: "PL123".to_string(),
+            title: "My Playlist".to_string(),
+            description: None,
+            channel: None,
+            video_count: 2,
+            video_ids: vec!["vid1".to_string(), "vid2".to_string()],
+        };
+
+        let entries = playlist.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].index, 1);
+        assert_eq!(entries[1].index, 2);
+        assert_eq!(entries[1].video_id, "vid2");
+        assert_eq!(entries[1].playlist_id, "PL123");
+        assert_eq!(entries[1].playlist_count, 2);
+        assert_eq!(entries[0].url(), "https://www.youtube.com/watch?v=vid1");
+    }
+
     // ============== QualityFilter Tests ==============
 
     #[test]
@@ -1076,6 +1877,27 @@ mod tests {
         assert_eq!(video.duration, 120);
     }
 
+    #[test]
+    fn test_stream_info_deserialize_without_format_id() {
+        let json = r#"{
+            "url": "https://example.com/s",
+            "quality": "720p",
+            "format": "mp4",
+            "video_codec": null,
+            "audio_codec": null,
+            "is_audio_only": false,
+            "file_size": null,
+            "bitrate": null,
+            "fps": null
+        }"#;
+
+        let stream: StreamInfo = serde_json::from_str(json).unwrap();
+
+        assert_eq!(stream.format_id, "");
+        assert!(stream.width.is_none());
+        assert!(stream.height.is_none());
+    }
+
     #[test]
     fn test_stream_info_serialize() {
         let stream = create_test_stream("720p", "mp4", false);
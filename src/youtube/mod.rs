@@ -6,8 +6,10 @@
 //!
 //! # Modules
 //!
+//! - [`format`] - Codec, fps, HDR and orientation preferences for stream selection
 //! - [`metadata`] - Data structures for video and playlist information
 //! - [`playlist`] - Playlist URL validation and video ID extraction
+//! - [`url`] - Classification and canonicalization of YouTube URLs
 //! - [`ytdlp`] - Integration with yt-dlp command-line tool (primary client)
 //!
 //! # Examples
@@ -24,10 +26,20 @@
 //! # }
 //! ```
 
+pub mod format;
 pub mod metadata;
 pub mod playlist;
+pub mod url;
 pub mod ytdlp;
 
-pub use metadata::{PlaylistInfo, QualityFilter, StreamInfo, VideoInfo};
-pub use playlist::{extract_playlist_ids, filter_valid_playlist_urls, PlaylistClient};
+pub use format::{AudioCodec, FormatPreference, HdrMode, VideoCodec};
+pub use metadata::{
+    LiveStatus, PlaylistEntry, PlaylistInfo, QualityFilter, StreamInfo, StreamSortKey, VideoInfo,
+    VideoSummary,
+};
+pub use playlist::{
+    extract_playlist_ids, filter_valid_playlist_urls, ItemOrder, ItemSpec, PlaylistClient,
+    PlaylistSelection,
+};
+pub use url::{canonicalize_url, validate_url, YouTubeUrl};
 pub use ytdlp::YtDlpClient;
@@ -9,10 +9,18 @@
 //! - Standard: `https://www.youtube.com/playlist?list=PLxxxxx`
 //! - With video: `https://www.youtube.com/watch?v=VIDEO_ID&list=PLxxxxx`
 //! - With parameters: URLs containing index, shuffle, etc.
+//!
+//! # Item Selection
+//!
+//! [`PlaylistSelection`] picks which entries of an enumerated playlist are
+//! downloaded (`--items`) and in which order (`--reverse`, `--shuffle`).
+
+use std::collections::HashSet;
+use std::str::FromStr;
 
 use crate::error::{AppError, AppResult};
 use crate::utils::extract_playlist_id;
-use crate::youtube::metadata::PlaylistInfo;
+use crate::youtube::metadata::{PlaylistEntry, PlaylistInfo};
 
 /// Client for working with YouTube playlists.
 ///
@@ -238,6 +246,314 @@ pub fn filter_valid_playlist_urls(urls: &[String]) -> Vec<&String> {
         .collect()
 }
 
+/// Playlist positions to download, as given to `--items`.
+///
+/// A comma-separated list of positions (starting at 1) and ranges:
+///
+/// - `25` - a single item
+/// - `1-10` or `1:10` - items 1 to 10, inclusive
+/// - `-5:` - the last five items
+/// - `::2` - every other item
+/// - `-1::-1` - every item, last first
+///
+/// Negative positions count from the end. Items are returned in the order
+/// they are listed, without duplicates; positions past the end are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::youtube::ItemSpec;
+///
+/// let items: ItemSpec = "1-3,7,-2:".parse().unwrap();
+/// assert_eq!(items.indices(10).unwrap(), vec![1, 2, 3, 7, 9, 10]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSpec {
+    ranges: Vec<ItemRange>,
+}
+
+/// One comma-separated part of an [`ItemSpec`]: `start:stop:step`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ItemRange {
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: i64,
+}
+
+impl FromStr for ItemSpec {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ranges = s
+            .split(',')
+            .map(|part| ItemRange::parse(part.trim()))
+            .collect::<AppResult<Vec<_>>>()?;
+        Ok(Self { ranges })
+    }
+}
+
+impl ItemRange {
+    fn parse(part: &str) -> AppResult<Self> {
+        let invalid = || {
+            AppError::invalid_arg(
+                "items",
+                format!("invalid item '{}' (expected e.g. 1-10,25,-5:)", part),
+            )
+        };
+        let position = |value: &str| -> AppResult<Option<i64>> {
+            if value.is_empty() {
+                return Ok(None);
+            }
+            match value.parse::<i64>() {
+                Ok(0) => Err(AppError::invalid_arg("items", "positions start at 1")),
+                Ok(n) => Ok(Some(n)),
+                Err(_) => Err(invalid()),
+            }
+        };
+
+        if part.contains(':') {
+            let fields: Vec<&str> = part.split(':').collect();
+            let step = match fields.as_slice() {
+                [_, _] => 1,
+                [_, _, ""] => 1,
+                [_, _, step] => match step.parse::<i64>() {
+                    Ok(0) => return Err(AppError::invalid_arg("items", "step cannot be 0")),
+                    Ok(step) => step,
+                    Err(_) => return Err(invalid()),
+                },
+                _ => return Err(invalid()),
+            };
+            return Ok(Self {
+                start: position(fields[0])?,
+                stop: position(fields[1])?,
+                step,
+            });
+        }
+
+        // `A-B` ranges; a leading `-` is the sign of a single position
+        if let Some((start, stop)) = part.get(1..).and_then(|rest| rest.split_once('-')) {
+            let start = format!("{}{}", &part[..1], start);
+            let (start, stop) = (position(&start)?, position(stop)?);
+            if start.is_none() || stop.is_none() {
+                return Err(invalid());
+            }
+            return Ok(Self {
+                start,
+                stop,
+                step: 1,
+            });
+        }
+
+        let single = position(part)?.ok_or_else(invalid)?;
+        Ok(Self {
+            start: Some(single),
+            stop: Some(single),
+            step: 1,
+        })
+    }
+
+    /// Returns the positions of this range in a playlist of `count` items.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if a position or step is too large
+    /// to resolve against the playlist.
+    fn indices(&self, count: usize) -> AppResult<Vec<usize>> {
+        let overflow = || AppError::invalid_arg("items", "item range is out of bounds");
+        let count = i64::try_from(count).map_err(|_| overflow())?;
+        let resolve = |n: i64| {
+            if n < 0 {
+                count.checked_add(n).and_then(|n| n.checked_add(1))
+            } else {
+                Some(n)
+            }
+        };
+        let (default_start, default_stop) = if self.step > 0 {
+            (1, count)
+        } else {
+            (count, 1)
+        };
+        let mut start = match self.start {
+            Some(n) => resolve(n).ok_or_else(overflow)?,
+            None => default_start,
+        };
+        let stop = match self.stop {
+            Some(n) => resolve(n).ok_or_else(overflow)?,
+            None => default_stop,
+        };
+
+        // Skip straight to the first position inside the playlist
+        let outside = if self.step > 0 {
+            1i64.checked_sub(start)
+        } else {
+            start.checked_sub(count)
+        }
+        .ok_or_else(overflow)?;
+        if outside > 0 {
+            let stride = self.step.checked_abs().ok_or_else(overflow)?;
+            let steps = outside.checked_add(stride - 1).ok_or_else(overflow)? / stride;
+            start = steps
+                .checked_mul(self.step)
+                .and_then(|offset| start.checked_add(offset))
+                .ok_or_else(overflow)?;
+        }
+
+        let mut indices = Vec::new();
+        let mut i = start;
+        while (1..=count).contains(&i) && (self.step > 0 && i <= stop || self.step < 0 && i >= stop)
+        {
+            indices.push(i as usize);
+            // A step past `i64` is past the end of the playlist too
+            match i.checked_add(self.step) {
+                Some(next) => i = next,
+                None => break,
+            }
+        }
+        Ok(indices)
+    }
+}
+
+impl ItemSpec {
+    /// Returns the selected positions in a playlist of `count` items.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if a range is too large to
+    /// resolve against the playlist, e.g. `-9223372036854775808:`.
+    pub fn indices(&self, count: usize) -> AppResult<Vec<usize>> {
+        let mut seen = HashSet::new();
+        let mut indices = Vec::new();
+        for range in &self.ranges {
+            indices.extend(
+                range
+                    .indices(count)?
+                    .into_iter()
+                    .filter(|index| seen.insert(*index)),
+            );
+        }
+        Ok(indices)
+    }
+}
+
+/// Order in which the selected playlist items are downloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItemOrder {
+    /// The order of `--items`, or the playlist order
+    #[default]
+    Playlist,
+    /// The reverse of [`ItemOrder::Playlist`]
+    Reverse,
+    /// A random order, applied before `--items` so that items sample the
+    /// shuffled playlist; the same seed always gives the same order
+    Shuffle(u64),
+}
+
+/// Which entries of a playlist to download, and in which order.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::youtube::{ItemOrder, PlaylistInfo, PlaylistSelection};
+///
+/// let playlist = PlaylistInfo {
+///     id: "PL1".to_string(),
+///     title: "Talks".to_string(),
+///     description: None,
+///     channel: None,
+///     video_count: 4,
+///     video_ids: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+/// };
+///
+/// let selection = PlaylistSelection::new()
+///     .with_items(Some("1-3".parse().unwrap()))
+///     .with_order(ItemOrder::Reverse);
+///
+/// let ids: Vec<_> = selection
+///     .apply(playlist.entries())
+///     .unwrap()
+///     .into_iter()
+///     .map(|entry| entry.video_id)
+///     .collect();
+/// assert_eq!(ids, vec!["c", "b", "a"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlaylistSelection {
+    /// Positions to keep; every entry when `None`
+    pub items: Option<ItemSpec>,
+    /// Download order
+    pub order: ItemOrder,
+}
+
+impl PlaylistSelection {
+    /// Creates a selection of every entry, in playlist order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the positions to keep.
+    pub fn with_items(mut self, items: Option<ItemSpec>) -> Self {
+        self.items = items;
+        self
+    }
+
+    /// Sets the download order.
+    pub fn with_order(mut self, order: ItemOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Returns the selected entries in download order. Each entry keeps its
+    /// original `index`.
+    ///
+    /// A shuffle happens before the items are picked, so `--shuffle --items
+    /// 1-5` is a random sample of five; a reverse happens after.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidArgument` if the items cannot be resolved
+    /// against the playlist (see [`ItemSpec::indices`]).
+    pub fn apply(&self, mut entries: Vec<PlaylistEntry>) -> AppResult<Vec<PlaylistEntry>> {
+        if let ItemOrder::Shuffle(seed) = self.order {
+            shuffle(&mut entries, seed);
+        }
+
+        let mut selected = match &self.items {
+            Some(items) => {
+                let mut slots: Vec<Option<PlaylistEntry>> = entries.into_iter().map(Some).collect();
+                items
+                    .indices(slots.len())?
+                    .into_iter()
+                    .filter_map(|index| slots[index - 1].take())
+                    .collect()
+            }
+            None => entries,
+        };
+
+        if self.order == ItemOrder::Reverse {
+            selected.reverse();
+        }
+        Ok(selected)
+    }
+}
+
+/// Shuffles `items` in place with a Fisher-Yates shuffle driven by
+/// SplitMix64, so a seed gives the same order on every platform.
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+
+    for i in (1..items.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
 // ==================================================
 //          UNITARY TESTS
 // ==================================================
@@ -250,14 +566,13 @@ mod tests {
 
     #[test]
     fn test_playlist_client_new() {
-        let client = PlaylistClient::new();
-        assert!(true);
+        let _client = PlaylistClient::new();
     }
 
     #[test]
+    #[allow(clippy::default_constructed_unit_structs)]
     fn test_playlist_client_default() {
-        let client = PlaylistClient::default();
-        assert!(true);
+        let _client = PlaylistClient::default();
     }
 
     // ============== is_playlist_url Tests ==============
@@ -640,11 +955,165 @@ mod tests {
 
     #[test]
     fn test_multiple_client_instances_independent() {
-        let client1 = PlaylistClient::new();
-        let client2 = PlaylistClient::new();
+        let _client1 = PlaylistClient::new();
+        let _client2 = PlaylistClient::new();
 
         assert!(PlaylistClient::is_playlist_url(
             "https://www.youtube.com/playlist?list=PLtest"
         ));
     }
+
+    // ============== ItemSpec Tests ==============
+
+    fn items(spec: &str, count: usize) -> Vec<usize> {
+        spec.parse::<ItemSpec>().unwrap().indices(count).unwrap()
+    }
+
+    #[test]
+    fn test_items_single_and_ranges() {
+        assert_eq!(items("3", 10), vec![3]);
+        assert_eq!(items("1-3,25", 30), vec![1, 2, 3, 25]);
+        assert_eq!(items("2:4", 10), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_items_negative_positions() {
+        assert_eq!(items("-5:", 12), vec![8, 9, 10, 11, 12]);
+        assert_eq!(items("-1", 12), vec![12]);
+        assert_eq!(items(":-10", 12), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_items_steps() {
+        assert_eq!(items("1:3,7,-5::2", 15), vec![1, 2, 3, 7, 11, 13, 15]);
+        assert_eq!(items("::-1", 3), vec![3, 2, 1]);
+        assert_eq!(items("-100::3", 7), vec![1, 4, 7]);
+    }
+
+    #[test]
+    fn test_items_skip_out_of_range_and_duplicates() {
+        assert_eq!(items("1-3,2,50", 5), vec![1, 2, 3]);
+        assert_eq!(items("10-20", 5), Vec::<usize>::new());
+        assert_eq!(items("-9", 5), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_items_overflow_is_an_error() {
+        for (spec, count) in [
+            ("-9223372036854775808:", 0),
+            ("10::-9223372036854775808", 5),
+            ("-9223372036854775808::9223372036854775807", 5),
+        ] {
+            let items = spec.parse::<ItemSpec>().unwrap();
+            assert!(items.indices(count).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_items_huge_steps() {
+        assert_eq!(items("1::9223372036854775807", 5), vec![1]);
+        assert_eq!(items("-1::-9223372036854775807", 5), vec![5]);
+        assert_eq!(items("9223372036854775807:", 5), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_items_invalid() {
+        for spec in ["", "0", "a", "1-", "1:2:0", "1:2:3:4", "3-x", "1,,2"] {
+            assert!(spec.parse::<ItemSpec>().is_err(), "{}", spec);
+        }
+    }
+
+    // ============== PlaylistSelection Tests ==============
+
+    fn sample_playlist(count: usize) -> PlaylistInfo {
+        PlaylistInfo {
+            id: "PL1".to_string(),
+            title: "Talks".to_string(),
+            description: None,
+            channel: None,
+            video_count: count as u64,
+            video_ids: (1..=count).map(|i| format!("v{}", i)).collect(),
+        }
+    }
+
+    fn positions(entries: &[PlaylistEntry]) -> Vec<usize> {
+        entries.iter().map(|entry| entry.index).collect()
+    }
+
+    #[test]
+    fn test_selection_default_keeps_everything() {
+        let selected = PlaylistSelection::new()
+            .apply(sample_playlist(3).entries())
+            .unwrap();
+        assert_eq!(positions(&selected), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_selection_items_keep_original_index() {
+        let selected = PlaylistSelection::new()
+            .with_items(Some("-2:".parse().unwrap()))
+            .apply(sample_playlist(5).entries())
+            .unwrap();
+
+        assert_eq!(positions(&selected), vec![4, 5]);
+        assert_eq!(selected[0].video_id, "v4");
+    }
+
+    #[test]
+    fn test_selection_reverse() {
+        let selected = PlaylistSelection::new()
+            .with_order(ItemOrder::Reverse)
+            .apply(sample_playlist(3).entries())
+            .unwrap();
+        assert_eq!(positions(&selected), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_selection_items_then_reverse() {
+        let selected = PlaylistSelection::new()
+            .with_items(Some("-3:".parse().unwrap()))
+            .with_order(ItemOrder::Reverse)
+            .apply(sample_playlist(10).entries())
+            .unwrap();
+        assert_eq!(positions(&selected), vec![10, 9, 8]);
+    }
+
+    #[test]
+    fn test_selection_shuffle_then_items_samples() {
+        let selection = PlaylistSelection::new()
+            .with_items(Some("1-3".parse().unwrap()))
+            .with_order(ItemOrder::Shuffle(7));
+
+        let sample = positions(&selection.apply(sample_playlist(50).entries()).unwrap());
+        let shuffled = positions(
+            &PlaylistSelection::new()
+                .with_order(ItemOrder::Shuffle(7))
+                .apply(sample_playlist(50).entries())
+                .unwrap(),
+        );
+
+        assert_eq!(sample, shuffled[..3]);
+        assert_ne!(sample, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_selection_shuffle_is_reproducible() {
+        let shuffled = |seed| {
+            positions(
+                &PlaylistSelection::new()
+                    .with_order(ItemOrder::Shuffle(seed))
+                    .apply(sample_playlist(20).entries())
+                    .unwrap(),
+            )
+        };
+
+        let first = shuffled(42);
+        assert_eq!(first, shuffled(42));
+        assert_ne!(first, shuffled(43));
+        assert_ne!(first, (1..=20).collect::<Vec<_>>());
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, (1..=20).collect::<Vec<_>>());
+    }
 }
@@ -0,0 +1,570 @@
+//! Classification and canonicalization of YouTube URLs.
+//!
+//! YouTube links come in many shapes: `watch?v=`, `youtu.be`, embeds,
+//! Shorts, live streams, the mobile and Music sites, privacy-enhanced
+//! embeds, channel handles and searches. [`YouTubeUrl`] recognizes them all
+//! and rebuilds a canonical `https://www.youtube.com/...` URL that keeps
+//! only what changes the result (`t=` and `list=`), dropping tracking
+//! parameters such as `si=`, `feature=` and `pp=`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rust_yt_downloader::youtube::YouTubeUrl;
+//!
+//! let url: YouTubeUrl = "https://m.youtube.com/shorts/dQw4w9WgXcQ?si=abc".parse().unwrap();
+//! assert_eq!(url.video_id(), Some("dQw4w9WgXcQ"));
+//! assert_eq!(url.to_string(), "https://www.youtube.com/shorts/dQw4w9WgXcQ");
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use reqwest::Url;
+
+use crate::error::{AppError, AppResult};
+
+/// Hosts serving the regular YouTube site.
+const YOUTUBE_HOSTS: &[&str] = &[
+    "youtube.com",
+    "www.youtube.com",
+    "m.youtube.com",
+    "music.youtube.com",
+    "youtube-nocookie.com",
+    "www.youtube-nocookie.com",
+];
+
+/// Hosts of the short-link service.
+const SHORT_LINK_HOSTS: &[&str] = &["youtu.be", "www.youtu.be"];
+
+/// Path prefixes of channel URLs, besides `@handle`.
+const CHANNEL_PREFIXES: &[&str] = &["channel", "c", "user"];
+
+/// A YouTube URL, classified by what it points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YouTubeUrl {
+    /// A regular video, optionally opened at a time or inside a playlist
+    Video {
+        /// Video ID
+        id: String,
+        /// Start time (`t=`), as given
+        start: Option<String>,
+        /// Playlist the video was opened from (`list=`)
+        list: Option<String>,
+    },
+    /// A YouTube Short (`/shorts/<id>`)
+    Short {
+        /// Video ID
+        id: String,
+    },
+    /// A live stream or its replay (`/live/<id>`)
+    Live {
+        /// Video ID
+        id: String,
+    },
+    /// A playlist (`/playlist?list=`)
+    Playlist {
+        /// Playlist ID
+        id: String,
+    },
+    /// A channel, or one of its tabs
+    Channel {
+        /// `@handle`, `channel/<ID>`, `c/<name>` or `user/<name>`
+        channel: String,
+        /// Tab such as `videos`, `shorts` or `streams`
+        tab: Option<String>,
+    },
+    /// A search results page (`/results?search_query=`)
+    Search {
+        /// The search terms
+        query: String,
+    },
+}
+
+impl YouTubeUrl {
+    /// Classifies a URL.
+    ///
+    /// Accepts `youtube.com` with or without `www.`, `m.youtube.com`,
+    /// `music.youtube.com`, `youtube-nocookie.com` and `youtu.be`. A missing
+    /// scheme is assumed to be `https`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InvalidUrl` if the URL is not a YouTube URL or
+    /// does not point to a video, playlist, channel or search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_yt_downloader::youtube::YouTubeUrl;
+    ///
+    /// let url = YouTubeUrl::parse("youtu.be/dQw4w9WgXcQ?t=42").unwrap();
+    /// assert_eq!(url.to_string(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42");
+    ///
+    /// assert!(YouTubeUrl::parse("https://vimeo.com/123").is_err());
+    /// ```
+    pub fn parse(url: &str) -> AppResult<Self> {
+        let invalid = || AppError::InvalidUrl(url.to_string());
+        let trimmed = url.trim();
+
+        let parsed = match Url::parse(trimmed) {
+            Ok(parsed) => parsed,
+            Err(_) if !trimmed.contains("://") => {
+                Url::parse(&format!("https://{}", trimmed)).map_err(|_| invalid())?
+            }
+            Err(_) => return Err(invalid()),
+        };
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(invalid());
+        }
+
+        let host = parsed.host_str().ok_or_else(invalid)?.to_ascii_lowercase();
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let query = |key: &str| {
+            parsed
+                .query_pairs()
+                .find(|(k, v)| k == key && !v.is_empty())
+                .map(|(_, v)| v.into_owned())
+        };
+
+        let classified = if SHORT_LINK_HOSTS.contains(&host.as_str()) {
+            match segments.as_slice() {
+                [id] => Some(Self::Video {
+                    id: id.to_string(),
+                    start: query("t"),
+                    list: query("list"),
+                }),
+                _ => None,
+            }
+        } else if YOUTUBE_HOSTS.contains(&host.as_str()) {
+            Self::classify_path(&segments, query)
+        } else {
+            None
+        };
+
+        classified.filter(Self::has_valid_ids).ok_or_else(invalid)
+    }
+
+    /// Classifies the path of a `youtube.com` URL.
+    fn classify_path(segments: &[&str], query: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let video = |id: &str, start: Option<String>| Self::Video {
+            id: id.to_string(),
+            start,
+            list: query("list"),
+        };
+
+        let classified = match segments {
+            ["watch"] => match query("v") {
+                Some(id) => video(&id, query("t")),
+                None => Self::Playlist { id: query("list")? },
+            },
+            ["playlist"] => Self::Playlist { id: query("list")? },
+            ["embed", "videoseries"] => Self::Playlist { id: query("list")? },
+            ["embed" | "v" | "e", id] => video(id, query("start").or_else(|| query("t"))),
+            ["shorts", id] => Self::Short { id: id.to_string() },
+            ["live", id] => Self::Live { id: id.to_string() },
+            ["results"] => Self::Search {
+                query: query("search_query").or_else(|| query("q"))?,
+            },
+            [handle, rest @ ..] if handle.starts_with('@') && handle.len() > 1 => Self::Channel {
+                channel: handle.to_string(),
+                tab: Self::tab(rest)?,
+            },
+            [prefix, name, rest @ ..] if CHANNEL_PREFIXES.contains(prefix) => Self::Channel {
+                channel: format!("{}/{}", prefix, name),
+                tab: Self::tab(rest)?,
+            },
+            _ => return None,
+        };
+        Some(classified)
+    }
+
+    /// Returns the tab of a channel URL, `None` inside if there is none, or
+    /// `None` if the path goes deeper than one tab.
+    fn tab(rest: &[&str]) -> Option<Option<String>> {
+        match rest {
+            [] => Some(None),
+            [tab] => Some(Some(tab.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Checks that video and playlist IDs only use the characters YouTube
+    /// uses: letters, digits, `-` and `_`.
+    fn has_valid_ids(&self) -> bool {
+        let valid = |id: &str| {
+            !id.is_empty()
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+
+        match self {
+            Self::Video { id, list, .. } => valid(id) && list.as_deref().map_or(true, valid),
+            Self::Short { id } | Self::Live { id } | Self::Playlist { id } => valid(id),
+            Self::Channel { .. } | Self::Search { .. } => true,
+        }
+    }
+
+    /// Returns the video ID of a video, Short or live stream.
+    pub fn video_id(&self) -> Option<&str> {
+        match self {
+            Self::Video { id, .. } | Self::Short { id } | Self::Live { id } => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the ID of a playlist, or of the playlist a video was opened
+    /// from.
+    pub fn playlist_id(&self) -> Option<&str> {
+        match self {
+            Self::Playlist { id } => Some(id),
+            Self::Video { list, .. } => list.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for URLs that list several videos: playlists,
+    /// channels and searches.
+    pub fn is_collection(&self) -> bool {
+        matches!(
+            self,
+            Self::Playlist { .. } | Self::Channel { .. } | Self::Search { .. }
+        )
+    }
+}
+
+impl FromStr for YouTubeUrl {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Formats the canonical `https://www.youtube.com/...` URL.
+impl fmt::Display for YouTubeUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut url = Url::parse("https://www.youtube.com/").expect("valid base URL");
+
+        match self {
+            Self::Video { id, start, list } => {
+                url.set_path("watch");
+                let mut query = url.query_pairs_mut();
+                query.append_pair("v", id);
+                if let Some(list) = list {
+                    query.append_pair("list", list);
+                }
+                if let Some(start) = start {
+                    query.append_pair("t", start);
+                }
+            }
+            Self::Short { id } => url.set_path(&format!("shorts/{}", id)),
+            Self::Live { id } => url.set_path(&format!("live/{}", id)),
+            Self::Playlist { id } => {
+                url.set_path("playlist");
+                url.query_pairs_mut().append_pair("list", id);
+            }
+            Self::Channel { channel, tab } => match tab {
+                Some(tab) => url.set_path(&format!("{}/{}", channel, tab)),
+                None => url.set_path(channel),
+            },
+            Self::Search { query } => {
+                url.set_path("results");
+                url.query_pairs_mut().append_pair("search_query", query);
+            }
+        }
+
+        f.write_str(url.as_str())
+    }
+}
+
+/// Returns the canonical form of a YouTube URL, or the URL unchanged if it
+/// cannot be classified (yt-dlp supports many other sites).
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::youtube::canonicalize_url;
+///
+/// assert_eq!(
+///     canonicalize_url("https://music.youtube.com/watch?v=dQw4w9WgXcQ&feature=share"),
+///     "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+/// );
+/// assert_eq!(canonicalize_url("https://vimeo.com/123"), "https://vimeo.com/123");
+/// ```
+pub fn canonicalize_url(url: &str) -> String {
+    YouTubeUrl::parse(url)
+        .map(|parsed| parsed.to_string())
+        .unwrap_or_else(|_| url.to_string())
+}
+
+/// Validates a URL before it is handed to yt-dlp and returns the form to
+/// use.
+///
+/// YouTube URLs must be classifiable by [`YouTubeUrl`] and are returned in
+/// canonical form. URLs of other sites must be absolute `http` or `https`
+/// URLs with a host, and are returned as given. Anything else, including
+/// values starting with `-` that yt-dlp would read as options, is rejected.
+///
+/// # Errors
+///
+/// Returns `AppError::InvalidUrl` if the URL is malformed.
+///
+/// # Examples
+///
+/// ```
+/// use rust_yt_downloader::youtube::validate_url;
+///
+/// assert_eq!(
+///     validate_url("https://youtu.be/dQw4w9WgXcQ?si=abc").unwrap(),
+///     "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+/// );
+/// assert_eq!(validate_url("https://vimeo.com/123").unwrap(), "https://vimeo.com/123");
+/// assert!(validate_url("--exec=rm -rf ~").is_err());
+/// assert!(validate_url("https://www.youtube.com/watch?v=").is_err());
+/// ```
+pub fn validate_url(url: &str) -> AppResult<String> {
+    let invalid = || AppError::InvalidUrl(url.to_string());
+    let trimmed = url.trim();
+
+    if trimmed.starts_with('-') {
+        return Err(invalid());
+    }
+    if let Ok(parsed) = YouTubeUrl::parse(trimmed) {
+        return Ok(parsed.to_string());
+    }
+
+    let parsed = Url::parse(trimmed).map_err(|_| invalid())?;
+    let host = parsed.host_str().unwrap_or_default().to_ascii_lowercase();
+    let is_youtube =
+        YOUTUBE_HOSTS.contains(&host.as_str()) || SHORT_LINK_HOSTS.contains(&host.as_str());
+
+    if !matches!(parsed.scheme(), "http" | "https") || host.is_empty() || is_youtube {
+        return Err(invalid());
+    }
+
+    Ok(trimmed.to_string())
+}
+
+// ==== UNITARY TESTS ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "dQw4w9WgXcQ";
+
+    fn video(id: &str) -> YouTubeUrl {
+        YouTubeUrl::Video {
+            id: id.to_string(),
+            start: None,
+            list: None,
+        }
+    }
+
+    fn canonical(url: &str) -> String {
+        YouTubeUrl::parse(url).unwrap().to_string()
+    }
+
+    // ============== Video Tests ==============
+
+    #[test]
+    fn test_parse_video_shapes() {
+        for url in [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?v=dQw4w9WgXcQ",
+            "http://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ",
+            "https://www.youtube.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/v/dQw4w9WgXcQ",
+            "www.youtube.com/watch?v=dQw4w9WgXcQ",
+        ] {
+            assert_eq!(YouTubeUrl::parse(url).unwrap(), video(ID), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_parse_short_and_live() {
+        assert_eq!(
+            YouTubeUrl::parse("https://www.youtube.com/shorts/dQw4w9WgXcQ").unwrap(),
+            YouTubeUrl::Short { id: ID.to_string() }
+        );
+        assert_eq!(
+            YouTubeUrl::parse("https://youtube.com/live/dQw4w9WgXcQ?feature=share").unwrap(),
+            YouTubeUrl::Live { id: ID.to_string() }
+        );
+    }
+
+    #[test]
+    fn test_canonical_strips_tracking_params() {
+        assert_eq!(
+            canonical("https://youtu.be/dQw4w9WgXcQ?si=XyZ&t=42"),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42"
+        );
+        assert_eq!(
+            canonical(
+                "https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ&pp=ygU&list=PL1&index=3"
+            ),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1"
+        );
+        assert_eq!(
+            canonical("https://www.youtube.com/shorts/dQw4w9WgXcQ?si=abc"),
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ"
+        );
+    }
+
+    #[test]
+    fn test_embed_start_becomes_t() {
+        assert_eq!(
+            canonical("https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?start=30"),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=30"
+        );
+    }
+
+    // ============== Playlist Tests ==============
+
+    #[test]
+    fn test_parse_playlist() {
+        let expected = YouTubeUrl::Playlist {
+            id: "PLrAXtmErZgOe".to_string(),
+        };
+        assert_eq!(
+            YouTubeUrl::parse("https://www.youtube.com/playlist?list=PLrAXtmErZgOe&si=x").unwrap(),
+            expected
+        );
+        assert_eq!(
+            YouTubeUrl::parse("https://music.youtube.com/playlist?list=PLrAXtmErZgOe").unwrap(),
+            expected
+        );
+        assert_eq!(
+            YouTubeUrl::parse("https://www.youtube.com/embed/videoseries?list=PLrAXtmErZgOe")
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_playlist_id() {
+        let url =
+            YouTubeUrl::parse("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1").unwrap();
+        assert_eq!(url.playlist_id(), Some("PL1"));
+        assert_eq!(url.video_id(), Some(ID));
+        assert!(!url.is_collection());
+    }
+
+    // ============== Channel Tests ==============
+
+    #[test]
+    fn test_parse_channels() {
+        assert_eq!(
+            YouTubeUrl::parse("https://www.youtube.com/@LofiGirl").unwrap(),
+            YouTubeUrl::Channel {
+                channel: "@LofiGirl".to_string(),
+                tab: None
+            }
+        );
+        assert_eq!(
+            canonical("https://m.youtube.com/channel/UCSJ4gkVC6NrvII8umztf0Ow/videos?view=0"),
+            "https://www.youtube.com/channel/UCSJ4gkVC6NrvII8umztf0Ow/videos"
+        );
+        assert_eq!(
+            canonical("https://youtube.com/c/Ferris/"),
+            "https://www.youtube.com/c/Ferris"
+        );
+        assert!(YouTubeUrl::parse("https://www.youtube.com/user/name")
+            .unwrap()
+            .is_collection());
+    }
+
+    // ============== Search Tests ==============
+
+    #[test]
+    fn test_parse_search() {
+        let url =
+            YouTubeUrl::parse("https://www.youtube.com/results?search_query=rust+talks").unwrap();
+        assert_eq!(
+            url,
+            YouTubeUrl::Search {
+                query: "rust talks".to_string()
+            }
+        );
+        assert_eq!(
+            url.to_string(),
+            "https://www.youtube.com/results?search_query=rust+talks"
+        );
+    }
+
+    // ============== Rejection Tests ==============
+
+    #[test]
+    fn test_parse_rejects_non_youtube() {
+        for url in [
+            "",
+            "not a url",
+            "https://vimeo.com/123456789",
+            "https://notyoutube.com/watch?v=dQw4w9WgXcQ",
+            "ftp://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube.com/",
+            "https://www.youtube.com/watch",
+            "https://www.youtube.com/shorts/",
+            "https://www.youtube.com/watch?v=bad%20id",
+            "https://www.youtube.com/@",
+        ] {
+            assert!(YouTubeUrl::parse(url).is_err(), "{}", url);
+        }
+    }
+
+    // ============== Validation Tests ==============
+
+    #[test]
+    fn test_validate_url_canonicalizes_youtube() {
+        assert_eq!(
+            validate_url("  youtu.be/dQw4w9WgXcQ?t=5&si=x ").unwrap(),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=5"
+        );
+    }
+
+    #[test]
+    fn test_validate_url_keeps_other_sites() {
+        assert_eq!(
+            validate_url("https://vimeo.com/123?x=1").unwrap(),
+            "https://vimeo.com/123?x=1"
+        );
+    }
+
+    #[test]
+    fn test_validate_url_rejects_malformed() {
+        for url in [
+            "",
+            "not-a-valid-url",
+            "--exec=touch pwned",
+            "-o/tmp/x",
+            "file:///etc/passwd",
+            "vimeo.com/123",
+            "https://www.youtube.com/",
+            "https://youtu.be/",
+            "https://www.youtube.com/watch?v=a;b",
+        ] {
+            assert!(
+                matches!(validate_url(url), Err(AppError::InvalidUrl(_))),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_unknown_urls() {
+        assert_eq!(canonicalize_url("not a url"), "not a url");
+        assert_eq!(
+            canonicalize_url("https://youtu.be/dQw4w9WgXcQ"),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+        );
+    }
+}
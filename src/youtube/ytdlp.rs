@@ -21,15 +21,18 @@
 
 use serde::Deserialize;
 use std::process::Command;
+use std::sync::{Arc, OnceLock};
 
 use crate::error::{AppError, AppResult};
-use crate::youtube::metadata::{PlaylistInfo, StreamInfo, VideoInfo};
+use crate::youtube::metadata::{LiveStatus, PlaylistInfo, StreamInfo, VideoInfo, VideoSummary};
+use crate::youtube::url::validate_url;
 
 /// Client for interacting with the yt-dlp command-line tool.
 ///
 /// Provides a Rust interface to yt-dlp functionality including video downloads,
 /// metadata extraction, and playlist processing. All methods check for yt-dlp
-/// availability before execution.
+/// availability before execution; the check runs once per client, and clones
+/// share it.
 ///
 /// # Examples
 ///
@@ -57,7 +60,11 @@ use crate::youtube::metadata::{PlaylistInfo, StreamInfo, VideoInfo};
 /// # Ok(())
 /// # }
 /// ```
-pub struct YtDlpClient;
+#[derive(Debug, Clone)]
+pub struct YtDlpClient {
+    /// Set once yt-dlp has been found
+    available: Arc<OnceLock<()>>,
+}
 
 /// Internal structure for deserializing yt-dlp JSON output for videos.
 ///
@@ -72,6 +79,14 @@ struct YtDlpOutput {
     channel: Option<String>,
     upload_date: Option<String>,
     view_count: Option<u64>,
+    #[serde(default)]
+    live_status: Option<String>,
+    #[serde(default)]
+    is_live: Option<bool>,
+    #[serde(default)]
+    release_timestamp: Option<i64>,
+    #[serde(default)]
+    availability: Option<String>,
     formats: Option<Vec<YtDlpFormat>>,
 }
 
@@ -91,6 +106,7 @@ struct YtDlpFormat {
     filesize: Option<u64>,
     tbr: Option<f64>,
     fps: Option<f64>,
+    dynamic_range: Option<String>,
 }
 
 /// Internal structure for deserializing yt-dlp playlist JSON output.
@@ -107,16 +123,53 @@ struct YtDlpPlaylist {
 }
 
 /// Internal structure for deserializing individual playlist entries.
+///
+/// Besides the ID, listings carry whatever "flat" metadata the site exposes
+/// without opening each video; missing fields are `None`.
 #[derive(Debug, Deserialize)]
 struct YtDlpPlaylistEntry {
     id: String,
+    #[serde(default)]
     title: Option<String>,
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    upload_date: Option<String>,
+    #[serde(default)]
+    view_count: Option<u64>,
+    #[serde(default)]
+    live_status: Option<String>,
+    #[serde(default)]
+    availability: Option<String>,
+    /// Set on each line of `--flat-playlist` output
+    #[serde(default)]
+    playlist_id: Option<String>,
+    #[serde(default)]
+    playlist_title: Option<String>,
+}
+
+impl YtDlpPlaylistEntry {
+    /// Returns the flat metadata of the entry.
+    fn summary(&self) -> VideoSummary {
+        VideoSummary {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            duration: self.duration.map(|d| d as u64),
+            upload_date: self.upload_date.clone(),
+            view_count: self.view_count,
+            live_status: self.live_status.clone(),
+            availability: self.availability.clone(),
+            complete: false,
+        }
+    }
 }
 
 impl YtDlpClient {
     /// Creates a new yt-dlp client instance.
     pub fn new() -> Self {
-        Self
+        Self {
+            available: Arc::new(OnceLock::new()),
+        }
     }
 
     /// Checks if yt-dlp is installed and available on the system.
@@ -188,6 +241,20 @@ impl YtDlpClient {
         Ok(())
     }
 
+    /// Like [`YtDlpClient::require()`], but only runs `yt-dlp --version` the
+    /// first time it succeeds for this client or one of its clones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with installation instructions if yt-dlp is not found.
+    pub fn ensure_available(&self) -> AppResult<()> {
+        if self.available.get().is_none() {
+            Self::require()?;
+            let _ = self.available.set(());
+        }
+        Ok(())
+    }
+
     /// Extracts video metadata using yt-dlp.
     ///
     /// Runs `yt-dlp --dump-json` to retrieve complete video information including
@@ -218,23 +285,57 @@ impl YtDlpClient {
     /// # }
     /// ```
     pub fn get_video_info(&self, url: &str) -> AppResult<VideoInfo> {
-        Self::require()?;
+        let yt_info = self.dump_video_json(url)?;
+        Ok(self.convert_to_video_info(yt_info))
+    }
 
+    /// Fetches the fields `--filter` expressions can test for one video.
+    ///
+    /// Unlike playlist listings, the result always has `complete` set.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`YtDlpClient::get_video_info()`].
+    pub fn get_video_summary(&self, url: &str) -> AppResult<VideoSummary> {
+        let yt = self.dump_video_json(url)?;
+        Ok(VideoSummary {
+            id: yt.id,
+            title: Some(yt.title),
+            duration: yt.duration.map(|d| d as u64),
+            upload_date: yt.upload_date,
+            view_count: yt.view_count,
+            live_status: yt.live_status,
+            availability: yt.availability,
+            complete: true,
+        })
+    }
+
+    /// Runs `yt-dlp --dump-json` for a single video and parses its output.
+    fn dump_video_json(&self, url: &str) -> AppResult<YtDlpOutput> {
+        let url = validate_url(url)?;
+        self.ensure_available()?;
+
+        // Upcoming live streams have no formats yet; keep their info instead of failing
         let output = Command::new("yt-dlp")
-            .args(["--dump-json", "--no-warnings", "--no-playlist", url])
+            .args([
+                "--dump-json",
+                "--no-warnings",
+                "--no-playlist",
+                "--ignore-no-formats-error",
+                "--",
+                &url,
+            ])
             .output()
             .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(AppError::ExtractionFailed(stderr.to_string()));
+            return Err(classify_failure(&stderr));
         }
 
         let json_str = String::from_utf8_lossy(&output.stdout);
-        let yt_info: YtDlpOutput = serde_json::from_str(&json_str)
-            .map_err(|e| AppError::ExtractionFailed(format!("Failed to parse JSON: {}", e)))?;
-
-        Ok(self.convert_to_video_info(yt_info))
+        serde_json::from_str(&json_str)
+            .map_err(|e| AppError::ExtractionFailed(format!("Failed to parse JSON: {}", e)))
     }
 
     /// Extracts playlist metadata using yt-dlp.
@@ -269,16 +370,33 @@ impl YtDlpClient {
     /// # }
     /// ```
     pub fn get_playlist_info(&self, url: &str) -> AppResult<PlaylistInfo> {
-        Self::require()?;
+        self.get_playlist_entries(url).map(|(playlist, _)| playlist)
+    }
+
+    /// Extracts playlist metadata together with the flat metadata of each
+    /// video, in playlist order.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`YtDlpClient::get_playlist_info()`].
+    pub fn get_playlist_entries(&self, url: &str) -> AppResult<(PlaylistInfo, Vec<VideoSummary>)> {
+        let url = validate_url(url)?;
+        self.ensure_available()?;
 
         let output = Command::new("yt-dlp")
-            .args(["--dump-json", "--flat-playlist", "--no-warnings", url])
+            .args([
+                "--dump-json",
+                "--flat-playlist",
+                "--no-warnings",
+                "--",
+                &url,
+            ])
             .output()
             .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(AppError::ExtractionFailed(stderr.to_string()));
+            return Err(classify_failure(&stderr));
         }
 
         let json_str = String::from_utf8_lossy(&output.stdout);
@@ -291,26 +409,37 @@ impl YtDlpClient {
         }
 
         if let Ok(playlist) = serde_json::from_str::<YtDlpPlaylist>(&json_str) {
-            return Ok(self.convert_to_playlist_info(playlist));
+            let summaries = playlist
+                .entries
+                .iter()
+                .flatten()
+                .map(YtDlpPlaylistEntry::summary)
+                .collect();
+            return Ok((self.convert_to_playlist_info(playlist), summaries));
         }
 
-        let mut video_ids = Vec::new();
-        let mut title = "Unknown Playlist".to_string();
+        let mut summaries = Vec::new();
+        let mut id = None;
+        let mut title = None;
 
         for line in lines {
             if let Ok(entry) = serde_json::from_str::<YtDlpPlaylistEntry>(line) {
-                video_ids.push(entry.id);
+                summaries.push(entry.summary());
+                id = id.or(entry.playlist_id);
+                title = title.or(entry.playlist_title);
             }
         }
 
-        Ok(PlaylistInfo {
-            id: "unknown".to_string(),
-            title,
+        let video_ids: Vec<String> = summaries.iter().map(|summary| summary.id.clone()).collect();
+        let playlist = PlaylistInfo {
+            id: id.unwrap_or_else(|| "unknown".to_string()),
+            title: title.unwrap_or_else(|| "Unknown Playlist".to_string()),
             description: None,
             channel: None,
             video_count: video_ids.len() as u64,
             video_ids,
-        })
+        };
+        Ok((playlist, summaries))
     }
 
     /// Downloads a video using yt-dlp.
@@ -354,7 +483,8 @@ impl YtDlpClient {
     /// # }
     /// ```
     pub fn download(&self, url: &str, output_path: &str, format: Option<&str>) -> AppResult<()> {
-        Self::require()?;
+        let url = validate_url(url)?;
+        self.ensure_available()?;
 
         let mut args = vec![
             "--no-warnings".to_string(),
@@ -367,7 +497,8 @@ impl YtDlpClient {
             args.push(fmt.to_string());
         }
 
-        args.push(url.to_string());
+        args.push("--".to_string());
+        args.push(url);
 
         let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
@@ -378,7 +509,7 @@ impl YtDlpClient {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(AppError::ExtractionFailed(stderr.to_string()));
+            return Err(classify_failure(&stderr));
         }
 
         Ok(())
@@ -418,7 +549,8 @@ impl YtDlpClient {
     /// # }
     /// ```
     pub fn download_audio(&self, url: &str, output_path: &str, format: &str) -> AppResult<()> {
-        Self::require()?;
+        let url = validate_url(url)?;
+        self.ensure_available()?;
 
         let output = Command::new("yt-dlp")
             .args([
@@ -428,14 +560,15 @@ impl YtDlpClient {
                 format,
                 "-o",
                 output_path,
-                url,
+                "--",
+                &url,
             ])
             .output()
             .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(AppError::ExtractionFailed(stderr.to_string()));
+            return Err(classify_failure(&stderr));
         }
 
         Ok(())
@@ -481,18 +614,27 @@ impl YtDlpClient {
         output_path: &str,
         quality: &str,
     ) -> AppResult<()> {
-        Self::require()?;
+        let url = validate_url(url)?;
+        self.ensure_available()?;
 
         let format_str = self.quality_to_format(quality);
 
         let output = Command::new("yt-dlp")
-            .args(["--no-warnings", "-f", &format_str, "-o", output_path, url])
+            .args([
+                "--no-warnings",
+                "-f",
+                &format_str,
+                "-o",
+                output_path,
+                "--",
+                &url,
+            ])
             .output()
             .map_err(|e| AppError::ExtractionFailed(e.to_string()))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(AppError::ExtractionFailed(stderr.to_string()));
+            return Err(classify_failure(&stderr));
         }
 
         Ok(())
@@ -509,6 +651,7 @@ impl YtDlpClient {
             .into_iter()
             .filter_map(|f| self.convert_to_stream_info(f))
             .collect();
+        let live_status = LiveStatus::from_ytdlp(yt.live_status.as_deref(), yt.is_live);
 
         VideoInfo {
             id: yt.id,
@@ -520,6 +663,8 @@ impl YtDlpClient {
             publish_date: yt.upload_date,
             view_count: yt.view_count,
             streams,
+            live_status,
+            release_timestamp: yt.release_timestamp,
         }
     }
 
@@ -543,6 +688,7 @@ impl YtDlpClient {
         };
 
         Some(StreamInfo {
+            format_id: f.format_id,
             url,
             quality,
             format: f.ext.unwrap_or_else(|| "unknown".to_string()),
@@ -550,8 +696,12 @@ impl YtDlpClient {
             audio_codec: f.acodec.filter(|a| a != "none"),
             is_audio_only,
             file_size: f.filesize,
-            bitrate: f.tbr.map(|b| b as u64),
+            // yt-dlp reports tbr in kbit/s
+            bitrate: f.tbr.map(|b| (b * 1000.0) as u64),
             fps: f.fps.map(|f| f as u32),
+            width: f.width.filter(|_| !is_audio_only),
+            height: f.height.filter(|_| !is_audio_only),
+            dynamic_range: f.dynamic_range.filter(|_| !is_audio_only),
         })
     }
 
@@ -610,3 +760,138 @@ impl Default for YtDlpClient {
         Self::new()
     }
 }
+
+/// Maps yt-dlp's stderr output to the most specific [`AppError`].
+///
+/// yt-dlp only reports failures as text, so transient network problems are
+/// recognised by their messages and surfaced as retryable errors; everything
+/// else stays an `ExtractionFailed`.
+pub(crate) fn classify_failure(stderr: &str) -> AppError {
+    const NETWORK: [&str; 8] = [
+        "unable to download webpage",
+        "timed out",
+        "connection reset",
+        "connection refused",
+        "urlopen error",
+        "temporary failure in name resolution",
+        "http error 5",
+        "http error 429",
+    ];
+    const INTERRUPTED: [&str; 2] = ["interrupted", "did not get any data blocks"];
+
+    let message = stderr.trim().to_string();
+    let lower = message.to_lowercase();
+
+    if NETWORK.iter().any(|needle| lower.contains(needle)) {
+        AppError::Connection(message)
+    } else if INTERRUPTED.iter().any(|needle| lower.contains(needle)) {
+        AppError::DownloadInterrupted(message)
+    } else {
+        AppError::ExtractionFailed(message)
+    }
+}
+
+// ==================================================
+//          UNITARY TESTS
+// ==================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ============== Availability Tests ==============
+
+    #[test]
+    fn test_clones_share_availability() {
+        let client = YtDlpClient::new();
+        let clone = client.clone();
+        client.available.set(()).unwrap();
+
+        // Passes without running yt-dlp, which may not be installed here
+        assert!(clone.ensure_available().is_ok());
+        assert!(YtDlpClient::new().available.get().is_none());
+    }
+
+    // ============== Playlist Entry Tests ==============
+
+    #[test]
+    fn test_flat_entry_summary() {
+        let entry: YtDlpPlaylistEntry = serde_json::from_str(
+            r#"{"id": "abc123", "title": "Talk", "duration": 754.0, "view_count": 42,
+                "availability": null, "playlist_id": "PL1"}"#,
+        )
+        .unwrap();
+
+        let summary = entry.summary();
+        assert_eq!(summary.id, "abc123");
+        assert_eq!(summary.title.as_deref(), Some("Talk"));
+        assert_eq!(summary.duration, Some(754));
+        assert_eq!(summary.view_count, Some(42));
+        assert_eq!(summary.upload_date, None);
+        assert_eq!(summary.availability, None);
+        assert!(!summary.complete);
+    }
+
+    #[test]
+    fn test_flat_entry_with_only_an_id() {
+        let entry: YtDlpPlaylistEntry = serde_json::from_str(r#"{"id": "abc123"}"#).unwrap();
+        assert_eq!(entry.summary(), VideoSummary::new("abc123"));
+    }
+
+    // ============== Video Info Tests ==============
+
+    #[test]
+    fn test_convert_upcoming_video() {
+        let yt: YtDlpOutput = serde_json::from_str(
+            r#"{"id": "abc123", "title": "Premiere", "live_status": "is_upcoming",
+                "release_timestamp": 1718000000, "formats": []}"#,
+        )
+        .unwrap();
+
+        let info = YtDlpClient::new().convert_to_video_info(yt);
+        assert!(info.is_upcoming());
+        assert_eq!(info.release_timestamp, Some(1718000000));
+        assert!(info.streams.is_empty());
+    }
+
+    #[test]
+    fn test_convert_live_from_is_live_flag() {
+        let yt: YtDlpOutput =
+            serde_json::from_str(r#"{"id": "abc123", "title": "Stream", "is_live": true}"#)
+                .unwrap();
+
+        let info = YtDlpClient::new().convert_to_video_info(yt);
+        assert!(info.is_live());
+        assert_eq!(info.release_timestamp, None);
+    }
+
+    // ============== classify_failure Tests ==============
+
+    #[test]
+    fn test_classify_network_failure_is_retryable() {
+        let error = classify_failure(
+            "ERROR: [youtube] abc: Unable to download webpage: <urlopen error [Errno 110]>",
+        );
+        assert!(matches!(error, AppError::Connection(_)));
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn test_classify_server_error_is_retryable() {
+        let error = classify_failure("ERROR: unable to download video data: HTTP Error 503");
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn test_classify_interrupted_download() {
+        let error = classify_failure("ERROR: Download interrupted by peer");
+        assert!(matches!(error, AppError::DownloadInterrupted(_)));
+    }
+
+    #[test]
+    fn test_classify_other_failure_is_permanent() {
+        let error = classify_failure("ERROR: [youtube] abc: Video unavailable\n");
+        assert!(matches!(error, AppError::ExtractionFailed(ref m) if m.ends_with("unavailable")));
+        assert!(!error.is_retryable());
+    }
+}
@@ -1,7 +1,7 @@
 mod common;
 
 #[allow(unused_imports)]
-use common::{run_ytdl, run_ytdl_stderr, run_ytdl_stdout};
+use common::{create_temp_dir, run_ytdl, run_ytdl_stderr, run_ytdl_stdout};
 
 // ============== Help Tests ==============
 
@@ -63,6 +63,17 @@ fn test_info_help() {
     assert!(stdout.contains("URL"));
 }
 
+#[test]
+fn test_formats_help() {
+    let output = run_ytdl(&["formats", "--help"]);
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("--sort"));
+    assert!(stdout.contains("--reverse"));
+}
+
 #[test]
 fn test_config_help() {
     let output = run_ytdl(&["config", "--help"]);
@@ -133,6 +144,46 @@ fn test_info_missing_url() {
     assert!(!output.status.success());
 }
 
+// ============== Batch Input Tests ==============
+
+#[test]
+fn test_download_batch_file_in_help() {
+    let stdout = run_ytdl_stdout(&["download", "--help"]);
+
+    assert!(stdout.contains("--batch-file"));
+}
+
+#[test]
+fn test_download_missing_batch_file() {
+    let output = run_ytdl(&["download", "-a", "/nonexistent/ytdl-urls.txt"]);
+
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ytdl-urls.txt"));
+}
+
+#[test]
+fn test_info_empty_batch_file() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("urls.txt");
+    std::fs::write(&path, "# nothing here\n\n").unwrap();
+
+    let output = run_ytdl(&["info", "-a", path.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+}
+
+// ============== Routing Rules Tests ==============
+
+#[test]
+fn test_explain_flag_in_help() {
+    for command in ["download", "audio", "playlist"] {
+        let stdout = run_ytdl_stdout(&[command, "--help"]);
+        assert!(stdout.contains("--explain"), "{}", command);
+    }
+}
+
 // ============== Quality Flag Tests ==============
 
 #[test]
@@ -165,6 +216,26 @@ fn test_audio_format_flags() {
     assert!(stdout.contains("mp3") || stdout.contains("format"));
 }
 
+#[test]
+fn test_download_format_id_flag() {
+    let output = run_ytdl(&["download", "--help"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("--format-id"));
+}
+
+#[test]
+fn test_download_format_id_rejects_selector_syntax() {
+    let output = run_ytdl(&[
+        "download",
+        "https://youtube.com/watch?v=abc123",
+        "--format-id",
+        "best[height<=720]",
+    ]);
+
+    assert!(!output.status.success());
+}
+
 // ============== Output Flag Tests ==============
 
 #[test]
@@ -1,6 +1,8 @@
 mod common;
 
-use common::run_ytdl;
+use std::process::Command;
+
+use common::{create_temp_dir, get_binary_path, run_ytdl};
 
 // ============== Config Show Tests ==============
 
@@ -115,6 +117,8 @@ fn test_all_config_keys_readable() {
         "video.format",
         "video.include_thumbnail",
         "video.include_subtitles",
+        "video.write_info_json",
+        "video.write_nfo",
         "network.retry_attempts",
         "network.timeout",
     ];
@@ -158,3 +162,277 @@ fn test_config_persists_after_change_serial() {
 
     run_ytdl(&["config", "reset"]);
 }
+
+// ============== Layered Config Tests ==============
+
+#[test]
+fn test_config_flag_set_and_get() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    let path = path.to_str().unwrap();
+
+    let set_output = run_ytdl(&["--config", path, "config", "set", "audio.bitrate", "96k"]);
+    assert!(set_output.status.success());
+
+    let output = run_ytdl(&["config", "get", "audio.bitrate", "--config", path]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "96k");
+
+    let output = run_ytdl(&["--config", path, "config", "path"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), path);
+}
+
+#[test]
+fn test_config_show_origin() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    std::fs::write(&path, "[audio]\nformat = \"flac\"\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "config",
+            "show",
+            "--origin",
+        ])
+        .env("YTDL_VIDEO_FORMAT", "mkv")
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = |key: &str| {
+        stdout
+            .lines()
+            .find(|line| line.contains(key))
+            .unwrap_or_default()
+            .to_string()
+    };
+    assert!(line("audio.format").contains("user file"));
+    assert!(line("video.format").contains("env YTDL_VIDEO_FORMAT"));
+    assert!(line("network.timeout").contains("default"));
+}
+
+#[test]
+fn test_config_env_override() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+
+    let output = Command::new(get_binary_path())
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "config",
+            "get",
+            "audio.format",
+        ])
+        .env("YTDL_AUDIO_FORMAT", "opus")
+        .output()
+        .unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "opus");
+}
+
+// ============== Validate / Edit Tests ==============
+
+#[test]
+fn test_config_validate_reports_lines() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    std::fs::write(
+        &path,
+        "[general]\noutput_dir = \".\"\ndefault_quality = \"999p\"\n[audio]\nformat = \"wma\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["--config", path.to_str().unwrap(), "config", "validate"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 3: general.default_quality"));
+    assert!(stderr.contains("line 5: audio.format"));
+}
+
+#[test]
+fn test_config_validate_valid_file() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    std::fs::write(&path, "[general]\noutput_dir = \".\"\n").unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["--config", path.to_str().unwrap(), "config", "validate"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_config_set_rejects_invalid_value() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    let path = path.to_str().unwrap();
+
+    let output = run_ytdl(&["--config", path, "config", "set", "audio.format", "wma"]);
+
+    assert!(!output.status.success());
+    assert!(!std::path::Path::new(path).exists());
+}
+
+#[test]
+fn test_config_upgrades_legacy_file() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    let original = "[network]\ntimeout = \"60\"\n\n[general]\nfilename_template = \"%(title)s\"\n";
+    std::fs::write(&path, original).unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args([
+            "--config",
+            path.to_str().unwrap(),
+            "config",
+            "get",
+            "network.timeout",
+        ])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("from version 0 to 1"));
+    assert!(stdout.contains("network.timeout: \"60\" -> 60"));
+    assert!(stdout.contains("ignoring unknown setting 'general.filename_template'"));
+    assert_eq!(stdout.lines().last().unwrap_or_default(), "60");
+
+    let backup = temp_dir.path().join("custom.toml.v0.bak");
+    assert_eq!(std::fs::read_to_string(backup).unwrap(), original);
+
+    let upgraded = std::fs::read_to_string(&path).unwrap();
+    assert!(upgraded.contains("version = 1"));
+    assert!(upgraded.contains("filename_template"));
+}
+
+#[test]
+fn test_config_rejects_newer_version() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    std::fs::write(&path, "version = 99\n").unwrap();
+
+    let output = run_ytdl(&["--config", path.to_str().unwrap(), "config", "show"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("newer"));
+}
+
+#[test]
+fn test_config_show_lists_rules() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    std::fs::write(
+        &path,
+        "version = 1\n\n[[rules]]\nname = \"music\"\nchannel = \"Lofi Girl\"\naudio_format = \"flac\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["--config", path.to_str().unwrap(), "config", "show"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Rules:"));
+    assert!(stdout.contains("#1 (music)"));
+    assert!(stdout.contains("channel = Lofi Girl -> audio_format = flac"));
+}
+
+#[test]
+fn test_config_validate_reports_rule_problems() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    std::fs::write(
+        &path,
+        "version = 1\n\n[[rules]]\ntitle = \"(\"\nquality = \"720p\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(get_binary_path())
+        .args(["--config", path.to_str().unwrap(), "config", "validate"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 4: rules[1].title"));
+}
+
+/// Writes an executable "editor" script that replaces the file it is given.
+#[cfg(unix)]
+fn fake_editor(dir: &std::path::Path, content: &str) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let script = dir.join("editor.sh");
+    std::fs::write(
+        &script,
+        format!("#!/bin/sh\ncat > \"$1\" <<'TOML'\n{}TOML\n", content),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_edit_saves_valid_file() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    let editor = fake_editor(temp_dir.path(), "[audio]\nformat = \"flac\"\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["--config", path.to_str().unwrap(), "config", "edit"])
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(std::fs::read_to_string(&path).unwrap().contains("flac"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_edit_refuses_invalid_file() {
+    let temp_dir = create_temp_dir();
+    let path = temp_dir.path().join("custom.toml");
+    std::fs::write(&path, "[audio]\nformat = \"mp3\"\n").unwrap();
+    let editor = fake_editor(temp_dir.path(), "[audio]\nformat = \"wma\"\n");
+
+    let output = Command::new(get_binary_path())
+        .args(["--config", path.to_str().unwrap(), "config", "edit"])
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "[audio]\nformat = \"mp3\"\n"
+    );
+    assert!(!temp_dir.path().join("custom.edit.toml").exists());
+}
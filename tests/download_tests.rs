@@ -194,6 +194,67 @@ fn test_download_invalid_url() {
     assert!(!output.status.success());
 }
 
+#[test]
+fn test_download_dry_run_downloads_nothing() {
+    let temp_dir = create_temp_dir();
+    let output_path = temp_dir.path().to_string_lossy().to_string();
+
+    let output = run_ytdl(&[
+        "download",
+        TEST_VIDEO_SHORT,
+        "https://youtu.be/aaaaaaaaaaa",
+        "-o",
+        &output_path,
+        "--dry-run",
+    ]);
+
+    // The made-up video cannot be checked, so the plan reports a failure
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("videos planned"));
+    assert!(stdout.contains("Failed:  https://www.youtube.com/watch?v=aaaaaaaaaaa"));
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_download_dry_run_json() {
+    let temp_dir = create_temp_dir();
+    let output_path = temp_dir.path().to_string_lossy().to_string();
+
+    let output = run_ytdl(&[
+        "--dry-run=json",
+        "download",
+        "https://youtu.be/aaaaaaaaaaa",
+        "-o",
+        &output_path,
+    ]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let plan: serde_json::Value = serde_json::from_str(&stdout).expect("plan is JSON");
+    assert!(plan["downloads"].as_array().unwrap().is_empty());
+    assert_eq!(
+        plan["failed"][0]["url"],
+        "https://www.youtube.com/watch?v=aaaaaaaaaaa"
+    );
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_dry_run_rejected_by_other_commands() {
+    let output = run_ytdl(&["--dry-run", "history"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--dry-run"));
+}
+
+#[test]
+fn test_download_invalid_filter() {
+    let output = run_ytdl(&["download", TEST_VIDEO_SHORT, "--filter", "duration >= "]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("has no value"));
+}
+
 #[test]
 fn test_download_worst_quality() {
     if skip_if_no_ytdlp() {
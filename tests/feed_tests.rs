@@ -0,0 +1,77 @@
+mod common;
+
+use std::path::Path;
+
+use common::{create_temp_dir, run_ytdl};
+
+fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+// ============== Feed Build Tests ==============
+
+#[test]
+fn test_feed_build_writes_rss() {
+    let temp_dir = create_temp_dir();
+    let dir = temp_dir.path();
+    write(&dir.join("lecture-1.mp3"), "audio");
+    write(
+        &dir.join("lecture-1.info.json"),
+        r#"{"id": "abc123", "title": "Lecture 1", "description": "Sets & maps",
+            "upload_date": "20240501", "duration": 3600}"#,
+    );
+
+    let output = run_ytdl(&[
+        "feed",
+        "build",
+        dir.to_str().unwrap(),
+        "--base-url",
+        "https://example.com/lectures/",
+        "--title",
+        "Lectures",
+    ]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(1 episodes)"));
+    assert!(stdout.contains("1 new, 0 changed, 0 unchanged, 0 removed"));
+
+    let xml = std::fs::read_to_string(dir.join("feed.xml")).unwrap();
+    assert!(xml.contains("<rss version=\"2.0\""));
+    assert!(xml.contains("<title>Lectures</title>"));
+    assert!(xml.contains("<title>Lecture 1</title>"));
+    assert!(xml.contains("<description>Sets &amp; maps</description>"));
+    assert!(xml.contains(
+        "<enclosure url=\"https://example.com/lectures/lecture-1.mp3\" length=\"5\" type=\"audio/mpeg\"/>"
+    ));
+    assert!(xml.contains("<pubDate>Wed, 1 May 2024 00:00:00 +0000</pubDate>"));
+    assert!(xml.contains("<itunes:duration>01:00:00</itunes:duration>"));
+
+    let again = run_ytdl(&[
+        "feed",
+        "build",
+        dir.to_str().unwrap(),
+        "--base-url",
+        "https://example.com/lectures",
+    ]);
+    let stdout = String::from_utf8_lossy(&again.stdout);
+    assert!(stdout.contains("0 new, 0 changed, 1 unchanged, 0 removed"));
+}
+
+#[test]
+fn test_feed_build_rejects_relative_base_url() {
+    let temp_dir = create_temp_dir();
+
+    let output = run_ytdl(&[
+        "feed",
+        "build",
+        temp_dir.path().to_str().unwrap(),
+        "--base-url",
+        "example.com",
+    ]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("must start with http:// or https://"));
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::{create_temp_dir, get_binary_path};
+
+/// Runs ytdl with `--config <dir>/config.toml`, so the history lives in `dir`.
+fn run_in(dir: &Path, args: &[&str]) -> Output {
+    let config = dir.join("config.toml");
+
+    Command::new(get_binary_path())
+        .arg("--config")
+        .arg(&config)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute ytdl")
+}
+
+fn write_history(dir: &Path, lines: &[&str]) {
+    std::fs::write(dir.join("history.jsonl"), lines.join("\n") + "\n").unwrap();
+}
+
+const RUST_TALK: &str = r#"{"timestamp":"2024-05-01T12:00:00Z","url":"https://youtu.be/aaa","video_id":"aaa","title":"Rust in Production","channel":"Ferris Talks","path":"/nonexistent/rust.mp4","size":2048,"format":"mp4","outcome":"success"}"#;
+const FAILED_OLD: &str = r#"{"timestamp":"2024-04-01T12:00:00Z","url":"https://youtu.be/bbb","outcome":"failed","error":"Video unavailable"}"#;
+const FAILED_NEW: &str = r#"{"timestamp":"2024-06-01T12:00:00Z","url":"https://youtu.be/ccc","outcome":"failed","error":"Network error"}"#;
+
+// ============== History Listing Tests ==============
+
+#[test]
+fn test_history_empty() {
+    let temp_dir = create_temp_dir();
+    let output = run_in(temp_dir.path(), &["history"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No matching downloads"));
+}
+
+#[test]
+fn test_history_lists_entries() {
+    let temp_dir = create_temp_dir();
+    write_history(temp_dir.path(), &[FAILED_OLD, RUST_TALK]);
+
+    let output = run_in(temp_dir.path(), &["history"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Rust in Production"));
+    assert!(stdout.contains("Ferris Talks"));
+    assert!(stdout.contains("Video unavailable"));
+    assert!(stdout.contains("2 entries"));
+}
+
+#[test]
+fn test_history_json_filters() {
+    let temp_dir = create_temp_dir();
+    write_history(temp_dir.path(), &[FAILED_OLD, RUST_TALK, FAILED_NEW]);
+
+    let output = run_in(
+        temp_dir.path(),
+        &["history", "--failed", "--since", "2024-05-01", "--json"],
+    );
+
+    assert!(output.status.success());
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["url"], "https://youtu.be/ccc");
+    assert_eq!(entries[0]["outcome"], "failed");
+}
+
+#[test]
+fn test_history_search() {
+    let temp_dir = create_temp_dir();
+    write_history(temp_dir.path(), &[FAILED_OLD, RUST_TALK]);
+
+    let output = run_in(
+        temp_dir.path(),
+        &["history", "--search", "ferris", "--json"],
+    );
+
+    assert!(output.status.success());
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+    assert_eq!(entries[0]["title"], "Rust in Production");
+}
+
+#[test]
+fn test_history_invalid_since() {
+    let temp_dir = create_temp_dir();
+    let output = run_in(temp_dir.path(), &["history", "--since", "yesterday"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is not a date"));
+}
+
+// ============== History Prune Tests ==============
+
+#[test]
+fn test_history_prune_removes_missing_files() {
+    let temp_dir = create_temp_dir();
+    write_history(temp_dir.path(), &[FAILED_OLD, RUST_TALK]);
+
+    let output = run_in(temp_dir.path(), &["history", "prune"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Removed 1 history entries, 1 kept"));
+
+    let content = std::fs::read_to_string(temp_dir.path().join("history.jsonl")).unwrap();
+    assert!(content.contains("https://youtu.be/bbb"));
+    assert!(!content.contains("Rust in Production"));
+}
+
+#[test]
+fn test_history_prune_before() {
+    let temp_dir = create_temp_dir();
+    write_history(temp_dir.path(), &[FAILED_OLD, FAILED_NEW]);
+
+    let output = run_in(
+        temp_dir.path(),
+        &["history", "prune", "--before", "2024-05-01"],
+    );
+
+    assert!(output.status.success());
+    let content = std::fs::read_to_string(temp_dir.path().join("history.jsonl")).unwrap();
+    assert!(!content.contains("https://youtu.be/bbb"));
+    assert!(content.contains("https://youtu.be/ccc"));
+}
+
+// ============== History Recording Tests ==============
+
+#[test]
+fn test_failed_download_is_recorded() {
+    let temp_dir = create_temp_dir();
+    let output_dir = temp_dir.path().join("out");
+
+    let download = run_in(
+        temp_dir.path(),
+        &[
+            "download",
+            "https://www.youtube.com/watch?v=abc123",
+            "-o",
+            output_dir.to_str().unwrap(),
+        ],
+    );
+    assert!(!download.status.success());
+
+    let output = run_in(temp_dir.path(), &["history", "--failed", "--json"]);
+    assert!(output.status.success());
+
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["url"], "https://www.youtube.com/watch?v=abc123");
+    assert!(entries[0]["error"].as_str().is_some_and(|e| !e.is_empty()));
+}
+
+#[test]
+fn test_malformed_url_is_rejected_before_download() {
+    let temp_dir = create_temp_dir();
+    let batch = temp_dir.path().join("urls.txt");
+    std::fs::write(
+        &batch,
+        "https://youtu.be/abc123
+--exec=touch pwned
+",
+    )
+    .unwrap();
+
+    let output = run_in(
+        temp_dir.path(),
+        &["download", "--batch-file", batch.to_str().unwrap()],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid YouTube URL: --exec"));
+
+    assert!(!temp_dir.path().join("history.jsonl").exists());
+    assert!(!temp_dir.path().join("pwned").exists());
+}
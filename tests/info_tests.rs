@@ -130,3 +130,32 @@ fn test_info_output_format() {
     assert!(title_pos < id_pos);
     assert!(id_pos < duration_pos);
 }
+
+// ============== Formats Command Tests ==============
+
+#[test]
+fn test_formats_lists_streams() {
+    if skip_if_no_ytdlp() {
+        return;
+    }
+
+    let output = run_ytdl(&["formats", TEST_VIDEO_SHORT]);
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ID"));
+    assert!(stdout.contains("RESOLUTION"));
+    assert!(stdout.contains("BITRATE"));
+}
+
+#[test]
+fn test_formats_sorted_by_size() {
+    if skip_if_no_ytdlp() {
+        return;
+    }
+
+    let output = run_ytdl(&["formats", TEST_VIDEO_SHORT, "--sort", "size"]);
+
+    assert!(output.status.success());
+}
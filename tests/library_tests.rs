@@ -0,0 +1,84 @@
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::{create_temp_dir, get_binary_path};
+
+/// Runs ytdl with `--config <dir>/config.toml`, so the library index lives in `dir`.
+fn run_in(dir: &Path, args: &[&str]) -> Output {
+    Command::new(get_binary_path())
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute ytdl")
+}
+
+fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+// ============== Library Scan Tests ==============
+
+#[test]
+fn test_library_scan_indexes_sidecars() {
+    let temp_dir = create_temp_dir();
+    let media = temp_dir.path().join("media");
+    write(&media.join("one.mp4"), "video");
+    write(&media.join("one.info.json"), r#"{"id": "video_one"}"#);
+    write(&media.join("sub/two.mp3"), "audio");
+    write(&media.join("sub/two.info.json"), r#"{"id": "video_two"}"#);
+
+    let output = run_in(
+        temp_dir.path(),
+        &["library", "scan", media.to_str().unwrap()],
+    );
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Scanned 2 media files: 2 added, 0 already indexed"));
+
+    let index = std::fs::read_to_string(temp_dir.path().join("library.json")).unwrap();
+    assert!(index.contains("video_one"));
+    assert!(index.contains("video_two"));
+
+    let again = run_in(
+        temp_dir.path(),
+        &["library", "scan", media.to_str().unwrap()],
+    );
+    let stdout = String::from_utf8_lossy(&again.stdout);
+    assert!(stdout.contains("0 added, 2 already indexed"));
+}
+
+#[test]
+fn test_library_scan_lists_unidentified_files() {
+    let temp_dir = create_temp_dir();
+    let media = temp_dir.path().join("media");
+    write(&media.join("mystery.flac"), "audio");
+
+    let output = run_in(
+        temp_dir.path(),
+        &["library", "scan", media.to_str().unwrap()],
+    );
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 files have no video ID"));
+    assert!(stdout.contains("mystery.flac"));
+}
+
+#[test]
+fn test_library_scan_missing_dir_fails() {
+    let temp_dir = create_temp_dir();
+    let missing = temp_dir.path().join("missing");
+
+    let output = run_in(
+        temp_dir.path(),
+        &["library", "scan", missing.to_str().unwrap()],
+    );
+
+    assert!(!output.status.success());
+}
@@ -14,6 +14,7 @@ fn skip_if_no_ytdlp() -> bool {
     }
 }
 
+#[allow(dead_code)]
 const TEST_PLAYLIST_SHORT: &str =
     "https://www.youtube.com/playlist?list=PLzMcBGfZo4-mP7qA9cagf68V06UM5z1ka";
 
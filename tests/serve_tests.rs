@@ -0,0 +1,413 @@
+//! Tests for `ytdl serve`, run against a stand-in for yt-dlp.
+//!
+//! The stand-in is a shell script put first on `PATH`. It describes every
+//! video as a small MP4 and "downloads" it by writing a few bytes to the
+//! path yt-dlp would use. Videos whose ID starts with `slow` take about 20
+//! seconds, `unavailable` ones fail and `doomed` ones fail after 2 seconds.
+#![cfg(unix)]
+
+mod common;
+
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use common::{create_temp_dir, get_binary_path};
+
+const FAKE_YTDLP: &str = r#"#!/bin/sh
+out=""
+report=""
+dump=""
+url=""
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --version) echo "2024.01.01"; exit 0 ;;
+        --dump-json) dump=1 ;;
+        -o) out="$2"; shift ;;
+        --print-to-file) report="$3"; shift 2 ;;
+    esac
+    url="$1"
+    shift
+done
+
+id="${url##*v=}"
+case "$id" in
+    unavailable*) echo "ERROR: [youtube] $id: Video unavailable" >&2; exit 1 ;;
+    doomed*) sleep 2; echo "ERROR: [youtube] $id: Video unavailable" >&2; exit 1 ;;
+esac
+
+if [ -n "$dump" ]; then
+    echo "{\"id\": \"$id\", \"title\": \"Video $id\", \"duration\": 10, \"channel\": \"Stand-in\", \"formats\": [{\"format_id\": \"18\", \"ext\": \"mp4\", \"height\": 360, \"width\": 640, \"vcodec\": \"avc1\", \"acodec\": \"mp4a\", \"filesize\": 2048}]}"
+    exit 0
+fi
+
+steps=2
+case "$id" in
+    slow*) steps=100 ;;
+esac
+i=1
+while [ $i -le $steps ]; do
+    echo "[download]  $i.0% of 2.00KiB at 1.00KiB/s ETA 00:01"
+    sleep 0.2
+    i=$((i + 1))
+done
+
+file=$(printf '%s' "$out" | sed "s/%(title)s/Video_$id/; s/%(ext)s/mp4/")
+printf 'fake video' > "$file"
+[ -n "$report" ] && echo "$file" > "$report"
+exit 0
+"#;
+
+/// A running `ytdl serve`, killed when dropped.
+struct TestServer {
+    child: Child,
+    base: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl TestServer {
+    /// Starts `ytdl serve` on a free port, with the history in `dir` and
+    /// `dir` as the output directory.
+    fn start(dir: &Path, token: Option<&str>) -> Self {
+        std::fs::write(
+            dir.join("config.toml"),
+            format!("[general]\noutput_dir = {:?}\n", dir.display().to_string()),
+        )
+        .unwrap();
+
+        let bin = dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let script = bin.join("yt-dlp");
+        std::fs::write(&script, FAKE_YTDLP).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path = format!(
+            "{}:{}",
+            bin.display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let mut command = Command::new(get_binary_path());
+        command
+            .arg("--config")
+            .arg(dir.join("config.toml"))
+            .args(["serve", "--listen", "127.0.0.1:0", "--jobs", "2"])
+            .env("PATH", path)
+            .env_remove("YTDL_SERVE_TOKEN")
+            .current_dir(dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        if let Some(token) = token {
+            command.args(["--token", token]);
+        }
+        let mut child = command.spawn().expect("Failed to start ytdl serve");
+
+        let stdout = child.stdout.take().unwrap();
+        let mut lines = BufReader::new(stdout).lines();
+        let base = lines
+            .by_ref()
+            .map_while(Result::ok)
+            .find_map(|line| {
+                let start = line.find("http://")?;
+                line[start..].split_whitespace().next().map(String::from)
+            })
+            .expect("ytdl serve did not print its address");
+        // Keeps reading, so that the server never blocks on a full pipe
+        std::thread::spawn(move || lines.for_each(drop));
+
+        Self {
+            child,
+            base,
+            token: token.map(String::from),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}{}", self.base, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn get(&self, path: &str) -> (StatusCode, Value) {
+        let response = self
+            .request(reqwest::Method::GET, path)
+            .send()
+            .await
+            .unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+
+    async fn delete(&self, path: &str) -> (StatusCode, Value) {
+        let response = self
+            .request(reqwest::Method::DELETE, path)
+            .send()
+            .await
+            .unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+
+    async fn post_job(&self, body: Value) -> (StatusCode, Value) {
+        let response = self
+            .request(reqwest::Method::POST, "/jobs")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+
+    /// Polls a job until `done` accepts it, failing after 30 seconds.
+    async fn wait_for(&self, id: u64, done: impl Fn(&Value) -> bool) -> Value {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        loop {
+            let (status, job) = self.get(&format!("/jobs/{}", id)).await;
+            assert_eq!(status, StatusCode::OK);
+            if done(&job) {
+                return job;
+            }
+            assert!(Instant::now() < deadline, "job {} stuck: {}", id, job);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn output_dir(temp_dir: &TempDir) -> PathBuf {
+    temp_dir.path().join("out")
+}
+
+// ============== Job Tests ==============
+
+#[tokio::test]
+async fn test_serve_downloads_job() {
+    let temp_dir = create_temp_dir();
+    let server = TestServer::start(temp_dir.path(), None);
+
+    let (status, job) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=aaaaaaaaaaa",
+            "output_dir": "out",
+            "quality": "360p",
+        }))
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(job["state"], "queued");
+    let id = job["id"].as_u64().unwrap();
+
+    let job = server
+        .wait_for(id, |job| {
+            job["state"] != "queued" && job["state"] != "running"
+        })
+        .await;
+    assert_eq!(job["state"], "finished", "{}", job);
+    assert_eq!(job["title"], "Video aaaaaaaaaaa");
+    assert_eq!(job["video_id"], "aaaaaaaaaaa");
+
+    let file = PathBuf::from(job["file"].as_str().unwrap());
+    assert_eq!(file, output_dir(&temp_dir).join("Video_aaaaaaaaaaa.mp4"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "fake video");
+    assert_eq!(job["size"], 10);
+
+    let (status, jobs) = server.get("/jobs").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(jobs.as_array().unwrap().len(), 1);
+
+    let (status, history) = server.get("/history").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history[0]["title"], "Video aaaaaaaaaaa");
+    assert_eq!(history[0]["outcome"], "success");
+}
+
+#[tokio::test]
+async fn test_serve_reports_failed_job() {
+    let temp_dir = create_temp_dir();
+    let server = TestServer::start(temp_dir.path(), None);
+
+    let (_, job) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=unavailable",
+            "output_dir": "out",
+        }))
+        .await;
+    let id = job["id"].as_u64().unwrap();
+
+    let job = server
+        .wait_for(id, |job| {
+            job["state"] != "queued" && job["state"] != "running"
+        })
+        .await;
+    assert_eq!(job["state"], "failed");
+    assert!(job["error"].as_str().unwrap().contains("Video unavailable"));
+
+    let (_, history) = server.get("/history?failed=true").await;
+    assert_eq!(history.as_array().unwrap().len(), 1);
+    assert_eq!(
+        history[0]["url"],
+        "https://www.youtube.com/watch?v=unavailable"
+    );
+
+    let (_, history) = server.get("/history?search=nothing-like-this").await;
+    assert!(history.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_serve_progress_and_cancel() {
+    let temp_dir = create_temp_dir();
+    let server = TestServer::start(temp_dir.path(), None);
+
+    let (_, job) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=slowwwwwwww",
+            "output_dir": "out",
+        }))
+        .await;
+    let id = job["id"].as_u64().unwrap();
+
+    let job = server
+        .wait_for(id, |job| job["progress"]["percent"].as_f64() > Some(1.0))
+        .await;
+    assert_eq!(job["state"], "running");
+    assert_eq!(job["progress"]["total_bytes"], 2048);
+    assert_eq!(job["progress"]["speed"], 1024);
+
+    let (status, job) = server.delete(&format!("/jobs/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["state"], "cancelled");
+
+    let (status, error) = server.delete(&format!("/jobs/{}", id)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(error["error"].as_str().unwrap().contains("already ended"));
+
+    // The stand-in is killed before it writes the file
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let (_, job) = server.get(&format!("/jobs/{}", id)).await;
+    assert_eq!(job["state"], "cancelled");
+    assert!(!output_dir(&temp_dir).join("Video_slowwwwwwww.mp4").exists());
+
+    let (_, history) = server.get("/history").await;
+    assert!(history.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_serve_cancelled_job_stays_cancelled() {
+    let temp_dir = create_temp_dir();
+    let server = TestServer::start(temp_dir.path(), None);
+
+    // The stand-in fails on its own shortly after the job is cancelled
+    let (_, job) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=doomedwwwww",
+            "output_dir": "out",
+        }))
+        .await;
+    let id = job["id"].as_u64().unwrap();
+
+    server.wait_for(id, |job| job["state"] == "running").await;
+    let (status, job) = server.delete(&format!("/jobs/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["state"], "cancelled");
+
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    let (_, job) = server.get(&format!("/jobs/{}", id)).await;
+    assert_eq!(job["state"], "cancelled", "{}", job);
+    assert!(job["error"].is_null());
+
+    let (_, history) = server.get("/history").await;
+    assert!(history.as_array().unwrap().is_empty());
+}
+
+// ============== Request Validation Tests ==============
+
+#[tokio::test]
+async fn test_serve_rejects_bad_requests() {
+    let temp_dir = create_temp_dir();
+    let server = TestServer::start(temp_dir.path(), None);
+
+    let (status, error) = server.post_job(json!({"url": "not a url"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].is_string());
+
+    let (status, error) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=aaaaaaaaaaa",
+            "quality": "8k",
+        }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("8k"));
+
+    let (status, error) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=aaaaaaaaaaa",
+            "qualty": "720p",
+        }))
+        .await;
+    assert!(status.is_client_error());
+    assert!(error["error"].as_str().unwrap().contains("qualty"));
+
+    let (status, error) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=aaaaaaaaaaa",
+            "output_dir": "../elsewhere",
+        }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains(".."));
+
+    let (status, _) = server
+        .post_job(json!({
+            "url": "https://www.youtube.com/watch?v=aaaaaaaaaaa",
+            "filename_template": "/tmp/%(title)s.%(ext)s",
+        }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, error) = server.get("/jobs/42").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["error"], "No job 42");
+
+    let (status, _) = server.get("/history?since=someday").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+// ============== Token Tests ==============
+
+#[tokio::test]
+async fn test_serve_requires_token() {
+    let temp_dir = create_temp_dir();
+    let server = TestServer::start(temp_dir.path(), Some("s3cret"));
+
+    let response = reqwest::get(format!("{}/jobs", server.base)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let response = server
+        .client
+        .get(format!("{}/jobs", server.base))
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let (status, jobs) = server.get("/jobs").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(jobs.as_array().unwrap().is_empty());
+}
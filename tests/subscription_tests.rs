@@ -0,0 +1,133 @@
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::{create_temp_dir, get_binary_path};
+
+/// Runs ytdl with `--config <dir>/config.toml`, so the subscriptions live in `dir`.
+fn run_in(dir: &Path, args: &[&str]) -> Output {
+    Command::new(get_binary_path())
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute ytdl")
+}
+
+fn list_json(dir: &Path) -> serde_json::Value {
+    let output = run_in(dir, &["subscribe", "list", "--json"]);
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+// ============== Subscribe Tests ==============
+
+#[test]
+fn test_subscribe_add_list_remove() {
+    let temp_dir = create_temp_dir();
+
+    let output = run_in(
+        temp_dir.path(),
+        &[
+            "--profile",
+            "music",
+            "subscribe",
+            "add",
+            "https://www.youtube.com/@LofiGirl",
+            "--audio-only",
+            "--min-duration",
+            "600",
+        ],
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Subscribed to @LofiGirl"));
+
+    let list = list_json(temp_dir.path());
+    let entries = list.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["name"], "@LofiGirl");
+    assert_eq!(entries[0]["profile"], "music");
+    assert_eq!(entries[0]["audio_only"], true);
+    assert_eq!(entries[0]["filters"]["min_duration"], 600);
+    assert!(temp_dir.path().join("subscriptions.json").is_file());
+
+    let output = run_in(temp_dir.path(), &["subscribe", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("@LofiGirl"));
+    assert!(stdout.contains("never synced"));
+
+    let output = run_in(temp_dir.path(), &["subscribe", "remove", "@LofiGirl"]);
+    assert!(output.status.success());
+    assert!(list_json(temp_dir.path()).as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_subscribe_add_duplicate_fails() {
+    let temp_dir = create_temp_dir();
+    let url = "https://www.youtube.com/playlist?list=PL123";
+
+    assert!(run_in(temp_dir.path(), &["subscribe", "add", url])
+        .status
+        .success());
+    let output = run_in(temp_dir.path(), &["subscribe", "add", url]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already subscribed as 'PL123'"));
+}
+
+#[test]
+fn test_subscribe_add_invalid_title_pattern() {
+    let temp_dir = create_temp_dir();
+    let output = run_in(
+        temp_dir.path(),
+        &[
+            "subscribe",
+            "add",
+            "https://www.youtube.com/@a",
+            "--title",
+            "(",
+        ],
+    );
+
+    assert!(!output.status.success());
+    assert!(!temp_dir.path().join("subscriptions.json").exists());
+}
+
+#[test]
+fn test_subscribe_remove_unknown() {
+    let temp_dir = create_temp_dir();
+    let output = run_in(temp_dir.path(), &["subscribe", "remove", "@nobody"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no subscription named '@nobody'"));
+}
+
+// ============== Sync Tests ==============
+
+#[test]
+fn test_sync_without_subscriptions() {
+    let temp_dir = create_temp_dir();
+    let output = run_in(temp_dir.path(), &["sync"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No subscriptions"));
+}
+
+#[test]
+fn test_sync_unknown_subscription() {
+    let temp_dir = create_temp_dir();
+    let output = run_in(temp_dir.path(), &["sync", "@nobody"]);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_sync_rejects_short_watch_interval() {
+    let temp_dir = create_temp_dir();
+    let output = run_in(temp_dir.path(), &["sync", "--watch", "10s"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("too short"));
+}